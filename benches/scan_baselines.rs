@@ -37,10 +37,16 @@ impl WorkloadFixture {
     fn scanner(&self) -> Scanner {
         Scanner::new(self.root()).max_risk(self.max_risk)
     }
+
+    /// Discovery walk followed by a second walk per target
+    fn two_pass_scanner(&self) -> Scanner {
+        self.scanner().single_pass(false)
+    }
 }
 
 fn benchmark_scans(c: &mut Criterion) {
     let fixtures = build_workloads();
+    let large_fixtures = build_large_workloads();
 
     let mut scan_group = c.benchmark_group("scan_total");
     for fixture in &fixtures {
@@ -73,6 +79,27 @@ fn benchmark_scans(c: &mut Criterion) {
         );
     }
     streaming_group.finish();
+
+    let mut passes_group = c.benchmark_group("scan_walk_passes");
+    for fixture in fixtures.iter().chain(&large_fixtures) {
+        let summary = validate_walk_passes(fixture);
+        passes_group.throughput(Throughput::Bytes(summary.total_bytes.max(1)));
+        passes_group.bench_with_input(
+            BenchmarkId::new("single_pass", fixture.name),
+            fixture,
+            |b, fixture| {
+                b.iter(|| black_box(run_scan(fixture)));
+            },
+        );
+        passes_group.bench_with_input(
+            BenchmarkId::new("two_pass", fixture.name),
+            fixture,
+            |b, fixture| {
+                b.iter(|| black_box(run_two_pass_scan(fixture)));
+            },
+        );
+    }
+    passes_group.finish();
 }
 
 fn run_scan(fixture: &WorkloadFixture) -> Vec<ProjectInfo> {
@@ -82,6 +109,13 @@ fn run_scan(fixture: &WorkloadFixture) -> Vec<ProjectInfo> {
         .expect("scan baseline should succeed")
 }
 
+fn run_two_pass_scan(fixture: &WorkloadFixture) -> Vec<ProjectInfo> {
+    fixture
+        .two_pass_scanner()
+        .scan()
+        .expect("two-pass scan baseline should succeed")
+}
+
 fn run_streaming_scan(fixture: &WorkloadFixture) -> Vec<ProjectInfo> {
     let (expected, rx) = fixture
         .scanner()
@@ -116,6 +150,32 @@ fn validate_fixture(fixture: &WorkloadFixture) -> ConsistencySummary {
     }
 }
 
+fn validate_walk_passes(fixture: &WorkloadFixture) -> ConsistencySummary {
+    let single_pass_projects = run_scan(fixture);
+    let two_pass_projects = run_two_pass_scan(fixture);
+
+    assert_eq!(
+        snapshot(&single_pass_projects),
+        snapshot(&two_pass_projects),
+        "single-pass and two-pass walks diverged for {}",
+        fixture.name
+    );
+    println!(
+        "walk_passes workload={} results={} description=\"{}\"",
+        fixture.name,
+        single_pass_projects.len(),
+        fixture.description
+    );
+
+    ConsistencySummary {
+        results: single_pass_projects.len(),
+        total_bytes: single_pass_projects
+            .iter()
+            .map(|project| project.size)
+            .sum(),
+    }
+}
+
 fn snapshot(projects: &[ProjectInfo]) -> Vec<ProjectSnapshot> {
     let mut snapshot = projects
         .iter()
@@ -141,6 +201,14 @@ fn build_workloads() -> Vec<WorkloadFixture> {
     ]
 }
 
+/// Larger trees only measured in the walk-passes group
+fn build_large_workloads() -> Vec<WorkloadFixture> {
+    vec![
+        build_large_monorepo(RiskLevel::Medium),
+        build_large_monorepo(RiskLevel::High),
+    ]
+}
+
 fn build_wide_small() -> WorkloadFixture {
     let tempdir = TempDir::new().expect("wide_small tempdir");
     let root = tempdir.path();
@@ -281,6 +349,62 @@ fn build_risk_high() -> WorkloadFixture {
     }
 }
 
+fn build_large_monorepo(max_risk: RiskLevel) -> WorkloadFixture {
+    let tempdir = TempDir::new().expect("large_monorepo tempdir");
+    let root = tempdir.path();
+
+    for team in 0..8 {
+        let team_root = root.join(format!("team-{team}"));
+        for idx in 0..12 {
+            let node_root = team_root.join("apps").join(format!("web-{idx:02}"));
+            fs::create_dir_all(&node_root).unwrap();
+            fs::write(node_root.join("package.json"), "{\"name\":\"large\"}\n").unwrap();
+            create_tree(&node_root.join("src").join("components"), 6, 6, 96);
+            for package in 0..16 {
+                create_tree(
+                    &node_root
+                        .join("node_modules")
+                        .join(format!("pkg-{package:02}"))
+                        .join("dist"),
+                    2,
+                    6,
+                    160,
+                );
+            }
+
+            let rust_root = team_root.join("services").join(format!("svc-{idx:02}"));
+            fs::create_dir_all(&rust_root).unwrap();
+            fs::write(rust_root.join("Cargo.toml"), package_manifest("large-rust")).unwrap();
+            create_tree(&rust_root.join("src").join("handlers"), 4, 5, 128);
+            create_tree(
+                &rust_root.join("target").join("debug").join("deps"),
+                8,
+                10,
+                256,
+            );
+            fs::write(rust_root.join("target").join(".rustc_info.json"), "{}").unwrap();
+        }
+        create_tree(&team_root.join("docs").join("guides"), 12, 4, 64);
+    }
+
+    let (name, description) = match max_risk {
+        RiskLevel::High => (
+            "large_monorepo_high",
+            "large source tree with build outputs, every directory matched",
+        ),
+        _ => (
+            "large_monorepo",
+            "large source tree with build outputs, candidate prefilter enabled",
+        ),
+    };
+    WorkloadFixture {
+        name,
+        description,
+        max_risk,
+        tempdir,
+    }
+}
+
 fn package_manifest(name: &str) -> String {
     format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")
 }
@@ -2,8 +2,9 @@ use crate::app::cleanup::{BlockedSummary, CleanupRequest, CleanupSelection, Clea
 use crate::app::scan::canonicalize_lossy;
 use crate::config::Config;
 use crate::evaluation::{EvaluatedProject, SafetyFlags, SkipReason};
+use crate::guard::check_path;
 use crate::plan::CleanupPlan;
-use crate::policy::KeepPolicy;
+use crate::policy::{KeepPolicy, ProcessSnapshot, VcsGuard};
 use crate::scanner::{ProjectInfo, Scanner};
 use anyhow::{bail, Result};
 use std::collections::HashMap;
@@ -31,6 +32,13 @@ pub struct ApplyPlanResult {
     pub cleanup_selection: CleanupSelection,
 }
 
+/// Policies shared by every project of a single `verify` run
+struct VerifyPolicies {
+    keep: KeepPolicy,
+    vcs: VcsGuard,
+    processes: ProcessSnapshot,
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct ApplyPlanService {
     cleanup: CleanupService,
@@ -54,7 +62,11 @@ impl ApplyPlanService {
         } = request;
         self.validate_plan_schema(plan.schema_version)?;
 
-        let keep_policy = KeepPolicy::from_config(config);
+        let policies = VerifyPolicies {
+            keep: KeepPolicy::from_config(config),
+            vcs: VcsGuard::new(),
+            processes: ProcessSnapshot::capture(),
+        };
         let scan_root_is_absolute = plan.scan_root.is_absolute();
         let scan_root = if scan_root_is_absolute {
             canonicalize_lossy(&plan.scan_root)
@@ -74,7 +86,7 @@ impl ApplyPlanService {
         for project in &plan.projects {
             let candidate = self.verify_project(
                 config,
-                &keep_policy,
+                &policies,
                 &mut scanner_cache,
                 &scan_root,
                 scan_root_is_absolute,
@@ -88,7 +100,7 @@ impl ApplyPlanService {
                 recent_days,
             )?;
 
-            if let Some(reason) = candidate.skip_reason {
+            if let Some(reason) = &candidate.skip_reason {
                 skipped_pre_count += 1;
                 skipped_pre_bytes = skipped_pre_bytes.saturating_add(candidate.info.size);
                 match reason {
@@ -104,12 +116,18 @@ impl ApplyPlanService {
                             .recent_bytes
                             .saturating_add(candidate.info.size);
                     }
-                    SkipReason::InUse => {
+                    SkipReason::InUse | SkipReason::HeldByProcess(_) => {
                         verification_blocked.in_use_count += 1;
                         verification_blocked.in_use_bytes = verification_blocked
                             .in_use_bytes
                             .saturating_add(candidate.info.size);
                     }
+                    SkipReason::TrackedByVcs => {
+                        verification_blocked.tracked_count += 1;
+                        verification_blocked.tracked_bytes = verification_blocked
+                            .tracked_bytes
+                            .saturating_add(candidate.info.size);
+                    }
                     _ => {}
                 }
                 skipped_projects.push(candidate);
@@ -142,7 +160,7 @@ impl ApplyPlanService {
     fn verify_project(
         &self,
         config: &Config,
-        keep_policy: &KeepPolicy,
+        policies: &VerifyPolicies,
         scanner_cache: &mut HashMap<PathBuf, Scanner>,
         scan_root: &Path,
         scan_root_is_absolute: bool,
@@ -168,10 +186,24 @@ impl ApplyPlanService {
                 .with_skip_reason(SkipReason::OutsideScanRoot));
         }
 
+        // Keep partial-clean paths relative to the (possibly canonicalized) target.
+        let prune_paths = project
+            .prune_paths
+            .iter()
+            .map(|prune| {
+                let mut prune = prune.clone();
+                if let Ok(relative) = prune.path.strip_prefix(&project.cleanable_dir) {
+                    prune.path = cleanable_dir.join(relative);
+                }
+                prune
+            })
+            .collect::<Vec<_>>();
+
         let mut candidate = if no_verify {
             let mut info = project.clone();
             info.root = project_root.clone();
             info.cleanable_dir = cleanable_dir.clone();
+            info.prune_paths = prune_paths;
             EvaluatedProject::new(info)
         } else {
             let scanner = scanner_cache
@@ -181,6 +213,9 @@ impl ApplyPlanService {
                         .exclude_dirs(&config.exclude_dirs)
                         .custom_patterns(&config.custom_patterns);
 
+                    if let Some(days) = config.rust_stale_days {
+                        scanner = scanner.rust_stale_days(days);
+                    }
                     if let Some(max_risk) = params_max_risk {
                         scanner = scanner.max_risk(max_risk);
                     }
@@ -191,7 +226,19 @@ impl ApplyPlanService {
                     scanner
                 });
 
-            match scanner.revalidate_target(&cleanable_dir) {
+            let revalidated = if prune_paths.is_empty() {
+                scanner.revalidate_target(&cleanable_dir)
+            } else {
+                scanner.revalidate_prune_target(&cleanable_dir, &prune_paths)
+            };
+
+            match revalidated {
+                // Compared against the directory the plan was made from, not today's.
+                Some(mut info) if project.identity().is_some() => {
+                    info.device = project.device;
+                    info.inode = project.inode;
+                    EvaluatedProject::from(info)
+                }
                 Some(info) => EvaluatedProject::from(info),
                 None => {
                     return Ok(EvaluatedProject::new(project.clone())
@@ -200,14 +247,30 @@ impl ApplyPlanService {
             }
         };
 
-        let decision = keep_policy.evaluate(&candidate.info);
-        candidate.safety = SafetyFlags {
-            protected: decision.protected,
-            protected_by: decision.reason,
-            recent: candidate.info.days_since_modified() < recent_days,
-        };
-
-        if candidate.is_protected() && !force_protected {
+        // Re-read the index and running processes even without verification: files may
+        // have been committed, or a process started, since the plan was written.
+        candidate.info.tracked_files = policies.vcs.tracked_files_for(&candidate.info);
+        let hold = policies
+            .processes
+            .holder_of_project(&candidate.info)
+            .cloned();
+        candidate.info.set_held_by(hold);
+
+        let decision = policies.keep.evaluate(&candidate.info);
+        candidate.safety = SafetyFlags::assess(&candidate.info, decision, recent_days);
+
+        let unsafe_path = check_path(
+            &candidate.info.cleanable_dir,
+            &candidate.info.root,
+            candidate.info.identity(),
+        );
+        if let Err(violation) = unsafe_path {
+            candidate.skip_reason = Some(SkipReason::UnsafePath(violation));
+        } else if let Some(hold) = candidate.info.in_use_by.clone() {
+            candidate.skip_reason = Some(SkipReason::HeldByProcess(hold));
+        } else if candidate.info.tracked_files > 0 && !force_protected {
+            candidate.skip_reason = Some(SkipReason::TrackedByVcs);
+        } else if candidate.is_protected() && !force_protected {
             candidate.skip_reason = Some(SkipReason::Protected);
         } else if candidate.is_recent() && !include_recent {
             candidate.skip_reason = Some(SkipReason::Recent);
@@ -219,7 +282,7 @@ impl ApplyPlanService {
     }
 
     fn validate_plan_schema(&self, schema_version: u32) -> Result<()> {
-        if schema_version != 1 && schema_version != 2 && schema_version != 3 {
+        if !(1..=4).contains(&schema_version) {
             bail!("Unsupported plan schema_version: {}", schema_version);
         }
         Ok(())
@@ -279,6 +342,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -307,6 +380,16 @@ mod tests {
                 recent: false,
                 selection_reason: None,
                 skip_reason: None,
+                feeding_projects: Vec::new(),
+                prune_paths: Vec::new(),
+                tracked_files: 0,
+                in_use_source: Default::default(),
+                recent_source: Default::default(),
+                activity: None,
+                in_use_by: None,
+                disk_usage: None,
+                device: None,
+                inode: None,
             }],
         }
     }
@@ -554,6 +637,70 @@ mod tests {
         assert_eq!(forced_result.cleanup_selection.selected.len(), 3);
     }
 
+    #[test]
+    fn verify_blocks_targets_with_git_tracked_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("repo");
+        let target = root.join("vendor");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(root.join("go.mod"), "module demo\n").unwrap();
+        fs::write(target.join("modules.txt"), "").unwrap();
+        touch_days_ago(&target, 30);
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args(args)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        };
+        if !git(&["init", "-q"]) {
+            // No usable git in this environment.
+            return;
+        }
+        assert!(git(&["add", "vendor"]));
+
+        let plan = CleanupPlan {
+            schema_version: 3,
+            tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            created_at: Utc::now(),
+            scan_root: root.clone(),
+            params: None,
+            projects: vec![sample_project(
+                root.clone(),
+                target.clone(),
+                ProjectType::Go,
+                Category::Deps,
+                RiskLevel::High,
+                false,
+                Utc::now() - Duration::days(30),
+            )],
+        };
+
+        let result = ApplyPlanService::new()
+            .verify(
+                &Config::default(),
+                ApplyPlanRequest {
+                    plan,
+                    no_verify: true,
+                    include_recent: false,
+                    force: false,
+                    force_protected: false,
+                    recent_days: 7,
+                },
+            )
+            .unwrap();
+        assert_eq!(result.verified_projects.len(), 0);
+        assert_eq!(result.verification_blocked.tracked_count, 1);
+        assert_eq!(result.skipped_projects[0].info.tracked_files, 1);
+        assert_eq!(
+            result.skipped_projects[0].skip_reason,
+            Some(SkipReason::TrackedByVcs)
+        );
+    }
+
     #[test]
     fn verify_respects_plan_params_max_risk_and_category() {
         let temp = TempDir::new().unwrap();
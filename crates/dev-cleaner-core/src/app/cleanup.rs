@@ -8,21 +8,24 @@ pub struct BlockedSummary {
     pub protected_bytes: u64,
     pub recent_count: usize,
     pub recent_bytes: u64,
+    pub tracked_count: usize,
+    pub tracked_bytes: u64,
 }
 
 impl BlockedSummary {
     pub fn is_empty(&self) -> bool {
-        self.in_use_count == 0 && self.protected_count == 0 && self.recent_count == 0
+        self.total_count() == 0
     }
 
     pub fn total_count(&self) -> usize {
-        self.in_use_count + self.protected_count + self.recent_count
+        self.in_use_count + self.protected_count + self.recent_count + self.tracked_count
     }
 
     pub fn total_bytes(&self) -> u64 {
         self.in_use_bytes
             .saturating_add(self.protected_bytes)
             .saturating_add(self.recent_bytes)
+            .saturating_add(self.tracked_bytes)
     }
 }
 
@@ -68,6 +71,28 @@ impl CleanupService {
         let mut blocked_summary = BlockedSummary::default();
 
         for mut project in projects {
+            // A running process outranks `--force`: deleting its files breaks it.
+            if let Some(hold) = project.info.in_use_by.clone() {
+                project.skip_reason = Some(SkipReason::HeldByProcess(hold));
+                blocked_summary.in_use_count += 1;
+                blocked_summary.in_use_bytes = blocked_summary
+                    .in_use_bytes
+                    .saturating_add(project.info.size);
+                blocked.push(project);
+                continue;
+            }
+
+            // Tracked content is protected by the repository itself.
+            if project.info.tracked_files > 0 && !request.force_protected {
+                project.skip_reason = Some(SkipReason::TrackedByVcs);
+                blocked_summary.tracked_count += 1;
+                blocked_summary.tracked_bytes = blocked_summary
+                    .tracked_bytes
+                    .saturating_add(project.info.size);
+                blocked.push(project);
+                continue;
+            }
+
             if project.is_protected() && !request.force_protected {
                 project.skip_reason = Some(SkipReason::Protected);
                 blocked_summary.protected_count += 1;
@@ -145,6 +170,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         })
     }
 
@@ -155,6 +190,7 @@ mod tests {
             protected: true,
             protected_by: Some("config_keep_paths".to_string()),
             recent: false,
+            recent_source: Default::default(),
         };
 
         let mut recent = sample_project();
@@ -177,4 +213,29 @@ mod tests {
             .iter()
             .any(|p| p.skip_reason == Some(SkipReason::Protected)));
     }
+
+    #[test]
+    fn split_blocks_tracked_targets_unless_forced() {
+        let mut tracked = sample_project();
+        tracked.info.tracked_files = 3;
+
+        let selection =
+            CleanupService::new().split(vec![tracked.clone()], CleanupRequest::default());
+        assert!(selection.selected.is_empty());
+        assert_eq!(selection.blocked_summary.tracked_count, 1);
+        assert_eq!(selection.blocked_summary.tracked_bytes, 99);
+        assert_eq!(
+            selection.blocked[0].skip_reason,
+            Some(SkipReason::TrackedByVcs)
+        );
+
+        let forced = CleanupService::new().split(
+            vec![tracked],
+            CleanupRequest {
+                force_protected: true,
+                ..CleanupRequest::default()
+            },
+        );
+        assert_eq!(forced.selected.len(), 1);
+    }
 }
@@ -1,11 +1,16 @@
+use crate::cancel::CancellationToken;
 use crate::config::{Config, ScanProfile};
 use crate::evaluation::{EvaluatedProject, SafetyFlags};
 use crate::policy::KeepPolicy;
-use crate::scanner::{Category, ProjectInfo, RiskLevel, Scanner};
+use crate::scanner::{
+    default_scan_cache_path, Category, DiagnosticKind, ProjectInfo, RiskLevel, ScanCache,
+    ScanDiagnostic, ScanDiagnostics, ScanObserver, Scanner,
+};
 use anyhow::{bail, Context, Result};
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VisibilityOptions {
@@ -77,14 +82,33 @@ pub struct ResolvedScanInput {
 pub struct DiscoveredProjects {
     pub resolved: ResolvedScanInput,
     pub projects: Vec<EvaluatedProject>,
+    /// What the scan could not look at; check `is_complete()` before trusting totals
+    pub diagnostics: ScanDiagnostics,
+    /// Stopped by the service's cancellation token; `projects` holds what finished
+    pub cancelled: bool,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
-pub struct ScanService;
+#[derive(Default, Clone)]
+pub struct ScanService {
+    observer: Option<Arc<dyn ScanObserver>>,
+    cancel: CancellationToken,
+}
 
 impl ScanService {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Report the progress of every scanner this service builds to `observer`
+    pub fn observer(mut self, observer: Arc<dyn ScanObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stop scans once `token` is cancelled; remaining roots are not scanned
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
     }
 
     pub fn resolve_inputs(
@@ -140,8 +164,14 @@ impl ScanService {
         let mut scanner = Scanner::new(root)
             .exclude_dirs(&config.exclude_dirs)
             .custom_patterns(&config.custom_patterns)
-            .max_risk(resolved.max_risk);
+            .max_risk(resolved.max_risk)
+            .one_file_system(config.one_file_system)
+            .skip_special_mounts(config.skip_special_mounts)
+            .cancellation(self.cancel.clone());
 
+        if let Some(observer) = &self.observer {
+            scanner = scanner.observer(Arc::clone(observer));
+        }
         if let Some(category) = resolved.category {
             scanner = scanner.category(category);
         }
@@ -154,6 +184,18 @@ impl ScanService {
         if let Some(days) = resolved.older_than_days {
             scanner = scanner.max_age_days(days);
         }
+        if let Some(days) = config.rust_stale_days {
+            scanner = scanner.rust_stale_days(days);
+        }
+        if config.scan_cache.enabled {
+            let path = config
+                .scan_cache
+                .path
+                .clone()
+                .unwrap_or_else(default_scan_cache_path);
+            let cache = ScanCache::open(path, config.scan_cache.refresh);
+            scanner = scanner.scan_cache(Arc::new(cache));
+        }
 
         scanner.respect_gitignore(resolved.respect_gitignore)
     }
@@ -163,22 +205,69 @@ impl ScanService {
         projects: Vec<ProjectInfo>,
         keep_policy: &KeepPolicy,
         recent_days: i64,
+    ) -> Vec<EvaluatedProject> {
+        let mut diagnostics = ScanDiagnostics::default();
+        self.evaluate_projects_reporting(projects, keep_policy, recent_days, &mut diagnostics)
+    }
+
+    /// [`Self::evaluate_projects`], adding unreadable `.dev-cleaner-keep-patterns`
+    /// files to `diagnostics`
+    pub fn evaluate_projects_reporting(
+        &self,
+        projects: Vec<ProjectInfo>,
+        keep_policy: &KeepPolicy,
+        recent_days: i64,
+        diagnostics: &mut ScanDiagnostics,
     ) -> Vec<EvaluatedProject> {
         projects
             .into_iter()
             .map(|info| {
-                let decision = keep_policy.evaluate(&info);
-                let safety = SafetyFlags {
-                    protected: decision.protected,
-                    protected_by: decision.reason,
-                    recent: info.days_since_modified() < recent_days,
-                };
-
-                EvaluatedProject::new(info).with_safety(safety)
+                self.evaluate_project_reporting(info, keep_policy, recent_days, diagnostics)
             })
             .collect()
     }
 
+    /// Evaluate one project as it streams in, adding an unreadable
+    /// `.dev-cleaner-keep-patterns` file to `diagnostics` once
+    pub fn evaluate_project_reporting(
+        &self,
+        info: ProjectInfo,
+        keep_policy: &KeepPolicy,
+        recent_days: i64,
+        diagnostics: &mut ScanDiagnostics,
+    ) -> EvaluatedProject {
+        let decision = keep_policy.evaluate(&info);
+        if let Some(error) = &decision.pattern_error {
+            let path = info.root.join(".dev-cleaner-keep-patterns");
+            let reported = diagnostics.entries.iter().any(|diagnostic| {
+                diagnostic.kind == DiagnosticKind::KeepPatternError
+                    && diagnostic.path.as_ref() == Some(&path)
+            });
+            if !reported {
+                diagnostics.push(ScanDiagnostic {
+                    kind: DiagnosticKind::KeepPatternError,
+                    path: Some(path),
+                    message: error.clone(),
+                });
+            }
+        }
+        let safety = SafetyFlags::assess(&info, decision, recent_days);
+        EvaluatedProject::new(info).with_safety(safety)
+    }
+
+    /// Problems in the config itself that affect a scan: invalid keep globs
+    pub fn config_diagnostics(&self, keep_policy: &KeepPolicy) -> ScanDiagnostics {
+        let mut diagnostics = ScanDiagnostics::default();
+        for (pattern, error) in keep_policy.invalid_patterns() {
+            diagnostics.push(ScanDiagnostic {
+                kind: DiagnosticKind::KeepPatternError,
+                path: None,
+                message: format!("invalid keep pattern `{pattern}`: {error}"),
+            });
+        }
+        diagnostics
+    }
+
     pub fn evaluate_projects_with_config(
         &self,
         config: &Config,
@@ -220,21 +309,32 @@ impl ScanService {
         let resolved = self.resolve_inputs(config, request)?;
         let keep_policy = KeepPolicy::from_config(config);
         let mut discovered = Vec::new();
+        let mut diagnostics = self.config_diagnostics(&keep_policy);
 
         for root in &resolved.roots {
+            if self.cancel.is_cancelled() {
+                break;
+            }
             let scanner = self.build_scanner(root, config, &resolved);
             let mut projects = scanner.scan()?;
             discovered.append(&mut projects);
+            diagnostics.merge(scanner.diagnostics());
         }
 
-        let mut evaluated =
-            self.evaluate_projects(discovered, &keep_policy, resolved.visibility.recent_days);
+        let mut evaluated = self.evaluate_projects_reporting(
+            discovered,
+            &keep_policy,
+            resolved.visibility.recent_days,
+            &mut diagnostics,
+        );
         evaluated = self.deduplicate_projects(evaluated);
         evaluated.sort_by(|a, b| b.info.size.cmp(&a.info.size));
 
         Ok(DiscoveredProjects {
             resolved,
             projects: evaluated,
+            diagnostics,
+            cancelled: self.cancel.is_cancelled(),
         })
     }
 
@@ -323,8 +423,15 @@ fn deduplicate_projects(mut projects: Vec<EvaluatedProject>) -> Vec<EvaluatedPro
     let mut kept_paths = HashSet::new();
     let mut deduplicated = Vec::new();
 
+    let mut kept_targets = HashSet::new();
+
     for project in projects {
-        if kept_paths.contains(&project.info.cleanable_dir) {
+        // A partial clean of a target is a separate candidate from the full target.
+        let target = (
+            project.info.cleanable_dir.clone(),
+            project.info.is_partial(),
+        );
+        if kept_targets.contains(&target) {
             continue;
         }
 
@@ -341,6 +448,7 @@ fn deduplicate_projects(mut projects: Vec<EvaluatedProject>) -> Vec<EvaluatedPro
 
         if !is_nested {
             kept_paths.insert(project.info.cleanable_dir.clone());
+            kept_targets.insert(target);
             deduplicated.push(project);
         }
     }
@@ -413,6 +521,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -604,6 +722,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keep_pattern_errors_are_reported_once_per_project() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("app");
+        let target = root.join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(root.join(".dev-cleaner-keep-patterns"), "[unclosed\n").unwrap();
+
+        let config = Config {
+            keep_globs: vec!["**/[bad".to_string()],
+            ..Config::default()
+        };
+        let service = ScanService::new();
+        let keep_policy = KeepPolicy::from_config(&config);
+        let mut diagnostics = service.config_diagnostics(&keep_policy);
+        let project = project_info(
+            root.clone(),
+            target,
+            ProjectType::Rust,
+            Category::Build,
+            RiskLevel::Medium,
+            false,
+            false,
+            Utc::now(),
+        );
+        let evaluated = service.evaluate_projects_reporting(
+            vec![project.clone(), project],
+            &keep_policy,
+            7,
+            &mut diagnostics,
+        );
+
+        assert!(evaluated.iter().all(EvaluatedProject::is_protected));
+        assert_eq!(diagnostics.count(DiagnosticKind::KeepPatternError), 2);
+        assert_eq!(
+            diagnostics.entries[1].path.as_deref(),
+            Some(root.join(".dev-cleaner-keep-patterns").as_path())
+        );
+        assert!(diagnostics.is_complete());
+    }
+
     #[test]
     fn evaluate_projects_marks_exact_recent_boundary_as_not_recent() {
         let service = ScanService::new();
@@ -636,7 +795,8 @@ mod tests {
         fs::write(project_root.join("package.json"), "{}").unwrap();
         fs::write(project_root.join(".dev-cleaner-keep"), "").unwrap();
 
-        let config = Config::default();
+        let mut config = Config::default();
+        config.scan_cache.path = Some(root.join("scan-cache.json"));
         let service = ScanService::new();
         let request = ScanRequest {
             path: Some(root.to_path_buf()),
@@ -672,6 +832,33 @@ mod tests {
         assert!(discovered.projects[0].safety.recent);
     }
 
+    #[test]
+    fn discover_stops_when_cancelled() {
+        let temp = TempDir::new().unwrap();
+        let project_root = temp.path().join("node-project");
+        fs::create_dir_all(project_root.join("node_modules")).unwrap();
+        fs::write(project_root.join("package.json"), "{}").unwrap();
+
+        let mut config = Config::default();
+        config.scan_cache.enabled = false;
+        let token = CancellationToken::new();
+        let service = ScanService::new().cancellation(token.clone());
+        let request = ScanRequest {
+            path: Some(temp.path().to_path_buf()),
+            max_risk: Some(RiskLevel::High),
+            ..ScanRequest::default()
+        };
+
+        let discovered = service.discover(&config, &request).unwrap();
+        assert!(!discovered.cancelled);
+        assert_eq!(discovered.projects.len(), 1);
+
+        token.cancel();
+        let discovered = service.discover(&config, &request).unwrap();
+        assert!(discovered.cancelled);
+        assert!(discovered.projects.is_empty());
+    }
+
     #[test]
     fn deduplicate_projects_removes_exact_path_duplicates() {
         let service = ScanService::new();
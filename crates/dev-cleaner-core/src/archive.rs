@@ -0,0 +1,745 @@
+//! Archive store: targets packed into zstd-compressed tarballs instead of deleted.
+//!
+//! Some targets are expensive to rebuild but rarely needed again, such as an old
+//! virtualenv or `DerivedData`. Archiving trades most of their space for a tarball that
+//! `undo --archive` unpacks back in place, with permissions and mtimes intact. Each
+//! batch lives in its own directory next to an append-only log, like the trash.
+
+use crate::cancel::{CancellationToken, Cancelled, CANCEL_POLL_ENTRIES};
+use crate::cleaner::{DeleteEngine, DeletionOptions};
+use crate::guard::check_path;
+use crate::permissions::make_tree_writable;
+use crate::trash::{
+    is_batch_id, new_batch_id, path_to_trash_relpath, remove_batch_dirs, save_log,
+    select_gc_batches, GcResult, RestoreResult,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const ARCHIVE_LOG_FILENAME: &str = "archive_log.jsonl";
+const ARCHIVE_EXTENSION: &str = "tar.zst";
+/// zstd's own default: fast enough to keep up with a disk, most of the gain of higher levels
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Archive size over original size assumed before any archive has been made
+pub const DEFAULT_COMPRESSION_RATIO: f64 = 0.35;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub batch_id: String,
+    pub created_at: DateTime<Utc>,
+    pub original_path: PathBuf,
+    pub archive_path: PathBuf,
+    /// Size of the target before it was packed
+    pub size_before: u64,
+    /// Size of the compressed tarball
+    pub size_after: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_version: Option<String>,
+    /// Target this entry was pruned from (partial clean); the target itself stays in place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pruned_from: Option<PathBuf>,
+}
+
+impl ArchiveEntry {
+    /// Space actually given back: the target minus the tarball that replaced it
+    pub fn bytes_saved(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+pub struct ArchiveStore {
+    pub batch_id: String,
+    root: PathBuf,
+    log_path: PathBuf,
+    /// Stops packing; the original stays in place until its tarball is complete
+    cancel: CancellationToken,
+    /// Make read-only directories writable before removing the packed original
+    repair_permissions: bool,
+}
+
+impl ArchiveStore {
+    pub fn new_default() -> Result<Self> {
+        Self::new_with_root(default_archive_root())
+    }
+
+    pub fn new_with_root(root: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create archive directory: {}", root.display()))?;
+
+        let batch_id = new_batch_id();
+        let batch_dir = root.join(&batch_id);
+        fs::create_dir_all(&batch_dir).with_context(|| {
+            format!(
+                "Failed to create archive batch directory: {}",
+                batch_dir.display()
+            )
+        })?;
+
+        let log_path = archive_log_path(&root);
+        Ok(Self {
+            batch_id,
+            root,
+            log_path,
+            cancel: CancellationToken::new(),
+            repair_permissions: false,
+        })
+    }
+
+    /// Stop packing once `token` is cancelled; the unfinished tarball is removed.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Add owner write permission to read-only directories the user owns before removing
+    /// a packed original (see [`crate::permissions`]).
+    pub fn repair_permissions(mut self, repair: bool) -> Self {
+        self.repair_permissions = repair;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn archive_dir(&self, original: &Path, size: u64) -> Result<ArchiveEntry> {
+        self.archive_path(original, size, None)
+    }
+
+    /// Pack one pruned sub-path of `target`.
+    pub fn archive_pruned(
+        &self,
+        original: &Path,
+        size: u64,
+        target: &Path,
+    ) -> Result<ArchiveEntry> {
+        self.archive_path(original, size, Some(target))
+    }
+
+    fn archive_path(
+        &self,
+        original: &Path,
+        size: u64,
+        pruned_from: Option<&Path>,
+    ) -> Result<ArchiveEntry> {
+        let metadata = fs::symlink_metadata(original)
+            .with_context(|| format!("Failed to stat {}", original.display()))?;
+        if metadata.file_type().is_symlink() {
+            anyhow::bail!("Refusing to archive symlink path: {}", original.display());
+        }
+
+        let archive_path = archive_path_for(&self.root, &self.batch_id, original);
+        if let Some(parent) = archive_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create archive destination directory: {}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        // The original is only removed once a complete tarball is in place.
+        let mut partial = archive_path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        if let Err(err) = pack(original, &partial, &self.cancel) {
+            let _ = fs::remove_file(&partial);
+            return Err(err).with_context(|| format!("Failed to archive {}", original.display()));
+        }
+        fs::rename(&partial, &archive_path).with_context(|| {
+            format!(
+                "Failed to move archive into place: {}",
+                archive_path.display()
+            )
+        })?;
+        let size_after = fs::metadata(&archive_path)
+            .with_context(|| format!("Failed to stat {}", archive_path.display()))?
+            .len();
+
+        let entry = ArchiveEntry {
+            batch_id: self.batch_id.clone(),
+            created_at: Utc::now(),
+            original_path: original.to_path_buf(),
+            archive_path,
+            size_before: size,
+            size_after,
+            tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            pruned_from: pruned_from.map(Path::to_path_buf),
+        };
+        append_archive_log(&self.log_path, &entry)?;
+
+        if metadata.is_dir() {
+            if self.repair_permissions {
+                make_tree_writable(original);
+            }
+            fs::remove_dir_all(original)
+        } else {
+            fs::remove_file(original)
+        }
+        .with_context(|| format!("Failed to remove archived original: {}", original.display()))?;
+
+        Ok(entry)
+    }
+}
+
+pub fn default_archive_root() -> PathBuf {
+    if let Ok(custom) = std::env::var("DEV_CLEANER_ARCHIVE_DIR") {
+        return PathBuf::from(custom);
+    }
+
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("dev-cleaner")
+        .join("archives")
+}
+
+fn archive_log_path(root: &Path) -> PathBuf {
+    root.join(ARCHIVE_LOG_FILENAME)
+}
+
+/// Where a batch keeps the tarball of `original`
+fn archive_path_for(root: &Path, batch_id: &str, original: &Path) -> PathBuf {
+    let mut path = root
+        .join(batch_id)
+        .join(path_to_trash_relpath(original))
+        .into_os_string();
+    path.push(".");
+    path.push(ARCHIVE_EXTENSION);
+    PathBuf::from(path)
+}
+
+fn append_archive_log(log_path: &Path, entry: &ArchiveEntry) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Failed to open archive log: {}", log_path.display()))?;
+
+    serde_json::to_writer(&mut file, entry)?;
+    writeln!(&mut file)?;
+    Ok(())
+}
+
+pub fn load_archive_log(log_path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let content = match fs::read_to_string(log_path) {
+        Ok(c) => c,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read {}", log_path.display()))
+        }
+    };
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_str::<ArchiveEntry>(line).ok())
+        .collect())
+}
+
+/// Write `src` as a tarball to `dst`, named by its file name so it unpacks as itself.
+///
+/// Symlinks are stored as symlinks, and sockets, fifos and devices are left out.
+fn pack(src: &Path, dst: &Path, cancel: &CancellationToken) -> Result<()> {
+    let name = src
+        .file_name()
+        .with_context(|| format!("Path has no file name: {}", src.display()))?;
+    let file =
+        fs::File::create(dst).with_context(|| format!("Failed to create {}", dst.display()))?;
+    let encoder = zstd::Encoder::new(BufWriter::new(file), COMPRESSION_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    builder.mode(tar::HeaderMode::Complete);
+
+    for (index, entry) in walkdir::WalkDir::new(src)
+        .follow_links(false)
+        .into_iter()
+        .enumerate()
+    {
+        if (index as u64).is_multiple_of(CANCEL_POLL_ENTRIES) && cancel.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        let entry =
+            entry.with_context(|| format!("Failed to read dir entry under {}", src.display()))?;
+        let file_type = entry.file_type();
+        if !(file_type.is_dir() || file_type.is_file() || file_type.is_symlink()) {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(src).with_context(|| {
+            format!(
+                "Failed to compute relative path for {}",
+                entry.path().display()
+            )
+        })?;
+        builder
+            .append_path_with_name(entry.path(), Path::new(name).join(rel))
+            .with_context(|| format!("Failed to pack {}", entry.path().display()))?;
+    }
+
+    let encoder = builder.into_inner()?;
+    let file = encoder
+        .finish()?
+        .into_inner()
+        .map_err(|err| err.into_error())?;
+    file.sync_all()
+        .with_context(|| format!("Failed to fsync {}", dst.display()))?;
+    Ok(())
+}
+
+/// Unpack the tarball at `archive` back to `original`.
+///
+/// Everything is unpacked into a staging directory next to `original` first and only
+/// renamed into place once complete, so a failure leaves nothing half-restored.
+fn unpack(archive: &Path, original: &Path) -> Result<()> {
+    let parent = original
+        .parent()
+        .with_context(|| format!("Path has no parent: {}", original.display()))?;
+    let name = original
+        .file_name()
+        .with_context(|| format!("Path has no file name: {}", original.display()))?;
+    fs::create_dir_all(parent).with_context(|| {
+        format!(
+            "Failed to create restore parent directory: {}",
+            parent.display()
+        )
+    })?;
+
+    let staging = parent.join(format!(".dev-cleaner-unpack-{}", new_batch_id()));
+    fs::create_dir(&staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+
+    let result = unpack_into(archive, &staging).and_then(|()| {
+        fs::rename(staging.join(name), original).with_context(|| {
+            format!(
+                "Failed to move unpacked archive into place: {}",
+                original.display()
+            )
+        })
+    });
+    make_tree_writable(&staging);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn unpack_into(archive: &Path, staging: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let decoder = zstd::Decoder::new(BufReader::new(file))?;
+    let mut tarball = tar::Archive::new(decoder);
+    tarball.set_preserve_permissions(true);
+    tarball.set_preserve_mtime(true);
+
+    // Directories come last, deepest first: creating an entry changes its parent's
+    // mtime, and a read-only directory would refuse its children.
+    let mut directories = Vec::new();
+    for entry in tarball
+        .entries()
+        .with_context(|| format!("Failed to read {}", archive.display()))?
+    {
+        let mut entry = entry.with_context(|| format!("Failed to read {}", archive.display()))?;
+        if entry.header().entry_type() == tar::EntryType::Directory {
+            directories.push(entry);
+        } else {
+            entry
+                .unpack_in(staging)
+                .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+        }
+    }
+    directories.sort_by(|a, b| b.path_bytes().cmp(&a.path_bytes()));
+    for mut dir in directories {
+        let path = staging.join(dir.path()?);
+        let mtime = dir.header().mtime().ok();
+        if !dir
+            .unpack_in(staging)
+            .with_context(|| format!("Failed to unpack {}", archive.display()))?
+        {
+            continue;
+        }
+        // tar restores permissions on directories but not their mtime.
+        if let Some(mtime) = mtime {
+            let mtime = filetime::FileTime::from_unix_time(mtime as i64, 0);
+            filetime::set_file_mtime(&path, mtime)
+                .with_context(|| format!("Failed to set mtime of {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveBatchSummary {
+    pub batch_id: String,
+    pub created_at: DateTime<Utc>,
+    pub entries_count: usize,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+pub fn list_archive_batches(root: &Path) -> Result<Vec<ArchiveBatchSummary>> {
+    let entries = load_archive_log(&archive_log_path(root))?;
+    Ok(summarize_batches(&entries))
+}
+
+pub fn archive_entries_for_batch(root: &Path, batch_id: &str) -> Result<Vec<ArchiveEntry>> {
+    let mut entries: Vec<ArchiveEntry> = load_archive_log(&archive_log_path(root))?
+        .into_iter()
+        .filter(|e| e.batch_id == batch_id)
+        .collect();
+    entries.sort_by_key(|e| e.original_path.clone());
+    Ok(entries)
+}
+
+pub fn latest_archive_batch_id(root: &Path) -> Result<Option<String>> {
+    let entries = load_archive_log(&archive_log_path(root))?;
+    Ok(entries
+        .into_iter()
+        .max_by_key(|e| e.created_at)
+        .map(|e| e.batch_id))
+}
+
+/// Archive size over original size across the store, once anything has been archived
+pub fn compression_ratio(root: &Path) -> Result<Option<f64>> {
+    let entries = load_archive_log(&archive_log_path(root))?;
+    let before = entries.iter().map(|e| e.size_before).sum::<u64>();
+    let after = entries.iter().map(|e| e.size_after).sum::<u64>();
+    if before == 0 {
+        return Ok(None);
+    }
+    Ok(Some(after as f64 / before as f64))
+}
+
+/// Unpack every entry of `batch_id` back in place and drop it from the store.
+pub fn restore_archive_batch(
+    root: &Path,
+    batch_id: &str,
+    dry_run: bool,
+    force: bool,
+) -> Result<RestoreResult> {
+    if !is_batch_id(batch_id) {
+        anyhow::bail!("Invalid archive batch id: {}", batch_id);
+    }
+
+    let log_path = archive_log_path(root);
+    let all_entries = load_archive_log(&log_path)?;
+    let mut entries: Vec<&ArchiveEntry> = all_entries
+        .iter()
+        .filter(|e| e.batch_id == batch_id)
+        .collect();
+
+    // Restore deeper paths first just in case.
+    entries.sort_by_key(|e| std::cmp::Reverse(e.original_path.components().count()));
+
+    if entries.is_empty() {
+        return Ok(RestoreResult {
+            restored_count: 0,
+            skipped_count: 0,
+            failed_count: 0,
+            errors: vec![format!("No entries found for batch_id `{}`", batch_id)],
+        });
+    }
+
+    let mut restored_count = 0;
+    let mut skipped_count = 0;
+    let mut failed_count = 0;
+    let mut errors = Vec::new();
+    let mut restored = Vec::new();
+
+    let batch_dir = root.join(batch_id);
+    for entry in entries {
+        if !entry.archive_path.exists() {
+            skipped_count += 1;
+            continue;
+        }
+
+        if let Err(violation) = check_path(&entry.archive_path, &batch_dir, None) {
+            skipped_count += 1;
+            errors.push(format!(
+                "Refusing to restore {}: {}",
+                entry.archive_path.display(),
+                violation
+            ));
+            continue;
+        }
+        let existing = fs::symlink_metadata(&entry.original_path).ok();
+        if existing
+            .as_ref()
+            .is_some_and(|m| m.file_type().is_symlink())
+        {
+            skipped_count += 1;
+            errors.push(format!(
+                "Refusing to restore over symlink path: {}",
+                entry.original_path.display()
+            ));
+            continue;
+        }
+
+        if existing.is_some() && !force {
+            skipped_count += 1;
+            errors.push(format!(
+                "Restore target already exists (use --force to override): {}",
+                entry.original_path.display()
+            ));
+            continue;
+        }
+
+        if dry_run {
+            restored_count += 1;
+            continue;
+        }
+
+        if let Some(existing) = existing {
+            // If forced, remove the existing target first.
+            if existing.is_dir() {
+                fs::remove_dir_all(&entry.original_path)
+            } else {
+                fs::remove_file(&entry.original_path)
+            }
+            .with_context(|| {
+                format!(
+                    "Failed to remove existing path: {}",
+                    entry.original_path.display()
+                )
+            })?;
+        }
+
+        match unpack(&entry.archive_path, &entry.original_path) {
+            Ok(()) => {
+                restored_count += 1;
+                let _ = fs::remove_file(&entry.archive_path);
+                restored.push(entry.archive_path.clone());
+            }
+            Err(err) => {
+                failed_count += 1;
+                errors.push(format!(
+                    "Failed to restore {}: {:#}",
+                    entry.original_path.display(),
+                    err
+                ));
+            }
+        }
+    }
+
+    if !restored.is_empty() {
+        let kept = all_entries
+            .iter()
+            .filter(|e| !(e.batch_id == batch_id && restored.contains(&e.archive_path)))
+            .cloned()
+            .collect::<Vec<_>>();
+        save_log(&log_path, &kept)?;
+    }
+
+    Ok(RestoreResult {
+        restored_count,
+        skipped_count,
+        failed_count,
+        errors,
+    })
+}
+
+/// Remove whole archive batches by age and total archive size, like `trash gc`.
+pub fn gc_archives(
+    root: &Path,
+    keep_days: Option<i64>,
+    keep_bytes: Option<u64>,
+    dry_run: bool,
+    deletion: &DeletionOptions,
+    cancel: CancellationToken,
+) -> Result<GcResult> {
+    let log_path = archive_log_path(root);
+    let entries = load_archive_log(&log_path)?;
+
+    let summaries = summarize_batches(&entries);
+    let usage = summaries
+        .iter()
+        .map(|s| (s.batch_id.clone(), s.created_at, s.size_after))
+        .collect::<Vec<_>>();
+    let total_bytes = summaries.iter().map(|s| s.size_after).sum::<u64>();
+    let selection = select_gc_batches(&usage, keep_days, keep_bytes);
+    let selected = summaries
+        .iter()
+        .filter(|s| selection.batch_ids.contains(&s.batch_id))
+        .collect::<Vec<_>>();
+
+    if dry_run {
+        return Ok(GcResult {
+            removed_batches: selected.len(),
+            removed_entries: selected.iter().map(|s| s.entries_count).sum(),
+            removed_bytes: selected.iter().map(|s| s.size_after).sum(),
+            remaining_bytes: selection.bytes_after,
+            target_keep_bytes: keep_bytes,
+            blocked_by_keep_days: selection.blocked_by_keep_days,
+            failed_batches: 0,
+            errors: Vec::new(),
+        });
+    }
+
+    let engine = DeleteEngine::new(deletion, cancel)?;
+    let (removed_ok_ids, failed_batches, errors) =
+        remove_batch_dirs(&engine, root, selected.iter().map(|s| s.batch_id.as_str()))?;
+
+    if !removed_ok_ids.is_empty() {
+        let kept_entries = entries
+            .into_iter()
+            .filter(|e| !removed_ok_ids.contains(&e.batch_id))
+            .collect::<Vec<_>>();
+        save_log(&log_path, &kept_entries)?;
+    }
+
+    let removed = selected
+        .iter()
+        .filter(|s| removed_ok_ids.contains(&s.batch_id))
+        .collect::<Vec<_>>();
+    let removed_bytes = removed.iter().map(|s| s.size_after).sum();
+
+    Ok(GcResult {
+        removed_batches: removed.len(),
+        removed_entries: removed.iter().map(|s| s.entries_count).sum(),
+        removed_bytes,
+        remaining_bytes: total_bytes.saturating_sub(removed_bytes),
+        target_keep_bytes: keep_bytes,
+        blocked_by_keep_days: selection.blocked_by_keep_days,
+        failed_batches,
+        errors,
+    })
+}
+
+fn summarize_batches(entries: &[ArchiveEntry]) -> Vec<ArchiveBatchSummary> {
+    let mut batches: HashMap<&str, ArchiveBatchSummary> = HashMap::new();
+    for entry in entries {
+        let summary = batches
+            .entry(&entry.batch_id)
+            .or_insert_with(|| ArchiveBatchSummary {
+                batch_id: entry.batch_id.clone(),
+                created_at: entry.created_at,
+                entries_count: 0,
+                size_before: 0,
+                size_after: 0,
+            });
+
+        summary.entries_count += 1;
+        summary.size_before += entry.size_before;
+        summary.size_after += entry.size_after;
+        summary.created_at = summary.created_at.min(entry.created_at);
+    }
+
+    let mut results = batches.into_values().collect::<Vec<_>>();
+    results.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn populate(dir: &Path) {
+        fs::create_dir_all(dir.join("lib/site-packages")).unwrap();
+        fs::write(dir.join("pyvenv.cfg"), "home = /usr/bin\n".repeat(64)).unwrap();
+        fs::write(dir.join("lib/site-packages/mod.py"), "x = 1\n".repeat(256)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn archive_and_restore_keeps_modes_mtimes_and_symlinks() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp = TempDir::new().unwrap();
+        let store = ArchiveStore::new_with_root(temp.path().join("archives")).unwrap();
+        let venv = temp.path().join("project/.venv");
+        populate(&venv);
+        let script = venv.join("lib/site-packages/run.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o751)).unwrap();
+        symlink("site-packages/mod.py", venv.join("lib/link.py")).unwrap();
+        let old = filetime::FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(&script, old).unwrap();
+        filetime::set_file_mtime(venv.join("lib"), old).unwrap();
+
+        let entry = store.archive_dir(&venv, 4096).unwrap();
+        assert!(!venv.exists());
+        assert!(entry.archive_path.exists());
+        assert_eq!(entry.size_before, 4096);
+        assert!(entry.size_after > 0 && entry.size_after < entry.size_before);
+
+        let result = restore_archive_batch(store.root(), &store.batch_id, false, false).unwrap();
+        assert_eq!(result.restored_count, 1, "{:?}", result.errors);
+        assert!(venv.join("pyvenv.cfg").exists());
+        let mode = fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o751);
+        let mtime = |path: &Path| {
+            filetime::FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+        };
+        assert_eq!(mtime(&script), old);
+        assert_eq!(mtime(&venv.join("lib")), old);
+        assert_eq!(
+            fs::read_link(venv.join("lib/link.py")).unwrap(),
+            Path::new("site-packages/mod.py")
+        );
+        assert!(!entry.archive_path.exists());
+        assert!(list_archive_batches(store.root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn cancelled_archive_keeps_the_original() {
+        let temp = TempDir::new().unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let store = ArchiveStore::new_with_root(temp.path().join("archives"))
+            .unwrap()
+            .cancellation(token);
+        let venv = temp.path().join("project/.venv");
+        populate(&venv);
+
+        let err = store.archive_dir(&venv, 1).unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert!(venv.join("pyvenv.cfg").exists());
+        assert!(list_archive_batches(store.root()).unwrap().is_empty());
+        let batch_dir = store.root().join(&store.batch_id);
+        assert_eq!(
+            walkdir::WalkDir::new(&batch_dir)
+                .into_iter()
+                .flatten()
+                .filter(|e| e.file_type().is_file())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn list_ratio_and_gc_use_archive_sizes() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("archives");
+        let store = ArchiveStore::new_with_root(root.clone()).unwrap();
+        for name in ["a", "b"] {
+            let dir = temp.path().join(name).join(".stack-work");
+            populate(&dir);
+            store.archive_dir(&dir, 10_000).unwrap();
+        }
+
+        let batches = list_archive_batches(&root).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].entries_count, 2);
+        assert_eq!(batches[0].size_before, 20_000);
+        let ratio = compression_ratio(&root).unwrap().unwrap();
+        assert!(ratio > 0.0 && ratio < 1.0);
+
+        let gc = gc_archives(
+            &root,
+            None,
+            Some(0),
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(gc.removed_batches, 1);
+        assert_eq!(gc.removed_bytes, batches[0].size_after);
+        assert!(!root.join(&store.batch_id).exists());
+        assert!(compression_ratio(&root).unwrap().is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::scanner::ProjectInfo;
 use anyhow::{Context, Result};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -26,6 +27,9 @@ pub enum AuditRecord {
         result: String,
         bytes: u64,
         reason: Option<String>,
+        /// Sub-paths removed when the item was a partial clean.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pruned_paths: Vec<String>,
         ts: String,
     },
     RunFinished {
@@ -49,6 +53,9 @@ pub struct AuditRunSummary {
     pub skipped: usize,
     pub failed: usize,
     pub freed_bytes: u64,
+    /// Tombstones of this run not reaped yet, filled in from the tombstone journal
+    pub pending_reap_count: usize,
+    pub pending_reap_bytes: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -109,6 +116,40 @@ impl AuditLogger {
             result: result.to_string(),
             bytes,
             reason,
+            pruned_paths: Vec::new(),
+            ts: Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Logs an item action for a scanned project, using its size as the byte
+    /// count. Partial cleans are recorded as `prune` with the sub-paths they touched.
+    pub fn log_project_item(
+        &self,
+        run_id: &str,
+        command: &str,
+        project: &ProjectInfo,
+        action: &str,
+        result: &str,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let action = if project.is_partial() && action == "remove" {
+            "prune"
+        } else {
+            action
+        };
+        self.append(&AuditRecord::ItemAction {
+            run_id: run_id.to_string(),
+            command: command.to_string(),
+            path: project.cleanable_dir.display().to_string(),
+            action: action.to_string(),
+            result: result.to_string(),
+            bytes: project.size,
+            reason,
+            pruned_paths: project
+                .prune_paths
+                .iter()
+                .map(|prune| prune.path.display().to_string())
+                .collect(),
             ts: Utc::now().to_rfc3339(),
         })
     }
@@ -208,6 +249,8 @@ impl AuditLogger {
                         skipped: 0,
                         failed: 0,
                         freed_bytes: 0,
+                        pending_reap_count: 0,
+                        pending_reap_bytes: 0,
                     });
                     summary.started_at = Some(ts);
                 }
@@ -229,6 +272,8 @@ impl AuditLogger {
                         skipped: 0,
                         failed: 0,
                         freed_bytes: 0,
+                        pending_reap_count: 0,
+                        pending_reap_bytes: 0,
                     });
                     summary.finished_at = Some(ts);
                     summary.cleaned = cleaned;
@@ -270,6 +315,7 @@ impl AuditLogger {
                     bytes,
                     reason,
                     ts,
+                    ..
                 } => {
                     out.push_str(&format!(
                         "item_action,{},{},{},{},{},{},{},{},,,,\n",
@@ -390,6 +436,46 @@ mod tests {
         assert_eq!(runs[0].run_id, run);
     }
 
+    #[test]
+    fn partial_clean_is_logged_as_prune_with_paths() {
+        let temp = TempDir::new().unwrap();
+        let logger = AuditLogger::new(temp.path().join("operations.jsonl"), true, 1024 * 1024);
+        let run = logger.start_run("clean").unwrap();
+        let mut project = ProjectInfo::new_pending(
+            PathBuf::from("/tmp/web"),
+            crate::ProjectType::NodeJs,
+            PathBuf::from("/tmp/web/node_modules"),
+            Utc::now(),
+            false,
+        );
+        project.size = 5;
+        project.prune_paths = vec![crate::scanner::PrunePath {
+            path: PathBuf::from("/tmp/web/node_modules/.cache"),
+            size: 5,
+            reason: String::new(),
+        }];
+        logger
+            .log_project_item(&run, "clean", &project, "remove", "attempted", None)
+            .unwrap();
+
+        let records = logger.records_for_run(&run).unwrap();
+        let AuditRecord::ItemAction {
+            action,
+            bytes,
+            pruned_paths,
+            ..
+        } = &records[1]
+        else {
+            panic!("expected item action");
+        };
+        assert_eq!(action, "prune");
+        assert_eq!(*bytes, 5);
+        assert_eq!(
+            pruned_paths,
+            &vec!["/tmp/web/node_modules/.cache".to_string()]
+        );
+    }
+
     #[test]
     fn disabled_logger_is_a_noop() {
         let temp = TempDir::new().unwrap();
@@ -466,6 +552,7 @@ mod tests {
                 result: "ok".to_string(),
                 bytes: 7,
                 reason: Some("needs,\"quote\"".to_string()),
+                pruned_paths: Vec::new(),
                 ts: "2026-01-01T00:00:01Z".to_string(),
             },
             AuditRecord::RunFinished {
@@ -0,0 +1,123 @@
+//! Cooperative cancellation for long-running scans and cleanups.
+//!
+//! A [`CancellationToken`] is cloned into every part of the work; any clone can
+//! cancel, and the work checks the token at points where stopping leaves a
+//! consistent result behind. Other triggers, such as a sentinel file, cancel the
+//! token rather than being polled by the work itself.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often a sentinel file is looked for
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Entries removed or copied between two looks at a token
+pub(crate) const CANCEL_POLL_ENTRIES: u64 = 64;
+
+/// Shared flag asking running work to stop early
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the work holding this token, or any clone of it, to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Cancel this token once `path` exists; watches until the returned guard drops
+    pub fn cancel_on_file(&self, path: PathBuf) -> FileTrigger {
+        let token = self.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) && !token.is_cancelled() {
+                if path.exists() {
+                    token.cancel();
+                    break;
+                }
+                thread::park_timeout(FILE_POLL_INTERVAL);
+            }
+        });
+        FileTrigger {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+/// Watches a sentinel file for [`CancellationToken::cancel_on_file`]
+#[derive(Debug)]
+pub struct FileTrigger {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for FileTrigger {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Error returned by work that stopped because its token was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(!CancellationToken::new().is_cancelled());
+    }
+
+    #[test]
+    fn sentinel_file_cancels_while_watched() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let sentinel = temp.path().join("cancel");
+
+        let token = CancellationToken::new();
+        drop(token.cancel_on_file(sentinel.clone()));
+        std::fs::write(&sentinel, "").unwrap();
+        thread::sleep(FILE_POLL_INTERVAL * 2);
+        assert!(!token.is_cancelled());
+
+        let _trigger = token.cancel_on_file(sentinel);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !token.is_cancelled() && std::time::Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(token.is_cancelled());
+    }
+}
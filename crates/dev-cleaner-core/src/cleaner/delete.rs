@@ -0,0 +1,553 @@
+//! Parallel, throttled removal of directory trees.
+//!
+//! A [`DeleteEngine`] owns a small thread pool. Each removal fans out over the
+//! entries of every directory, and several targets can be removed at once; the
+//! [`super::Cleaner`] still reports them to its observer one after another. All
+//! removals share one throttle, so a rate cap holds for the whole run.
+
+use super::{interruption, IncompleteClean, UnremovableEntry};
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::permissions::make_writable;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running removal reports the bytes it freed
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest single sleep of the throttle, so cancellation is noticed while paced
+const MAX_THROTTLE_SLEEP: Duration = Duration::from_millis(100);
+
+/// How deletions are run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionOptions {
+    /// Deletion threads; 0 uses one per CPU
+    #[serde(default)]
+    pub workers: usize,
+
+    /// Run deletion threads at idle I/O priority and the lowest CPU priority (Linux)
+    #[serde(default)]
+    pub low_priority: bool,
+
+    /// Cap on file bytes removed per second, summed over all threads
+    #[serde(default)]
+    pub max_bytes_per_sec: Option<u64>,
+
+    /// Cap on files and directories removed per second, summed over all threads
+    #[serde(default)]
+    pub max_ops_per_sec: Option<u64>,
+
+    /// Rename whole targets to tombstones and delete those later (see [`crate::tombstone`])
+    #[serde(default)]
+    pub tombstone: bool,
+
+    /// Add owner write permission to read-only directories the user owns before
+    /// removing or trashing them (see [`crate::permissions`])
+    #[serde(default)]
+    pub repair_permissions: bool,
+}
+
+impl DeletionOptions {
+    /// Worker count with 0 resolved to the number of CPUs
+    pub fn effective_workers(&self) -> usize {
+        if self.workers == 0 {
+            num_cpus::get().max(1)
+        } else {
+            self.workers
+        }
+    }
+}
+
+/// Removes directory trees on a dedicated thread pool
+pub(crate) struct DeleteEngine {
+    pool: rayon::ThreadPool,
+    shared: Arc<Shared>,
+}
+
+/// State every removal of one engine uses
+struct Shared {
+    throttle: Throttle,
+    cancel: CancellationToken,
+    repair_permissions: bool,
+}
+
+impl DeleteEngine {
+    pub(crate) fn new(options: &DeletionOptions, cancel: CancellationToken) -> Result<Self> {
+        let low_priority = options.low_priority;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(options.effective_workers())
+            .thread_name(|index| format!("dev-cleaner-delete-{index}"))
+            .start_handler(move |_| {
+                if low_priority {
+                    lower_thread_priority();
+                }
+            })
+            .build()
+            .context("Failed to start deletion threads")?;
+
+        Ok(Self {
+            pool,
+            shared: Arc::new(Shared {
+                throttle: Throttle::new(options.max_bytes_per_sec, options.max_ops_per_sec),
+                cancel,
+                repair_permissions: options.repair_permissions,
+            }),
+        })
+    }
+
+    /// Number of threads removing files
+    pub(crate) fn workers(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Start removing `path` in the background
+    pub(crate) fn spawn(&self, path: PathBuf) -> DeleteJob {
+        let freed = Arc::new(AtomicU64::new(0));
+        let removed = Arc::new(AtomicU64::new(0));
+        let (done_tx, done) = mpsc::channel();
+
+        let shared = Arc::clone(&self.shared);
+        let progress = Progress {
+            freed: Arc::clone(&freed),
+            removed: Arc::clone(&removed),
+            unremovable: Mutex::default(),
+        };
+        self.pool.spawn(move || {
+            let _ = done_tx.send(remove_tree(&path, &shared, &progress));
+        });
+
+        DeleteJob {
+            freed,
+            removed,
+            done,
+        }
+    }
+
+    /// Remove `path` and wait for it, reporting freed bytes along the way
+    pub(crate) fn remove(&self, path: &Path, on_progress: impl FnMut(u64)) -> Result<()> {
+        self.spawn(path.to_path_buf()).wait(path, on_progress)
+    }
+}
+
+/// A removal started by [`DeleteEngine::spawn`]
+pub(crate) struct DeleteJob {
+    freed: Arc<AtomicU64>,
+    removed: Arc<AtomicU64>,
+    done: Receiver<Result<()>>,
+}
+
+impl DeleteJob {
+    /// Wait for the removal of `path` to end, passing the bytes freed so far to
+    /// `on_progress` whenever they grew.
+    ///
+    /// A removal stopped by cancellation fails with [`Cancelled`] when nothing was
+    /// removed yet and with [`super::InterruptedClean`] otherwise. One that finished
+    /// but left entries behind fails with [`IncompleteClean`].
+    pub(crate) fn wait(self, path: &Path, mut on_progress: impl FnMut(u64)) -> Result<()> {
+        let mut reported = 0u64;
+        let result = loop {
+            match self.done.recv_timeout(PROGRESS_INTERVAL) {
+                Ok(result) => break result,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    break Err(anyhow::anyhow!(
+                        "Deletion thread stopped while removing {}",
+                        path.display()
+                    ))
+                }
+            }
+            let freed = self.freed.load(Ordering::Relaxed);
+            if freed > reported {
+                reported = freed;
+                on_progress(freed);
+            }
+        };
+
+        let freed = self.freed.load(Ordering::Relaxed);
+        if freed > reported {
+            on_progress(freed);
+        }
+        result.map_err(|err| {
+            if err.is::<Cancelled>() {
+                interruption(path, self.removed.load(Ordering::Relaxed) > 0, freed)
+            } else {
+                err
+            }
+        })
+    }
+}
+
+/// Counters of one removal, updated by every thread working on it
+struct Progress {
+    freed: Arc<AtomicU64>,
+    removed: Arc<AtomicU64>,
+    /// Entries that could not be removed; the rest of the tree is still removed
+    unremovable: Mutex<Vec<UnremovableEntry>>,
+}
+
+impl Progress {
+    fn unremovable(&self, path: &Path, error: &std::io::Error) {
+        self.unremovable.lock().unwrap().push(UnremovableEntry {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Remove `path` and everything below it; a symlink is removed itself, never followed
+fn remove_tree(path: &Path, shared: &Shared, progress: &Progress) -> Result<()> {
+    remove_tree_entries(path, shared, progress)?;
+
+    let unremovable = std::mem::take(&mut *progress.unremovable.lock().unwrap());
+    if unremovable.is_empty() {
+        return Ok(());
+    }
+    Err(IncompleteClean {
+        path: path.to_path_buf(),
+        bytes_freed: progress.freed.load(Ordering::Relaxed),
+        unremovable,
+    }
+    .into())
+}
+
+fn remove_tree_entries(path: &Path, shared: &Shared, progress: &Progress) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if metadata.is_symlink() {
+        return fs::remove_file(path)
+            .with_context(|| format!("Failed to remove directory: {}", path.display()));
+    }
+    if !metadata.is_dir() {
+        anyhow::bail!(
+            "Failed to remove directory: {}: not a directory",
+            path.display()
+        );
+    }
+
+    if remove_contents(path, shared, progress)? {
+        remove_entry(path, true, 0, shared, progress)?;
+    }
+    Ok(())
+}
+
+/// Remove everything inside `dir`; returns whether it is empty now.
+///
+/// Entries that cannot be removed are recorded in `progress` and skipped; only
+/// cancellation stops the removal.
+fn remove_contents(dir: &Path, shared: &Shared, progress: &Progress) -> Result<bool> {
+    if shared.cancel.is_cancelled() {
+        return Err(Cancelled.into());
+    }
+    if shared.repair_permissions {
+        let _ = make_writable(dir);
+    }
+    let entries = match fs::read_dir(dir).and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+    {
+        Ok(entries) => entries,
+        Err(err) => {
+            progress.unremovable(dir, &err);
+            return Ok(false);
+        }
+    };
+
+    entries
+        .par_iter()
+        .map(|entry| {
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    progress.unremovable(&path, &err);
+                    return Ok(false);
+                }
+            };
+            if file_type.is_dir() {
+                Ok(remove_contents(&path, shared, progress)?
+                    && remove_entry(&path, true, 0, shared, progress)?)
+            } else {
+                let len = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                remove_entry(&path, false, len, shared, progress)
+            }
+        })
+        .try_reduce(|| true, |left, right| Ok(left && right))
+}
+
+/// Remove one file or empty directory; returns whether it is gone
+fn remove_entry(
+    path: &Path,
+    is_dir: bool,
+    len: u64,
+    shared: &Shared,
+    progress: &Progress,
+) -> Result<bool> {
+    if shared.cancel.is_cancelled() {
+        return Err(Cancelled.into());
+    }
+    shared.throttle.pace(len, &shared.cancel);
+
+    let remove = || {
+        if is_dir {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        }
+    };
+    let mut removed = remove();
+    // Read-only files block removal where the entry's own attribute counts (Windows).
+    if shared.repair_permissions
+        && matches!(&removed, Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied)
+        && make_writable(path).unwrap_or(false)
+    {
+        removed = remove();
+    }
+    if let Err(err) = removed {
+        progress.unremovable(path, &err);
+        return Ok(false);
+    }
+
+    progress.removed.fetch_add(1, Ordering::Relaxed);
+    progress.freed.fetch_add(len, Ordering::Relaxed);
+    Ok(true)
+}
+
+/// Spaces removals out so neither cap is exceeded on average
+struct Throttle {
+    max_bytes_per_sec: Option<u64>,
+    max_ops_per_sec: Option<u64>,
+    /// Earliest time the next removal may start
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    fn new(max_bytes_per_sec: Option<u64>, max_ops_per_sec: Option<u64>) -> Self {
+        Self {
+            max_bytes_per_sec: max_bytes_per_sec.filter(|rate| *rate > 0),
+            max_ops_per_sec: max_ops_per_sec.filter(|rate| *rate > 0),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Time one removal of `bytes` takes up under the caps
+    fn cost(&self, bytes: u64) -> Duration {
+        let by_bytes = self
+            .max_bytes_per_sec
+            .map(|rate| Duration::from_secs_f64(bytes as f64 / rate as f64))
+            .unwrap_or_default();
+        let by_ops = self
+            .max_ops_per_sec
+            .map(|rate| Duration::from_secs_f64(1.0 / rate as f64))
+            .unwrap_or_default();
+        by_bytes.max(by_ops)
+    }
+
+    /// Wait for this removal's slot; returns early once `cancel` is cancelled
+    fn pace(&self, bytes: u64, cancel: &CancellationToken) {
+        let cost = self.cost(bytes);
+        if cost.is_zero() {
+            return;
+        }
+
+        let start = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let start = next_slot.map_or(now, |slot| slot.max(now));
+            *next_slot = Some(start + cost);
+            start
+        };
+        loop {
+            let wait = start.saturating_duration_since(Instant::now());
+            if wait.is_zero() || cancel.is_cancelled() {
+                return;
+            }
+            thread::sleep(wait.min(MAX_THROTTLE_SLEEP));
+        }
+    }
+}
+
+/// Put the calling thread in the idle I/O class and at the lowest CPU priority
+#[cfg(target_os = "linux")]
+fn lower_thread_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    // SAFETY: both calls take plain integers; `who` 0 means the calling thread.
+    // Failing leaves the thread at its normal priority, which is fine.
+    unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        );
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lower_thread_priority() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::InterruptedClean;
+    use tempfile::TempDir;
+
+    fn make_tree(root: &Path, dirs: usize, files_per_dir: usize) {
+        for dir in 0..dirs {
+            let dir = root.join(format!("d{dir}/nested"));
+            fs::create_dir_all(&dir).unwrap();
+            for file in 0..files_per_dir {
+                fs::write(dir.join(format!("f{file}")), "1234").unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn removes_tree_in_parallel_and_reports_bytes() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("node_modules");
+        make_tree(&target, 8, 25);
+        let outside = temp.path().join("keep");
+        fs::write(&outside, "x").unwrap();
+
+        let engine = DeleteEngine::new(
+            &DeletionOptions {
+                workers: 4,
+                ..DeletionOptions::default()
+            },
+            CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(engine.workers(), 4);
+
+        let mut reported = Vec::new();
+        engine
+            .remove(&target, |freed| reported.push(freed))
+            .unwrap();
+
+        assert!(!target.exists());
+        assert!(outside.exists());
+        assert_eq!(reported.last(), Some(&(8 * 25 * 4)));
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn symlinks_are_removed_not_followed() {
+        let temp = TempDir::new().unwrap();
+        let elsewhere = temp.path().join("elsewhere");
+        make_tree(&elsewhere, 1, 2);
+        let target = temp.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&elsewhere, target.join("link")).unwrap();
+
+        let engine =
+            DeleteEngine::new(&DeletionOptions::default(), CancellationToken::new()).unwrap();
+        engine.remove(&target, |_| {}).unwrap();
+
+        assert!(!target.exists());
+        assert!(elsewhere.join("d0/nested/f1").exists());
+    }
+
+    #[test]
+    fn ops_cap_paces_removal_and_cancel_interrupts_it() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        make_tree(&target, 1, 200);
+
+        let cancel = CancellationToken::new();
+        let engine = DeleteEngine::new(
+            &DeletionOptions {
+                workers: 2,
+                max_ops_per_sec: Some(100),
+                ..DeletionOptions::default()
+            },
+            cancel.clone(),
+        )
+        .unwrap();
+
+        let job = engine.spawn(target.clone());
+        thread::sleep(Duration::from_millis(300));
+        cancel.cancel();
+        let err = job.wait(&target, |_| {}).unwrap_err();
+
+        let partial = err.downcast_ref::<InterruptedClean>().unwrap();
+        assert_eq!(partial.path, target);
+        assert!(partial.bytes_freed > 0);
+        // About 30 removals fit in 300ms at 100 per second.
+        assert!(partial.bytes_freed < 100 * 4);
+        assert!(target.join("d0/nested").exists());
+    }
+
+    #[test]
+    fn cancelled_before_start_removes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        make_tree(&target, 2, 3);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let engine = DeleteEngine::new(&DeletionOptions::default(), cancel).unwrap();
+        let err = engine.remove(&target, |_| {}).unwrap_err();
+
+        assert!(err.is::<Cancelled>());
+        assert!(target.join("d1/nested/f2").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_dirs_are_left_behind_unless_repaired() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("mod");
+        make_tree(&target, 2, 2);
+        let locked = target.join("d0/nested");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root removes entries from read-only directories anyway.
+        // SAFETY: `geteuid` takes no arguments and cannot fail.
+        if unsafe { libc::geteuid() } != 0 {
+            let engine =
+                DeleteEngine::new(&DeletionOptions::default(), CancellationToken::new()).unwrap();
+            let err = engine.remove(&target, |_| {}).unwrap_err();
+            let incomplete = err.downcast_ref::<IncompleteClean>().unwrap();
+            assert_eq!(incomplete.path, target);
+            assert_eq!(incomplete.unremovable.len(), 2);
+            assert!(incomplete
+                .unremovable
+                .iter()
+                .all(|entry| entry.path.parent() == Some(locked.as_path())));
+            assert!(!target.join("d1").exists());
+            assert!(locked.join("f0").exists());
+        }
+
+        let engine = DeleteEngine::new(
+            &DeletionOptions {
+                repair_permissions: true,
+                ..DeletionOptions::default()
+            },
+            CancellationToken::new(),
+        )
+        .unwrap();
+        engine.remove(&target, |_| {}).unwrap();
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn throttle_cost_takes_the_stricter_cap() {
+        let throttle = Throttle::new(Some(1000), Some(10));
+        assert_eq!(throttle.cost(0), Duration::from_millis(100));
+        assert_eq!(throttle.cost(500), Duration::from_millis(500));
+        assert!(Throttle::new(None, Some(0)).cost(1 << 30).is_zero());
+    }
+}
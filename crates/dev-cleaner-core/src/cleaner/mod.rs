@@ -1,9 +1,25 @@
+mod delete;
+
+pub use delete::DeletionOptions;
+
+use crate::archive::{ArchiveEntry, ArchiveStore};
+use crate::cancel::{CancellationToken, Cancelled, FileTrigger};
+use crate::guard::{check_path, PathViolation};
+use crate::permissions::make_writable;
+use crate::policy::{ProcessHold, ProcessSnapshot};
+use crate::run_journal::RunJournal;
 use crate::scanner::ProjectInfo;
-use crate::trash::TrashManager;
+use crate::tombstone::TombstoneJournal;
+use crate::trash::{TrashBackend, TrashManager};
 use crate::utils::format_size;
 use anyhow::{Context, Result};
+pub(crate) use delete::{DeleteEngine, DeleteJob};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
 /// Options for cleaning operations
 #[derive(Debug, Clone)]
@@ -20,7 +36,7 @@ pub struct CleanOptions {
     /// Include recently modified targets
     pub include_recent: bool,
 
-    /// Allow deleting protected targets
+    /// Allow deleting protected targets, including ones holding git-tracked files
     pub force_protected: bool,
 
     /// Move directories to Dev Cleaner's trash (undoable) instead of deleting
@@ -29,8 +45,30 @@ pub struct CleanOptions {
     /// Explicit trash root for non-CLI callers that cannot use process defaults.
     pub trash_root: Option<PathBuf>,
 
-    /// Optional cancellation sentinel checked between projects.
+    /// Dev Cleaner's own trash or the desktop's, when `trash` is set.
+    pub trash_backend: TrashBackend,
+
+    /// Pack directories into compressed tarballs (undoable) instead of deleting;
+    /// takes precedence over `trash`
+    pub archive: bool,
+
+    /// Explicit archive store root for non-CLI callers that cannot use process defaults.
+    pub archive_root: Option<PathBuf>,
+
+    /// Optional cancellation sentinel; its appearance cancels `cancel`.
     pub cancel_file: Option<PathBuf>,
+
+    /// Stops the run between projects or partway through a removal.
+    pub cancel: CancellationToken,
+
+    /// Worker count, priority and rate caps for deletions.
+    pub deletion: DeletionOptions,
+
+    /// Explicit tombstone journal directory for `deletion.tombstone`.
+    pub tombstone_root: Option<PathBuf>,
+
+    /// Audit run this clean belongs to, recorded with its tombstones.
+    pub run_id: Option<String>,
 }
 
 impl Default for CleanOptions {
@@ -43,7 +81,14 @@ impl Default for CleanOptions {
             force_protected: false,
             trash: false,
             trash_root: None,
+            trash_backend: TrashBackend::default(),
+            archive: false,
+            archive_root: None,
             cancel_file: None,
+            cancel: CancellationToken::new(),
+            deletion: DeletionOptions::default(),
+            tombstone_root: None,
+            run_id: None,
         }
     }
 }
@@ -72,8 +117,30 @@ pub struct CleanResult {
     /// Trash batch id (when `trash=true`)
     pub trash_batch_id: Option<String>,
 
+    /// Archive batch id (when `archive=true`)
+    pub archive_batch_id: Option<String>,
+
+    /// Size of the tarballs written, when archiving; `bytes_freed` already has it taken off
+    pub archive_bytes: u64,
+
     /// Optional audit run id
     pub run_id: Option<String>,
+
+    /// Whether the run stopped early because it was cancelled
+    pub cancelled: bool,
+
+    /// Targets the run stopped removing partway through; their bytes are in `bytes_freed`
+    pub interrupted: Vec<InterruptedClean>,
+
+    /// Bytes of targets buried as tombstones, counted in `bytes_freed` but still on
+    /// disk until reaped
+    pub pending_reap_bytes: u64,
+
+    /// Entries left behind by failed targets, one per file or directory
+    pub unremovable: Vec<UnremovableEntry>,
+
+    /// Problems that did not stop a target being cleaned
+    pub warnings: Vec<String>,
 }
 
 impl CleanResult {
@@ -83,18 +150,102 @@ impl CleanResult {
     }
 }
 
+/// A target whose removal was cancelled after part of it was already gone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterruptedClean {
+    pub path: PathBuf,
+    /// Bytes removed before stopping
+    pub bytes_freed: u64,
+}
+
+impl fmt::Display for InterruptedClean {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cancelled partway through {} ({} removed)",
+            self.path.display(),
+            format_size(self.bytes_freed)
+        )
+    }
+}
+
+impl std::error::Error for InterruptedClean {}
+
+/// A file or directory a removal could not delete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnremovableEntry {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A target removed except for entries that could not be deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncompleteClean {
+    pub path: PathBuf,
+    /// Bytes removed around the entries left behind
+    pub bytes_freed: u64,
+    pub unremovable: Vec<UnremovableEntry>,
+}
+
+impl fmt::Display for IncompleteClean {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} could not be removed ({} removed)",
+            self.unremovable.len(),
+            if self.unremovable.len() == 1 {
+                "entry"
+            } else {
+                "entries"
+            },
+            format_size(self.bytes_freed)
+        )
+    }
+}
+
+impl std::error::Error for IncompleteClean {}
+
 /// The type of cleanup being performed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CleanAction {
     Delete,
     Trash,
+    /// Remove only the target's `prune_paths`, keeping the rest of it
+    Prune,
+    /// Pack the target into a compressed tarball in the archive store
+    Archive,
 }
 
 impl CleanAction {
+    /// Action taken for `project`; partial cleans moved to trash or archived stay
+    /// `Trash` or `Archive`.
+    pub fn for_project(project: &ProjectInfo, options: &CleanOptions) -> Self {
+        if options.archive {
+            Self::Archive
+        } else if options.trash {
+            Self::Trash
+        } else if project.is_partial() {
+            Self::Prune
+        } else {
+            Self::Delete
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Trash => "trash",
+            Self::Prune => "prune",
+            Self::Archive => "archive",
+        }
+    }
+
     pub fn dry_run_label(self) -> &'static str {
         match self {
             Self::Delete => "[DRY RUN] Would remove",
             Self::Trash => "[DRY RUN] Would move to trash",
+            Self::Prune => "[DRY RUN] Would prune",
+            Self::Archive => "[DRY RUN] Would archive",
         }
     }
 }
@@ -106,9 +257,17 @@ pub trait CleanObserver {
     fn on_skipped_in_use(&mut self, _project: &ProjectInfo) {}
     fn on_skipped_protected(&mut self, _project: &ProjectInfo) {}
     fn on_skipped_recent(&mut self, _project: &ProjectInfo) {}
+    fn on_skipped_tracked(&mut self, _project: &ProjectInfo) {}
+    fn on_skipped_held(&mut self, _project: &ProjectInfo, _hold: &ProcessHold) {}
+    /// The target changed on disk since it was scanned and was left alone
+    fn on_skipped_unsafe(&mut self, _project: &ProjectInfo, _violation: PathViolation) {}
     fn on_dry_run(&mut self, _project: &ProjectInfo, _action: CleanAction) {}
+    /// Bytes removed from `project` so far while it is being deleted or pruned.
+    fn on_bytes_freed(&mut self, _project: &ProjectInfo, _bytes_freed: u64) {}
     fn on_cleaned(&mut self, _project: &ProjectInfo, _size: u64) {}
     fn on_failed(&mut self, _project: &ProjectInfo, _error: &anyhow::Error) {}
+    /// Cancellation stopped the removal of `project` after `bytes_freed` bytes.
+    fn on_interrupted(&mut self, _project: &ProjectInfo, _bytes_freed: u64) {}
     fn on_cancelled(&mut self, _remaining_projects: usize) {}
     fn on_finish(&mut self, _result: &CleanResult) {}
 }
@@ -122,19 +281,21 @@ impl CleanObserver for NoopCleanObserver {}
 /// Main cleaner for removing project directories
 pub struct Cleaner {
     options: CleanOptions,
+    journal: Option<Arc<RunJournal>>,
 }
 
 impl Cleaner {
     /// Create a new cleaner with default options
     pub fn new() -> Self {
-        Self {
-            options: CleanOptions::default(),
-        }
+        Self::with_options(CleanOptions::default())
     }
 
     /// Create a cleaner with custom options
     pub fn with_options(options: CleanOptions) -> Self {
-        Self { options }
+        Self {
+            options,
+            journal: None,
+        }
     }
 
     /// Set dry run mode
@@ -179,12 +340,55 @@ impl Cleaner {
         self
     }
 
+    /// Set the trash backend.
+    pub fn trash_backend(mut self, trash_backend: TrashBackend) -> Self {
+        self.options.trash_backend = trash_backend;
+        self
+    }
+
+    /// Set archive mode
+    pub fn archive(mut self, archive: bool) -> Self {
+        self.options.archive = archive;
+        self
+    }
+
+    /// Set an explicit archive store root.
+    pub fn archive_root(mut self, archive_root: Option<PathBuf>) -> Self {
+        self.options.archive_root = archive_root;
+        self
+    }
+
     /// Set an optional cancellation sentinel path.
     pub fn cancel_file(mut self, cancel_file: Option<PathBuf>) -> Self {
         self.options.cancel_file = cancel_file;
         self
     }
 
+    /// Stop the run once `token` is cancelled.
+    ///
+    /// Cancelling between projects leaves the remaining ones untouched; cancelling
+    /// during a removal stops it and reports the target in [`CleanResult::interrupted`].
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.options.cancel = token;
+        self
+    }
+
+    /// Set how deletions are parallelized and throttled.
+    pub fn deletion(mut self, deletion: DeletionOptions) -> Self {
+        self.options.deletion = deletion;
+        self
+    }
+
+    /// Write what [`Self::clean_multiple`] is about to do to `journal` first.
+    ///
+    /// Targets are planned before any is touched and each is marked right before its
+    /// removal starts; a target whose mark cannot be written is not removed. The
+    /// caller finishes the journal once the run is logged. Dry runs write nothing.
+    pub fn journal(mut self, journal: Arc<RunJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
     /// Clean multiple projects with progress bar
     pub fn clean_multiple(&self, projects: &[ProjectInfo]) -> Result<CleanResult> {
         let mut observer = NoopCleanObserver;
@@ -192,6 +396,11 @@ impl Cleaner {
     }
 
     /// Clean multiple projects while reporting progress through an observer.
+    ///
+    /// Deletions run on a [`DeleteEngine`]: while one target is reported, up to the
+    /// engine's worker count of the following ones are already being removed.
+    /// Observer events still arrive in project order. Running processes are read once
+    /// when the run starts and every target is checked against that snapshot.
     pub fn clean_multiple_with_observer<O: CleanObserver>(
         &self,
         projects: &[ProjectInfo],
@@ -203,98 +412,300 @@ impl Cleaner {
 
         let total_size: u64 = projects.iter().map(|p| p.size).sum();
         observer.on_start(projects.len(), total_size);
-
-        let mut cleaned_count = 0;
-        let mut bytes_freed = 0u64;
-        let mut skipped_count = 0;
-        let mut bytes_skipped = 0u64;
-        let mut failed_count = 0;
-        let mut errors = Vec::new();
-
-        let mut trash_manager = None;
+        let _cancel_trigger = self.watch_cancel_file();
+        if let Some(journal) = self.journaled() {
+            journal.plan(projects)?;
+        }
+        let processes = ProcessSnapshot::capture();
+
+        let mut result = empty_clean_result();
+        let mut removers = Removers {
+            trash: None,
+            archive: None,
+            engine: self.build_delete_engine()?,
+            tombstones: self.tombstone_journal(),
+        };
+        let mut ahead = BTreeMap::new();
+        let mut next_ahead = 0;
+        // First project not cleaned, skipped or failed when the run is cancelled
+        let mut stopped_at = None;
 
         for (index, project) in projects.iter().enumerate() {
             if self.cancel_requested() {
-                observer.on_cancelled(projects.len().saturating_sub(index));
+                stopped_at = Some(index);
                 break;
             }
 
             observer.on_project(project);
 
-            if self.skip_blocked_project(project, observer, &mut skipped_count, &mut bytes_skipped)
-            {
-                continue;
+            let job = match ahead.remove(&index) {
+                Some(Ahead::Blocked(blocked)) => {
+                    record_skip(
+                        project,
+                        &blocked,
+                        observer,
+                        &mut result.skipped_count,
+                        &mut result.bytes_skipped,
+                    );
+                    continue;
+                }
+                Some(Ahead::Started(job)) => Some(job),
+                None => {
+                    if self.skip_blocked_project(
+                        project,
+                        &processes,
+                        observer,
+                        &mut result.skipped_count,
+                        &mut result.bytes_skipped,
+                    ) {
+                        continue;
+                    }
+                    match &removers.engine {
+                        Some(engine)
+                            if self.runs_on_engine(project)
+                                && self.journal_started(project).is_ok() =>
+                        {
+                            Some(engine.spawn(project.cleanable_dir.clone()))
+                        }
+                        _ => None,
+                    }
+                }
+            };
+
+            if let Some(engine) = &removers.engine {
+                next_ahead = next_ahead.max(index + 1);
+                self.start_ahead(projects, &processes, engine, &mut ahead, &mut next_ahead);
             }
 
             if self.options.trash
                 && !self.options.dry_run
                 && project.cleanable_dir.exists()
-                && trash_manager.is_none()
+                && removers.trash.is_none()
+            {
+                removers.trash = self.build_trash_manager()?;
+                if let (Some(journal), Some(manager)) = (self.journaled(), &removers.trash) {
+                    journal.trash_batch(manager.root(), &manager.batch_id)?;
+                }
+            }
+            if self.options.archive
+                && !self.options.dry_run
+                && project.cleanable_dir.exists()
+                && removers.archive.is_none()
             {
-                trash_manager = self.build_trash_manager()?;
+                removers.archive = self.build_archive_store()?;
             }
 
-            match self.clean_single_impl(project, trash_manager.as_ref(), observer) {
-                Ok(size) => {
-                    cleaned_count += 1;
-                    bytes_freed += size;
-                    if !self.options.dry_run {
-                        observer.on_cleaned(project, size);
-                    }
+            let outcome = match job {
+                Some(job) => job
+                    .wait(&project.cleanable_dir, |freed| {
+                        observer.on_bytes_freed(project, freed)
+                    })
+                    .map(|()| project.size),
+                None => self
+                    .journal_started(project)
+                    .and_then(|()| self.clean_single_impl(project, &removers, observer))
+                    .map(|removed| {
+                        if removed.buried {
+                            result.pending_reap_bytes += removed.bytes;
+                        }
+                        result.archive_bytes += removed.archive_bytes;
+                        removed.bytes
+                    }),
+            };
+
+            match self.record_outcome(project, outcome, &mut result, observer) {
+                Outcome::Done => {}
+                Outcome::Untouched => {
+                    stopped_at = Some(index);
+                    break;
                 }
-                Err(e) => {
-                    failed_count += 1;
-                    let error_msg =
-                        format!("Failed to clean {}: {}", project.cleanable_dir.display(), e);
-                    errors.push(error_msg.clone());
-                    observer.on_failed(project, &e);
+                Outcome::Interrupted => {
+                    stopped_at = Some(index + 1);
+                    break;
                 }
             }
         }
 
-        let result = CleanResult {
-            cleaned_count,
-            bytes_freed,
-            skipped_count,
-            bytes_skipped,
-            failed_count,
-            errors,
-            trash_batch_id: trash_manager.as_ref().map(|m| m.batch_id.clone()),
-            run_id: None,
-        };
+        if let Some(stopped_at) = stopped_at {
+            // Targets already being removed ahead of the reports finish or stop on
+            // their own; report what became of them before giving up on the rest.
+            let mut remaining = projects.len().saturating_sub(stopped_at);
+            for (index, ahead) in ahead {
+                let Ahead::Started(job) = ahead else {
+                    continue;
+                };
+                let project = &projects[index];
+                let outcome = job
+                    .wait(&project.cleanable_dir, |_| {})
+                    .map(|()| project.size);
+                if outcome.as_ref().is_err_and(|err| err.is::<Cancelled>()) {
+                    continue;
+                }
+                observer.on_project(project);
+                self.record_outcome(project, outcome, &mut result, observer);
+                remaining -= 1;
+            }
+            result.cancelled = true;
+            observer.on_cancelled(remaining);
+        }
+
+        result.trash_batch_id = removers.trash.as_ref().map(|m| m.batch_id.clone());
+        result.warnings = removers
+            .trash
+            .as_ref()
+            .map(TrashManager::take_warnings)
+            .unwrap_or_default();
+        result.archive_batch_id = removers.archive.as_ref().map(|s| s.batch_id.clone());
         observer.on_finish(&result);
         Ok(result)
     }
 
+    /// Start removing the targets after `next_ahead` until the engine has as many
+    /// removals in flight as it has workers
+    fn start_ahead(
+        &self,
+        projects: &[ProjectInfo],
+        processes: &ProcessSnapshot,
+        engine: &DeleteEngine,
+        ahead: &mut BTreeMap<usize, Ahead>,
+        next_ahead: &mut usize,
+    ) {
+        let in_flight = |ahead: &BTreeMap<usize, Ahead>| {
+            ahead
+                .values()
+                .filter(|ahead| matches!(ahead, Ahead::Started(_)))
+                .count()
+        };
+        while *next_ahead < projects.len() && in_flight(ahead) + 1 < engine.workers() {
+            if self.cancel_requested() {
+                return;
+            }
+            let index = *next_ahead;
+            let project = &projects[index];
+            *next_ahead += 1;
+            // Anything else is checked again and cleaned inline when reached.
+            if let Some(blocked) = self.blocked(project, processes) {
+                ahead.insert(index, Ahead::Blocked(blocked));
+            } else if self.runs_on_engine(project) && self.journal_started(project).is_ok() {
+                ahead.insert(
+                    index,
+                    Ahead::Started(engine.spawn(project.cleanable_dir.clone())),
+                );
+            }
+        }
+    }
+
+    /// Whether the whole target is deleted, as opposed to trashed, archived, pruned or
+    /// skipped
+    fn runs_on_engine(&self, project: &ProjectInfo) -> bool {
+        !self.options.dry_run
+            && !self.options.trash
+            && !self.options.archive
+            && !self.options.deletion.tombstone
+            && !project.is_partial()
+            && project.cleanable_dir.exists()
+    }
+
+    /// Why `project` must be left alone, if it must
+    fn blocked(&self, project: &ProjectInfo, processes: &ProcessSnapshot) -> Option<Blocked> {
+        // Checked first and never overridable: the path no longer names what was scanned.
+        if let Err(violation) = unsafe_path(project) {
+            return Some(Blocked::Unsafe(violation));
+        }
+
+        // Re-checked against the processes running when the clean started; not
+        // overridable, since deleting files a process is using breaks it.
+        if project.cleanable_dir.exists() {
+            if let Some(hold) = processes.holder_of_project(project) {
+                return Some(Blocked::Held(hold.clone()));
+            }
+        }
+
+        if project.tracked_files > 0 && !self.options.force_protected {
+            return Some(Blocked::Tracked);
+        }
+
+        if project.protected && !self.options.force_protected {
+            return Some(Blocked::Protected);
+        }
+
+        if project.recent && !self.options.include_recent {
+            return Some(Blocked::Recent);
+        }
+
+        if project.in_use && !self.options.force {
+            return Some(Blocked::InUse);
+        }
+
+        None
+    }
+
     fn skip_blocked_project(
         &self,
         project: &ProjectInfo,
+        processes: &ProcessSnapshot,
         observer: &mut dyn CleanObserver,
         skipped_count: &mut usize,
         bytes_skipped: &mut u64,
     ) -> bool {
-        if project.protected && !self.options.force_protected {
-            *skipped_count += 1;
-            *bytes_skipped = bytes_skipped.saturating_add(project.size);
-            observer.on_skipped_protected(project);
-            return true;
-        }
+        let Some(blocked) = self.blocked(project, processes) else {
+            return false;
+        };
+        record_skip(project, &blocked, observer, skipped_count, bytes_skipped);
+        true
+    }
 
-        if project.recent && !self.options.include_recent {
-            *skipped_count += 1;
-            *bytes_skipped = bytes_skipped.saturating_add(project.size);
-            observer.on_skipped_recent(project);
-            return true;
+    /// Add one target's outcome to `result` and report it
+    fn record_outcome(
+        &self,
+        project: &ProjectInfo,
+        outcome: Result<u64>,
+        result: &mut CleanResult,
+        observer: &mut dyn CleanObserver,
+    ) -> Outcome {
+        if let Some(journal) = self.journaled() {
+            // Only the mark before a removal has to be durable; a lost outcome is
+            // worked out from the file system again.
+            let _ = match &outcome {
+                Ok(size) => journal.cleaned(&project.cleanable_dir, *size),
+                Err(e) if e.is::<Cancelled>() || e.is::<InterruptedClean>() => Ok(()),
+                Err(e) => journal.failed(&project.cleanable_dir, e),
+            };
         }
 
-        if project.in_use && !self.options.force {
-            *skipped_count += 1;
-            *bytes_skipped = bytes_skipped.saturating_add(project.size);
-            observer.on_skipped_in_use(project);
-            return true;
+        match outcome {
+            Ok(size) => {
+                result.cleaned_count += 1;
+                result.bytes_freed += size;
+                if !self.options.dry_run {
+                    observer.on_cleaned(project, size);
+                }
+                Outcome::Done
+            }
+            Err(e) if e.is::<Cancelled>() => Outcome::Untouched,
+            Err(e) => {
+                if let Some(partial) = e.downcast_ref::<InterruptedClean>() {
+                    result.bytes_freed += partial.bytes_freed;
+                    observer.on_interrupted(project, partial.bytes_freed);
+                    result.interrupted.push(partial.clone());
+                    return Outcome::Interrupted;
+                }
+                result.failed_count += 1;
+                result.errors.push(format!(
+                    "Failed to clean {}: {}",
+                    project.cleanable_dir.display(),
+                    e
+                ));
+                if let Some(incomplete) = e.downcast_ref::<IncompleteClean>() {
+                    result.bytes_freed += incomplete.bytes_freed;
+                    result
+                        .unremovable
+                        .extend(incomplete.unremovable.iter().cloned());
+                }
+                observer.on_failed(project, &e);
+                Outcome::Done
+            }
         }
-
-        false
     }
 
     fn blocked_single_result(
@@ -304,16 +715,17 @@ impl Cleaner {
     ) -> Option<u64> {
         let mut skipped_count = 0usize;
         let mut bytes_skipped = 0u64;
-        if self.skip_blocked_project(project, observer, &mut skipped_count, &mut bytes_skipped) {
+        if self.skip_blocked_project(
+            project,
+            &ProcessSnapshot::capture(),
+            observer,
+            &mut skipped_count,
+            &mut bytes_skipped,
+        ) {
             let result = CleanResult {
-                cleaned_count: 0,
-                bytes_freed: 0,
                 skipped_count,
                 bytes_skipped,
-                failed_count: 0,
-                errors: Vec::new(),
-                trash_batch_id: None,
-                run_id: None,
+                ..empty_clean_result()
             };
             observer.on_finish(&result);
             return Some(0);
@@ -336,19 +748,44 @@ impl Cleaner {
         let existed = project.cleanable_dir.exists();
         observer.on_start(1, project.size);
         observer.on_project(project);
+        let _cancel_trigger = self.watch_cancel_file();
 
         if let Some(size) = self.blocked_single_result(project, observer) {
             return Ok(size);
         }
 
-        let trash_manager = if self.options.trash && !self.options.dry_run && existed {
-            self.build_trash_manager()?
+        let removers = if existed {
+            Removers {
+                trash: if self.options.trash && !self.options.dry_run {
+                    self.build_trash_manager()?
+                } else {
+                    None
+                },
+                archive: self.build_archive_store()?,
+                engine: self.build_delete_engine()?,
+                tombstones: self.tombstone_journal(),
+            }
         } else {
-            None
+            Removers::default()
         };
+        let trash_batch_id = removers
+            .trash
+            .as_ref()
+            .map(|manager| manager.batch_id.clone());
+        let archive_batch_id = removers
+            .archive
+            .as_ref()
+            .map(|store| store.batch_id.clone());
 
-        match self.clean_single_impl(project, trash_manager.as_ref(), observer) {
-            Ok(size) => {
+        let outcome = self.clean_single_impl(project, &removers, observer);
+        let warnings = removers
+            .trash
+            .as_ref()
+            .map(TrashManager::take_warnings)
+            .unwrap_or_default();
+        match outcome {
+            Ok(removed) => {
+                let size = removed.bytes;
                 if !self.options.dry_run && existed {
                     observer.on_cleaned(project, size);
                 }
@@ -356,36 +793,45 @@ impl Cleaner {
                 let result = CleanResult {
                     cleaned_count: usize::from(existed),
                     bytes_freed: if existed { size } else { 0 },
-                    skipped_count: 0,
-                    bytes_skipped: 0,
-                    failed_count: 0,
-                    errors: Vec::new(),
-                    trash_batch_id: trash_manager
-                        .as_ref()
-                        .map(|manager| manager.batch_id.clone()),
-                    run_id: None,
+                    trash_batch_id,
+                    archive_batch_id,
+                    archive_bytes: removed.archive_bytes,
+                    pending_reap_bytes: if removed.buried { size } else { 0 },
+                    warnings,
+                    ..empty_clean_result()
                 };
                 observer.on_finish(&result);
                 Ok(size)
             }
             Err(error) => {
-                observer.on_failed(project, &error);
-                let result = CleanResult {
-                    cleaned_count: 0,
-                    bytes_freed: 0,
-                    skipped_count: 0,
-                    bytes_skipped: 0,
-                    failed_count: 1,
-                    errors: vec![format!(
+                let mut result = CleanResult {
+                    trash_batch_id,
+                    archive_batch_id,
+                    warnings,
+                    ..empty_clean_result()
+                };
+                if error.is::<Cancelled>() {
+                    result.cancelled = true;
+                    observer.on_cancelled(1);
+                } else if let Some(partial) = error.downcast_ref::<InterruptedClean>() {
+                    result.cancelled = true;
+                    result.bytes_freed = partial.bytes_freed;
+                    result.interrupted.push(partial.clone());
+                    observer.on_interrupted(project, partial.bytes_freed);
+                    observer.on_cancelled(0);
+                } else {
+                    observer.on_failed(project, &error);
+                    result.failed_count = 1;
+                    result.errors.push(format!(
                         "Failed to clean {}: {}",
                         project.cleanable_dir.display(),
                         error
-                    )],
-                    trash_batch_id: trash_manager
-                        .as_ref()
-                        .map(|manager| manager.batch_id.clone()),
-                    run_id: None,
-                };
+                    ));
+                    if let Some(incomplete) = error.downcast_ref::<IncompleteClean>() {
+                        result.bytes_freed = incomplete.bytes_freed;
+                        result.unremovable = incomplete.unremovable.clone();
+                    }
+                }
                 observer.on_finish(&result);
                 Err(error)
             }
@@ -395,38 +841,179 @@ impl Cleaner {
     fn clean_single_impl(
         &self,
         project: &ProjectInfo,
-        trash_manager: Option<&TrashManager>,
+        removers: &Removers,
         observer: &mut dyn CleanObserver,
-    ) -> Result<u64> {
+    ) -> Result<Removed> {
         let path = &project.cleanable_dir;
 
         if !path.exists() {
-            return Ok(0);
+            return Ok(Removed::deleted(0));
         }
 
         let size = project.size;
 
         if self.options.dry_run {
-            observer.on_dry_run(
-                project,
-                if self.options.trash {
-                    CleanAction::Trash
-                } else {
-                    CleanAction::Delete
-                },
-            );
-            return Ok(size);
+            observer.on_dry_run(project, CleanAction::for_project(project, &self.options));
+            return Ok(Removed::deleted(size));
+        }
+
+        if project.is_partial() {
+            return self.prune_paths_impl(project, removers, observer);
+        }
+
+        if self.options.archive {
+            let store = removers
+                .archive
+                .as_ref()
+                .context("Archive store not initialized")?;
+            let entry = store.archive_dir(path, size)?;
+            return Ok(Removed::archived(&entry));
         }
 
         if self.options.trash {
-            let manager = trash_manager.context("Trash manager not initialized")?;
+            let manager = removers
+                .trash
+                .as_ref()
+                .context("Trash manager not initialized")?;
             manager.trash_dir(path, size)?;
-        } else {
-            remove_dir_all(path)
-                .with_context(|| format!("Failed to remove directory: {}", path.display()))?;
+            return Ok(Removed::deleted(size));
+        }
+
+        if let Some(journal) = &removers.tombstones {
+            // A target that cannot be renamed, e.g. a mount point, is deleted in place.
+            if journal
+                .bury(path, size, self.options.run_id.as_deref())
+                .is_ok()
+            {
+                return Ok(Removed {
+                    bytes: size,
+                    buried: true,
+                    archive_bytes: 0,
+                });
+            }
+        }
+
+        let engine = removers
+            .engine
+            .as_ref()
+            .context("Deletion engine not initialized")?;
+        engine.remove(path, |freed| observer.on_bytes_freed(project, freed))?;
+        Ok(Removed::deleted(size))
+    }
+
+    /// Remove only the project's prune paths, leaving the rest of the target in place.
+    fn prune_paths_impl(
+        &self,
+        project: &ProjectInfo,
+        removers: &Removers,
+        observer: &mut dyn CleanObserver,
+    ) -> Result<Removed> {
+        // Validate every path up front so a bad plan never leaves a half-pruned target.
+        for prune in &project.prune_paths {
+            let inside = prune.path != project.cleanable_dir
+                && prune.path.starts_with(&project.cleanable_dir)
+                && !prune
+                    .path
+                    .components()
+                    .any(|component| matches!(component, Component::ParentDir));
+            if !inside {
+                anyhow::bail!(
+                    "Refusing to prune path outside {}: {}",
+                    project.cleanable_dir.display(),
+                    prune.path.display()
+                );
+            }
+        }
+
+        let mut freed = 0u64;
+        let mut archive_bytes = 0u64;
+        let mut removed_any = false;
+        for prune in &project.prune_paths {
+            let path = &prune.path;
+            let Ok(metadata) = fs::symlink_metadata(path) else {
+                // Already gone (e.g. removed by a newer build).
+                continue;
+            };
+            if self.cancel_requested() {
+                return Err(interruption(&project.cleanable_dir, removed_any, freed));
+            }
+
+            if self.options.deletion.repair_permissions {
+                // The directory holding a prune path is part of the target; the one
+                // holding the target itself is not, and is never changed.
+                if let Some(parent) = path
+                    .parent()
+                    .filter(|parent| parent.starts_with(&project.cleanable_dir))
+                {
+                    let _ = make_writable(parent);
+                }
+            }
+
+            // What the prune path gives back: all of it, unless a tarball replaces it
+            let mut saved = prune.size;
+            let removed = if self.options.archive {
+                let store = removers
+                    .archive
+                    .as_ref()
+                    .context("Archive store not initialized")?;
+                store
+                    .archive_pruned(path, prune.size, &project.cleanable_dir)
+                    .map(|entry| {
+                        saved = entry.bytes_saved();
+                        archive_bytes += entry.size_after;
+                    })
+            } else if self.options.trash {
+                let manager = removers
+                    .trash
+                    .as_ref()
+                    .context("Trash manager not initialized")?;
+                manager
+                    .trash_pruned(path, prune.size, &project.cleanable_dir)
+                    .map(|_| ())
+            } else if metadata.is_dir() {
+                removers
+                    .engine
+                    .as_ref()
+                    .context("Deletion engine not initialized")
+                    .and_then(|engine| {
+                        engine.remove(path, |bytes| {
+                            observer.on_bytes_freed(project, freed.saturating_add(bytes))
+                        })
+                    })
+            } else {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove file: {}", path.display()))
+            };
+            if let Err(err) = removed {
+                // Prune paths removed before this one count towards the interruption.
+                return Err(match err.downcast::<InterruptedClean>() {
+                    Ok(partial) => {
+                        interruption(&project.cleanable_dir, true, freed + partial.bytes_freed)
+                    }
+                    Err(err) if err.is::<Cancelled>() => {
+                        interruption(&project.cleanable_dir, removed_any, freed)
+                    }
+                    Err(err) => match err.downcast::<IncompleteClean>() {
+                        Ok(incomplete) => IncompleteClean {
+                            path: project.cleanable_dir.clone(),
+                            bytes_freed: freed + incomplete.bytes_freed,
+                            unremovable: incomplete.unremovable,
+                        }
+                        .into(),
+                        Err(err) => err,
+                    },
+                });
+            }
+            removed_any = true;
+            freed = freed.saturating_add(saved);
+            observer.on_bytes_freed(project, freed);
         }
 
-        Ok(size)
+        Ok(Removed {
+            bytes: freed,
+            buried: false,
+            archive_bytes,
+        })
     }
 
     fn build_trash_manager(&self) -> Result<Option<TrashManager>> {
@@ -439,31 +1026,193 @@ impl Cleaner {
             None => TrashManager::new_default()?,
         };
 
-        Ok(Some(manager))
+        Ok(Some(
+            manager
+                .cancellation(self.options.cancel.clone())
+                .repair_permissions(self.options.deletion.repair_permissions)
+                .backend(self.options.trash_backend),
+        ))
+    }
+
+    fn build_archive_store(&self) -> Result<Option<ArchiveStore>> {
+        if !self.options.archive || self.options.dry_run {
+            return Ok(None);
+        }
+
+        let store = match &self.options.archive_root {
+            Some(root) => ArchiveStore::new_with_root(root.clone())?,
+            None => ArchiveStore::new_default()?,
+        };
+
+        Ok(Some(
+            store
+                .cancellation(self.options.cancel.clone())
+                .repair_permissions(self.options.deletion.repair_permissions),
+        ))
+    }
+
+    fn build_delete_engine(&self) -> Result<Option<DeleteEngine>> {
+        if self.options.trash || self.options.archive || self.options.dry_run {
+            return Ok(None);
+        }
+
+        DeleteEngine::new(&self.options.deletion, self.options.cancel.clone()).map(Some)
+    }
+
+    fn tombstone_journal(&self) -> Option<TombstoneJournal> {
+        if !self.options.deletion.tombstone
+            || self.options.trash
+            || self.options.archive
+            || self.options.dry_run
+        {
+            return None;
+        }
+
+        Some(match &self.options.tombstone_root {
+            Some(root) => TombstoneJournal::new(root.clone()),
+            None => TombstoneJournal::new_default(),
+        })
+    }
+
+    fn journaled(&self) -> Option<&RunJournal> {
+        self.journal.as_deref().filter(|_| !self.options.dry_run)
+    }
+
+    /// Mark `project` as being removed, before anything of it is touched
+    fn journal_started(&self, project: &ProjectInfo) -> Result<()> {
+        match self.journaled() {
+            Some(journal) => journal.started(&project.cleanable_dir),
+            None => Ok(()),
+        }
     }
 
     fn cancel_requested(&self) -> bool {
-        self.options
+        if self.options.cancel.is_cancelled() {
+            return true;
+        }
+        let file_seen = self
+            .options
             .cancel_file
             .as_ref()
-            .is_some_and(|path| path.exists())
+            .is_some_and(|path| path.exists());
+        if file_seen {
+            self.options.cancel.cancel();
+        }
+        file_seen
+    }
+
+    /// Keep watching the cancel file while a removal runs
+    fn watch_cancel_file(&self) -> Option<FileTrigger> {
+        self.options
+            .cancel_file
+            .clone()
+            .map(|path| self.options.cancel.cancel_on_file(path))
     }
 }
 
-/// Safely remove a directory and all its contents
-fn remove_dir_all(path: &Path) -> Result<()> {
-    if !path.exists() {
-        return Ok(());
+/// What a run removes targets with; each is only set up when the options need it
+#[derive(Default)]
+struct Removers {
+    trash: Option<TrashManager>,
+    archive: Option<ArchiveStore>,
+    engine: Option<DeleteEngine>,
+    tombstones: Option<TombstoneJournal>,
+}
+
+/// What cleaning one target did
+struct Removed {
+    bytes: u64,
+    /// Renamed to a tombstone; the bytes are freed once it is reaped
+    buried: bool,
+    /// Size of the tarballs written; already taken off `bytes`
+    archive_bytes: u64,
+}
+
+impl Removed {
+    fn deleted(bytes: u64) -> Self {
+        Self {
+            bytes,
+            buried: false,
+            archive_bytes: 0,
+        }
+    }
+
+    fn archived(entry: &ArchiveEntry) -> Self {
+        Self {
+            bytes: entry.bytes_saved(),
+            buried: false,
+            archive_bytes: entry.size_after,
+        }
     }
+}
+
+/// Why a target is left alone
+enum Blocked {
+    Unsafe(PathViolation),
+    Held(ProcessHold),
+    Tracked,
+    Protected,
+    Recent,
+    InUse,
+}
+
+/// A target looked at before its turn to be reported
+enum Ahead {
+    Blocked(Blocked),
+    Started(DeleteJob),
+}
+
+/// How one target's clean ended, as far as the rest of the run is concerned
+enum Outcome {
+    /// Cleaned, or failed without stopping the run
+    Done,
+    /// Cancelled before anything was removed
+    Untouched,
+    /// Cancelled partway through
+    Interrupted,
+}
 
-    // Use fs::remove_dir_all for simplicity
-    // Could be replaced with a more robust implementation if needed
-    fs::remove_dir_all(path)
-        .with_context(|| format!("Failed to remove directory: {}", path.display()))?;
+fn record_skip(
+    project: &ProjectInfo,
+    blocked: &Blocked,
+    observer: &mut dyn CleanObserver,
+    skipped_count: &mut usize,
+    bytes_skipped: &mut u64,
+) {
+    *skipped_count += 1;
+    *bytes_skipped = bytes_skipped.saturating_add(project.size);
+    match blocked {
+        Blocked::Unsafe(violation) => observer.on_skipped_unsafe(project, *violation),
+        Blocked::Held(hold) => observer.on_skipped_held(project, hold),
+        Blocked::Tracked => observer.on_skipped_tracked(project),
+        Blocked::Protected => observer.on_skipped_protected(project),
+        Blocked::Recent => observer.on_skipped_recent(project),
+        Blocked::InUse => observer.on_skipped_in_use(project),
+    }
+}
 
+/// Whether `project`'s target, or any sub-path it prunes, changed since it was scanned
+fn unsafe_path(project: &ProjectInfo) -> Result<(), PathViolation> {
+    check_path(&project.cleanable_dir, &project.root, project.identity())?;
+    for prune in &project.prune_paths {
+        check_path(&prune.path, &project.cleanable_dir, None)?;
+    }
     Ok(())
 }
 
+/// Error for a removal of `path` stopped by cancellation
+fn interruption(path: &Path, removed_any: bool, bytes_freed: u64) -> anyhow::Error {
+    if removed_any {
+        InterruptedClean {
+            path: path.to_path_buf(),
+            bytes_freed,
+        }
+        .into()
+    } else {
+        Cancelled.into()
+    }
+}
+
 fn empty_clean_result() -> CleanResult {
     CleanResult {
         cleaned_count: 0,
@@ -473,7 +1222,14 @@ fn empty_clean_result() -> CleanResult {
         failed_count: 0,
         errors: Vec::new(),
         trash_batch_id: None,
+        archive_batch_id: None,
+        archive_bytes: 0,
         run_id: None,
+        cancelled: false,
+        interrupted: Vec::new(),
+        pending_reap_bytes: 0,
+        unremovable: Vec::new(),
+        warnings: Vec::new(),
     }
 }
 
@@ -527,6 +1283,22 @@ mod tests {
                 .push(format!("skip_recent:{}", project.cleanable_dir.display()));
         }
 
+        fn on_skipped_held(&mut self, project: &ProjectInfo, hold: &ProcessHold) {
+            self.events.borrow_mut().push(format!(
+                "skip_held:{}:{}",
+                project.cleanable_dir.display(),
+                hold.pid
+            ));
+        }
+
+        fn on_skipped_unsafe(&mut self, project: &ProjectInfo, violation: PathViolation) {
+            self.events.borrow_mut().push(format!(
+                "skip_unsafe:{}:{}",
+                project.cleanable_dir.display(),
+                violation.label()
+            ));
+        }
+
         fn on_dry_run(&mut self, project: &ProjectInfo, action: CleanAction) {
             self.events.borrow_mut().push(format!(
                 "dry_run:{action:?}:{}",
@@ -548,6 +1320,12 @@ mod tests {
             ));
         }
 
+        fn on_cancelled(&mut self, remaining_projects: usize) {
+            self.events
+                .borrow_mut()
+                .push(format!("cancelled:{remaining_projects}"));
+        }
+
         fn on_finish(&mut self, result: &CleanResult) {
             self.events.borrow_mut().push(format!(
                 "finish:{}:{}:{}",
@@ -575,6 +1353,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -618,6 +1406,76 @@ mod tests {
         );
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cleaner_rechecks_running_processes_even_when_forced() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        let Ok(mut child) = std::process::Command::new("sleep")
+            .arg("30")
+            .current_dir(target.join("debug"))
+            .spawn()
+        else {
+            return;
+        };
+
+        let cleaner = Cleaner::new().force(true).force_protected(true);
+        let mut observer = RecordingObserver::default();
+        let result = cleaner
+            .clean_multiple_with_observer(&[project(target.clone(), 10, false)], &mut observer);
+        child.kill().ok();
+        child.wait().ok();
+
+        let result = result.unwrap();
+        assert_eq!(result.skipped_count, 1);
+        assert!(target.exists());
+        assert!(observer.events.borrow().contains(&format!(
+            "skip_held:{}:{}",
+            target.display(),
+            child.id()
+        )));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cleaner_refuses_targets_changed_since_scan() {
+        let temp = TempDir::new().unwrap();
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(outside.join("keep")).unwrap();
+        let project_root = temp.path().join("project");
+        let linked = project_root.join("linked");
+        let replaced = project_root.join("replaced");
+        fs::create_dir_all(&linked).unwrap();
+        fs::create_dir_all(&replaced).unwrap();
+
+        let mut linked_project = project(linked.clone(), 10, false);
+        let mut replaced_project = project(replaced.clone(), 20, false);
+        linked_project.record_identity();
+        replaced_project.record_identity();
+
+        fs::remove_dir(&linked).unwrap();
+        std::os::unix::fs::symlink(&outside, &linked).unwrap();
+        fs::rename(&replaced, project_root.join("moved")).unwrap();
+        fs::create_dir(&replaced).unwrap();
+
+        let mut observer = RecordingObserver::default();
+        let result = Cleaner::new()
+            .force(true)
+            .force_protected(true)
+            .clean_multiple_with_observer(&[linked_project, replaced_project], &mut observer)
+            .unwrap();
+
+        assert_eq!(result.cleaned_count, 0);
+        assert_eq!(result.skipped_count, 2);
+        assert!(outside.join("keep").exists());
+        assert!(replaced.exists());
+
+        let events = observer.events.borrow().clone();
+        assert!(events.contains(&format!("skip_unsafe:{}:symlink", linked.display())));
+        assert!(events.contains(&format!("skip_unsafe:{}:replaced", replaced.display())));
+    }
+
     #[test]
     fn test_cleaner_blocks_protected_and_recent_by_default() {
         let temp = TempDir::new().unwrap();
@@ -741,4 +1599,289 @@ mod tests {
         assert!(events[2].starts_with(&format!("failed:{}:", target.display())));
         assert_eq!(events[3], "finish:0:0:1");
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_targets_need_permission_repair() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("mod");
+        let locked = target.join("pkg@v1");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(locked.join("go.mod"), "module pkg").unwrap();
+        fs::write(locked.join("main.go"), "package pkg").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root removes entries from read-only directories anyway.
+        // SAFETY: `geteuid` takes no arguments and cannot fail.
+        if unsafe { libc::geteuid() } != 0 {
+            let result = Cleaner::new()
+                .clean_multiple(&[project(target.clone(), 10, false)])
+                .unwrap();
+            assert_eq!(result.failed_count, 1);
+            assert_eq!(result.errors.len(), 1);
+            let mut left = result
+                .unremovable
+                .iter()
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<_>>();
+            left.sort();
+            assert_eq!(left, vec![locked.join("go.mod"), locked.join("main.go")]);
+        }
+
+        let result = Cleaner::new()
+            .trash(true)
+            .trash_root(Some(temp.path().join("trash")))
+            .deletion(DeletionOptions {
+                repair_permissions: true,
+                ..DeletionOptions::default()
+            })
+            .clean_multiple(&[project(target.clone(), 10, false)])
+            .unwrap();
+        assert_eq!(result.cleaned_count, 1);
+        assert!(result.unremovable.is_empty());
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_partial_clean_removes_only_prune_paths() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        let incremental = target.join("debug/incremental");
+        let stale_rlib = target.join("debug/deps/libold-aaa.rlib");
+        let fresh_rlib = target.join("debug/deps/libnew-bbb.rlib");
+        fs::create_dir_all(incremental.join("s-1")).unwrap();
+        fs::write(incremental.join("s-1/query-cache.bin"), "x").unwrap();
+        fs::create_dir_all(stale_rlib.parent().unwrap()).unwrap();
+        fs::write(&stale_rlib, "old").unwrap();
+        fs::write(&fresh_rlib, "new").unwrap();
+
+        let mut partial = project(target.clone(), 4, false);
+        partial.prune_paths = vec![
+            crate::scanner::PrunePath {
+                path: incremental.clone(),
+                size: 1,
+                reason: String::new(),
+            },
+            crate::scanner::PrunePath {
+                path: stale_rlib.clone(),
+                size: 3,
+                reason: String::new(),
+            },
+        ];
+
+        let freed = Cleaner::new().clean_single(&partial).unwrap();
+
+        assert_eq!(freed, 4);
+        assert!(!incremental.exists());
+        assert!(!stale_rlib.exists());
+        assert!(fresh_rlib.exists());
+        assert!(target.exists());
+
+        partial.prune_paths = vec![crate::scanner::PrunePath {
+            path: target.join("../elsewhere"),
+            size: 1,
+            reason: String::new(),
+        }];
+        assert!(Cleaner::new().clean_single(&partial).is_err());
+    }
+
+    #[test]
+    fn test_archive_clean_counts_bytes_net_of_the_tarball() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("a/.venv");
+        let second = temp.path().join("b/.venv");
+        for target in [&first, &second] {
+            fs::create_dir_all(target).unwrap();
+            fs::write(target.join("pyvenv.cfg"), "version = 3.12\n".repeat(512)).unwrap();
+        }
+
+        let archive_root = temp.path().join("archives");
+        let cleaner = Cleaner::new()
+            .archive(true)
+            .archive_root(Some(archive_root.clone()));
+        let result = cleaner
+            .clean_multiple(&[
+                project(first.clone(), 10_000, false),
+                project(second.clone(), 10_000, false),
+            ])
+            .unwrap();
+
+        assert_eq!(result.cleaned_count, 2);
+        assert!(!first.exists() && !second.exists());
+        let batch_id = result.archive_batch_id.clone().unwrap();
+        let entries = crate::archive::archive_entries_for_batch(&archive_root, &batch_id).unwrap();
+        let packed = entries.iter().map(|e| e.size_after).sum::<u64>();
+        assert_eq!(result.archive_bytes, packed);
+        assert_eq!(result.bytes_freed, 20_000 - packed);
+
+        crate::archive::restore_archive_batch(&archive_root, &batch_id, false, false).unwrap();
+        assert!(first.join("pyvenv.cfg").exists() && second.join("pyvenv.cfg").exists());
+    }
+
+    #[test]
+    fn test_cancelled_cleaner_leaves_targets_untouched() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("target");
+        let second = temp.path().join("cache");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let cleaner = Cleaner::new().cancellation(token);
+        let mut observer = RecordingObserver::default();
+        let projects = vec![
+            project(first.clone(), 10, false),
+            project(second.clone(), 20, false),
+        ];
+
+        let result = cleaner
+            .clean_multiple_with_observer(&projects, &mut observer)
+            .unwrap();
+
+        assert!(result.cancelled);
+        assert_eq!(result.cleaned_count, 0);
+        assert!(first.exists());
+        assert!(second.exists());
+        assert_eq!(
+            observer.events.borrow().clone(),
+            vec![
+                "start:2:30".to_string(),
+                "cancelled:2".to_string(),
+                "finish:0:0:0".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parallel_clean_reports_targets_in_order() {
+        let temp = TempDir::new().unwrap();
+        let mut projects = Vec::new();
+        for index in 0..6 {
+            let target = temp.path().join(format!("p{index}/node_modules"));
+            for package in 0..4 {
+                let dir = target.join(format!("pkg{package}"));
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(dir.join("index.js"), "12345").unwrap();
+            }
+            projects.push(project(target, 20, index == 2));
+        }
+
+        let cleaner = Cleaner::new().deletion(DeletionOptions {
+            workers: 3,
+            ..DeletionOptions::default()
+        });
+        let mut observer = RecordingObserver::default();
+        let result = cleaner
+            .clean_multiple_with_observer(&projects, &mut observer)
+            .unwrap();
+
+        assert_eq!(result.cleaned_count, 5);
+        assert_eq!(result.bytes_freed, 100);
+        assert_eq!(result.skipped_count, 1);
+        for (index, project) in projects.iter().enumerate() {
+            assert_eq!(project.cleanable_dir.exists(), index == 2);
+        }
+
+        let mut expected = vec!["start:6:120".to_string()];
+        for (index, project) in projects.iter().enumerate() {
+            let path = project.cleanable_dir.display();
+            expected.push(format!("project:{path}"));
+            expected.push(if index == 2 {
+                format!("skip_in_use:{path}")
+            } else {
+                format!("cleaned:{path}:20")
+            });
+        }
+        expected.push("finish:5:1:0".to_string());
+        assert_eq!(observer.events.borrow().clone(), expected);
+    }
+
+    #[test]
+    fn test_journaled_clean_marks_each_target() {
+        let temp = TempDir::new().unwrap();
+        let journal_root = temp.path().join("runs");
+        let cleaned = temp.path().join("a/target");
+        let held_back = temp.path().join("b/target");
+        fs::create_dir_all(&cleaned).unwrap();
+        fs::create_dir_all(&held_back).unwrap();
+        let projects = [
+            project(cleaned.clone(), 7, false),
+            project(held_back, 9, true),
+        ];
+
+        let journal = Arc::new(
+            RunJournal::create(&journal_root, "run-1", "clean", &CleanOptions::default()).unwrap(),
+        );
+        let cleaner = Cleaner::new().journal(Arc::clone(&journal));
+        cleaner.clean_multiple(&projects).unwrap();
+        drop(cleaner);
+        drop(journal);
+
+        // Left unfinished, as if the process died before logging the run.
+        let run = crate::run_journal::unfinished_runs(&journal_root)
+            .unwrap()
+            .remove(0);
+        let states = run
+            .targets
+            .iter()
+            .map(|target| target.state.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            vec![
+                crate::run_journal::TargetState::Cleaned { bytes: 7 },
+                crate::run_journal::TargetState::Planned,
+            ]
+        );
+        let reconciliation = run.reconcile().unwrap();
+        assert_eq!(reconciliation.completed[0].project.cleanable_dir, cleaned);
+        assert_eq!(reconciliation.remaining.len(), 1);
+    }
+
+    #[test]
+    fn test_tombstone_clean_buries_then_reaps() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("app/node_modules");
+        fs::create_dir_all(target.join("pkg")).unwrap();
+        fs::write(target.join("pkg/index.js"), "12345").unwrap();
+        let journal_root = temp.path().join("tombstones");
+
+        let cleaner = Cleaner::with_options(CleanOptions {
+            deletion: DeletionOptions {
+                tombstone: true,
+                ..DeletionOptions::default()
+            },
+            tombstone_root: Some(journal_root.clone()),
+            run_id: Some("run-1".to_string()),
+            ..CleanOptions::default()
+        });
+        let result = cleaner
+            .clean_multiple(&[project(target.clone(), 5, false)])
+            .unwrap();
+
+        assert_eq!(result.cleaned_count, 1);
+        assert_eq!(result.bytes_freed, 5);
+        assert_eq!(result.pending_reap_bytes, 5);
+        assert!(!target.exists());
+
+        let journal = TombstoneJournal::new(journal_root);
+        let pending = journal.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].original_path, target);
+        assert_eq!(pending[0].run_id.as_deref(), Some("run-1"));
+        assert!(pending[0].tombstone_path.exists());
+
+        let reaped = journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(reaped.reaped_count, 1);
+        assert_eq!(reaped.bytes_freed, 5);
+        assert!(!pending[0].tombstone_path.exists());
+        assert!(journal.pending().unwrap().is_empty());
+    }
 }
@@ -46,6 +46,32 @@ pub struct Config {
     /// Audit configuration
     #[serde(default)]
     pub audit: AuditConfig,
+
+    /// Days after which an untouched Rust build profile or incremental cache is offered
+    /// for pruning (default 30)
+    #[serde(default)]
+    pub rust_stale_days: Option<i64>,
+
+    /// Persistent scan cache configuration
+    #[serde(default)]
+    pub scan_cache: ScanCacheConfig,
+
+    /// Stay on the scan root's filesystem, like `find -xdev` (`--one-file-system`)
+    #[serde(default)]
+    pub one_file_system: bool,
+
+    /// Skip network and pseudo filesystems mounted below a scan root (default true)
+    #[serde(default = "default_true")]
+    pub skip_special_mounts: bool,
+
+    /// Deletion workers, I/O priority and rate caps (`[deletion]`)
+    #[serde(default)]
+    pub deletion: crate::cleaner::DeletionOptions,
+
+    /// Where `--trash` moves targets: `"dev-cleaner"` (default) or `"xdg"`, the
+    /// desktop's freedesktop.org trash
+    #[serde(default)]
+    pub trash_backend: crate::trash::TrashBackend,
 }
 
 impl Default for Config {
@@ -65,6 +91,12 @@ impl Default for Config {
             keep_globs: Vec::new(),
             keep_project_roots: Vec::new(),
             audit: AuditConfig::default(),
+            rust_stale_days: None,
+            scan_cache: ScanCacheConfig::default(),
+            one_file_system: false,
+            skip_special_mounts: true,
+            deletion: Default::default(),
+            trash_backend: Default::default(),
         }
     }
 }
@@ -107,6 +139,27 @@ impl Default for AuditConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCacheConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Ignore cached entries for this run (`--refresh`); not read from the config file
+    #[serde(skip)]
+    pub refresh: bool,
+}
+
+impl Default for ScanCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            refresh: false,
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -130,6 +183,10 @@ pub struct CustomPattern {
     /// How to interpret `marker_files`
     #[serde(default)]
     pub marker_mode: MarkerMode,
+
+    /// Sub-paths inside `directory` to offer as a separate partial clean
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prune: Vec<crate::scanner::PruneRule>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -1,3 +1,5 @@
+use crate::guard::PathViolation;
+use crate::policy::{ActivitySource, ProcessHold, ProtectionDecision};
 use crate::scanner::ProjectInfo;
 use serde::{Deserialize, Serialize};
 
@@ -8,14 +10,28 @@ pub struct SafetyFlags {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub protected_by: Option<String>,
     pub recent: bool,
+    #[serde(default, skip_serializing_if = "ActivitySource::is_heuristic")]
+    pub recent_source: ActivitySource,
 }
 
 impl SafetyFlags {
+    /// Flags for `info` from its keep-policy decision and the recent threshold
+    pub fn assess(info: &ProjectInfo, decision: ProtectionDecision, recent_days: i64) -> Self {
+        let (recent, recent_source) = info.recent_within(recent_days);
+        Self {
+            protected: decision.protected,
+            protected_by: decision.reason,
+            recent,
+            recent_source,
+        }
+    }
+
     pub fn from_project_info(info: &ProjectInfo) -> Self {
         Self {
             protected: info.protected,
             protected_by: info.protected_by.clone(),
             recent: info.recent,
+            recent_source: info.recent_source,
         }
     }
 
@@ -23,10 +39,11 @@ impl SafetyFlags {
         info.protected = self.protected;
         info.protected_by = self.protected_by.clone();
         info.recent = self.recent;
+        info.recent_source = self.recent_source;
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SkipReason {
     InUse,
@@ -36,10 +53,16 @@ pub enum SkipReason {
     OutsideProjectRoot,
     OutsideScanRoot,
     RuleMismatchOrMissing,
+    /// Target contains files tracked by the enclosing git repository
+    TrackedByVcs,
+    /// A running process uses the target (cwd, executable, mapped or open file)
+    HeldByProcess(ProcessHold),
+    /// The target changed on disk since it was scanned and no longer names what was looked at
+    UnsafePath(PathViolation),
 }
 
 impl SkipReason {
-    pub fn legacy_label(self) -> &'static str {
+    pub fn legacy_label(&self) -> &'static str {
         match self {
             Self::InUse => "blocked_in_use",
             Self::Protected => "blocked_protected",
@@ -48,6 +71,9 @@ impl SkipReason {
             Self::OutsideProjectRoot => "outside_project_root",
             Self::OutsideScanRoot => "outside_scan_root",
             Self::RuleMismatchOrMissing => "rule_mismatch_or_missing",
+            Self::TrackedByVcs => "blocked_tracked_by_vcs",
+            Self::HeldByProcess(_) => "blocked_held_by_process",
+            Self::UnsafePath(_) => "blocked_unsafe_path",
         }
     }
 
@@ -60,6 +86,7 @@ impl SkipReason {
             "outside_project_root" => Some(Self::OutsideProjectRoot),
             "outside_scan_root" => Some(Self::OutsideScanRoot),
             "rule_mismatch_or_missing" => Some(Self::RuleMismatchOrMissing),
+            "blocked_tracked_by_vcs" => Some(Self::TrackedByVcs),
             _ => None,
         }
     }
@@ -67,7 +94,11 @@ impl SkipReason {
 
 impl std::fmt::Display for SkipReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.legacy_label())
+        match self {
+            Self::HeldByProcess(hold) => write!(f, "{}: {}", self.legacy_label(), hold),
+            Self::UnsafePath(violation) => write!(f, "{}: {}", self.legacy_label(), violation),
+            _ => f.write_str(self.legacy_label()),
+        }
     }
 }
 
@@ -145,6 +176,7 @@ impl EvaluatedProject {
             .map(|reason| reason.legacy_label().to_string());
         info.skip_reason = self
             .skip_reason
+            .as_ref()
             .map(|reason| reason.legacy_label().to_string());
         info
     }
@@ -176,10 +208,13 @@ impl From<ProjectInfo> for EvaluatedProject {
             .selection_reason
             .as_deref()
             .and_then(SelectionReason::from_legacy_label);
-        let skip_reason = info
-            .skip_reason
-            .as_deref()
-            .and_then(SkipReason::from_legacy_label);
+        let skip_reason = match info.skip_reason.as_deref() {
+            // The label alone cannot carry the process; it lives on `in_use_by`.
+            Some("blocked_held_by_process") => {
+                info.in_use_by.clone().map(SkipReason::HeldByProcess)
+            }
+            label => label.and_then(SkipReason::from_legacy_label),
+        };
 
         Self {
             safety: SafetyFlags::from_project_info(&info),
@@ -229,6 +264,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -239,6 +284,7 @@ mod tests {
                 protected: true,
                 protected_by: Some("config_keep_paths".to_string()),
                 recent: true,
+                recent_source: ActivitySource::VerifiedGit,
             })
             .with_selection_reason(SelectionReason::StrategyBalanced)
             .with_skip_reason(SkipReason::Protected);
@@ -247,7 +293,48 @@ mod tests {
         assert!(info.protected);
         assert_eq!(info.protected_by.as_deref(), Some("config_keep_paths"));
         assert!(info.recent);
+        assert_eq!(info.recent_source, ActivitySource::VerifiedGit);
         assert_eq!(info.selection_reason.as_deref(), Some("strategy_balanced"));
         assert_eq!(info.skip_reason.as_deref(), Some("blocked_protected"));
     }
+
+    #[test]
+    fn assess_prefers_git_activity_over_target_mtime() {
+        use crate::policy::GitActivity;
+        use chrono::Duration;
+
+        let decision = || ProtectionDecision {
+            protected: false,
+            reason: None,
+            pattern_error: None,
+        };
+        let activity = |days: i64| GitActivity {
+            last_commit: Utc::now() - Duration::days(days),
+            head_moved: None,
+            branch_checked_out: None,
+        };
+
+        let mut info = sample_info();
+        info.last_modified = Utc::now() - Duration::days(60);
+        let heuristic = SafetyFlags::assess(&info, decision(), 7);
+        assert!(!heuristic.recent);
+        assert_eq!(heuristic.recent_source, ActivitySource::Heuristic);
+
+        info.activity = Some(activity(2));
+        let active = SafetyFlags::assess(&info, decision(), 7);
+        assert!(active.recent);
+        assert_eq!(active.recent_source, ActivitySource::VerifiedGit);
+        assert_eq!(info.days_idle(), 2);
+
+        info.activity = Some(activity(90));
+        let idle = SafetyFlags::assess(&info, decision(), 7);
+        assert!(!idle.recent);
+        assert_eq!(idle.recent_source, ActivitySource::VerifiedGit);
+
+        // A fresh build without new commits is still recent, on mtime evidence.
+        info.last_modified = Utc::now();
+        let rebuilt = SafetyFlags::assess(&info, decision(), 7);
+        assert!(rebuilt.recent);
+        assert_eq!(rebuilt.recent_source, ActivitySource::Heuristic);
+    }
 }
@@ -0,0 +1,161 @@
+//! Checks a path again right before it is removed or moved.
+//!
+//! A scan, a plan or a trash log can be minutes or months old by the time it is acted
+//! on. In between, a target may have been swapped for a symlink, replaced by another
+//! directory, or the project around it moved, so the path no longer names what was
+//! looked at.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::path::Path;
+
+/// Device and inode a directory had when it was looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileIdentity {
+    pub device: u64,
+    pub inode: u64,
+}
+
+impl FileIdentity {
+    #[cfg(unix)]
+    pub fn of(metadata: &Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_metadata: &Metadata) -> Option<Self> {
+        None
+    }
+
+    /// Identity of `path` itself, without following a symlink
+    pub fn of_path(path: &Path) -> Option<Self> {
+        fs::symlink_metadata(path)
+            .ok()
+            .and_then(|metadata| Self::of(&metadata))
+    }
+}
+
+/// Why a path is no longer safe to remove
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathViolation {
+    /// The path is now a symlink
+    Symlink,
+    /// The path resolves outside the directory it belongs to
+    EscapesRoot,
+    /// The path now names a different directory than the one looked at
+    Replaced,
+}
+
+impl PathViolation {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Symlink => "symlink",
+            Self::EscapesRoot => "escapes_root",
+            Self::Replaced => "replaced",
+        }
+    }
+}
+
+impl fmt::Display for PathViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Symlink => "path is a symlink",
+            Self::EscapesRoot => "path resolves outside its root",
+            Self::Replaced => "path was replaced since it was scanned",
+        })
+    }
+}
+
+impl std::error::Error for PathViolation {}
+
+/// Check that `path` is still the directory inside `root` that was looked at.
+///
+/// A path that no longer exists passes; there is nothing left to remove. `expected`
+/// is compared when known, so a target recorded without one is only checked for
+/// symlinks and escapes.
+pub fn check_path(
+    path: &Path,
+    root: &Path,
+    expected: Option<FileIdentity>,
+) -> Result<(), PathViolation> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if metadata.file_type().is_symlink() {
+        return Err(PathViolation::Symlink);
+    }
+
+    // A symlinked ancestor moves the whole path somewhere else.
+    match (fs::canonicalize(path), fs::canonicalize(root)) {
+        (Ok(resolved), Ok(root)) if resolved.starts_with(&root) => {}
+        _ => return Err(PathViolation::EscapesRoot),
+    }
+
+    if let (Some(expected), Some(actual)) = (expected, FileIdentity::of(&metadata)) {
+        if expected != actual {
+            return Err(PathViolation::Replaced);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_paths_pass() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(
+            check_path(&temp.path().join("gone"), temp.path(), None),
+            Ok(())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_and_escapes_are_refused() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(outside.join("target")).unwrap();
+
+        std::os::unix::fs::symlink(&outside, project.join("target")).unwrap();
+        assert_eq!(
+            check_path(&project.join("target"), &project, None),
+            Err(PathViolation::Symlink)
+        );
+
+        fs::remove_file(project.join("target")).unwrap();
+        std::os::unix::fs::symlink(&outside, project.join("linked")).unwrap();
+        assert_eq!(
+            check_path(&project.join("linked/target"), &project, None),
+            Err(PathViolation::EscapesRoot)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replaced_directories_are_refused() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir(&target).unwrap();
+        let identity = FileIdentity::of_path(&target);
+        assert_eq!(check_path(&target, temp.path(), identity), Ok(()));
+
+        fs::rename(&target, temp.path().join("old")).unwrap();
+        fs::create_dir(&target).unwrap();
+        assert_eq!(
+            check_path(&target, temp.path(), identity),
+            Err(PathViolation::Replaced)
+        );
+    }
+}
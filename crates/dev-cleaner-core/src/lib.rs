@@ -1,15 +1,22 @@
 pub mod app;
+pub mod archive;
 pub mod audit;
+pub mod cancel;
 pub mod cleaner;
 pub mod config;
 pub mod evaluation;
+pub mod guard;
+pub mod permissions;
 pub mod plan;
 pub mod policy;
 pub mod recommend;
+pub mod run_journal;
 pub mod scanner;
 pub mod stats;
+pub mod tombstone;
 pub mod trash;
 pub mod utils;
+pub mod xdg_trash;
 
 pub use app::{
     canonicalize_lossy, common_ancestor, derive_scan_root, ApplyPlanRequest, ApplyPlanResult,
@@ -17,19 +24,38 @@ pub use app::{
     CleanupService, DiscoveredProjects, ResolvedScanInput, ScanRequest, ScanResult, ScanService,
     VisibilityOptions,
 };
+pub use archive::{
+    default_archive_root, gc_archives, list_archive_batches, restore_archive_batch,
+    ArchiveBatchSummary, ArchiveEntry, ArchiveStore,
+};
 pub use audit::{AuditLogger, AuditRecord, AuditRunSummary};
-pub use cleaner::{CleanAction, CleanObserver, CleanOptions, CleanResult, Cleaner};
-pub use config::{AuditConfig, Config, CustomPattern, MarkerMode, ScanProfile};
+pub use cancel::{CancellationToken, Cancelled, FileTrigger};
+pub use cleaner::{
+    CleanAction, CleanObserver, CleanOptions, CleanResult, Cleaner, DeletionOptions,
+    IncompleteClean, UnremovableEntry,
+};
+pub use config::{AuditConfig, Config, CustomPattern, MarkerMode, ScanCacheConfig, ScanProfile};
 pub use evaluation::{EvaluatedProject, SafetyFlags, SelectionReason, SkipReason};
+pub use guard::{check_path, FileIdentity, PathViolation};
 pub use plan::{CleanupPlan, PlanParams};
 pub use recommend::{recommend_projects, RecommendOptions, RecommendResult, RecommendStrategy};
+pub use run_journal::{
+    default_run_journal_root, unfinished_runs, CompletedTarget, Reconciliation, RunJournal,
+    TargetState, UnfinishedRun,
+};
 pub use scanner::{
-    Category, Confidence, ProjectDetector, ProjectInfo, ProjectType, RiskLevel, RuleRef,
-    RuleSource, Scanner, SizeCalculator,
+    Category, Confidence, ContentCheck, DiagnosticKind, DiskUsage, MountSkipReason,
+    ProjectDetector, ProjectInfo, ProjectType, PrunePath, RiskLevel, RuleRef, RuleSource,
+    ScanCache, ScanDiagnostic, ScanDiagnostics, ScanObserver, ScanPhase, Scanner, SizeCalculator,
+    SkippedMount,
 };
 pub use stats::Statistics;
+pub use tombstone::{
+    default_tombstone_root, ReapObserver, ReapResult, Tombstone, TombstoneJournal,
+};
 pub use trash::{
-    default_trash_root, gc_trash, latest_batch_id, list_trash_batches, purge_trash_batch,
-    restore_batch, restore_batch_with_observer, trash_entries_for_batch, GcResult, PurgeResult,
-    RestoreObserver, RestoreResult, TrashBatchSummary, TrashEntry, TrashManager,
+    default_trash_root, gc_trash, latest_batch_id, list_trash_batches, other_trash_roots,
+    purge_trash_batch, restore_batch, restore_batch_with_observer, trash_entries_for_batch,
+    GcResult, PurgeResult, RestoreObserver, RestoreResult, TrashBackend, TrashBatchSummary,
+    TrashEntry, TrashManager,
 };
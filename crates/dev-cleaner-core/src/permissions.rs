@@ -0,0 +1,93 @@
+//! Opt-in repair of read-only trees so they can be removed.
+//!
+//! The Go module cache, some npm packages and Nix-built outputs make their
+//! directories read-only, and an entry cannot be unlinked from a directory without
+//! write permission on it. Repair adds owner write permission, and nothing else, to
+//! entries the current user owns. Symlinks are never followed.
+
+use std::io;
+use std::path::Path;
+
+/// Add owner write permission to the directory at `path`; returns whether it changed.
+///
+/// Entries owned by another user, symlinks and anything already writable are left
+/// alone. The directory is opened without following symlinks and changed through that
+/// handle, so a path swapped for a symlink in between is not touched either.
+#[cfg(unix)]
+pub fn make_writable(path: &Path) -> io::Result<bool> {
+    use std::fs::{OpenOptions, Permissions};
+    use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+
+    let dir = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_DIRECTORY)
+        .open(path)?;
+    let metadata = dir.metadata()?;
+    // SAFETY: `geteuid` takes no arguments and cannot fail.
+    if metadata.uid() != unsafe { libc::geteuid() } {
+        return Ok(false);
+    }
+    let mode = metadata.mode() & 0o7777;
+    if mode & 0o200 != 0 {
+        return Ok(false);
+    }
+    dir.set_permissions(Permissions::from_mode(mode | 0o200))?;
+    Ok(true)
+}
+
+/// Clear the read-only attribute of the file or directory at `path`; returns whether it
+/// changed. Ownership is not checked here.
+#[cfg(not(unix))]
+pub fn make_writable(path: &Path) -> io::Result<bool> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() || !metadata.permissions().readonly() {
+        return Ok(false);
+    }
+    let mut permissions = metadata.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(true)
+}
+
+/// [`make_writable`] every directory in the tree at `path`, best effort
+pub fn make_tree_writable(path: &Path) {
+    for entry in walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+    {
+        if entry.file_type().is_dir() || cfg!(not(unix)) {
+            let _ = make_writable(entry.path());
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn mode(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn adds_only_owner_write_and_skips_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("pkg");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        let link = temp.path().join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+
+        assert!(make_writable(&link).is_err());
+        assert_eq!(mode(&dir), 0o555);
+
+        assert!(make_writable(&dir).unwrap());
+        assert_eq!(mode(&dir), 0o755);
+        assert!(!make_writable(&dir).unwrap());
+    }
+}
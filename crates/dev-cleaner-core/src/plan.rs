@@ -35,10 +35,21 @@ pub struct CleanupPlan {
     pub projects: Vec<ProjectInfo>,
 }
 
+/// Schema written for plans that only contain full-target cleans.
+const PLAN_SCHEMA_VERSION: u32 = 3;
+/// Schema written once a plan contains partial cleans; older readers would
+/// otherwise ignore `prune_paths` and remove the whole target.
+const PLAN_SCHEMA_VERSION_PARTIAL: u32 = 4;
+
 impl CleanupPlan {
     pub fn new(scan_root: PathBuf, projects: Vec<ProjectInfo>) -> Self {
+        let schema_version = if projects.iter().any(ProjectInfo::is_partial) {
+            PLAN_SCHEMA_VERSION_PARTIAL
+        } else {
+            PLAN_SCHEMA_VERSION
+        };
         Self {
-            schema_version: 3,
+            schema_version,
             tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
             created_at: Utc::now(),
             scan_root,
@@ -108,6 +119,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }];
 
         let plan = CleanupPlan::new(PathBuf::from("/scan"), projects);
@@ -117,4 +138,26 @@ mod tests {
         assert_eq!(loaded.schema_version, 3);
         assert_eq!(loaded.projects.len(), 1);
     }
+
+    #[test]
+    fn test_plan_with_partial_clean_uses_newer_schema() {
+        let mut project = ProjectInfo::new_pending(
+            PathBuf::from("/scan/p1"),
+            ProjectType::NodeJs,
+            PathBuf::from("/scan/p1/node_modules"),
+            Utc::now(),
+            false,
+        );
+        project.prune_paths = vec![crate::scanner::PrunePath {
+            path: PathBuf::from("/scan/p1/node_modules/.cache"),
+            size: 10,
+            reason: String::new(),
+        }];
+
+        let plan = CleanupPlan::new(PathBuf::from("/scan"), vec![project]);
+        assert_eq!(plan.schema_version, 4);
+
+        let loaded: CleanupPlan = serde_json::from_str(&plan.to_json_pretty().unwrap()).unwrap();
+        assert_eq!(loaded.projects[0].prune_paths.len(), 1);
+    }
 }
@@ -0,0 +1,283 @@
+use super::vcs::find_repo_root;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// Where a recent/in-use decision came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActivitySource {
+    /// Filesystem timestamps (target mtime, lock files)
+    #[default]
+    #[serde(rename = "heuristic")]
+    Heuristic,
+    /// The enclosing git repository's history and reflog
+    #[serde(rename = "verified:git")]
+    VerifiedGit,
+    /// A running process using the target (`/proc`)
+    #[serde(rename = "verified:process")]
+    VerifiedProcess,
+}
+
+impl ActivitySource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Heuristic => "heuristic",
+            Self::VerifiedGit => "verified:git",
+            Self::VerifiedProcess => "verified:process",
+        }
+    }
+
+    pub fn is_heuristic(&self) -> bool {
+        *self == Self::Heuristic
+    }
+}
+
+impl std::fmt::Display for ActivitySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Local git evidence of when a project was last worked on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitActivity {
+    /// Committer time of the latest commit touching the project directory
+    pub last_commit: DateTime<Utc>,
+    /// Time of the latest HEAD reflog entry (commit, checkout, reset, rebase, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_moved: Option<DateTime<Utc>>,
+    /// Time the current branch was checked out (or the repository cloned)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_checked_out: Option<DateTime<Utc>>,
+}
+
+impl GitActivity {
+    /// Most recent of all recorded signals
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        [self.head_moved, self.branch_checked_out]
+            .into_iter()
+            .flatten()
+            .fold(self.last_commit, DateTime::max)
+    }
+
+    pub fn days_since_activity(&self) -> i64 {
+        (Utc::now() - self.last_activity()).num_days()
+    }
+}
+
+/// HEAD reflog signals shared by every project of a repository
+#[derive(Debug, Clone, Copy, Default)]
+struct ReflogActivity {
+    head_moved: Option<DateTime<Utc>>,
+    branch_checked_out: Option<DateTime<Utc>>,
+}
+
+/// Reads project activity from the enclosing git repository without network access.
+///
+/// The latest commit is looked up per project directory (`git log -1 -- <dir>`), so a
+/// monorepo or a dotfiles repository at `$HOME` only vouches for projects it actually
+/// tracks. HEAD movement and branch checkout come from the repository's HEAD reflog and
+/// are read once per repository. Projects outside a repository, with no commits, or
+/// when `git` is unavailable have no activity and keep the heuristic decisions.
+#[derive(Debug, Default)]
+pub struct GitActivityProvider {
+    reflog_cache: Mutex<HashMap<PathBuf, ReflogActivity>>,
+    project_cache: Mutex<HashMap<PathBuf, Option<GitActivity>>>,
+}
+
+impl GitActivityProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Activity of the project rooted at `project_root`
+    pub fn activity_for(&self, project_root: &Path) -> Option<GitActivity> {
+        if let Some(cached) = self.project_cache.lock().unwrap().get(project_root) {
+            return *cached;
+        }
+
+        let activity = self.read_activity(project_root);
+        self.project_cache
+            .lock()
+            .unwrap()
+            .insert(project_root.to_path_buf(), activity);
+        activity
+    }
+
+    fn read_activity(&self, project_root: &Path) -> Option<GitActivity> {
+        let repo_root = find_repo_root(project_root)?;
+        let relative = project_root.strip_prefix(&repo_root).ok()?;
+        let last_commit = last_commit_time(&repo_root, relative)?;
+        let reflog = self.reflog_for(&repo_root);
+        Some(GitActivity {
+            last_commit,
+            head_moved: reflog.head_moved,
+            branch_checked_out: reflog.branch_checked_out,
+        })
+    }
+
+    fn reflog_for(&self, repo_root: &Path) -> ReflogActivity {
+        if let Some(cached) = self.reflog_cache.lock().unwrap().get(repo_root) {
+            return *cached;
+        }
+
+        let reflog = git_dir(repo_root)
+            .and_then(|dir| fs::read_to_string(dir.join("logs").join("HEAD")).ok())
+            .map(|content| parse_head_reflog(&content))
+            .unwrap_or_default();
+        self.reflog_cache
+            .lock()
+            .unwrap()
+            .insert(repo_root.to_path_buf(), reflog);
+        reflog
+    }
+}
+
+fn last_commit_time(repo_root: &Path, relative: &Path) -> Option<DateTime<Utc>> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["log", "-1", "--format=%ct"]);
+    if !relative.as_os_str().is_empty() {
+        command.arg("--").arg(relative);
+    }
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let seconds = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+/// Git directory of a work tree: `.git` itself, or the target of a `gitdir:` file
+/// (linked worktrees, submodules)
+fn git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let target = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(repo_root.join(target))
+}
+
+/// Parse `logs/HEAD`: `<old> <new> <name> <<email>> <seconds> <tz>\t<message>`
+fn parse_head_reflog(content: &str) -> ReflogActivity {
+    let mut activity = ReflogActivity::default();
+    let mut first_entry = None;
+
+    for line in content.lines() {
+        let (meta, message) = line.split_once('\t').unwrap_or((line, ""));
+        let Some(time) = meta
+            .rsplit(' ')
+            .nth(1)
+            .and_then(|seconds| seconds.parse::<i64>().ok())
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        else {
+            continue;
+        };
+
+        first_entry.get_or_insert(time);
+        activity.head_moved = Some(time);
+        if message.starts_with("checkout:") {
+            activity.branch_checked_out = Some(time);
+        }
+    }
+
+    // Never switched branches: the branch dates from the clone or first commit.
+    if activity.branch_checked_out.is_none() {
+        activity.branch_checked_out = first_entry;
+    }
+    activity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(repo: &Path, args: &[&str]) -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args([
+                "-c",
+                "user.name=dev-cleaner",
+                "-c",
+                "user.email=dev-cleaner@example.com",
+            ])
+            .args(args)
+            .env("GIT_COMMITTER_DATE", "2024-01-02T00:00:00Z")
+            .env("GIT_AUTHOR_DATE", "2024-01-02T00:00:00Z")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn parses_head_reflog_signals() {
+        let zero = "0".repeat(40);
+        let one = "1".repeat(40);
+        let content = format!(
+            "{zero} {one} A <a@example.com> 1700000000 +0000\tclone: from https://example.com/repo\n\
+             {one} {one} A <a@example.com> 1700000500 +0800\tcheckout: moving from main to feature\n\
+             {one} {one} A <a@example.com> 1700000900 -0100\tcommit: work\n"
+        );
+        let activity = parse_head_reflog(&content);
+        assert_eq!(activity.head_moved.unwrap().timestamp(), 1_700_000_900);
+        assert_eq!(
+            activity.branch_checked_out.unwrap().timestamp(),
+            1_700_000_500
+        );
+
+        let cloned = parse_head_reflog(&format!(
+            "{zero} {one} A <a@example.com> 1700000000 +0000\tclone: from x\n"
+        ));
+        assert_eq!(
+            cloned.branch_checked_out.unwrap().timestamp(),
+            1_700_000_000
+        );
+    }
+
+    #[test]
+    fn reads_commit_time_per_project_directory() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join("app")).unwrap();
+        fs::create_dir_all(repo.join("untracked")).unwrap();
+        fs::write(repo.join("app/package.json"), "{}").unwrap();
+        if !git(&repo, &["init", "-q"]) {
+            // No usable git in this environment.
+            return;
+        }
+        assert!(git(&repo, &["add", "app"]));
+        assert!(git(&repo, &["commit", "-q", "-m", "init"]));
+
+        let provider = GitActivityProvider::new();
+        let activity = provider.activity_for(&repo.join("app")).unwrap();
+        assert_eq!(
+            activity.last_commit,
+            DateTime::parse_from_rfc3339("2024-01-02T00:00:00Z").unwrap()
+        );
+        assert!(activity.head_moved.is_some());
+        assert!(activity.last_activity() >= activity.last_commit);
+        assert!(provider.activity_for(&repo.join("untracked")).is_none());
+        assert!(provider.activity_for(temp.path()).is_none());
+    }
+}
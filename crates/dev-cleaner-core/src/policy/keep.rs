@@ -7,6 +7,8 @@ use std::path::{Path, PathBuf};
 pub struct ProtectionDecision {
     pub protected: bool,
     pub reason: Option<String>,
+    /// Why the project's `.dev-cleaner-keep-patterns` could not be used
+    pub pattern_error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -14,6 +16,8 @@ pub struct KeepPolicy {
     keep_paths: Vec<PathBuf>,
     keep_glob_set: GlobSet,
     keep_project_root_set: GlobSet,
+    /// Config globs that did not compile, with the error
+    invalid_patterns: Vec<(String, String)>,
 }
 
 impl KeepPolicy {
@@ -23,20 +27,30 @@ impl KeepPolicy {
             .iter()
             .map(|p| expand_tilde(p))
             .collect::<Vec<_>>();
-        let keep_glob_set = build_glob_set(&config.keep_globs);
-        let keep_project_root_set = build_glob_set(&config.keep_project_roots);
+        let mut invalid_patterns = Vec::new();
+        let keep_glob_set = build_glob_set(&config.keep_globs, &mut invalid_patterns);
+        let keep_project_root_set =
+            build_glob_set(&config.keep_project_roots, &mut invalid_patterns);
         Self {
             keep_paths,
             keep_glob_set,
             keep_project_root_set,
+            invalid_patterns,
         }
     }
 
+    /// `keep_globs` and `keep_project_roots` entries that were ignored as invalid,
+    /// with the reason
+    pub fn invalid_patterns(&self) -> &[(String, String)] {
+        &self.invalid_patterns
+    }
+
     pub fn evaluate(&self, info: &ProjectInfo) -> ProtectionDecision {
         if info.root.join(".dev-cleaner-keep").exists() {
             return ProtectionDecision {
                 protected: true,
                 reason: Some("project_marker:.dev-cleaner-keep".to_string()),
+                pattern_error: None,
             };
         }
 
@@ -45,6 +59,7 @@ impl KeepPolicy {
                 return ProtectionDecision {
                     protected: true,
                     reason: Some(reason),
+                    pattern_error: None,
                 };
             }
             Ok(None) => {}
@@ -55,6 +70,7 @@ impl KeepPolicy {
                         "project_marker:.dev-cleaner-keep-patterns(parse_error:{})",
                         err
                     )),
+                    pattern_error: Some(err.to_string()),
                 };
             }
         }
@@ -63,6 +79,7 @@ impl KeepPolicy {
             return ProtectionDecision {
                 protected: true,
                 reason: Some("config_keep_project_roots".to_string()),
+                pattern_error: None,
             };
         }
 
@@ -70,6 +87,7 @@ impl KeepPolicy {
             return ProtectionDecision {
                 protected: true,
                 reason: Some("config_keep_paths".to_string()),
+                pattern_error: None,
             };
         }
 
@@ -77,12 +95,14 @@ impl KeepPolicy {
             return ProtectionDecision {
                 protected: true,
                 reason: Some("config_keep_globs".to_string()),
+                pattern_error: None,
             };
         }
 
         ProtectionDecision {
             protected: false,
             reason: None,
+            pattern_error: None,
         }
     }
 
@@ -171,14 +191,16 @@ fn compile_glob_matcher(pattern: &str) -> anyhow::Result<GlobMatcher> {
     Ok(glob.compile_matcher())
 }
 
-fn build_glob_set(patterns: &[String]) -> GlobSet {
+fn build_glob_set(patterns: &[String], invalid: &mut Vec<(String, String)>) -> GlobSet {
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns {
-        let pattern = expand_tilde(pattern).to_string_lossy().to_string();
-        let Ok(glob) = GlobBuilder::new(&pattern).literal_separator(true).build() else {
-            continue;
-        };
-        builder.add(glob);
+        let expanded = expand_tilde(pattern).to_string_lossy().to_string();
+        match GlobBuilder::new(&expanded).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(err) => invalid.push((pattern.clone(), err.to_string())),
+        }
     }
     builder
         .build()
@@ -230,6 +252,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
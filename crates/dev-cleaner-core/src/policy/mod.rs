@@ -1,3 +1,9 @@
+pub mod activity;
 pub mod keep;
+pub mod process;
+pub mod vcs;
 
+pub use activity::{ActivitySource, GitActivity, GitActivityProvider};
 pub use keep::{KeepPolicy, ProtectionDecision};
+pub use process::{find_process_holding, HoldKind, ProcessHold, ProcessSnapshot};
+pub use vcs::VcsGuard;
@@ -0,0 +1,282 @@
+use crate::scanner::ProjectInfo;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// How a process holds a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldKind {
+    /// Working directory
+    Cwd,
+    /// Running executable
+    Exe,
+    /// Memory-mapped file (shared library, mmapped data)
+    Mapped,
+    /// Open file descriptor
+    OpenFile,
+}
+
+impl HoldKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cwd => "working directory",
+            Self::Exe => "executable",
+            Self::Mapped => "mapped file",
+            Self::OpenFile => "open file",
+        }
+    }
+}
+
+/// A running process using a path inside a cleanup target
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProcessHold {
+    pub pid: u32,
+    /// Command name (`/proc/<pid>/comm`)
+    pub command: String,
+    pub kind: HoldKind,
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for ProcessHold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PID {} ({}) has {} as its {}",
+            self.pid,
+            self.command,
+            self.path.display(),
+            self.kind.label()
+        )
+    }
+}
+
+/// Paths held by running processes, captured once and checked against many targets.
+///
+/// On Linux this reads `/proc/<pid>/{cwd,exe,maps,fd}`; processes owned by other users
+/// are skipped when their entries are unreadable. Elsewhere the snapshot is empty. The
+/// current process is ignored so Dev Cleaner's own open files never block a clean.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessSnapshot {
+    holds: Vec<ProcessHold>,
+}
+
+impl ProcessSnapshot {
+    pub fn capture() -> Self {
+        Self {
+            holds: running_processes().flat_map(process_holds).collect(),
+        }
+    }
+
+    /// First process holding `dir` or anything below it
+    pub fn holder_of(&self, dir: &Path) -> Option<&ProcessHold> {
+        self.holds.iter().find(|hold| hold.path.starts_with(dir))
+    }
+
+    /// First process holding a path the project would remove
+    pub fn holder_of_project(&self, info: &ProjectInfo) -> Option<&ProcessHold> {
+        removal_roots(info)
+            .iter()
+            .find_map(|dir| self.holder_of(dir))
+    }
+}
+
+/// Walk running processes until one holds a path the project would remove.
+///
+/// This still reads the maps and open files of every process up to the match, so it
+/// costs about as much as [`ProcessSnapshot::capture`]; checking many targets, capture
+/// one snapshot and look each up with [`ProcessSnapshot::holder_of_project`].
+pub fn find_process_holding(info: &ProjectInfo) -> Option<ProcessHold> {
+    let roots = removal_roots(info);
+    running_processes()
+        .flat_map(process_holds)
+        .find(|hold| roots.iter().any(|root| hold.path.starts_with(root)))
+}
+
+/// Paths the project would remove, resolved like the kernel reports them in `/proc`
+fn removal_roots(info: &ProjectInfo) -> Vec<PathBuf> {
+    let roots = if info.is_partial() {
+        info.prune_paths
+            .iter()
+            .map(|prune| prune.path.as_path())
+            .collect::<Vec<_>>()
+    } else {
+        vec![info.cleanable_dir.as_path()]
+    };
+    roots
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or_else(|_| root.to_path_buf()))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn running_processes() -> impl Iterator<Item = u32> {
+    let own_pid = std::process::id();
+    std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(move |pid| *pid != own_pid)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn running_processes() -> impl Iterator<Item = u32> {
+    std::iter::empty()
+}
+
+#[cfg(target_os = "linux")]
+fn process_holds(pid: u32) -> Vec<ProcessHold> {
+    use std::fs;
+
+    let proc_dir = PathBuf::from(format!("/proc/{pid}"));
+    let Ok(command) = fs::read_to_string(proc_dir.join("comm")) else {
+        return Vec::new();
+    };
+    let command = command.trim().to_string();
+    let mut holds = Vec::new();
+    let mut push = |kind: HoldKind, path: PathBuf| {
+        holds.push(ProcessHold {
+            pid,
+            command: command.clone(),
+            kind,
+            path,
+        });
+    };
+
+    if let Ok(cwd) = fs::read_link(proc_dir.join("cwd")) {
+        push(HoldKind::Cwd, cwd);
+    }
+    if let Ok(exe) = fs::read_link(proc_dir.join("exe")) {
+        push(HoldKind::Exe, strip_deleted(exe));
+    }
+    if let Ok(maps) = fs::read_to_string(proc_dir.join("maps")) {
+        let mut seen = std::collections::HashSet::new();
+        for path in maps.lines().filter_map(mapped_path) {
+            if seen.insert(path.clone()) {
+                push(HoldKind::Mapped, path);
+            }
+        }
+    }
+    if let Ok(fds) = fs::read_dir(proc_dir.join("fd")) {
+        for fd in fds.flatten() {
+            // Sockets, pipes and anonymous inodes read as `socket:[...]` etc.
+            if let Ok(target) = fs::read_link(fd.path()).map(strip_deleted) {
+                if target.is_absolute() {
+                    push(HoldKind::OpenFile, target);
+                }
+            }
+        }
+    }
+    holds
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_holds(_pid: u32) -> Vec<ProcessHold> {
+    Vec::new()
+}
+
+/// Path column of a `/proc/<pid>/maps` line:
+/// `<range> <perms> <offset> <dev> <inode>   <path>`
+#[cfg(target_os = "linux")]
+fn mapped_path(line: &str) -> Option<PathBuf> {
+    let mut rest = line;
+    for _ in 0..5 {
+        rest = rest.trim_start().split_once(' ')?.1;
+    }
+    let path = rest.trim_start();
+    path.starts_with('/')
+        .then(|| strip_deleted(PathBuf::from(path)))
+}
+
+/// The kernel appends " (deleted)" to links whose target was unlinked
+#[cfg(target_os = "linux")]
+fn strip_deleted(path: PathBuf) -> PathBuf {
+    match path
+        .to_str()
+        .and_then(|text| text.strip_suffix(" (deleted)"))
+    {
+        Some(stripped) => PathBuf::from(stripped),
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_mapped_paths() {
+        assert_eq!(
+            mapped_path("7f1c2a000000-7f1c2a021000 r-xp 00000000 08:01 1234   /work/app/target/debug/libfoo.so"),
+            Some(PathBuf::from("/work/app/target/debug/libfoo.so"))
+        );
+        assert_eq!(
+            mapped_path(
+                "7f1c2a000000-7f1c2a021000 r--p 00000000 08:01 1234 /work/my app/lib.so (deleted)"
+            ),
+            Some(PathBuf::from("/work/my app/lib.so"))
+        );
+        assert_eq!(
+            mapped_path(
+                "7ffd5b1e0000-7ffd5b201000 rw-p 00000000 00:00 0                          [stack]"
+            ),
+            None
+        );
+        assert_eq!(
+            mapped_path("7ffd5b1e0000-7ffd5b201000 rw-p 00000000 00:00 0"),
+            None
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_child_process_working_directory() {
+        use std::process::{Command, Stdio};
+        use tempfile::TempDir;
+
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("node_modules");
+        std::fs::create_dir_all(&target).unwrap();
+        let Ok(mut child) = Command::new("sleep")
+            .arg("30")
+            .current_dir(&target)
+            .stdout(Stdio::null())
+            .spawn()
+        else {
+            return;
+        };
+
+        let info = ProjectInfo::new_pending(
+            temp.path().to_path_buf(),
+            crate::ProjectType::NodeJs,
+            target.clone(),
+            chrono::Utc::now(),
+            false,
+        );
+        // Until the forked child execs, its comm is still this test thread's name.
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let hold = loop {
+            let hold = find_process_holding(&info);
+            if hold.as_ref().is_some_and(|hold| hold.command == "sleep")
+                || std::time::Instant::now() > deadline
+            {
+                break hold;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        let snapshot = ProcessSnapshot::capture();
+        child.kill().ok();
+        child.wait().ok();
+
+        let hold = hold.expect("sleep should hold the target as its cwd");
+        assert_eq!(hold.pid, child.id());
+        assert_eq!(hold.command, "sleep");
+        assert_eq!(hold.kind, HoldKind::Cwd);
+        assert_eq!(
+            snapshot.holder_of_project(&info).map(|hold| hold.pid),
+            Some(child.id())
+        );
+        assert!(snapshot.holder_of(&temp.path().join("other")).is_none());
+    }
+}
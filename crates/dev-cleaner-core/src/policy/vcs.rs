@@ -0,0 +1,159 @@
+use crate::scanner::ProjectInfo;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// Counts files tracked by git inside cleanup candidates.
+///
+/// A committed `vendor/` or `dist/` looks like any other build output, but deleting it
+/// damages the working tree. The enclosing repository's index is read once via the
+/// local `git` binary and cached per repository root; candidates outside a repository,
+/// or when `git` is unavailable, report no tracked files.
+#[derive(Debug, Default)]
+pub struct VcsGuard {
+    index_cache: Mutex<HashMap<PathBuf, Arc<BTreeSet<PathBuf>>>>,
+}
+
+impl VcsGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of tracked files at or below `dir`
+    pub fn tracked_files(&self, dir: &Path) -> usize {
+        let Some(repo_root) = find_repo_root(dir) else {
+            return 0;
+        };
+        let Ok(relative) = dir.strip_prefix(&repo_root) else {
+            return 0;
+        };
+        let index = self.index_for(&repo_root);
+        count_under(&index, relative)
+    }
+
+    /// Number of tracked files a candidate would remove: its prune paths for a partial
+    /// clean, otherwise the whole target
+    pub fn tracked_files_for(&self, info: &ProjectInfo) -> usize {
+        if info.is_partial() {
+            return info
+                .prune_paths
+                .iter()
+                .map(|prune| self.tracked_files(&prune.path))
+                .sum();
+        }
+        self.tracked_files(&info.cleanable_dir)
+    }
+
+    fn index_for(&self, repo_root: &Path) -> Arc<BTreeSet<PathBuf>> {
+        if let Some(cached) = self.index_cache.lock().unwrap().get(repo_root) {
+            return Arc::clone(cached);
+        }
+
+        // Read outside the lock; `git ls-files` can take a while on large repositories.
+        let index = Arc::new(read_index(repo_root));
+        let mut cache = self.index_cache.lock().unwrap();
+        Arc::clone(
+            cache
+                .entry(repo_root.to_path_buf())
+                .or_insert_with(|| Arc::clone(&index)),
+        )
+    }
+}
+
+/// Closest ancestor (or `dir` itself) holding a `.git` directory or worktree file
+pub(super) fn find_repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+fn read_index(repo_root: &Path) -> BTreeSet<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["ls-files", "-z", "--cached"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let Ok(output) = output else {
+        return BTreeSet::new();
+    };
+    if !output.status.success() {
+        return BTreeSet::new();
+    }
+
+    output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| PathBuf::from(String::from_utf8_lossy(entry).as_ref()))
+        .collect()
+}
+
+fn count_under(index: &BTreeSet<PathBuf>, relative: &Path) -> usize {
+    if relative.as_os_str().is_empty() {
+        return index.len();
+    }
+    // Paths order component-wise, so everything below `relative` is contiguous.
+    index
+        .range(relative.to_path_buf()..)
+        .take_while(|path| path.starts_with(relative))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git(repo: &Path, args: &[&str]) -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn counts_tracked_files_below_a_directory() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join("vendor/lib")).unwrap();
+        fs::create_dir_all(repo.join("vendor-extra")).unwrap();
+        fs::create_dir_all(repo.join("target")).unwrap();
+        fs::write(repo.join("vendor/lib/a.go"), "package lib").unwrap();
+        fs::write(repo.join("vendor/modules.txt"), "").unwrap();
+        fs::write(repo.join("vendor-extra/b.go"), "package extra").unwrap();
+        fs::write(repo.join("target/out.bin"), "bin").unwrap();
+        if !git(&repo, &["init", "-q"]) {
+            // No usable git in this environment.
+            return;
+        }
+        assert!(git(&repo, &["add", "vendor", "vendor-extra"]));
+
+        let guard = VcsGuard::new();
+        assert_eq!(guard.tracked_files(&repo.join("vendor")), 2);
+        assert_eq!(guard.tracked_files(&repo.join("vendor/lib")), 1);
+        assert_eq!(guard.tracked_files(&repo.join("target")), 0);
+        let mut partial = ProjectInfo::new_pending(
+            repo.clone(),
+            crate::ProjectType::Go,
+            repo.join("vendor"),
+            chrono::Utc::now(),
+            false,
+        );
+        partial.prune_paths = vec![crate::scanner::PrunePath {
+            path: repo.join("vendor/lib"),
+            size: 0,
+            reason: String::new(),
+        }];
+        assert_eq!(guard.tracked_files_for(&partial), 1);
+        assert_eq!(guard.tracked_files(temp.path()), 0);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::evaluation::{EvaluatedProject, SelectionReason, SkipReason};
 use crate::scanner::RiskLevel;
 use crate::ProjectInfo;
+use std::collections::HashSet;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RecommendStrategy {
@@ -28,6 +29,9 @@ pub struct RecommendOptions {
     pub recent_days: i64,
     pub strategy: RecommendStrategy,
     pub max_risk: Option<RiskLevel>,
+    /// Targets are archived rather than deleted: each gives back its size minus a
+    /// tarball of this fraction of it
+    pub archive_ratio: Option<f64>,
 }
 
 impl RecommendOptions {
@@ -40,6 +44,7 @@ impl RecommendOptions {
             recent_days: 7,
             strategy: RecommendStrategy::SafeFirst,
             max_risk: None,
+            archive_ratio: None,
         }
     }
 }
@@ -54,6 +59,8 @@ pub struct BlockedSummary {
     pub recent_bytes: u64,
     pub risk_count: usize,
     pub risk_bytes: u64,
+    pub tracked_count: usize,
+    pub tracked_bytes: u64,
 }
 
 impl BlockedSummary {
@@ -62,6 +69,7 @@ impl BlockedSummary {
             && self.protected_count == 0
             && self.recent_count == 0
             && self.risk_count == 0
+            && self.tracked_count == 0
     }
 }
 
@@ -84,34 +92,61 @@ pub fn recommend_projects(
 
     for project in candidates {
         let mut evaluated = EvaluatedProject::from(project);
-        evaluated.safety.recent = evaluated.info.days_since_modified() < options.recent_days;
+        (evaluated.safety.recent, evaluated.safety.recent_source) =
+            evaluated.info.recent_within(options.recent_days);
 
         if let Some(max_risk) = options.max_risk {
             if evaluated.info.risk_level > max_risk {
                 blocked.risk_count += 1;
-                blocked.risk_bytes = blocked.risk_bytes.saturating_add(evaluated.info.size);
+                blocked.risk_bytes = blocked
+                    .risk_bytes
+                    .saturating_add(evaluated.info.reclaimable_size());
                 blocked_projects.push(evaluated.with_skip_reason(SkipReason::Risk));
                 continue;
             }
         }
 
+        if let Some(hold) = evaluated.info.in_use_by.clone() {
+            blocked.in_use_count += 1;
+            blocked.in_use_bytes = blocked
+                .in_use_bytes
+                .saturating_add(evaluated.info.reclaimable_size());
+            blocked_projects.push(evaluated.with_skip_reason(SkipReason::HeldByProcess(hold)));
+            continue;
+        }
+
         if !options.include_in_use && evaluated.info.in_use {
             blocked.in_use_count += 1;
-            blocked.in_use_bytes = blocked.in_use_bytes.saturating_add(evaluated.info.size);
+            blocked.in_use_bytes = blocked
+                .in_use_bytes
+                .saturating_add(evaluated.info.reclaimable_size());
             blocked_projects.push(evaluated.with_skip_reason(SkipReason::InUse));
             continue;
         }
 
+        if !options.include_protected && evaluated.info.tracked_files > 0 {
+            blocked.tracked_count += 1;
+            blocked.tracked_bytes = blocked
+                .tracked_bytes
+                .saturating_add(evaluated.info.reclaimable_size());
+            blocked_projects.push(evaluated.with_skip_reason(SkipReason::TrackedByVcs));
+            continue;
+        }
+
         if !options.include_protected && evaluated.safety.protected {
             blocked.protected_count += 1;
-            blocked.protected_bytes = blocked.protected_bytes.saturating_add(evaluated.info.size);
+            blocked.protected_bytes = blocked
+                .protected_bytes
+                .saturating_add(evaluated.info.reclaimable_size());
             blocked_projects.push(evaluated.with_skip_reason(SkipReason::Protected));
             continue;
         }
 
         if !options.include_recent && evaluated.safety.recent {
             blocked.recent_count += 1;
-            blocked.recent_bytes = blocked.recent_bytes.saturating_add(evaluated.info.size);
+            blocked.recent_bytes = blocked
+                .recent_bytes
+                .saturating_add(evaluated.info.reclaimable_size());
             blocked_projects.push(evaluated.with_skip_reason(SkipReason::Recent));
             continue;
         }
@@ -122,22 +157,23 @@ pub fn recommend_projects(
     eligible.sort_by(|a, b| {
         score_project(b, options.strategy)
             .cmp(&score_project(a, options.strategy))
-            .then_with(|| b.info.size.cmp(&a.info.size))
-            .then_with(|| {
-                b.info
-                    .days_since_modified()
-                    .cmp(&a.info.days_since_modified())
-            })
+            .then_with(|| b.info.reclaimable_size().cmp(&a.info.reclaimable_size()))
+            .then_with(|| b.info.days_idle().cmp(&a.info.days_idle()))
     });
 
     let mut selected = Vec::new();
     let mut selected_bytes = 0u64;
+    let mut selected_dirs = HashSet::new();
 
     for mut project in eligible {
         if selected_bytes >= options.target_bytes {
             break;
         }
-        selected_bytes = selected_bytes.saturating_add(project.info.size);
+        // A partial clean and the full target overlap; only take the better-scored one.
+        if !selected_dirs.insert(project.info.cleanable_dir.clone()) {
+            continue;
+        }
+        selected_bytes = selected_bytes.saturating_add(freed_bytes(&project.info, options));
         project = project.with_selection_reason(match options.strategy {
             RecommendStrategy::SafeFirst => SelectionReason::StrategySafeFirst,
             RecommendStrategy::Balanced => SelectionReason::StrategyBalanced,
@@ -155,14 +191,24 @@ pub fn recommend_projects(
     }
 }
 
+/// Bytes cleaning `project` gives back, net of its tarball when archiving
+fn freed_bytes(project: &ProjectInfo, options: &RecommendOptions) -> u64 {
+    let size = project.reclaimable_size();
+    match options.archive_ratio {
+        Some(ratio) => size.saturating_sub((size as f64 * ratio.clamp(0.0, 1.0)) as u64),
+        None => size,
+    }
+}
+
 fn score_project(p: &EvaluatedProject, strategy: RecommendStrategy) -> i64 {
     let risk_penalty = match p.info.risk_level {
         RiskLevel::Low => 0,
         RiskLevel::Medium => 30,
         RiskLevel::High => 80,
     };
-    let age_bonus = p.info.days_since_modified().clamp(0, 365);
-    let size_mb = (p.info.size / (1024 * 1024)) as i64;
+    // Idle time counts git activity, so an old target in a busy repo scores as busy.
+    let age_bonus = p.info.days_idle().clamp(0, 365);
+    let size_mb = (p.info.reclaimable_size() / (1024 * 1024)) as i64;
 
     match strategy {
         RecommendStrategy::SafeFirst => age_bonus * 2 + size_mb - risk_penalty * 3,
@@ -199,6 +245,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -222,6 +278,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn partial_and_full_clean_of_same_target_are_exclusive() {
+        let full = mk_project(500 * 1024 * 1024, 100, RiskLevel::Medium);
+        let mut partial = mk_project(100 * 1024 * 1024, 100, RiskLevel::Low);
+        partial.cleanable_dir = full.cleanable_dir.clone();
+        partial.prune_paths = vec![crate::scanner::PrunePath {
+            path: full.cleanable_dir.join("debug/incremental"),
+            size: partial.size,
+            reason: String::new(),
+        }];
+
+        let result = recommend_projects(vec![full, partial], &RecommendOptions::new(u64::MAX));
+        assert_eq!(result.selected.len(), 1);
+        assert_eq!(result.selected_bytes, 500 * 1024 * 1024);
+        assert!(!result.selected[0].info.is_partial());
+    }
+
+    #[test]
+    fn ranks_and_counts_by_reclaimable_bytes() {
+        // A pnpm `node_modules` is mostly hardlinks into the shared store.
+        let mut linked = mk_project(500 * 1024 * 1024, 100, RiskLevel::Low);
+        linked.disk_usage = Some(crate::scanner::DiskUsage {
+            apparent: linked.size,
+            allocated: 4 * 1024 * 1024,
+            reclaimable: 4 * 1024 * 1024,
+        });
+        let plain = mk_project(50 * 1024 * 1024, 100, RiskLevel::Low);
+
+        let mut opts = RecommendOptions::new(40 * 1024 * 1024);
+        opts.strategy = RecommendStrategy::MaxSpace;
+        let result = recommend_projects(vec![linked, plain], &opts);
+        assert_eq!(result.selected.len(), 1);
+        assert_eq!(result.selected[0].info.size, 50 * 1024 * 1024);
+        assert_eq!(result.selected_bytes, 50 * 1024 * 1024);
+    }
+
+    #[test]
+    fn archiving_counts_bytes_net_of_the_tarball() {
+        let projects = vec![
+            mk_project(100 * 1024 * 1024, 100, RiskLevel::Low),
+            mk_project(60 * 1024 * 1024, 90, RiskLevel::Low),
+        ];
+
+        let mut opts = RecommendOptions::new(120 * 1024 * 1024);
+        opts.archive_ratio = Some(0.25);
+        let result = recommend_projects(projects, &opts);
+        assert_eq!(result.selected.len(), 2);
+        assert_eq!(result.selected_bytes, 120 * 1024 * 1024);
+    }
+
     #[test]
     fn blocks_recent_by_default() {
         let projects = vec![mk_project(1024, 1, RiskLevel::Low)];
@@ -248,4 +354,31 @@ mod tests {
         assert_eq!(original.skip_reason, None);
         assert_eq!(original.selection_reason, None);
     }
+
+    #[test]
+    fn git_activity_blocks_and_ranks_by_idle_time() {
+        use crate::policy::{ActivitySource, GitActivity};
+
+        let mut busy = mk_project(200 * 1024 * 1024, 90, RiskLevel::Low);
+        busy.activity = Some(GitActivity {
+            last_commit: Utc::now() - Duration::days(2),
+            head_moved: None,
+            branch_checked_out: None,
+        });
+        let idle = mk_project(100 * 1024 * 1024, 60, RiskLevel::Low);
+
+        let opts = RecommendOptions::new(1);
+        let result = recommend_projects(vec![busy.clone(), idle.clone()], &opts);
+        assert_eq!(result.blocked.recent_count, 1);
+        assert_eq!(
+            result.blocked_projects[0].safety.recent_source,
+            ActivitySource::VerifiedGit
+        );
+
+        let mut opts = RecommendOptions::new(1);
+        opts.include_recent = true;
+        let result = recommend_projects(vec![busy, idle], &opts);
+        assert_eq!(result.selected.len(), 1);
+        assert_eq!(result.selected[0].info.size, 100 * 1024 * 1024);
+    }
 }
@@ -0,0 +1,734 @@
+//! Write-ahead journal of a cleaning run.
+//!
+//! A `clean` or `apply` writes down every target it means to clean before touching
+//! any, marks each one right before its removal starts and again once it is done.
+//! The journal is removed when the run ends, so one left behind belongs to a process
+//! that was killed. Its targets are then reconciled with the file system and the trash
+//! log: a target marked started may be gone, half deleted, or half moved into the
+//! trash. A live run keeps its journal locked, so no one mistakes it for an
+//! unfinished one.
+
+use crate::cleaner::CleanOptions;
+use crate::guard::FileIdentity;
+use crate::scanner::ProjectInfo;
+use crate::trash::{
+    append_trash_log, device_trash_root, load_trash_log, trash_log_path, trashed_path_for,
+    TrashEntry,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalRecord {
+    RunStarted {
+        run_id: String,
+        command: String,
+        started_at: DateTime<Utc>,
+        trash: bool,
+        #[serde(default)]
+        archive: bool,
+        force: bool,
+        include_recent: bool,
+        force_protected: bool,
+    },
+    /// A target the run means to clean, written before any target is touched
+    Planned {
+        project: Box<ProjectInfo>,
+    },
+    /// Trash batch the run moves targets into
+    TrashBatch {
+        root: PathBuf,
+        batch_id: String,
+    },
+    /// The target's removal is about to start
+    Started {
+        path: PathBuf,
+    },
+    Cleaned {
+        path: PathBuf,
+        bytes: u64,
+    },
+    Failed {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+/// Journal a live run writes to; held locked until the run finishes or dies
+#[derive(Debug)]
+pub struct RunJournal {
+    path: PathBuf,
+    file: File,
+    planned: Mutex<HashSet<PathBuf>>,
+}
+
+impl RunJournal {
+    /// Start the journal of a new run
+    pub fn create(
+        root: &Path,
+        run_id: &str,
+        command: &str,
+        options: &CleanOptions,
+    ) -> Result<Self> {
+        fs::create_dir_all(root).with_context(|| {
+            format!("Failed to create run journal directory: {}", root.display())
+        })?;
+        let path = journal_path(root, run_id);
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create run journal: {}", path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+
+        let journal = Self {
+            path,
+            file,
+            planned: Mutex::new(HashSet::new()),
+        };
+        journal.append(
+            &JournalRecord::RunStarted {
+                run_id: run_id.to_string(),
+                command: command.to_string(),
+                started_at: Utc::now(),
+                trash: options.trash,
+                archive: options.archive,
+                force: options.force,
+                include_recent: options.include_recent,
+                force_protected: options.force_protected,
+            },
+            true,
+        )?;
+        Ok(journal)
+    }
+
+    /// Take over the journal of an unfinished run to carry on with it
+    pub fn resume(root: &Path, run_id: &str) -> Result<Self> {
+        let path = journal_path(root, run_id);
+        let file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("No run journal for {run_id}: {}", path.display()))?;
+        if file.try_lock_exclusive().is_err() {
+            anyhow::bail!("Run {run_id} is still in progress in another process");
+        }
+
+        let planned = read_records(&path)?
+            .into_iter()
+            .filter_map(|record| match record {
+                JournalRecord::Planned { project } => Some(project.cleanable_dir),
+                _ => None,
+            })
+            .collect();
+        Ok(Self {
+            path,
+            file,
+            planned: Mutex::new(planned),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record the targets not planned yet; durable before this returns
+    pub(crate) fn plan(&self, projects: &[ProjectInfo]) -> Result<()> {
+        let mut planned = self.planned.lock().unwrap();
+        let mut wrote = false;
+        for project in projects {
+            if planned.insert(project.cleanable_dir.clone()) {
+                self.append(
+                    &JournalRecord::Planned {
+                        project: Box::new(project.clone()),
+                    },
+                    false,
+                )?;
+                wrote = true;
+            }
+        }
+        if wrote {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn trash_batch(&self, root: &Path, batch_id: &str) -> Result<()> {
+        self.append(
+            &JournalRecord::TrashBatch {
+                root: root.to_path_buf(),
+                batch_id: batch_id.to_string(),
+            },
+            true,
+        )
+    }
+
+    /// Mark `path` as being removed; durable before this returns
+    pub(crate) fn started(&self, path: &Path) -> Result<()> {
+        self.append(
+            &JournalRecord::Started {
+                path: path.to_path_buf(),
+            },
+            true,
+        )
+    }
+
+    pub(crate) fn cleaned(&self, path: &Path, bytes: u64) -> Result<()> {
+        self.append(
+            &JournalRecord::Cleaned {
+                path: path.to_path_buf(),
+                bytes,
+            },
+            false,
+        )
+    }
+
+    pub(crate) fn failed(&self, path: &Path, error: &anyhow::Error) -> Result<()> {
+        self.append(
+            &JournalRecord::Failed {
+                path: path.to_path_buf(),
+                error: error.to_string(),
+            },
+            false,
+        )
+    }
+
+    /// The run ended and everything about it is logged elsewhere; drop the journal
+    pub fn finish(&self) -> Result<()> {
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove run journal: {}", self.path.display()))
+    }
+
+    fn append(&self, record: &JournalRecord, sync: bool) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        (&self.file)
+            .write_all(&line)
+            .with_context(|| format!("Failed to write run journal: {}", self.path.display()))?;
+        if sync {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.file
+            .sync_data()
+            .with_context(|| format!("Failed to sync run journal: {}", self.path.display()))
+    }
+}
+
+/// How far an unfinished run got with one target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetState {
+    Planned,
+    /// Removal started but was never reported done
+    Started,
+    Cleaned {
+        bytes: u64,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalTarget {
+    pub project: ProjectInfo,
+    pub state: TargetState,
+}
+
+/// A run whose process died before it finished
+#[derive(Debug, Clone)]
+pub struct UnfinishedRun {
+    pub run_id: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    pub trash: bool,
+    pub archive: bool,
+    pub force: bool,
+    pub include_recent: bool,
+    pub force_protected: bool,
+    pub targets: Vec<JournalTarget>,
+    /// Trash batches the run moved targets into, as `(trash root, batch id)`
+    pub trash_batches: Vec<(PathBuf, String)>,
+    journal_path: PathBuf,
+}
+
+impl UnfinishedRun {
+    fn load(path: &Path) -> Result<Option<Self>> {
+        let mut records = read_records(path)?.into_iter();
+        let Some(JournalRecord::RunStarted {
+            run_id,
+            command,
+            started_at,
+            trash,
+            archive,
+            force,
+            include_recent,
+            force_protected,
+        }) = records.next()
+        else {
+            return Ok(None);
+        };
+
+        let mut run = Self {
+            run_id,
+            command,
+            started_at,
+            trash,
+            archive,
+            force,
+            include_recent,
+            force_protected,
+            targets: Vec::new(),
+            trash_batches: Vec::new(),
+            journal_path: path.to_path_buf(),
+        };
+        let mut index = HashMap::new();
+        for record in records {
+            let (path, state) = match record {
+                JournalRecord::Planned { project } => {
+                    index.insert(project.cleanable_dir.clone(), run.targets.len());
+                    run.targets.push(JournalTarget {
+                        project: *project,
+                        state: TargetState::Planned,
+                    });
+                    continue;
+                }
+                JournalRecord::TrashBatch { root, batch_id } => {
+                    run.trash_batches.push((root, batch_id));
+                    continue;
+                }
+                JournalRecord::RunStarted { .. } => continue,
+                JournalRecord::Started { path } => (path, TargetState::Started),
+                JournalRecord::Cleaned { path, bytes } => (path, TargetState::Cleaned { bytes }),
+                JournalRecord::Failed { path, error } => (path, TargetState::Failed { error }),
+            };
+            if let Some(&target) = index.get(&path) {
+                run.targets[target].state = state;
+            }
+        }
+        Ok(Some(run))
+    }
+
+    /// Options that clean the rest of the run the way it was started
+    pub fn clean_options(&self) -> CleanOptions {
+        CleanOptions {
+            trash: self.trash,
+            archive: self.archive,
+            force: self.force,
+            include_recent: self.include_recent,
+            force_protected: self.force_protected,
+            run_id: Some(self.run_id.clone()),
+            ..CleanOptions::default()
+        }
+    }
+
+    /// Work out what the run got done from the file system and the trash log.
+    ///
+    /// Nothing is changed; [`Reconciliation::repair_trash`] applies the trash fixes.
+    pub fn reconcile(&self) -> Result<Reconciliation> {
+        let mut logged = HashSet::new();
+        let mut batch_roots = HashMap::new();
+        for (root, batch_id) in &self.trash_batches {
+            if batch_roots.contains_key(batch_id) {
+                continue;
+            }
+            batch_roots.insert(batch_id.clone(), root.clone());
+            logged.extend(
+                load_trash_log(&trash_log_path(root))?
+                    .into_iter()
+                    .filter(|entry| &entry.batch_id == batch_id)
+                    .map(|entry| entry.trashed_path),
+            );
+        }
+
+        let mut reconciliation = Reconciliation {
+            batch_roots,
+            ..Reconciliation::default()
+        };
+        for target in &self.targets {
+            let project = &target.project;
+            let exists = fs::symlink_metadata(&project.cleanable_dir).is_ok();
+            match &target.state {
+                TargetState::Cleaned { bytes } => {
+                    reconciliation.completed.push(CompletedTarget {
+                        project: project.clone(),
+                        bytes_freed: *bytes,
+                    });
+                }
+                // Gone before the run got to it; not this run's doing.
+                TargetState::Planned | TargetState::Failed { .. } if !exists => {}
+                TargetState::Planned | TargetState::Failed { .. } => {
+                    reconciliation.remaining.push(project.clone());
+                }
+                TargetState::Started => {
+                    if self.trash {
+                        if project.is_partial() {
+                            for prune in &project.prune_paths {
+                                self.check_move(
+                                    &prune.path,
+                                    prune.size,
+                                    Some(&project.cleanable_dir),
+                                    &logged,
+                                    &mut reconciliation,
+                                );
+                            }
+                        } else {
+                            self.check_move(
+                                &project.cleanable_dir,
+                                project.size,
+                                None,
+                                &logged,
+                                &mut reconciliation,
+                            );
+                        }
+                    }
+                    // A pruned target stays in place, so its prunes are redone; paths
+                    // already gone are skipped then.
+                    if exists {
+                        reconciliation.remaining.push(project.clone());
+                    } else {
+                        reconciliation.completed.push(CompletedTarget {
+                            project: project.clone(),
+                            bytes_freed: project.size,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(reconciliation)
+    }
+
+    /// Look for `original` in the run's trash batches, after a move that may not have
+    /// finished.
+    ///
+    /// Moves into a freedesktop.org trash are not looked for: one that never made it
+    /// into the log is still in the desktop's trash with its `.trashinfo`.
+    fn check_move(
+        &self,
+        original: &Path,
+        size: u64,
+        pruned_from: Option<&Path>,
+        logged: &HashSet<PathBuf>,
+        reconciliation: &mut Reconciliation,
+    ) {
+        let original_exists = fs::symlink_metadata(original).is_ok();
+        for (root, batch_id) in &self.trash_batches {
+            // The move went to the root on the original's file system, unless that
+            // could not be used.
+            let device_root = device_trash_root(root, original).map(|(_, path)| path);
+            for trash_root in device_root.iter().chain([root]) {
+                let trashed_path = trashed_path_for(trash_root, batch_id, original);
+                if logged.contains(&trashed_path) || fs::symlink_metadata(&trashed_path).is_err() {
+                    continue;
+                }
+                if original_exists {
+                    // The original is still the valid copy until a move completes.
+                    reconciliation.partial_copies.push(trashed_path);
+                } else {
+                    reconciliation.unlogged.push(TrashEntry {
+                        batch_id: batch_id.clone(),
+                        created_at: Utc::now(),
+                        original_path: original.to_path_buf(),
+                        identity: FileIdentity::of_path(&trashed_path),
+                        trashed_path,
+                        size,
+                        tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+                        pruned_from: pruned_from.map(Path::to_path_buf),
+                        trash_root: (trash_root != root).then(|| trash_root.clone()),
+                        trash_info: None,
+                    });
+                }
+                return;
+            }
+        }
+    }
+
+    /// Drop the journal, once everything the run did is accounted for elsewhere
+    pub fn close(&self) -> Result<()> {
+        let file = File::open(&self.journal_path).with_context(|| {
+            format!(
+                "Failed to open run journal: {}",
+                self.journal_path.display()
+            )
+        })?;
+        if file.try_lock_exclusive().is_err() {
+            anyhow::bail!("Run {} is still in progress", self.run_id);
+        }
+        fs::remove_file(&self.journal_path).with_context(|| {
+            format!(
+                "Failed to remove run journal: {}",
+                self.journal_path.display()
+            )
+        })
+    }
+}
+
+/// A target an unfinished run is known to have cleaned
+#[derive(Debug, Clone)]
+pub struct CompletedTarget {
+    pub project: ProjectInfo,
+    pub bytes_freed: u64,
+}
+
+/// What an unfinished run got done, and what is left of it
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    pub completed: Vec<CompletedTarget>,
+    /// Targets still to clean: never reached, failed, or left half removed
+    pub remaining: Vec<ProjectInfo>,
+    /// Moves into the trash that finished but never made it into the trash log
+    pub unlogged: Vec<TrashEntry>,
+    /// Unfinished cross-device copies into the trash; their originals are intact
+    pub partial_copies: Vec<PathBuf>,
+    batch_roots: HashMap<String, PathBuf>,
+}
+
+impl Reconciliation {
+    /// Log the unlogged moves, so they can be restored, and remove the partial copies
+    pub fn repair_trash(&self) -> Result<()> {
+        for entry in &self.unlogged {
+            let root = self
+                .batch_roots
+                .get(&entry.batch_id)
+                .with_context(|| format!("Unknown trash batch {}", entry.batch_id))?;
+            append_trash_log(&trash_log_path(root), entry)?;
+        }
+        for copy in &self.partial_copies {
+            fs::remove_dir_all(copy)
+                .or_else(|_| fs::remove_file(copy))
+                .with_context(|| format!("Failed to remove partial copy: {}", copy.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs whose journal was left behind by a process that died, oldest first
+pub fn unfinished_runs(root: &Path) -> Result<Vec<UnfinishedRun>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to read run journals: {}", root.display()))
+        }
+    };
+
+    let mut runs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != JOURNAL_EXTENSION) {
+            continue;
+        }
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        if file.try_lock_shared().is_err() {
+            // Its run is still going.
+            continue;
+        }
+        if let Some(run) = UnfinishedRun::load(&path)? {
+            runs.push(run);
+        }
+    }
+    runs.sort_by_key(|run| run.started_at);
+    Ok(runs)
+}
+
+pub fn default_run_journal_root() -> PathBuf {
+    if let Ok(custom) = std::env::var("DEV_CLEANER_RUN_JOURNAL_DIR") {
+        return PathBuf::from(custom);
+    }
+
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("dev-cleaner")
+        .join("runs")
+}
+
+fn journal_path(root: &Path, run_id: &str) -> PathBuf {
+    root.join(format!("{run_id}.{JOURNAL_EXTENSION}"))
+}
+
+fn read_records(path: &Path) -> Result<Vec<JournalRecord>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read run journal: {}", path.display()))?;
+    // A record cut short by the crash is the last line; skip it.
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::ProjectType;
+    use crate::trash::TrashManager;
+    use tempfile::TempDir;
+
+    fn project(path: PathBuf, size: u64) -> ProjectInfo {
+        ProjectInfo {
+            root: path.parent().unwrap().to_path_buf(),
+            project_type: ProjectType::NodeJs,
+            project_name: None,
+            category: crate::scanner::Category::Deps,
+            risk_level: crate::scanner::RiskLevel::Low,
+            confidence: crate::scanner::Confidence::High,
+            matched_rule: None,
+            cleanable_dir: path,
+            size,
+            size_calculated: true,
+            last_modified: Utc::now(),
+            in_use: false,
+            protected: false,
+            protected_by: None,
+            recent: false,
+            selection_reason: None,
+            skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
+        }
+    }
+
+    #[test]
+    fn live_journals_are_not_unfinished() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("runs");
+        let target = temp.path().join("app/node_modules");
+        fs::create_dir_all(&target).unwrap();
+
+        let journal =
+            RunJournal::create(&root, "run-1", "clean", &CleanOptions::default()).unwrap();
+        journal.plan(&[project(target.clone(), 10)]).unwrap();
+        assert!(unfinished_runs(&root).unwrap().is_empty());
+
+        drop(journal);
+        let runs = unfinished_runs(&root).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].run_id, "run-1");
+        assert_eq!(runs[0].command, "clean");
+        assert_eq!(runs[0].targets[0].state, TargetState::Planned);
+
+        let resumed = RunJournal::resume(&root, "run-1").unwrap();
+        resumed.plan(&[project(target, 10)]).unwrap();
+        assert!(RunJournal::resume(&root, "run-1").is_err());
+        resumed.finish().unwrap();
+        drop(resumed);
+        assert!(unfinished_runs(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reconcile_sorts_targets_by_what_the_run_got_done() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("runs");
+        let dir = |name: &str| {
+            let path = temp.path().join(name).join("node_modules");
+            fs::create_dir_all(&path).unwrap();
+            path
+        };
+        let (done, gone, half, untouched) = (dir("done"), dir("gone"), dir("half"), dir("todo"));
+        let projects = [
+            project(done.clone(), 1),
+            project(gone.clone(), 2),
+            project(half.clone(), 4),
+            project(untouched.clone(), 8),
+        ];
+
+        let journal =
+            RunJournal::create(&root, "run-2", "apply", &CleanOptions::default()).unwrap();
+        journal.plan(&projects).unwrap();
+        for path in [&done, &gone, &half] {
+            journal.started(path).unwrap();
+        }
+        fs::remove_dir_all(&done).unwrap();
+        journal.cleaned(&done, 1).unwrap();
+        fs::remove_dir_all(&gone).unwrap();
+        drop(journal);
+
+        let run = unfinished_runs(&root).unwrap().remove(0);
+        let reconciliation = run.reconcile().unwrap();
+        let completed = reconciliation
+            .completed
+            .iter()
+            .map(|target| (target.project.cleanable_dir.clone(), target.bytes_freed))
+            .collect::<Vec<_>>();
+        assert_eq!(completed, vec![(done, 1), (gone, 2)]);
+        let remaining = reconciliation
+            .remaining
+            .iter()
+            .map(|project| project.cleanable_dir.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![half, untouched]);
+        assert!(run.clean_options().run_id.as_deref() == Some("run-2"));
+    }
+
+    #[test]
+    fn reconcile_logs_finished_moves_and_drops_partial_copies() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("runs");
+        let trash = TrashManager::new_with_root(temp.path().join("trash")).unwrap();
+        let moved = temp.path().join("a/node_modules");
+        let copying = temp.path().join("b/node_modules");
+        fs::create_dir_all(&copying).unwrap();
+
+        let options = CleanOptions {
+            trash: true,
+            ..CleanOptions::default()
+        };
+        let journal = RunJournal::create(&root, "run-3", "clean", &options).unwrap();
+        journal
+            .plan(&[project(moved.clone(), 3), project(copying.clone(), 5)])
+            .unwrap();
+        journal.trash_batch(trash.root(), &trash.batch_id).unwrap();
+        journal.started(&moved).unwrap();
+        journal.started(&copying).unwrap();
+        drop(journal);
+        // Killed after renaming one target but before logging it, and partway
+        // through copying the other.
+        let moved_to = trashed_path_for(trash.root(), &trash.batch_id, &moved);
+        let copied_to = trashed_path_for(trash.root(), &trash.batch_id, &copying);
+        fs::create_dir_all(&moved_to).unwrap();
+        fs::create_dir_all(&copied_to).unwrap();
+
+        let run = unfinished_runs(&root).unwrap().remove(0);
+        let reconciliation = run.reconcile().unwrap();
+        assert_eq!(reconciliation.completed.len(), 1);
+        assert_eq!(reconciliation.remaining.len(), 1);
+        assert_eq!(reconciliation.unlogged.len(), 1);
+        assert_eq!(reconciliation.partial_copies, vec![copied_to.clone()]);
+
+        reconciliation.repair_trash().unwrap();
+        let log = trash.load_log().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].original_path, moved);
+        assert_eq!(log[0].trashed_path, moved_to);
+        assert!(!copied_to.exists());
+        assert!(copying.exists());
+
+        run.close().unwrap();
+        assert!(unfinished_runs(&root).unwrap().is_empty());
+    }
+}
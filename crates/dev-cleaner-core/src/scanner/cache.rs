@@ -0,0 +1,461 @@
+use super::size_calculator::DirUsage;
+use super::{ProjectInfo, ProjectType};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_SCAN_CACHE_FILENAME: &str = "scan-cache.json";
+
+/// Bumped whenever the meaning of a cached entry changes; older indexes are discarded.
+const SCAN_CACHE_VERSION: u32 = 2;
+
+/// Directories modified this close to the scan are not cached: a later change within the
+/// same mtime tick would otherwise go unnoticed.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Modification time of one directory when an entry was recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DirStamp {
+    path: PathBuf,
+    mtime_ns: u64,
+}
+
+impl DirStamp {
+    pub(crate) fn new(path: PathBuf, metadata: &Metadata) -> Option<Self> {
+        Some(Self {
+            path,
+            mtime_ns: mtime_ns(metadata)?,
+        })
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        Self::new(path.to_path_buf(), &fs::symlink_metadata(path).ok()?)
+    }
+
+    /// Whether the directory at `path` still has the recorded mtime
+    fn is_current_at(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path)
+            .ok()
+            .and_then(|metadata| mtime_ns(&metadata))
+            == Some(self.mtime_ns)
+    }
+
+    fn is_racy(&self, now_ns: u64) -> bool {
+        is_racy(self.mtime_ns, now_ns)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TargetEntry {
+    /// Rules and scanner settings the match was made under
+    fingerprint: u64,
+    /// The target and its ancestors up to the project root, where markers live
+    stamps: Vec<DirStamp>,
+    info: ProjectInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SizeEntry {
+    usage: DirUsage,
+    /// Every directory below the measured one (relative paths); files can only be added,
+    /// removed or renamed by changing one of them
+    dirs: Vec<DirStamp>,
+}
+
+/// A directory the walk listed and found not to be a target, with what the walk took
+/// from its listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ListedDir {
+    /// Scanner settings and marked directories above it the listing was matched under
+    pub context: u64,
+    pub mtime_ns: u64,
+    /// Names of its subdirectories, before exclusions and mount checks
+    pub subdirs: Vec<String>,
+    /// It holds a `Cargo.toml`
+    pub has_manifest: bool,
+    /// Markers it has for the directories below it
+    pub project_types: Vec<ProjectType>,
+    pub custom_roots: Vec<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanIndex {
+    version: u32,
+    #[serde(default)]
+    targets: HashMap<PathBuf, TargetEntry>,
+    #[serde(default)]
+    sizes: HashMap<PathBuf, SizeEntry>,
+    #[serde(default)]
+    dirs: HashMap<PathBuf, ListedDir>,
+}
+
+/// Cache effectiveness of one run, reported in the perf trace
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanCacheStats {
+    /// Cached entries were ignored (`--refresh`)
+    pub refresh: bool,
+    /// Targets taken from the cache / detected from scratch
+    pub target_hits: usize,
+    pub target_misses: usize,
+    /// Sizes taken from the cache / measured by walking the directory
+    pub size_hits: usize,
+    pub size_misses: usize,
+    /// Directories the walk took from the cache / listed
+    pub dir_hits: usize,
+    pub dir_misses: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_error: Option<String>,
+}
+
+/// Persistent scan index reused between runs.
+///
+/// Stores each detected target together with the mtimes of the directories its match
+/// depends on, each measured size together with the mtimes of every directory below
+/// it, and for the single-pass walk each directory that is not a target together with
+/// its mtime and subdirectories. An entry is reused only while all recorded mtimes are
+/// unchanged, so checking it costs a `stat` per directory instead of listing it or a
+/// `stat` per file. Rewriting a file in place leaves its directory's mtime alone;
+/// `refresh` ignores every cached entry for runs that must not trust it.
+///
+/// The index is disposable: a missing, unreadable or outdated file starts an empty one.
+#[derive(Debug)]
+pub struct ScanCache {
+    path: PathBuf,
+    refresh: bool,
+    previous: ScanIndex,
+    current: Mutex<ScanIndex>,
+    stale_targets: Mutex<HashSet<PathBuf>>,
+    stale_sizes: Mutex<HashSet<PathBuf>>,
+    stale_dirs: Mutex<HashSet<PathBuf>>,
+    target_hits: AtomicUsize,
+    target_misses: AtomicUsize,
+    size_hits: AtomicUsize,
+    size_misses: AtomicUsize,
+    dir_hits: AtomicUsize,
+    dir_misses: AtomicUsize,
+}
+
+impl ScanCache {
+    /// Load the index at `path`; with `refresh`, entries are recomputed and overwritten.
+    pub fn open(path: impl Into<PathBuf>, refresh: bool) -> Self {
+        let path = path.into();
+        let previous = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ScanIndex>(&content).ok())
+            .filter(|index| index.version == SCAN_CACHE_VERSION)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            refresh,
+            previous,
+            current: Mutex::new(ScanIndex::default()),
+            stale_targets: Mutex::new(HashSet::new()),
+            stale_sizes: Mutex::new(HashSet::new()),
+            stale_dirs: Mutex::new(HashSet::new()),
+            target_hits: AtomicUsize::new(0),
+            target_misses: AtomicUsize::new(0),
+            size_hits: AtomicUsize::new(0),
+            size_misses: AtomicUsize::new(0),
+            dir_hits: AtomicUsize::new(0),
+            dir_misses: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn stats(&self) -> ScanCacheStats {
+        ScanCacheStats {
+            refresh: self.refresh,
+            target_hits: self.target_hits.load(Ordering::Relaxed),
+            target_misses: self.target_misses.load(Ordering::Relaxed),
+            size_hits: self.size_hits.load(Ordering::Relaxed),
+            size_misses: self.size_misses.load(Ordering::Relaxed),
+            dir_hits: self.dir_hits.load(Ordering::Relaxed),
+            dir_misses: self.dir_misses.load(Ordering::Relaxed),
+            save_error: None,
+        }
+    }
+
+    /// Previously detected target at `dir`, if nothing it depends on has changed
+    pub(crate) fn cached_target(&self, dir: &Path, fingerprint: u64) -> Option<ProjectInfo> {
+        if self.refresh {
+            return None;
+        }
+        let entry = self.previous.targets.get(dir)?;
+        if entry.fingerprint != fingerprint
+            || !entry
+                .stamps
+                .iter()
+                .all(|stamp| stamp.is_current_at(&stamp.path))
+        {
+            self.stale_targets.lock().unwrap().insert(dir.to_path_buf());
+            return None;
+        }
+        self.target_hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.info.clone())
+    }
+
+    pub(crate) fn store_target(&self, info: &ProjectInfo, fingerprint: u64) {
+        self.target_misses.fetch_add(1, Ordering::Relaxed);
+        let dir = &info.cleanable_dir;
+        let chain = if dir.starts_with(&info.root) {
+            dir.ancestors()
+                .take_while(|ancestor| ancestor.starts_with(&info.root))
+                .collect::<Vec<_>>()
+        } else {
+            dir.ancestors().take(2).collect()
+        };
+        let Some(stamps) = chain
+            .into_iter()
+            .map(DirStamp::read)
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+        if stamps.iter().any(|stamp| stamp.is_racy(now_ns())) {
+            return;
+        }
+
+        let entry = TargetEntry {
+            fingerprint,
+            stamps,
+            info: info.clone(),
+        };
+        self.current
+            .lock()
+            .unwrap()
+            .targets
+            .insert(dir.clone(), entry);
+    }
+
+    /// Previously measured usage of `dir`, if no directory below it has changed
+    pub(crate) fn cached_size(&self, dir: &Path) -> Option<DirUsage> {
+        if self.refresh {
+            return None;
+        }
+        let entry = self.previous.sizes.get(dir)?;
+        if !entry
+            .dirs
+            .iter()
+            .all(|stamp| stamp.is_current_at(&dir.join(&stamp.path)))
+        {
+            self.stale_sizes.lock().unwrap().insert(dir.to_path_buf());
+            return None;
+        }
+        self.size_hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.usage.clone())
+    }
+
+    pub(crate) fn store_size(&self, dir: &Path, usage: &DirUsage, dirs: Vec<DirStamp>) {
+        self.size_misses.fetch_add(1, Ordering::Relaxed);
+        let now = now_ns();
+        if dirs.is_empty() || dirs.iter().any(|stamp| stamp.is_racy(now)) {
+            return;
+        }
+        self.current.lock().unwrap().sizes.insert(
+            dir.to_path_buf(),
+            SizeEntry {
+                usage: usage.clone(),
+                dirs,
+            },
+        );
+    }
+
+    /// Previous listing of `dir`, if it was matched under `context` and has not changed
+    /// since
+    pub(crate) fn cached_dir(&self, dir: &Path, context: u64) -> Option<ListedDir> {
+        if self.refresh {
+            return None;
+        }
+        let listed = self.previous.dirs.get(dir)?;
+        let mtime_ns = fs::symlink_metadata(dir)
+            .ok()
+            .and_then(|metadata| mtime_ns(&metadata));
+        if listed.context != context || mtime_ns != Some(listed.mtime_ns) {
+            self.stale_dirs.lock().unwrap().insert(dir.to_path_buf());
+            return None;
+        }
+        self.dir_hits.fetch_add(1, Ordering::Relaxed);
+        // Kept as recorded this run, so saving does not check it again.
+        self.current
+            .lock()
+            .unwrap()
+            .dirs
+            .insert(dir.to_path_buf(), listed.clone());
+        Some(listed.clone())
+    }
+
+    pub(crate) fn store_dir(&self, dir: &Path, listed: ListedDir) {
+        self.dir_misses.fetch_add(1, Ordering::Relaxed);
+        if is_racy(listed.mtime_ns, now_ns()) {
+            return;
+        }
+        self.current
+            .lock()
+            .unwrap()
+            .dirs
+            .insert(dir.to_path_buf(), listed);
+    }
+
+    /// Write the index: entries recorded this run, plus earlier entries that were not
+    /// found stale and whose directories still exist.
+    pub fn save(&self) -> Result<()> {
+        let current = self.current.lock().unwrap();
+        let stale_targets = self.stale_targets.lock().unwrap();
+        let stale_sizes = self.stale_sizes.lock().unwrap();
+        let stale_dirs = self.stale_dirs.lock().unwrap();
+
+        let mut index = ScanIndex {
+            version: SCAN_CACHE_VERSION,
+            targets: self
+                .previous
+                .targets
+                .iter()
+                .filter(|(path, _)| {
+                    !current.targets.contains_key(*path)
+                        && !stale_targets.contains(*path)
+                        && path.exists()
+                })
+                .map(|(path, entry)| (path.clone(), entry.clone()))
+                .collect(),
+            sizes: self
+                .previous
+                .sizes
+                .iter()
+                .filter(|(path, _)| {
+                    !current.sizes.contains_key(*path)
+                        && !stale_sizes.contains(*path)
+                        && path.exists()
+                })
+                .map(|(path, entry)| (path.clone(), entry.clone()))
+                .collect(),
+            dirs: self
+                .previous
+                .dirs
+                .iter()
+                .filter(|(path, _)| {
+                    !current.dirs.contains_key(*path)
+                        && !stale_dirs.contains(*path)
+                        && path.exists()
+                })
+                .map(|(path, listed)| (path.clone(), listed.clone()))
+                .collect(),
+        };
+        index.targets.extend(
+            current
+                .targets
+                .iter()
+                .map(|(path, entry)| (path.clone(), entry.clone())),
+        );
+        index.sizes.extend(
+            current
+                .sizes
+                .iter()
+                .map(|(path, entry)| (path.clone(), entry.clone())),
+        );
+        index.dirs.extend(
+            current
+                .dirs
+                .iter()
+                .map(|(path, listed)| (path.clone(), listed.clone())),
+        );
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+        // Concurrent runs each write their own file and the last rename wins.
+        let tmp = self
+            .path
+            .with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_vec(&index)?)
+            .with_context(|| format!("Failed to write scan cache: {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write scan cache: {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+pub fn default_scan_cache_path() -> PathBuf {
+    if let Ok(custom) = std::env::var("DEV_CLEANER_SCAN_CACHE") {
+        return PathBuf::from(custom);
+    }
+
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("dev-cleaner")
+        .join(DEFAULT_SCAN_CACHE_FILENAME)
+}
+
+pub(crate) fn mtime_ns(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+fn is_racy(mtime_ns: u64, now_ns: u64) -> bool {
+    mtime_ns.saturating_add(RACY_WINDOW.as_nanos() as u64) > now_ns
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn usage(apparent: u64) -> DirUsage {
+        DirUsage {
+            apparent,
+            single_link: apparent,
+            shared: Vec::new(),
+        }
+    }
+
+    fn stamps_of(dir: &Path) -> Vec<DirStamp> {
+        vec![DirStamp::new(PathBuf::new(), &fs::symlink_metadata(dir).unwrap()).unwrap()]
+    }
+
+    #[test]
+    fn persists_sizes_and_drops_stale_or_racy_entries() {
+        let temp = TempDir::new().unwrap();
+        let old = temp.path().join("old");
+        let fresh = temp.path().join("fresh");
+        fs::create_dir_all(&old).unwrap();
+        fs::create_dir_all(&fresh).unwrap();
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::open(&old).unwrap().set_modified(past).unwrap();
+        let path = temp.path().join("cache/scan-cache.json");
+
+        let cache = ScanCache::open(&path, false);
+        cache.store_size(&old, &usage(42), stamps_of(&old));
+        cache.store_size(&fresh, &usage(7), stamps_of(&fresh));
+        cache.save().unwrap();
+
+        let cache = ScanCache::open(&path, false);
+        assert_eq!(cache.cached_size(&old), Some(usage(42)));
+        assert_eq!(cache.cached_size(&fresh), None);
+        assert!(ScanCache::open(&path, true).cached_size(&old).is_none());
+
+        fs::write(old.join("new-file"), "x").unwrap();
+        let cache = ScanCache::open(&path, false);
+        assert_eq!(cache.cached_size(&old), None);
+        cache.save().unwrap();
+        assert!(ScanCache::open(&path, false).previous.sizes.is_empty());
+        assert_eq!(cache.stats().size_hits, 0);
+    }
+}
@@ -0,0 +1,745 @@
+//! Cargo workspace and target-dir resolution.
+//!
+//! Cargo does not always build into `<crate>/target`: workspace members share the
+//! workspace root's target, and `CARGO_TARGET_DIR` / `build.target-dir` in
+//! `.cargo/config.toml` can redirect it anywhere. This module resolves the
+//! effective target directory for a crate the same way cargo does (closest
+//! config wins, environment overrides config).
+//!
+//! It also finds stale content inside a target dir (cargo-sweep style) so an
+//! active project can be trimmed without paying for a full rebuild.
+
+use super::PruneCandidate;
+use globset::GlobBuilder;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Effective build output location for one crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoTarget {
+    pub crate_root: PathBuf,
+    pub workspace_root: PathBuf,
+    pub target_dir: PathBuf,
+    /// Whether the target dir differs from `<workspace_root>/target`
+    pub redirected: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Manifest {
+    /// `[workspace]` table present
+    workspace: Option<WorkspaceTable>,
+    /// Explicit `package.workspace = "..."` path
+    package_workspace: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct WorkspaceTable {
+    members: Vec<String>,
+    exclude: Vec<String>,
+}
+
+/// Resolves effective target dirs, caching manifest and config lookups.
+pub struct CargoTargetResolver {
+    target_dir_env: Option<PathBuf>,
+    cargo_home: Option<PathBuf>,
+    manifests: HashMap<PathBuf, Option<Manifest>>,
+    configs: HashMap<PathBuf, Option<PathBuf>>,
+}
+
+impl CargoTargetResolver {
+    /// Resolver honouring `CARGO_TARGET_DIR` and `CARGO_HOME` from the environment.
+    pub fn from_env() -> Self {
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")));
+        Self::new(
+            std::env::var_os("CARGO_TARGET_DIR").map(PathBuf::from),
+            cargo_home,
+        )
+    }
+
+    pub fn new(target_dir_env: Option<PathBuf>, cargo_home: Option<PathBuf>) -> Self {
+        Self {
+            target_dir_env,
+            cargo_home,
+            manifests: HashMap::new(),
+            configs: HashMap::new(),
+        }
+    }
+
+    /// Resolve the effective target dir for the crate (or virtual manifest) at `crate_root`.
+    ///
+    /// A relative `CARGO_TARGET_DIR` is taken relative to the workspace root, as if
+    /// cargo were invoked from there.
+    pub fn resolve(&mut self, crate_root: &Path) -> CargoTarget {
+        let workspace_root = self.workspace_root(crate_root);
+        let target_dir = match &self.target_dir_env {
+            Some(dir) if dir.is_absolute() => dir.clone(),
+            Some(dir) => workspace_root.join(dir),
+            None => self
+                .configured_target_dir(crate_root)
+                .unwrap_or_else(|| workspace_root.join("target")),
+        };
+        let redirected = target_dir != workspace_root.join("target");
+
+        CargoTarget {
+            crate_root: crate_root.to_path_buf(),
+            workspace_root,
+            target_dir,
+            redirected,
+        }
+    }
+
+    fn workspace_root(&mut self, crate_root: &Path) -> PathBuf {
+        if let Some(explicit) = self
+            .manifest(crate_root)
+            .and_then(|manifest| manifest.package_workspace)
+        {
+            return normalize(&crate_root.join(explicit));
+        }
+
+        for ancestor in crate_root.ancestors() {
+            let Some(workspace) = self.manifest(ancestor).and_then(|m| m.workspace) else {
+                continue;
+            };
+            if ancestor == crate_root {
+                return ancestor.to_path_buf();
+            }
+            let relative = crate_root
+                .strip_prefix(ancestor)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let is_member = workspace
+                .members
+                .iter()
+                .any(|pattern| path_glob_matches(pattern, &relative))
+                && !workspace
+                    .exclude
+                    .iter()
+                    .any(|pattern| path_glob_matches(pattern, &relative));
+            // Cargo stops at the first `[workspace]` it finds; a non-member is its own root.
+            return if is_member {
+                ancestor.to_path_buf()
+            } else {
+                crate_root.to_path_buf()
+            };
+        }
+
+        crate_root.to_path_buf()
+    }
+
+    fn manifest(&mut self, dir: &Path) -> Option<Manifest> {
+        if let Some(cached) = self.manifests.get(dir) {
+            return cached.clone();
+        }
+        let parsed = parse_manifest(&dir.join("Cargo.toml"));
+        self.manifests.insert(dir.to_path_buf(), parsed.clone());
+        parsed
+    }
+
+    /// `build.target-dir` from the closest `.cargo/config(.toml)`, then `$CARGO_HOME`.
+    fn configured_target_dir(&mut self, start: &Path) -> Option<PathBuf> {
+        for ancestor in start.ancestors() {
+            if let Some(dir) = self.config_target_dir(ancestor) {
+                return Some(dir);
+            }
+        }
+        let cargo_home = self.cargo_home.clone()?;
+        for name in ["config.toml", "config"] {
+            if let Some(dir) = read_config_target_dir(&cargo_home.join(name)) {
+                let base = cargo_home.parent().unwrap_or(&cargo_home);
+                return Some(normalize(&base.join(dir)));
+            }
+        }
+        None
+    }
+
+    fn config_target_dir(&mut self, dir: &Path) -> Option<PathBuf> {
+        if let Some(cached) = self.configs.get(dir) {
+            return cached.clone();
+        }
+        let resolved = ["config.toml", "config"].iter().find_map(|name| {
+            read_config_target_dir(&dir.join(".cargo").join(name))
+                .map(|target| normalize(&dir.join(target)))
+        });
+        self.configs.insert(dir.to_path_buf(), resolved.clone());
+        resolved
+    }
+}
+
+fn parse_manifest(path: &Path) -> Option<Manifest> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: toml::Value = toml::from_str(&content).ok()?;
+    let strings = |table: &toml::Value, key: &str| -> Vec<String> {
+        table
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Some(Manifest {
+        workspace: value.get("workspace").map(|workspace| WorkspaceTable {
+            members: strings(workspace, "members"),
+            exclude: strings(workspace, "exclude"),
+        }),
+        package_workspace: value
+            .get("package")
+            .and_then(|package| package.get("workspace"))
+            .and_then(|workspace| workspace.as_str())
+            .map(str::to_string),
+    })
+}
+
+fn read_config_target_dir(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: toml::Value = toml::from_str(&content).ok()?;
+    value
+        .get("build")?
+        .get("target-dir")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn path_glob_matches(pattern: &str, relative_path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/').replace('\\', "/");
+    GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher().is_match(relative_path))
+        .unwrap_or(false)
+}
+
+/// One compilation unit under `<profile>/.fingerprint/<name>-<hash>`.
+struct FingerprintUnit {
+    dir: PathBuf,
+    hash: String,
+    rustc: Option<u64>,
+    modified: SystemTime,
+}
+
+/// Find stale content inside a Cargo target dir.
+///
+/// For every profile (`debug`, `release`, `<triple>/<profile>`, ...):
+/// - a profile whose newest fingerprint is older than `stale_days` is stale as a whole;
+/// - otherwise the crates in its `incremental/` cache not compiled in `stale_days` are
+///   stale, as are units built by a rustc that is no longer installed (toolchain
+///   upgrades leave the old artifacts behind). A unit's rustc is read from the crate
+///   metadata in its `.rmeta`; units whose rustc is unknown are kept.
+pub(crate) fn stale_target_artifacts(
+    target_dir: &Path,
+    stale_days: i64,
+    installed: &InstalledRustc,
+) -> Vec<PruneCandidate> {
+    let threshold = Duration::from_secs(stale_days.max(0) as u64 * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let current_rustc = current_rustc_version(target_dir);
+    let mut stale = Vec::new();
+
+    for profile in profile_dirs(target_dir) {
+        let label = profile
+            .strip_prefix(target_dir)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let units = fingerprint_units(&profile.join(".fingerprint"));
+        let Some(newest) = units.iter().max_by_key(|unit| unit.modified) else {
+            continue;
+        };
+
+        if now
+            .duration_since(newest.modified)
+            .is_ok_and(|age| age > threshold)
+        {
+            stale.push(PruneCandidate {
+                path: profile.clone(),
+                reason: format!("profile `{}` not built in {} days", label, stale_days),
+                modified: newest.modified,
+            });
+            continue;
+        }
+
+        for (cache, modified) in incremental_caches(&profile.join("incremental")) {
+            if now
+                .duration_since(modified)
+                .is_ok_and(|age| age > threshold)
+            {
+                stale.push(PruneCandidate {
+                    path: cache,
+                    reason: format!("incremental cache not used in {} days", stale_days),
+                    modified,
+                });
+            }
+        }
+
+        if !installed.complete {
+            continue;
+        }
+        let deps = deps_by_hash(&profile.join("deps"));
+        // Units built by one compiler share its hash; read the version once per hash.
+        let mut versions: HashMap<u64, String> = HashMap::new();
+        for unit in &units {
+            let Some(rustc) = unit.rustc.filter(|rustc| !versions.contains_key(rustc)) else {
+                continue;
+            };
+            if let Some(version) = deps
+                .get(&unit.hash)
+                .into_iter()
+                .flatten()
+                .find_map(|path| artifact_rustc_version(path))
+            {
+                versions.insert(rustc, version);
+            }
+        }
+
+        for unit in &units {
+            let Some(version) = unit.rustc.and_then(|rustc| versions.get(&rustc)) else {
+                continue;
+            };
+            if installed.versions.contains(version) || current_rustc.as_ref() == Some(version) {
+                continue;
+            }
+            let reason = format!("built by {}, which is no longer installed", version);
+            let mut paths = vec![unit.dir.clone()];
+            if let Some(name) = unit.dir.file_name() {
+                let build = profile.join("build").join(name);
+                if build.exists() {
+                    paths.push(build);
+                }
+            }
+            if let Some(files) = deps.get(&unit.hash) {
+                paths.extend(files.iter().cloned());
+            }
+            stale.extend(paths.into_iter().map(|path| PruneCandidate {
+                path,
+                reason: reason.clone(),
+                modified: unit.modified,
+            }));
+        }
+    }
+
+    stale
+}
+
+/// Profile dirs are the ones holding a `.fingerprint` dir, directly under the target
+/// dir or under a `<triple>/` dir for cross builds.
+fn profile_dirs(target_dir: &Path) -> Vec<PathBuf> {
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut dirs = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        dirs.sort();
+        dirs
+    };
+
+    let mut profiles = Vec::new();
+    for dir in subdirs(target_dir) {
+        if dir.join(".fingerprint").is_dir() {
+            profiles.push(dir);
+            continue;
+        }
+        profiles.extend(
+            subdirs(&dir)
+                .into_iter()
+                .filter(|nested| nested.join(".fingerprint").is_dir()),
+        );
+    }
+    profiles
+}
+
+fn fingerprint_units(fingerprint_dir: &Path) -> Vec<FingerprintUnit> {
+    let Ok(entries) = fs::read_dir(fingerprint_dir) else {
+        return Vec::new();
+    };
+
+    let mut units = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let dir = entry.path();
+        let Some(hash) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.rsplit_once('-'))
+            .map(|(_, hash)| hash.to_string())
+        else {
+            continue;
+        };
+        let Ok(files) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        // Cargo rewrites files in place, so the unit dir's own mtime is not enough.
+        let mut modified = entry.metadata().and_then(|m| m.modified()).ok();
+        let mut rustc = None;
+        for file in files.filter_map(|file| file.ok()) {
+            let path = file.path();
+            if let Ok(file_modified) = file.metadata().and_then(|m| m.modified()) {
+                modified = modified.max(Some(file_modified));
+            }
+            if rustc.is_none() && path.extension().is_some_and(|ext| ext == "json") {
+                rustc = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                    .and_then(|value| value.get("rustc").and_then(|rustc| rustc.as_u64()));
+            }
+        }
+
+        if let Some(modified) = modified {
+            units.push(FingerprintUnit {
+                dir,
+                hash,
+                rustc,
+                modified,
+            });
+        }
+    }
+    units
+}
+
+/// Per-crate dirs of an `incremental/` cache with the time a session last touched them.
+fn incremental_caches(incremental_dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let Ok(entries) = fs::read_dir(incremental_dir) else {
+        return Vec::new();
+    };
+
+    let mut caches = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let dir = entry.path();
+        let Ok(sessions) = fs::read_dir(&dir) else {
+            continue;
+        };
+        // Every compile starts a new `s-*` session dir and lock file next to the old ones.
+        let modified = sessions
+            .filter_map(|session| session.ok())
+            .filter_map(|session| session.metadata().and_then(|m| m.modified()).ok())
+            .chain(entry.metadata().and_then(|m| m.modified()).ok())
+            .max();
+        if let Some(modified) = modified {
+            caches.push((dir, modified));
+        }
+    }
+    caches
+}
+
+/// Files in `deps/` keyed by the metadata hash in `lib<name>-<hash>.<ext>`.
+fn deps_by_hash(deps_dir: &Path) -> HashMap<String, Vec<PathBuf>> {
+    let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let Ok(entries) = fs::read_dir(deps_dir) else {
+        return by_hash;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let stem = name.split('.').next().unwrap_or_default();
+        if let Some((_, hash)) = stem.rsplit_once('-') {
+            by_hash
+                .entry(hash.to_string())
+                .or_default()
+                .push(entry.path());
+        }
+    }
+    by_hash
+}
+
+/// Rust compilers that can still reuse what they built: the rustup toolchains, besides
+/// the rustc a target dir was last built with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InstalledRustc {
+    /// `rustc x.y.z (...)` of every toolchain
+    versions: HashSet<String>,
+    /// Whether the version of every toolchain is known; without it nothing is pruned
+    /// for having been built by another rustc
+    complete: bool,
+}
+
+impl InstalledRustc {
+    /// Toolchains of the rustup install at `RUSTUP_HOME`, `~/.rustup` by default.
+    pub(crate) fn from_env() -> Self {
+        std::env::var_os("RUSTUP_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".rustup")))
+            .map(|home| Self::from_rustup_home(&home))
+            .unwrap_or_default()
+    }
+
+    /// Toolchains under `<rustup_home>/toolchains`.
+    pub(crate) fn from_rustup_home(rustup_home: &Path) -> Self {
+        let Ok(entries) = fs::read_dir(rustup_home.join("toolchains")) else {
+            return Self::default();
+        };
+        let mut installed = Self {
+            versions: HashSet::new(),
+            complete: true,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            match toolchain_rustc_version(&entry.path()) {
+                Some(version) => {
+                    installed.versions.insert(version);
+                }
+                // A linked custom toolchain has no channel manifest.
+                None => installed.complete = false,
+            }
+        }
+        installed
+    }
+}
+
+/// `rustc x.y.z (...)` of a rustup toolchain, from the channel manifest installed with
+/// it.
+fn toolchain_rustc_version(toolchain: &Path) -> Option<String> {
+    let manifest =
+        fs::read_to_string(toolchain.join("lib/rustlib/multirust-channel-manifest.toml")).ok()?;
+    // The manifest lists every package for every target; only one line is needed.
+    let mut lines = manifest.lines().skip_while(|line| *line != "[pkg.rustc]");
+    lines
+        .nth(1)?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(|version| format!("rustc {version}"))
+}
+
+/// `rustc x.y.z (...)` recorded near the start of the crate metadata in an `.rmeta`.
+fn artifact_rustc_version(path: &Path) -> Option<String> {
+    if path.extension().is_none_or(|ext| ext != "rmeta") {
+        return None;
+    }
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(1024)
+        .read_to_end(&mut head)
+        .ok()?;
+    let start = head.windows(6).position(|window| window == b"rustc ")?;
+    let len = head[start..].iter().position(|&byte| byte == b')')? + 1;
+    String::from_utf8(head[start..start + len].to_vec()).ok()
+}
+
+/// `rustc x.y.z (...)` line cached by cargo in `.rustc_info.json`.
+fn current_rustc_version(target_dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(target_dir.join(".rustc_info.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+    value
+        .get("outputs")?
+        .as_object()?
+        .values()
+        .filter_map(|output| output.get("stdout").and_then(|stdout| stdout.as_str()))
+        .flat_map(str::lines)
+        .find(|line| line.starts_with("rustc "))
+        .map(str::to_string)
+}
+
+/// Lexically resolve `.` and `..` components (the target may not exist yet).
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn touch_days_ago(path: &Path, days: u64) {
+        let modified = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        fs::File::open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_workspace_members_share_root_target() {
+        let temp = TempDir::new().unwrap();
+        let ws = temp.path().join("ws");
+        write(
+            &ws.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skip\"]\n",
+        );
+        write(&ws.join("crates/a/Cargo.toml"), "[package]\nname = \"a\"\n");
+        write(
+            &ws.join("crates/skip/Cargo.toml"),
+            "[package]\nname = \"s\"\n",
+        );
+
+        let mut resolver = CargoTargetResolver::new(None, None);
+        let member = resolver.resolve(&ws.join("crates/a"));
+        assert_eq!(member.workspace_root, ws);
+        assert_eq!(member.target_dir, ws.join("target"));
+        assert!(!member.redirected);
+
+        let excluded = resolver.resolve(&ws.join("crates/skip"));
+        assert_eq!(excluded.target_dir, ws.join("crates/skip/target"));
+    }
+
+    #[test]
+    fn test_config_target_dir_and_env_override() {
+        let temp = TempDir::new().unwrap();
+        let projects = temp.path().join("projects");
+        write(
+            &projects.join(".cargo/config.toml"),
+            "[build]\ntarget-dir = \"shared-target\"\n",
+        );
+        write(
+            &projects.join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\n",
+        );
+
+        let mut resolver = CargoTargetResolver::new(None, None);
+        let target = resolver.resolve(&projects.join("app"));
+        assert_eq!(target.target_dir, projects.join("shared-target"));
+        assert!(target.redirected);
+
+        let env_dir = temp.path().join("env-target");
+        let mut resolver = CargoTargetResolver::new(Some(env_dir.clone()), None);
+        assert_eq!(resolver.resolve(&projects.join("app")).target_dir, env_dir);
+    }
+
+    fn write_rmeta(path: &Path, rustc: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut content = b"rust\0\0\0\x09\0\0\0\0#".to_vec();
+        content.extend_from_slice(rustc.as_bytes());
+        content.extend_from_slice(&[0xc1, 0x02, 0xfb]);
+        fs::write(path, content).unwrap();
+    }
+
+    fn rustup_home(root: &Path, toolchains: &[(&str, &str)]) -> PathBuf {
+        let home = root.join("rustup");
+        for (name, version) in toolchains {
+            write(
+                &home.join(format!(
+                    "toolchains/{name}/lib/rustlib/multirust-channel-manifest.toml"
+                )),
+                &format!(
+                    "[pkg.cargo]\nversion = \"0.0.0\"\n\n[pkg.rustc]\nversion = \"{version}\"\n"
+                ),
+            );
+        }
+        home
+    }
+
+    #[test]
+    fn test_stale_target_artifacts() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        write(
+            &target.join(".rustc_info.json"),
+            r#"{"rustc_fingerprint":1,"outputs":{"1":{"success":true,"status":"","code":0,"stdout":"rustc 1.80.0 (abc 2024-07-21)\nbinary: rustc\n","stderr":""}}}"#,
+        );
+        let installed = InstalledRustc::from_rustup_home(&rustup_home(
+            temp.path(),
+            &[
+                ("stable-x86_64-unknown-linux-gnu", "1.80.0 (abc 2024-07-21)"),
+                (
+                    "nightly-x86_64-unknown-linux-gnu",
+                    "1.82.0-nightly (def 2024-08-01)",
+                ),
+            ],
+        ));
+
+        // `old` was built by an uninstalled toolchain, `new` by stable and `night` by
+        // nightly, which is kept although stable built last.
+        let old_json = target.join("debug/.fingerprint/old-aaa/lib-old.json");
+        write(&old_json, r#"{"rustc":1}"#);
+        touch_days_ago(&old_json, 2);
+        touch_days_ago(old_json.parent().unwrap(), 2);
+        write(
+            &target.join("debug/.fingerprint/new-bbb/lib-new.json"),
+            r#"{"rustc":2}"#,
+        );
+        let night_json = target.join("debug/.fingerprint/night-ddd/lib-night.json");
+        write(&night_json, r#"{"rustc":3}"#);
+        touch_days_ago(&night_json, 1);
+        touch_days_ago(night_json.parent().unwrap(), 1);
+        write(&target.join("debug/deps/libold-aaa.rlib"), "old");
+        write_rmeta(
+            &target.join("debug/deps/libold-aaa.rmeta"),
+            "rustc 1.79.0 (zzz 2024-06-13)",
+        );
+        write(&target.join("debug/deps/old-aaa.d"), "old");
+        write(&target.join("debug/deps/libnew-bbb.rlib"), "new");
+        write_rmeta(
+            &target.join("debug/deps/libnew-bbb.rmeta"),
+            "rustc 1.80.0 (abc 2024-07-21)",
+        );
+        write_rmeta(
+            &target.join("debug/deps/libnight-ddd.rmeta"),
+            "rustc 1.82.0-nightly (def 2024-08-01)",
+        );
+        write(
+            &target.join("debug/incremental/new-x/s-1/query-cache.bin"),
+            "x",
+        );
+        // Not compiled since it was dropped from the workspace.
+        let gone = target.join("debug/incremental/gone-y");
+        write(&gone.join("s-1/query-cache.bin"), "y");
+        touch_days_ago(&gone.join("s-1"), 45);
+        touch_days_ago(&gone, 45);
+
+        let release_json = target.join("release/.fingerprint/app-ccc/bin-app.json");
+        write(&release_json, r#"{"rustc":2}"#);
+        touch_days_ago(&release_json, 60);
+        touch_days_ago(release_json.parent().unwrap(), 60);
+
+        let mut stale = stale_target_artifacts(&target, 30, &installed)
+            .into_iter()
+            .map(|artifact| artifact.path)
+            .collect::<Vec<_>>();
+        stale.sort();
+
+        assert_eq!(
+            stale,
+            vec![
+                target.join("debug/.fingerprint/old-aaa"),
+                target.join("debug/deps/libold-aaa.rlib"),
+                target.join("debug/deps/libold-aaa.rmeta"),
+                target.join("debug/deps/old-aaa.d"),
+                target.join("debug/incremental/gone-y"),
+                target.join("release"),
+            ]
+        );
+
+        let reasons = stale_target_artifacts(&target, 30, &installed)
+            .into_iter()
+            .map(|artifact| artifact.reason)
+            .collect::<Vec<_>>();
+        assert!(reasons.iter().any(|reason| reason
+            == "built by rustc 1.79.0 (zzz 2024-06-13), which is no longer installed"));
+        assert!(reasons
+            .iter()
+            .any(|reason| reason == "profile `release` not built in 30 days"));
+
+        // A toolchain of unknown version might have built the old units; keep them.
+        let linked = rustup_home(temp.path(), &[]);
+        fs::create_dir_all(linked.join("toolchains/custom")).unwrap();
+        let unknown = InstalledRustc::from_rustup_home(&linked);
+        assert!(stale_target_artifacts(&target, 30, &unknown)
+            .iter()
+            .all(|artifact| !artifact.path.starts_with(target.join("debug/deps"))));
+    }
+}
@@ -1,11 +1,17 @@
+use super::RuleRegistry;
 use crate::config::{CustomPattern, MarkerMode};
 use globset::GlobBuilder;
 use ignore::gitignore::GitignoreBuilder;
 use ignore::Match;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 
+/// File name defined by the Cache Directory Tagging spec
+pub(crate) const CACHEDIR_TAG_FILENAME: &str = "CACHEDIR.TAG";
+const CACHEDIR_TAG_SIGNATURE: &str = "Signature: 8a477f597d28d172789f06886806bc55";
+
 /// Supported project types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ProjectType {
@@ -84,78 +90,14 @@ impl ProjectType {
 pub struct ProjectDetector;
 
 impl ProjectDetector {
-    /// Detect project type by checking marker files
+    /// Detect project type by checking rule pack marker files
     pub fn detect(dir: &Path) -> Option<ProjectType> {
-        if dir.join("package.json").exists() || dir.join("package-lock.json").exists() {
-            return Some(ProjectType::NodeJs);
-        }
-
-        if dir.join("Cargo.toml").exists() {
-            return Some(ProjectType::Rust);
-        }
-
-        if dir.join("requirements.txt").exists()
-            || dir.join("setup.py").exists()
-            || dir.join("pyproject.toml").exists()
-            || dir.join("Pipfile").exists()
-        {
-            return Some(ProjectType::Python);
-        }
-
-        if dir.join("pom.xml").exists() {
-            return Some(ProjectType::Maven);
-        }
-
-        if dir.join("build.gradle").exists() || dir.join("build.gradle.kts").exists() {
-            return Some(ProjectType::Gradle);
-        }
-
-        if dir.join("build.sbt").exists() {
-            return Some(ProjectType::Scala);
-        }
-
-        if dir.join("project.clj").exists() || dir.join("deps.edn").exists() {
-            return Some(ProjectType::Clojure);
-        }
-
-        if dir.join("pubspec.yaml").exists() {
-            return Some(ProjectType::Dart);
-        }
-
-        if dir.join("stack.yaml").exists() || dir_contains_extension(dir, &["cabal"]) {
-            return Some(ProjectType::Haskell);
-        }
-
-        if dir.join("go.mod").exists() {
-            return Some(ProjectType::Go);
-        }
-
-        if dir.join("Gemfile").exists() {
-            return Some(ProjectType::Ruby);
-        }
-
-        if dir.join("Package.swift").exists() {
-            return Some(ProjectType::Swift);
-        }
-
-        if dir.join("composer.json").exists() {
-            return Some(ProjectType::Php);
-        }
-
-        if dir.join("mix.exs").exists() {
-            return Some(ProjectType::Elixir);
-        }
-
-        if dir_contains_extension(dir, &["csproj", "sln"]) {
-            return Some(ProjectType::DotNet);
-        }
-
-        if dir.join("CMakeLists.txt").exists() || dir.join("Makefile").exists() {
-            // Could be C or C++, default to C++
-            return Some(ProjectType::Cpp);
-        }
+        RuleRegistry::global().detect(dir)
+    }
 
-        None
+    /// Detect every project type present in `dir`, in detection order
+    pub fn detect_all(dir: &Path) -> Vec<ProjectType> {
+        RuleRegistry::global().detect_all(dir)
     }
 
     /// Check if a directory is a CMake build directory (out-of-source build)
@@ -183,90 +125,35 @@ impl ProjectDetector {
         !dir.join("CMakeLists.txt").exists()
     }
 
-    /// Get cleanable directories for a project type
-    pub fn cleanable_dirs(project_type: ProjectType) -> Vec<&'static str> {
-        match project_type {
-            ProjectType::NodeJs => vec![
-                "node_modules",
-                ".next",
-                ".nuxt",
-                "dist",
-                "build",
-                ".cache",
-                ".turbo",
-                ".parcel-cache",
-            ],
-            ProjectType::Rust => vec!["target"],
-            ProjectType::Python => vec![
-                ".venv",
-                "venv",
-                "__pycache__",
-                ".pytest_cache",
-                ".mypy_cache",
-                ".tox",
-                "*.egg-info",
-                ".eggs",
-                "build",
-                "dist",
-            ],
-            ProjectType::Java | ProjectType::Maven => vec!["target", "out"],
-            ProjectType::Kotlin | ProjectType::Gradle => vec!["build", ".gradle", "out"],
-            ProjectType::Scala => vec!["target", "project/target"],
-            ProjectType::Clojure => vec!["target"],
-            ProjectType::Dart => vec!["build", ".dart_tool"],
-            ProjectType::Haskell => vec!["dist", "dist-newstyle", ".stack-work"],
-            ProjectType::Go => vec!["vendor", "bin"],
-            ProjectType::C | ProjectType::Cpp => {
-                vec!["build", "cmake-build-debug", "cmake-build-release", "out"]
-            }
-            ProjectType::Ruby => vec!["vendor/bundle", ".bundle"],
-            ProjectType::Swift => vec![".build", "DerivedData", ".swiftpm"],
-            ProjectType::Php => vec!["vendor"],
-            ProjectType::Elixir => vec!["_build", "deps"],
-            ProjectType::DotNet => vec!["bin", "obj"],
-            ProjectType::Generic => vec![],
-        }
+    /// Check if a directory is a Cargo target directory, wherever it lives
+    ///
+    /// Cargo writes `.rustc_info.json` into the root of every target dir, including
+    /// ones redirected by `CARGO_TARGET_DIR` or `build.target-dir`. A directory that
+    /// also holds a `Cargo.toml` is a crate, never a target.
+    pub fn is_cargo_target_dir(dir: &Path) -> bool {
+        dir.join(".rustc_info.json").is_file() && !dir.join("Cargo.toml").exists()
     }
 
-    /// Check if a directory is currently in use based on lock files
-    pub fn is_in_use(project_dir: &Path, project_type: ProjectType) -> bool {
-        match project_type {
-            ProjectType::NodeJs => {
-                // Check if package-lock.json or yarn.lock was recently modified
-                let lock_files = ["package-lock.json", "yarn.lock", "pnpm-lock.yaml"];
-                Self::check_recent_lock_files(project_dir, &lock_files)
-            }
-            ProjectType::Rust => Self::check_recent_lock_files(project_dir, &["Cargo.lock"]),
-            ProjectType::Python => {
-                Self::check_recent_lock_files(project_dir, &["Pipfile.lock", "poetry.lock"])
-            }
-            ProjectType::Dart => Self::check_recent_lock_files(project_dir, &["pubspec.lock"]),
-            ProjectType::Haskell => {
-                Self::check_recent_lock_files(project_dir, &["stack.yaml.lock"])
-            }
-            ProjectType::Go => Self::check_recent_lock_files(project_dir, &["go.sum"]),
-            ProjectType::Ruby => Self::check_recent_lock_files(project_dir, &["Gemfile.lock"]),
-            ProjectType::Php => Self::check_recent_lock_files(project_dir, &["composer.lock"]),
-            _ => false,
-        }
+    /// Check if a directory carries a valid `CACHEDIR.TAG`
+    ///
+    /// Per the Cache Directory Tagging spec the file must start with a fixed signature
+    /// line; cargo, many Python tools and build systems write it into their caches.
+    pub fn is_cache_dir_tagged(dir: &Path) -> bool {
+        let Ok(mut file) = fs::File::open(dir.join(CACHEDIR_TAG_FILENAME)) else {
+            return false;
+        };
+        let mut header = [0u8; CACHEDIR_TAG_SIGNATURE.len()];
+        file.read_exact(&mut header).is_ok() && header == CACHEDIR_TAG_SIGNATURE.as_bytes()
     }
 
-    fn check_recent_lock_files(dir: &Path, lock_files: &[&str]) -> bool {
-        use std::time::{Duration, SystemTime};
+    /// Get cleanable directory patterns for a project type
+    pub fn cleanable_dirs(project_type: ProjectType) -> Vec<String> {
+        RuleRegistry::global().cleanable_patterns(project_type)
+    }
 
-        for lock_file in lock_files {
-            if let Ok(metadata) = dir.join(lock_file).metadata() {
-                if let Ok(modified) = metadata.modified() {
-                    if let Ok(elapsed) = SystemTime::now().duration_since(modified) {
-                        // Consider in use if modified within last 7 days
-                        if elapsed < Duration::from_secs(7 * 24 * 60 * 60) {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
-        false
+    /// Check if a directory is currently in use based on lock files
+    pub fn is_in_use(project_dir: &Path, project_type: ProjectType) -> bool {
+        RuleRegistry::global().is_in_use(project_dir, project_type)
     }
 
     /// Parse .gitignore file and extract potential cleanable directory patterns
@@ -366,10 +253,7 @@ impl ProjectDetector {
         project_type: ProjectType,
         project_dir: &Path,
     ) -> Vec<String> {
-        let mut cleanable = Self::cleanable_dirs(project_type)
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+        let mut cleanable = Self::cleanable_dirs(project_type);
 
         // Add patterns from .gitignore
         let gitignore_patterns = Self::parse_gitignore(project_dir);
@@ -417,11 +301,14 @@ impl ProjectDetector {
             }
         }
 
-        // Then built-in patterns.
-        for pattern in Self::cleanable_dirs(project_type) {
-            if pattern_matches(pattern, basename, &relative_path) {
-                return format!("matched builtin pattern `{}`", pattern);
-            }
+        // Then built-in rule packs.
+        if let Some((pack, rule)) =
+            RuleRegistry::global().find_rule(project_type, project_root, basename, &relative_path)
+        {
+            return format!(
+                "matched builtin pattern `{}` (rule pack `{}` v{})",
+                rule.pattern, pack.name, pack.version
+            );
         }
 
         // Then `.gitignore`-derived patterns (conservative discovery).
@@ -434,6 +321,10 @@ impl ProjectDetector {
             return "matched CMake out-of-source build heuristic (CMakeCache.txt present, no CMakeLists.txt)".to_string();
         }
 
+        if Self::is_cargo_target_dir(cleanable_dir) {
+            return "matched Cargo target-dir heuristic (.rustc_info.json present)".to_string();
+        }
+
         "matched cleanable rules".to_string()
     }
 }
@@ -485,31 +376,6 @@ fn custom_root_matches(project_root: &Path, custom: &CustomPattern) -> bool {
     }
 }
 
-fn dir_contains_extension(dir: &Path, extensions: &[&str]) -> bool {
-    let entries = match fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return false,
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
-            continue;
-        };
-        if extensions
-            .iter()
-            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
-        {
-            return true;
-        }
-    }
-
-    false
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -703,6 +569,7 @@ lib/
             directory: "build".to_string(),
             marker_files: vec!["Assets".to_string(), "ProjectSettings".to_string()],
             marker_mode: MarkerMode::AllOf,
+            prune: Vec::new(),
         }];
 
         let custom_dir = root.join("build");
@@ -726,7 +593,10 @@ lib/
 
         let builtin =
             ProjectDetector::explain_cleanable_dir(ProjectType::NodeJs, root, &builtin_dir, &[]);
-        assert_eq!(builtin, "matched builtin pattern `dist`");
+        assert_eq!(
+            builtin,
+            "matched builtin pattern `dist` (rule pack `nodejs` v3)"
+        );
 
         let gitignore =
             ProjectDetector::explain_cleanable_dir(ProjectType::Generic, root, &gitignore_dir, &[]);
@@ -0,0 +1,254 @@
+//! What a scan could not look at.
+//!
+//! Walks and size calculations report unreadable directories, timeouts and similar
+//! problems into a shared [`DiagnosticsLog`] instead of dropping them, so callers can
+//! tell an incomplete scan from a clean one.
+
+use super::mounts::SkippedMount;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Entries kept per kind; counts stay exact beyond it
+const MAX_ENTRIES_PER_KIND: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    PermissionDenied,
+    /// Size measurement gave up after its time budget
+    Timeout,
+    BrokenSymlink,
+    /// Invalid keep glob in the config or a `.dev-cleaner-keep-patterns` file
+    KeepPatternError,
+    /// A `.gitignore` that exists but could not be read or parsed
+    GitignoreError,
+    /// Any other I/O error while walking or measuring
+    Io,
+    /// Mount point below the root that was not walked
+    SkippedMount,
+    /// Target whose size never resolved
+    UnresolvedSize,
+}
+
+impl DiagnosticKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PermissionDenied => "permission denied",
+            Self::Timeout => "size timeout",
+            Self::BrokenSymlink => "broken symlink",
+            Self::KeepPatternError => "keep pattern error",
+            Self::GitignoreError => "unreadable .gitignore",
+            Self::Io => "I/O error",
+            Self::SkippedMount => "skipped mount",
+            Self::UnresolvedSize => "unresolved size",
+        }
+    }
+
+    /// Whether a problem of this kind may hide targets or sizes
+    pub fn leaves_scan_incomplete(self) -> bool {
+        matches!(
+            self,
+            Self::PermissionDenied | Self::Timeout | Self::Io | Self::UnresolvedSize
+        )
+    }
+}
+
+/// One problem met during a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiagnostic {
+    pub kind: DiagnosticKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+/// Problems met during a scan, by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiagnostics {
+    /// Number of problems of each kind, including entries not listed
+    #[serde(default)]
+    pub counts: BTreeMap<DiagnosticKind, usize>,
+    /// The first problems of each kind, in the order they were met
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<ScanDiagnostic>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_mounts: Vec<SkippedMount>,
+    /// Targets listed without a size
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved_sizes: Vec<PathBuf>,
+}
+
+impl ScanDiagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Whether nothing the scan should have seen was missed
+    pub fn is_complete(&self) -> bool {
+        !self.counts.keys().any(|kind| kind.leaves_scan_incomplete())
+    }
+
+    pub fn count(&self, kind: DiagnosticKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn push(&mut self, diagnostic: ScanDiagnostic) {
+        let count = self.counts.entry(diagnostic.kind).or_insert(0);
+        *count += 1;
+        if *count <= MAX_ENTRIES_PER_KIND {
+            self.entries.push(diagnostic);
+        }
+    }
+
+    pub fn push_skipped_mount(&mut self, mount: SkippedMount) {
+        *self.counts.entry(DiagnosticKind::SkippedMount).or_insert(0) += 1;
+        self.skipped_mounts.push(mount);
+    }
+
+    pub fn push_unresolved_size(&mut self, path: PathBuf) {
+        *self
+            .counts
+            .entry(DiagnosticKind::UnresolvedSize)
+            .or_insert(0) += 1;
+        self.unresolved_sizes.push(path);
+    }
+
+    /// Add another scan's problems, e.g. from a second root
+    pub fn merge(&mut self, other: ScanDiagnostics) {
+        for diagnostic in other.entries {
+            self.push(diagnostic);
+        }
+        // Entries beyond the cap were counted but not listed.
+        for (kind, count) in other.counts {
+            let listed = match kind {
+                DiagnosticKind::SkippedMount | DiagnosticKind::UnresolvedSize => 0,
+                _ => count.min(MAX_ENTRIES_PER_KIND),
+            };
+            if count > listed {
+                *self.counts.entry(kind).or_insert(0) += count - listed;
+            }
+        }
+        self.skipped_mounts.extend(other.skipped_mounts);
+        self.unresolved_sizes.extend(other.unresolved_sizes);
+    }
+
+    /// Counts as `3 permission denied, 1 size timeout`
+    pub fn summary(&self) -> String {
+        self.counts
+            .iter()
+            .map(|(kind, count)| format!("{count} {}", kind.label()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Sink shared by the walks and size calculations of one scanner
+#[derive(Debug, Default)]
+pub(crate) struct DiagnosticsLog {
+    diagnostics: Mutex<ScanDiagnostics>,
+}
+
+impl DiagnosticsLog {
+    pub(crate) fn record(&self, kind: DiagnosticKind, path: Option<&Path>, message: impl Display) {
+        self.diagnostics.lock().unwrap().push(ScanDiagnostic {
+            kind,
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        });
+    }
+
+    /// Record a failed file system call on `path`
+    pub(crate) fn io_error(&self, path: &Path, err: &io::Error) {
+        let kind = match err.kind() {
+            io::ErrorKind::PermissionDenied => DiagnosticKind::PermissionDenied,
+            _ => DiagnosticKind::Io,
+        };
+        self.record(kind, Some(path), err);
+    }
+
+    pub(crate) fn unresolved_size(&self, path: &Path) {
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .push_unresolved_size(path.to_path_buf());
+    }
+
+    pub(crate) fn snapshot(&self) -> ScanDiagnostics {
+        self.diagnostics.lock().unwrap().clone()
+    }
+}
+
+/// A size measurement that ran out of time
+#[derive(Debug)]
+pub(crate) struct SizeTimeout(pub PathBuf);
+
+impl Display for SizeTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timeout calculating size for {:?}", self.0)
+    }
+}
+
+impl std::error::Error for SizeTimeout {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(kind: DiagnosticKind, path: &str) -> ScanDiagnostic {
+        ScanDiagnostic {
+            kind,
+            path: Some(PathBuf::from(path)),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn counts_stay_exact_past_the_entry_cap() {
+        let mut first = ScanDiagnostics::default();
+        for index in 0..MAX_ENTRIES_PER_KIND + 5 {
+            first.push(diagnostic(
+                DiagnosticKind::PermissionDenied,
+                &format!("/d{index}"),
+            ));
+        }
+        first.push_unresolved_size(PathBuf::from("/a/target"));
+        assert_eq!(first.entries.len(), MAX_ENTRIES_PER_KIND);
+        assert_eq!(
+            first.count(DiagnosticKind::PermissionDenied),
+            MAX_ENTRIES_PER_KIND + 5
+        );
+        assert!(!first.is_complete());
+
+        let mut second = ScanDiagnostics::default();
+        second.push(diagnostic(DiagnosticKind::BrokenSymlink, "/b/link"));
+        assert!(second.is_complete());
+        second.merge(first);
+        assert_eq!(
+            second.count(DiagnosticKind::PermissionDenied),
+            MAX_ENTRIES_PER_KIND + 5
+        );
+        assert_eq!(second.count(DiagnosticKind::UnresolvedSize), 1);
+        assert_eq!(second.entries.len(), MAX_ENTRIES_PER_KIND + 1);
+        assert_eq!(
+            second.summary(),
+            "55 permission denied, 1 broken symlink, 1 unresolved size"
+        );
+    }
+
+    #[test]
+    fn io_errors_are_classified() {
+        let log = DiagnosticsLog::default();
+        log.io_error(
+            Path::new("/x"),
+            &io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        log.io_error(Path::new("/y"), &io::Error::from(io::ErrorKind::Other));
+        let diagnostics = log.snapshot();
+        assert_eq!(diagnostics.count(DiagnosticKind::PermissionDenied), 1);
+        assert_eq!(diagnostics.count(DiagnosticKind::Io), 1);
+    }
+}
@@ -1,15 +1,30 @@
+mod cache;
+mod cargo;
 mod detector;
+mod diagnostics;
+mod mounts;
+mod observer;
+mod rules;
 mod size_calculator;
 mod walker;
 
+pub use cache::{default_scan_cache_path, ScanCache, ScanCacheStats};
+pub use cargo::{CargoTarget, CargoTargetResolver};
 pub use detector::{ProjectDetector, ProjectType};
+pub use diagnostics::{DiagnosticKind, ScanDiagnostic, ScanDiagnostics};
+pub use mounts::{MountSkipReason, SkippedMount};
+pub use observer::{NoopScanObserver, ScanObserver, ScanPhase};
+pub use rules::{CleanRule, InUseRules, MarkerRules, PruneRule, RulePack, RuleRegistry};
 pub use size_calculator::SizeCalculator;
 pub use walker::Scanner;
 
+use crate::guard::FileIdentity;
+use crate::policy::{ActivitySource, GitActivity, ProcessHold};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use std::{fmt, fmt::Display};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -68,6 +83,16 @@ impl RiskLevel {
     }
 }
 
+impl RiskLevel {
+    /// One step riskier, used when a match could not be confirmed
+    pub fn raised(self) -> Self {
+        match self {
+            Self::Low => Self::Medium,
+            Self::Medium | Self::High => Self::High,
+        }
+    }
+}
+
 impl Display for RiskLevel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(self.as_str())
@@ -100,6 +125,17 @@ impl Confidence {
     }
 }
 
+impl Confidence {
+    /// One step more confident, used when independent evidence corroborates a match
+    pub fn raised(self) -> Self {
+        match self {
+            Self::High | Self::Medium => Self::High,
+            Self::Low => Self::Medium,
+            Self::Unknown => Self::Low,
+        }
+    }
+}
+
 impl Display for Confidence {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(self.as_str())
@@ -113,6 +149,8 @@ pub enum RuleSource {
     Builtin,
     Gitignore,
     Heuristic,
+    /// Directory tagged with a `CACHEDIR.TAG` per the Cache Directory Tagging spec
+    CacheTag,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +159,28 @@ pub struct RuleRef {
     pub pattern: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Rule pack that supplied a builtin match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<RulePackRef>,
+    /// Whether the directory also carries a valid `CACHEDIR.TAG`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_tagged: bool,
+    /// Content checks the matching rule ran against the directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<ContentCheck>,
+}
+
+/// A file or directory a rule expects inside generated output, and whether it was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentCheck {
+    pub path: String,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulePackRef {
+    pub name: String,
+    pub version: u32,
 }
 
 /// Information about a cleanable project directory
@@ -155,9 +215,21 @@ pub struct ProjectInfo {
     /// Cleanable directory path (e.g., node_modules, target)
     pub cleanable_dir: PathBuf,
 
-    /// Size of the cleanable directory in bytes
+    /// Size of the cleanable directory in bytes (apparent: sum of file lengths)
     pub size: u64,
 
+    /// Allocated and reclaimable bytes, measured alongside `size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_usage: Option<DiskUsage>,
+
+    /// Device id (`st_dev`) of the filesystem holding `cleanable_dir`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<u64>,
+
+    /// Inode of `cleanable_dir`; with `device`, checked again right before removal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+
     /// Whether the size has been calculated
     #[serde(default = "default_true")]
     pub size_calculated: bool,
@@ -165,9 +237,17 @@ pub struct ProjectInfo {
     /// Last modified time of the cleanable directory
     pub last_modified: DateTime<Utc>,
 
-    /// Whether this directory is currently in use (based on lock files)
+    /// Whether this directory is currently in use (lock files or recent git activity)
     pub in_use: bool,
 
+    /// Evidence behind `in_use`
+    #[serde(default, skip_serializing_if = "ActivitySource::is_heuristic")]
+    pub in_use_source: ActivitySource,
+
+    /// Running process found using the target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_use_by: Option<ProcessHold>,
+
     /// Whether this target is protected by keep policy
     #[serde(default)]
     pub protected: bool,
@@ -180,6 +260,14 @@ pub struct ProjectInfo {
     #[serde(default)]
     pub recent: bool,
 
+    /// Evidence behind `recent`
+    #[serde(default, skip_serializing_if = "ActivitySource::is_heuristic")]
+    pub recent_source: ActivitySource,
+
+    /// Last activity recorded by the enclosing git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity: Option<GitActivity>,
+
     /// Selection reason (recommend/apply explainability)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub selection_reason: Option<String>,
@@ -187,6 +275,55 @@ pub struct ProjectInfo {
     /// Skip reason (recommend/apply/clean explainability)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub skip_reason: Option<String>,
+
+    /// Projects whose builds feed this target (shared Cargo workspace/target-dir)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub feeding_projects: Vec<PathBuf>,
+
+    /// Sub-paths to remove instead of the whole `cleanable_dir` (partial clean)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prune_paths: Vec<PrunePath>,
+
+    /// Files inside the target (or its prune paths) tracked by the enclosing git repository
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub tracked_files: usize,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// Space a cleanup target takes on disk.
+///
+/// Hardlinked files (pnpm's store-linked `node_modules`, Cargo's `deps/` outputs) and
+/// sparse files make the apparent size overstate what a clean frees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    /// Sum of file lengths
+    pub apparent: u64,
+    /// Blocks allocated on disk; an inode is counted once across all candidates of a scan
+    pub allocated: u64,
+    /// Allocated bytes freed by removing this target: inodes with no link outside it
+    pub reclaimable: u64,
+}
+
+/// Sub-path found by a prune rule or heuristic, before sizing.
+#[derive(Debug, Clone)]
+pub(crate) struct PruneCandidate {
+    pub path: PathBuf,
+    pub reason: String,
+    pub modified: SystemTime,
+}
+
+/// One stale entry inside a cleanable directory, removed by a partial clean
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrunePath {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub size: u64,
+    /// Why this entry is considered stale
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
 }
 
 fn default_true() -> bool {
@@ -212,6 +349,9 @@ impl ProjectInfo {
             matched_rule: None,
             cleanable_dir,
             size: 0,
+            disk_usage: None,
+            device: None,
+            inode: None,
             size_calculated: false,
             last_modified,
             in_use,
@@ -220,6 +360,13 @@ impl ProjectInfo {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: ActivitySource::Heuristic,
+            in_use_by: None,
+            recent_source: ActivitySource::Heuristic,
+            activity: None,
         }
     }
 
@@ -229,6 +376,31 @@ impl ProjectInfo {
             .unwrap_or_else(|| self.project_type.name().to_string())
     }
 
+    /// Whether this candidate only removes `prune_paths` inside `cleanable_dir`
+    pub fn is_partial(&self) -> bool {
+        !self.prune_paths.is_empty()
+    }
+
+    /// Remember which directory `cleanable_dir` is now, to notice it being replaced
+    pub fn record_identity(&mut self) {
+        let identity = FileIdentity::of_path(&self.cleanable_dir);
+        self.device = identity.map(|identity| identity.device);
+        self.inode = identity.map(|identity| identity.inode);
+    }
+
+    /// Device and inode recorded for `cleanable_dir`, if both are known
+    pub fn identity(&self) -> Option<FileIdentity> {
+        Some(FileIdentity {
+            device: self.device?,
+            inode: self.inode?,
+        })
+    }
+
+    /// Bytes a clean would actually free; the apparent size when usage was not measured
+    pub fn reclaimable_size(&self) -> u64 {
+        self.disk_usage.map_or(self.size, |usage| usage.reclaimable)
+    }
+
     /// Returns a human-readable size string
     pub fn size_human(&self) -> String {
         if !self.size_calculated {
@@ -243,6 +415,40 @@ impl ProjectInfo {
         let now = Utc::now();
         (now - self.last_modified).num_days()
     }
+
+    /// Record a running process using the target; this marks it in use
+    pub fn set_held_by(&mut self, hold: Option<ProcessHold>) {
+        if hold.is_some() {
+            self.in_use = true;
+            self.in_use_source = ActivitySource::VerifiedProcess;
+        }
+        self.in_use_by = hold;
+    }
+
+    /// Days since the project was last touched: the newer of the target's mtime and the
+    /// last git activity
+    pub fn days_idle(&self) -> i64 {
+        let days = self.days_since_modified();
+        self.activity
+            .map_or(days, |activity| days.min(activity.days_since_activity()))
+    }
+
+    /// Whether the target counts as recent for `recent_days`, and the evidence used.
+    ///
+    /// Git activity inside the window is verified; git history older than the window
+    /// only verifies the decision when the target's mtime agrees, since a fresh build
+    /// without commits is still recent.
+    pub fn recent_within(&self, recent_days: i64) -> (bool, ActivitySource) {
+        let modified_recently = self.days_since_modified() < recent_days;
+        match self
+            .activity
+            .map(|activity| activity.days_since_activity() < recent_days)
+        {
+            Some(true) => (true, ActivitySource::VerifiedGit),
+            Some(false) if !modified_recently => (false, ActivitySource::VerifiedGit),
+            _ => (modified_recently, ActivitySource::Heuristic),
+        }
+    }
 }
 
 pub(crate) fn perf_trace_enabled() -> bool {
@@ -0,0 +1,323 @@
+//! Mounted filesystems below a scan root.
+//!
+//! Walks stop at mount points they should not cross: with one-file-system every mount
+//! (bind mounts included) and every directory on another device, and by default any
+//! network or pseudo filesystem. On Linux the mount table comes from
+//! `/proc/self/mountinfo`; elsewhere only the device check applies.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What kind of filesystem a mount holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FsKind {
+    Local,
+    /// Remote or userspace filesystem: slow to walk and not ours to clean
+    Network,
+    /// Kernel interface (`/proc`, `/sys`, cgroups, ...) with no files on disk
+    Pseudo,
+}
+
+impl FsKind {
+    /// Classify a `mountinfo` filesystem type.
+    ///
+    /// FUSE filesystems other than `fuseblk` count as network: most are remote
+    /// (sshfs, rclone, s3fs) or synthesized by a daemon.
+    pub(crate) fn of(fs_type: &str) -> Self {
+        match fs_type {
+            "nfs" | "nfs4" | "cifs" | "smb3" | "smbfs" | "ncpfs" | "afs" | "9p" | "ceph"
+            | "glusterfs" | "lustre" | "gpfs" | "davfs" | "fuse" | "sshfs" => Self::Network,
+            "proc" | "sysfs" | "devtmpfs" | "devpts" | "cgroup" | "cgroup2" | "securityfs"
+            | "debugfs" | "tracefs" | "pstore" | "bpf" | "configfs" | "fusectl" | "mqueue"
+            | "hugetlbfs" | "autofs" | "binfmt_misc" | "efivarfs" | "rpc_pipefs" | "nsfs"
+            | "selinuxfs" => Self::Pseudo,
+            other if other.starts_with("fuse.") => Self::Network,
+            _ => Self::Local,
+        }
+    }
+}
+
+/// Why a walk did not enter a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MountSkipReason {
+    /// Another filesystem than the scan root's, with one-file-system on
+    OtherFilesystem,
+    Network,
+    Pseudo,
+}
+
+/// A mount point below the scan root that was not walked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedMount {
+    pub path: PathBuf,
+    /// Filesystem type from the mount table, when listed there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fs_type: Option<String>,
+    pub reason: MountSkipReason,
+}
+
+/// One line of `/proc/self/mountinfo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MountEntry {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+/// Which directories below a scan root a walk may enter
+#[derive(Debug, Default)]
+pub(crate) struct MountFilter {
+    /// Device of the scan root, set when one-file-system is on
+    root_device: Option<u64>,
+    /// Mounts strictly below the root, keyed by their path as the walk sees it
+    mounts: HashMap<PathBuf, MountEntry>,
+    skipped: Mutex<BTreeMap<PathBuf, SkippedMount>>,
+}
+
+impl MountFilter {
+    pub(crate) fn new(root: &Path, one_file_system: bool, skip_special: bool) -> Self {
+        Self::from_table(
+            root,
+            read_mount_table(),
+            one_file_system,
+            skip_special,
+            std::fs::metadata(root)
+                .ok()
+                .and_then(|metadata| device_of(&metadata)),
+        )
+    }
+
+    /// Keep the mounts below `root` a walk has to stop at
+    pub(crate) fn from_table(
+        root: &Path,
+        table: Vec<MountEntry>,
+        one_file_system: bool,
+        skip_special: bool,
+        root_device: Option<u64>,
+    ) -> Self {
+        // The mount table lists canonical paths; walks see them under `root` as given.
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let mut mounts = HashMap::new();
+        for entry in table {
+            let Ok(relative) = entry.mount_point.strip_prefix(&canonical_root) else {
+                continue;
+            };
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+            let stops_walk =
+                one_file_system || (skip_special && FsKind::of(&entry.fs_type) != FsKind::Local);
+            let path = root.join(relative);
+            // A later mount on the same point hides the earlier one.
+            if stops_walk {
+                mounts.insert(path, entry);
+            } else {
+                mounts.remove(&path);
+            }
+        }
+
+        Self {
+            root_device: root_device.filter(|_| one_file_system),
+            mounts,
+            skipped: Mutex::default(),
+        }
+    }
+
+    /// Whether a walk may enter `dir`; `metadata` is only consulted for one-file-system
+    pub(crate) fn admits(&self, dir: &Path, metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+        let reason = if let Some(entry) = self.mounts.get(dir) {
+            let reason = match FsKind::of(&entry.fs_type) {
+                FsKind::Network => MountSkipReason::Network,
+                FsKind::Pseudo => MountSkipReason::Pseudo,
+                FsKind::Local => MountSkipReason::OtherFilesystem,
+            };
+            Some((reason, Some(entry.fs_type.clone())))
+        } else if let Some(root_device) = self.root_device {
+            metadata()
+                .and_then(|metadata| device_of(&metadata))
+                .filter(|device| *device != root_device)
+                .map(|_| (MountSkipReason::OtherFilesystem, None))
+        } else {
+            None
+        };
+
+        let Some((reason, fs_type)) = reason else {
+            return true;
+        };
+        self.skipped
+            .lock()
+            .unwrap()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| SkippedMount {
+                path: dir.to_path_buf(),
+                fs_type,
+                reason,
+            });
+        false
+    }
+
+    /// Mount points the walks stopped at so far, sorted by path
+    pub(crate) fn skipped(&self) -> Vec<SkippedMount> {
+        self.skipped.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// Device id (`st_dev`) a file lives on
+#[cfg(unix)]
+pub(crate) fn device_of(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn device_of(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn read_mount_table() -> Vec<MountEntry> {
+    std::fs::read_to_string("/proc/self/mountinfo")
+        .map(|text| parse_mountinfo(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_mount_table() -> Vec<MountEntry> {
+    Vec::new()
+}
+
+/// Parse `mountinfo` lines:
+/// `<id> <parent> <major:minor> <root> <mount point> <options> [optional...] - <type> <source> <super options>`
+#[cfg(any(target_os = "linux", test))]
+fn parse_mountinfo(text: &str) -> Vec<MountEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let (fields, fs_fields) = line.split_once(" - ")?;
+            let mount_point = fields.split(' ').nth(4)?;
+            let fs_type = fs_fields.split(' ').next()?;
+            Some(MountEntry {
+                mount_point: PathBuf::from(unescape_octal(mount_point)),
+                fs_type: fs_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Undo the kernel's `\040`-style escaping of spaces, tabs, newlines and backslashes
+#[cfg(any(target_os = "linux", test))]
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)))
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MOUNTINFO: &str = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:21 / /proc rw,nosuid - proc proc rw
+40 22 0:45 / /work/remote rw shared:20 - nfs4 server:/export rw
+41 22 0:46 / /work/with\\040space rw - fuse.sshfs host: rw
+42 22 8:2 / /work/data rw - ext4 /dev/sda2 rw
+";
+
+    fn entry(mount_point: &str, fs_type: &str) -> MountEntry {
+        MountEntry {
+            mount_point: PathBuf::from(mount_point),
+            fs_type: fs_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_mountinfo_lines() {
+        let entries = parse_mountinfo(MOUNTINFO);
+        assert_eq!(
+            entries,
+            vec![
+                entry("/", "ext4"),
+                entry("/proc", "proc"),
+                entry("/work/remote", "nfs4"),
+                entry("/work/with space", "fuse.sshfs"),
+                entry("/work/data", "ext4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_filesystem_types() {
+        assert_eq!(FsKind::of("nfs4"), FsKind::Network);
+        assert_eq!(FsKind::of("fuse.sshfs"), FsKind::Network);
+        assert_eq!(FsKind::of("fuseblk"), FsKind::Local);
+        assert_eq!(FsKind::of("proc"), FsKind::Pseudo);
+        assert_eq!(FsKind::of("tmpfs"), FsKind::Local);
+    }
+
+    #[test]
+    fn filter_skips_special_mounts_and_records_them() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let table = vec![
+            entry(root.to_str().unwrap(), "ext4"),
+            entry(root.join("remote").to_str().unwrap(), "nfs4"),
+            entry(root.join("proc").to_str().unwrap(), "proc"),
+            entry(root.join("data").to_str().unwrap(), "ext4"),
+            entry("/elsewhere", "nfs"),
+        ];
+
+        let filter = MountFilter::from_table(&root, table.clone(), false, true, None);
+        assert!(filter.admits(&root.join("data"), || None));
+        assert!(filter.admits(&root.join("src"), || None));
+        assert!(!filter.admits(&root.join("remote"), || None));
+        assert!(!filter.admits(&root.join("proc"), || None));
+        let skipped = filter.skipped();
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].reason, MountSkipReason::Pseudo);
+        assert_eq!(skipped[1].fs_type.as_deref(), Some("nfs4"));
+
+        let filter = MountFilter::from_table(&root, table.clone(), false, false, None);
+        assert!(filter.admits(&root.join("remote"), || None));
+
+        let filter = MountFilter::from_table(&root, table, true, true, None);
+        assert!(!filter.admits(&root.join("data"), || None));
+        assert_eq!(filter.skipped()[0].reason, MountSkipReason::OtherFilesystem);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn one_file_system_compares_devices() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("dir");
+        std::fs::create_dir(&dir).unwrap();
+        let device = device_of(&std::fs::metadata(&dir).unwrap()).unwrap();
+
+        let same = MountFilter::from_table(temp.path(), Vec::new(), true, true, Some(device));
+        assert!(same.admits(&dir, || std::fs::metadata(&dir).ok()));
+
+        let other = MountFilter::from_table(temp.path(), Vec::new(), true, true, Some(device + 1));
+        assert!(!other.admits(&dir, || std::fs::metadata(&dir).ok()));
+        assert_eq!(other.skipped()[0].fs_type, None);
+    }
+}
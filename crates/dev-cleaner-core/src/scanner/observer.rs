@@ -0,0 +1,41 @@
+//! Progress reporting for scans.
+//!
+//! Walks and size calculations run on many threads at once, so observers are shared
+//! (`&self`, `Send + Sync`) and should keep their callbacks cheap: a counter or a
+//! channel send, not I/O.
+
+use super::ProjectInfo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Stage a scan of one root is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanPhase {
+    /// Walking the tree for targets
+    Discovering,
+    /// Resolving Cargo targets, dedup and in-use checks on what was found
+    Analyzing,
+    /// Measuring the targets
+    Sizing,
+    Finished,
+    /// Stopped early by a cancellation token; results are partial
+    Cancelled,
+}
+
+/// Receives progress from a [`crate::Scanner`] or [`crate::SizeCalculator`]
+pub trait ScanObserver: Send + Sync {
+    fn on_phase(&self, _phase: ScanPhase) {}
+    /// A directory was listed by the discovery walk
+    fn on_dir_visited(&self, _dir: &Path) {}
+    /// A target matched and passed the non-size filters
+    fn on_candidate(&self, _project: &ProjectInfo) {}
+    /// A target's size was measured, or given up on (`size_calculated` is false)
+    fn on_size_resolved(&self, _project: &ProjectInfo) {}
+}
+
+/// No-op scan observer.
+#[derive(Debug, Default)]
+pub struct NoopScanObserver;
+
+impl ScanObserver for NoopScanObserver {}
@@ -0,0 +1,956 @@
+//! Data-driven ecosystem rule packs.
+//!
+//! Every supported ecosystem is described by a small TOML pack (marker files,
+//! cleanable globs with category/risk, lock files and the in-use window). The
+//! built-in packs live in `crates/dev-cleaner-core/rules/` and are embedded at
+//! compile time; users can drop extra packs into `<config dir>/dev-cleaner/rules/`.
+
+use super::{Category, ContentCheck, ProjectType, PruneCandidate, RiskLevel};
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// Built-in packs in detection order (first matching marker wins).
+const BUILTIN_PACKS: &[(&str, &str)] = &[
+    ("nodejs.toml", include_str!("../../rules/nodejs.toml")),
+    ("rust.toml", include_str!("../../rules/rust.toml")),
+    ("python.toml", include_str!("../../rules/python.toml")),
+    ("maven.toml", include_str!("../../rules/maven.toml")),
+    ("gradle.toml", include_str!("../../rules/gradle.toml")),
+    ("scala.toml", include_str!("../../rules/scala.toml")),
+    ("clojure.toml", include_str!("../../rules/clojure.toml")),
+    ("dart.toml", include_str!("../../rules/dart.toml")),
+    ("haskell.toml", include_str!("../../rules/haskell.toml")),
+    ("go.toml", include_str!("../../rules/go.toml")),
+    ("ruby.toml", include_str!("../../rules/ruby.toml")),
+    ("swift.toml", include_str!("../../rules/swift.toml")),
+    ("php.toml", include_str!("../../rules/php.toml")),
+    ("elixir.toml", include_str!("../../rules/elixir.toml")),
+    ("dotnet.toml", include_str!("../../rules/dotnet.toml")),
+    ("cpp.toml", include_str!("../../rules/cpp.toml")),
+    ("java.toml", include_str!("../../rules/java.toml")),
+    ("kotlin.toml", include_str!("../../rules/kotlin.toml")),
+    ("c.toml", include_str!("../../rules/c.toml")),
+];
+
+const DEFAULT_IN_USE_WINDOW_DAYS: u64 = 7;
+/// Longest in-use window a pack may declare, a century
+const MAX_IN_USE_WINDOW_DAYS: u64 = 36_500;
+
+static GLOBAL_REGISTRY: OnceLock<Arc<RuleRegistry>> = OnceLock::new();
+
+/// A versioned set of detection and cleaning rules for one ecosystem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePack {
+    /// Unique pack name; a user pack with the same name replaces the built-in one
+    pub name: String,
+
+    /// Pack version, reported by `--explain`
+    pub version: u32,
+
+    /// Project type reported for roots detected by this pack
+    pub project_type: ProjectType,
+
+    /// Display name override (useful for `Generic` user packs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// Files/extensions that identify a project root
+    #[serde(default)]
+    pub markers: MarkerRules,
+
+    /// Lock files used for in-use detection
+    #[serde(default)]
+    pub in_use: InUseRules,
+
+    /// Cleanable directory rules
+    #[serde(default)]
+    pub rules: Vec<CleanRule>,
+
+    /// File the pack was loaded from (None for built-in packs)
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkerRules {
+    /// Any of these files present in the root marks a project
+    #[serde(default)]
+    pub files: Vec<String>,
+
+    /// Any file with one of these extensions in the root marks a project
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InUseRules {
+    #[serde(default)]
+    pub lock_files: Vec<String>,
+
+    /// A lock file modified within this many days marks the project as in use
+    #[serde(default = "default_in_use_window_days")]
+    pub window_days: u64,
+}
+
+impl Default for InUseRules {
+    fn default() -> Self {
+        Self {
+            lock_files: Vec::new(),
+            window_days: DEFAULT_IN_USE_WINDOW_DAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanRule {
+    /// Basename glob, or a relative-path glob when it contains `/`
+    pub pattern: String,
+    pub category: Category,
+
+    /// Risk override (defaults to the category's risk)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<RiskLevel>,
+
+    /// Disposable sub-paths that can be pruned without removing the whole target
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prune: Vec<PruneRule>,
+
+    /// Paths inside the target that only generated output contains; any one of them
+    /// confirms the match (e.g. `pyvenv.cfg` in a virtualenv)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verify: Vec<String>,
+}
+
+/// Sub-path glob, relative to a matched target, that a partial clean removes.
+///
+/// Written either as a plain glob (`prune = [".cache"]`) or as a table that keeps
+/// the newest matches (`{ path = "caches/[0-9]*", keep_latest = 1 }`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "PruneRuleDef")]
+pub struct PruneRule {
+    pub path: String,
+
+    /// Number of most recently modified matches to keep
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub keep_latest: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PruneRuleDef {
+    Path(String),
+    Table {
+        path: String,
+        #[serde(default)]
+        keep_latest: usize,
+    },
+}
+
+impl From<PruneRuleDef> for PruneRule {
+    fn from(def: PruneRuleDef) -> Self {
+        match def {
+            PruneRuleDef::Path(path) => Self {
+                path,
+                keep_latest: 0,
+            },
+            PruneRuleDef::Table { path, keep_latest } => Self { path, keep_latest },
+        }
+    }
+}
+
+impl PruneRule {
+    pub(crate) fn validate(&self) -> Result<()> {
+        let pattern = self.pattern();
+        if pattern.is_empty() || pattern.split('/').any(|part| part == "..") {
+            bail!("Prune path `{}` must stay inside the target", self.path);
+        }
+        compile_glob(&pattern, false)
+            .with_context(|| format!("Invalid prune path `{}`", self.path))?;
+        Ok(())
+    }
+
+    fn pattern(&self) -> String {
+        self.path
+            .replace('\\', "/")
+            .trim_matches('/')
+            .trim_start_matches("./")
+            .to_string()
+    }
+
+    fn reason(&self) -> String {
+        if self.keep_latest > 0 {
+            format!(
+                "prune rule `{}` (keeping newest {})",
+                self.path, self.keep_latest
+            )
+        } else {
+            format!("prune rule `{}`", self.path)
+        }
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+fn default_in_use_window_days() -> u64 {
+    DEFAULT_IN_USE_WINDOW_DAYS
+}
+
+impl CleanRule {
+    pub fn risk_level(&self) -> RiskLevel {
+        self.risk
+            .unwrap_or_else(|| default_risk_level(self.category))
+    }
+}
+
+impl MarkerRules {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.extensions.is_empty()
+    }
+
+    pub fn matches(&self, dir: &Path) -> bool {
+        self.files.iter().any(|file| dir.join(file).exists())
+            || (!self.extensions.is_empty() && dir_contains_extension(dir, &self.extensions))
+    }
+
+    /// [`Self::matches`] against a listing of `dir` that was already read
+    pub(crate) fn matches_listing(&self, dir: &Path, listing: &DirListing) -> bool {
+        self.files.iter().any(|file| listing.contains(dir, file))
+            || listing.has_file_extension(&self.extensions)
+    }
+}
+
+/// Entries of one directory, read once while walking and matched against many markers
+#[derive(Debug, Default)]
+pub(crate) struct DirListing {
+    /// Names of entries that exist (dangling symlinks excluded)
+    names: HashSet<OsString>,
+    /// Lowercased extensions of regular files (symlinks followed)
+    file_extensions: HashSet<String>,
+}
+
+impl DirListing {
+    pub(crate) fn insert(&mut self, name: OsString, is_file: bool) {
+        if is_file {
+            if let Some(ext) = Path::new(&name).extension().and_then(|ext| ext.to_str()) {
+                self.file_extensions.insert(ext.to_ascii_lowercase());
+            }
+        }
+        self.names.insert(name);
+    }
+
+    /// Whether `relative` exists below `dir`; nested paths fall back to a `stat`.
+    pub(crate) fn contains(&self, dir: &Path, relative: &str) -> bool {
+        if relative.contains('/') {
+            return dir.join(relative).exists();
+        }
+        self.names.contains(OsStr::new(relative))
+    }
+
+    fn has_file_extension(&self, extensions: &[String]) -> bool {
+        extensions
+            .iter()
+            .any(|ext| self.file_extensions.contains(&ext.to_ascii_lowercase()))
+    }
+}
+
+impl RulePack {
+    /// Parse and validate a pack from TOML.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let pack: RulePack = toml::from_str(content).context("Failed to parse rule pack")?;
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    /// Load a pack from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rule pack: {}", path.display()))?;
+        let mut pack = Self::from_toml(&content)
+            .with_context(|| format!("Invalid rule pack: {}", path.display()))?;
+        pack.path = Some(path.to_path_buf());
+        Ok(pack)
+    }
+
+    /// Whether this pack's rules apply to the given project root.
+    ///
+    /// Marker-less packs extend every root of their project type.
+    pub fn applies_to(&self, project_root: &Path) -> bool {
+        self.markers.is_empty() || self.markers.matches(project_root)
+    }
+
+    fn detects(&self, dir: &Path) -> bool {
+        !self.markers.is_empty() && self.markers.matches(dir)
+    }
+
+    fn detects_listing(&self, dir: &Path, listing: &DirListing) -> bool {
+        !self.markers.is_empty() && self.markers.matches_listing(dir, listing)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Rule pack name must not be empty");
+        }
+        if self.version == 0 {
+            bail!("Rule pack `{}` must have a version >= 1", self.name);
+        }
+        if self.in_use.window_days > MAX_IN_USE_WINDOW_DAYS {
+            bail!(
+                "In-use window of pack `{}` must be at most {} days",
+                self.name,
+                MAX_IN_USE_WINDOW_DAYS
+            );
+        }
+        for rule in &self.rules {
+            if rule.category == Category::Unknown {
+                bail!(
+                    "Rule `{}` in pack `{}` must declare a category",
+                    rule.pattern,
+                    self.name
+                );
+            }
+            compile_glob(&rule.pattern, false).with_context(|| {
+                format!("Invalid pattern `{}` in pack `{}`", rule.pattern, self.name)
+            })?;
+            for prune in &rule.prune {
+                prune.validate().with_context(|| {
+                    format!("Invalid rule `{}` in pack `{}`", rule.pattern, self.name)
+                })?;
+            }
+            for check in &rule.verify {
+                let relative = Path::new(check);
+                if check.trim().is_empty()
+                    || relative.is_absolute()
+                    || relative
+                        .components()
+                        .any(|part| matches!(part, std::path::Component::ParentDir))
+                {
+                    bail!(
+                        "Content check `{}` of rule `{}` in pack `{}` must stay inside the target",
+                        check,
+                        rule.pattern,
+                        self.name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rule's pattern, compiled once when its pack is added
+struct RuleMatcher {
+    relative_path: bool,
+    matcher: GlobMatcher,
+}
+
+impl RuleMatcher {
+    fn new(pattern: &str, case_insensitive: bool) -> Option<Self> {
+        let pattern = pattern.replace('\\', "/");
+        Some(Self {
+            relative_path: pattern.contains('/'),
+            matcher: compile_glob(&pattern, case_insensitive).ok()?,
+        })
+    }
+
+    fn is_match(&self, basename: &str, relative_path: &str) -> bool {
+        if self.relative_path {
+            self.matcher.is_match(relative_path)
+        } else {
+            self.matcher.is_match(basename)
+        }
+    }
+}
+
+struct Classifier {
+    matcher: RuleMatcher,
+    category: Category,
+}
+
+/// Ordered collection of rule packs (built-in first, then user packs).
+pub struct RuleRegistry {
+    packs: Vec<RulePack>,
+    classifiers: Vec<Classifier>,
+    /// Matchers of each pack's rules, in the order of `packs` and their rules
+    rule_matchers: Vec<Vec<Option<RuleMatcher>>>,
+    load_errors: Vec<String>,
+}
+
+impl RuleRegistry {
+    /// Registry with only the embedded packs.
+    pub fn builtin() -> Self {
+        let packs = BUILTIN_PACKS
+            .iter()
+            .map(|(file, content)| {
+                RulePack::from_toml(content)
+                    .unwrap_or_else(|err| panic!("invalid built-in rule pack {file}: {err:#}"))
+            })
+            .collect();
+        Self::from_packs(packs)
+    }
+
+    /// Built-in packs plus every `*.toml` pack found in `dir`.
+    ///
+    /// Packs that fail to load are skipped and reported via [`Self::load_errors`].
+    pub fn with_user_dir<P: AsRef<Path>>(dir: P) -> Self {
+        let mut registry = Self::builtin();
+        registry.load_dir(dir.as_ref());
+        registry
+    }
+
+    /// Shared registry: built-in packs plus packs from [`Self::default_user_dir`].
+    pub fn global() -> Arc<Self> {
+        Arc::clone(GLOBAL_REGISTRY.get_or_init(|| {
+            Arc::new(match Self::default_user_dir() {
+                Some(dir) => Self::with_user_dir(dir),
+                None => Self::builtin(),
+            })
+        }))
+    }
+
+    /// Directory searched for user packs (`DEV_CLEANER_RULES_DIR` overrides).
+    pub fn default_user_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("DEV_CLEANER_RULES_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        Config::default_path()
+            .parent()
+            .map(|config_dir| config_dir.join("rules"))
+    }
+
+    fn from_packs(packs: Vec<RulePack>) -> Self {
+        let mut registry = Self {
+            packs: Vec::new(),
+            classifiers: Vec::new(),
+            rule_matchers: Vec::new(),
+            load_errors: Vec::new(),
+        };
+        for pack in packs {
+            registry.add_pack(pack);
+        }
+        registry
+    }
+
+    fn load_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            match RulePack::load(&path) {
+                Ok(pack) => self.add_pack(pack),
+                Err(err) => self.load_errors.push(format!("{err:#}")),
+            }
+        }
+    }
+
+    /// Add a pack, replacing any existing pack with the same name in place.
+    pub fn add_pack(&mut self, pack: RulePack) {
+        match self.packs.iter().position(|p| p.name == pack.name) {
+            Some(index) => self.packs[index] = pack,
+            None => self.packs.push(pack),
+        }
+        self.rebuild_matchers();
+    }
+
+    fn rebuild_matchers(&mut self) {
+        self.classifiers = self
+            .packs
+            .iter()
+            .flat_map(|pack| pack.rules.iter())
+            .filter_map(|rule| {
+                Some(Classifier {
+                    matcher: RuleMatcher::new(&rule.pattern, true)?,
+                    category: rule.category,
+                })
+            })
+            .collect();
+        self.rule_matchers = self
+            .packs
+            .iter()
+            .map(|pack| {
+                pack.rules
+                    .iter()
+                    .map(|rule| RuleMatcher::new(&rule.pattern, false))
+                    .collect()
+            })
+            .collect();
+    }
+
+    pub fn packs(&self) -> &[RulePack] {
+        &self.packs
+    }
+
+    /// Errors from user packs that could not be loaded.
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+
+    /// Packs that report the given project type.
+    pub fn packs_for(&self, project_type: ProjectType) -> impl Iterator<Item = &RulePack> {
+        self.packs
+            .iter()
+            .filter(move |pack| pack.project_type == project_type)
+    }
+
+    /// Look up a pack by name.
+    pub fn pack(&self, name: &str) -> Option<&RulePack> {
+        self.packs.iter().find(|pack| pack.name == name)
+    }
+
+    /// Detect the primary project type of `dir` (first matching pack).
+    pub fn detect(&self, dir: &Path) -> Option<ProjectType> {
+        self.detect_pack(dir).map(|pack| pack.project_type)
+    }
+
+    /// Detect every project type whose markers match `dir`, in detection order.
+    ///
+    /// Mixed repos (e.g. `package.json` + `Cargo.toml`) report all of them.
+    pub fn detect_all(&self, dir: &Path) -> Vec<ProjectType> {
+        let mut types = Vec::new();
+        for pack in self.packs.iter().filter(|pack| pack.detects(dir)) {
+            if !types.contains(&pack.project_type) {
+                types.push(pack.project_type);
+            }
+        }
+        types
+    }
+
+    /// [`Self::detect_all`] from a listing of `dir`, without further I/O for plain
+    /// marker names.
+    pub(crate) fn detect_all_listed(&self, dir: &Path, listing: &DirListing) -> Vec<ProjectType> {
+        let mut types = Vec::new();
+        for pack in self
+            .packs
+            .iter()
+            .filter(|pack| pack.detects_listing(dir, listing))
+        {
+            if !types.contains(&pack.project_type) {
+                types.push(pack.project_type);
+            }
+        }
+        types
+    }
+
+    /// First pack whose markers match `dir`.
+    pub fn detect_pack(&self, dir: &Path) -> Option<&RulePack> {
+        self.packs.iter().find(|pack| pack.detects(dir))
+    }
+
+    /// All cleanable patterns for a project type (deduplicated, in pack order).
+    pub fn cleanable_patterns(&self, project_type: ProjectType) -> Vec<String> {
+        let mut patterns: Vec<String> = Vec::new();
+        for rule in self
+            .packs_for(project_type)
+            .flat_map(|pack| pack.rules.iter())
+        {
+            if !patterns.contains(&rule.pattern) {
+                patterns.push(rule.pattern.clone());
+            }
+        }
+        patterns
+    }
+
+    /// Find the pack rule matching a cleanable dir of the given project root.
+    pub fn find_rule(
+        &self,
+        project_type: ProjectType,
+        project_root: &Path,
+        basename: &str,
+        relative_path: &str,
+    ) -> Option<(&RulePack, &CleanRule)> {
+        self.packs
+            .iter()
+            .zip(&self.rule_matchers)
+            .filter(|(pack, _)| pack.project_type == project_type && pack.applies_to(project_root))
+            .flat_map(|(pack, matchers)| {
+                pack.rules
+                    .iter()
+                    .zip(matchers)
+                    .map(move |(rule, matcher)| (pack, rule, matcher))
+            })
+            .find(|(_, _, matcher)| {
+                matcher
+                    .as_ref()
+                    .is_some_and(|matcher| matcher.is_match(basename, relative_path))
+            })
+            .map(|(pack, rule, _)| (pack, rule))
+    }
+
+    /// Check whether a project looks in use based on its packs' lock files.
+    pub fn is_in_use(&self, project_dir: &Path, project_type: ProjectType) -> bool {
+        self.packs_for(project_type).any(|pack| {
+            let window = Duration::from_secs(pack.in_use.window_days.saturating_mul(24 * 60 * 60));
+            pack.in_use
+                .lock_files
+                .iter()
+                .any(|lock_file| modified_within(&project_dir.join(lock_file), window))
+        })
+    }
+
+    /// Longest in-use window of the project type's packs, in days
+    pub fn in_use_window_days(&self, project_type: ProjectType) -> u64 {
+        self.packs_for(project_type)
+            .map(|pack| pack.in_use.window_days)
+            .max()
+            .unwrap_or(DEFAULT_IN_USE_WINDOW_DAYS)
+    }
+
+    /// Category for a directory name, using the first matching pack rule.
+    ///
+    /// Used for custom and `.gitignore` matches that carry no category themselves.
+    pub fn classify(&self, basename: &str, relative_path: &str) -> Category {
+        self.classifiers
+            .iter()
+            .find(|classifier| classifier.matcher.is_match(basename, relative_path))
+            .map(|classifier| classifier.category)
+            .unwrap_or(Category::Unknown)
+    }
+}
+
+pub(crate) fn default_risk_level(category: Category) -> RiskLevel {
+    match category {
+        Category::Cache => RiskLevel::Low,
+        Category::Build => RiskLevel::Medium,
+        Category::Deps => RiskLevel::High,
+        Category::Unknown => RiskLevel::Medium,
+    }
+}
+
+/// Entries inside `target` matched by prune rules.
+///
+/// Matched directories are not descended into; globs without `**` only look as deep
+/// as they have components.
+pub(crate) fn resolve_prune_rules(target: &Path, rules: &[PruneRule]) -> Vec<PruneCandidate> {
+    let mut found = Vec::new();
+    for rule in rules {
+        let pattern = rule.pattern();
+        let Ok(matcher) = compile_glob(&pattern, false) else {
+            continue;
+        };
+        let max_depth = if pattern.contains("**") {
+            usize::MAX
+        } else {
+            pattern.split('/').count()
+        };
+
+        let mut matches = Vec::new();
+        let mut walker = walkdir::WalkDir::new(target)
+            .min_depth(1)
+            .max_depth(max_depth)
+            .follow_links(false)
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let Ok(relative) = entry.path().strip_prefix(target) else {
+                continue;
+            };
+            if !matcher.is_match(relative.to_string_lossy().replace('\\', "/")) {
+                continue;
+            }
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            matches.push((entry.into_path(), modified));
+        }
+
+        matches.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+        let reason = rule.reason();
+        found.extend(
+            matches
+                .into_iter()
+                .skip(rule.keep_latest)
+                .map(|(path, modified)| PruneCandidate {
+                    path,
+                    reason: reason.clone(),
+                    modified,
+                }),
+        );
+    }
+    found
+}
+
+/// Run a rule's content checks against a matched directory
+pub(crate) fn run_content_checks(dir: &Path, verify: &[String]) -> Vec<ContentCheck> {
+    verify
+        .iter()
+        .map(|path| ContentCheck {
+            path: path.clone(),
+            passed: dir.join(path).exists(),
+        })
+        .collect()
+}
+
+fn compile_glob(pattern: &str, case_insensitive: bool) -> Result<GlobMatcher> {
+    Ok(GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(case_insensitive)
+        .build()?
+        .compile_matcher())
+}
+
+fn modified_within(path: &Path, window: Duration) -> bool {
+    path.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|elapsed| elapsed < window)
+}
+
+fn dir_contains_extension(dir: &Path, extensions: &[String]) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return false,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        if extensions
+            .iter()
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builtin_packs_parse_and_keep_detection_order() {
+        let registry = RuleRegistry::builtin();
+        assert_eq!(registry.packs().len(), BUILTIN_PACKS.len());
+        assert_eq!(registry.packs()[0].name, "nodejs");
+        assert!(registry.packs().iter().all(|pack| pack.version >= 1));
+
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("package.json"), "{}").unwrap();
+        std::fs::write(temp.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(registry.detect(temp.path()), Some(ProjectType::NodeJs));
+        assert_eq!(
+            registry.detect_all(temp.path()),
+            vec![ProjectType::NodeJs, ProjectType::Rust]
+        );
+    }
+
+    #[test]
+    fn test_classify_uses_pack_categories() {
+        let registry = RuleRegistry::builtin();
+        assert_eq!(
+            registry.classify("node_modules", "node_modules"),
+            Category::Deps
+        );
+        assert_eq!(
+            registry.classify("DerivedData", "DerivedData"),
+            Category::Build
+        );
+        assert_eq!(
+            registry.classify("deriveddata", "deriveddata"),
+            Category::Build
+        );
+        assert_eq!(registry.classify("bundle", "vendor/bundle"), Category::Deps);
+        assert_eq!(
+            registry.classify("demo.egg-info", "demo.egg-info"),
+            Category::Build
+        );
+        assert_eq!(
+            registry.classify(".pytest_cache", ".pytest_cache"),
+            Category::Cache
+        );
+        assert_eq!(registry.classify("src", "src"), Category::Unknown);
+    }
+
+    #[test]
+    fn test_user_pack_adds_ecosystem_and_overrides_builtin() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("unity.toml"),
+            r#"
+name = "unity"
+version = 2
+project_type = "Generic"
+display_name = "Unity"
+
+[markers]
+files = ["ProjectSettings"]
+
+[[rules]]
+pattern = "Library"
+category = "cache"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("rust.toml"),
+            r#"
+name = "rust"
+version = 9
+project_type = "Rust"
+
+[markers]
+files = ["Cargo.toml"]
+
+[[rules]]
+pattern = "target"
+category = "build"
+risk = "low"
+"#,
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("broken.toml"), "name = ").unwrap();
+
+        let registry = RuleRegistry::with_user_dir(temp.path());
+        assert_eq!(registry.packs().len(), BUILTIN_PACKS.len() + 1);
+        assert_eq!(registry.load_errors().len(), 1);
+        assert!(registry.load_errors()[0].contains("broken.toml"));
+
+        let rust = registry.packs_for(ProjectType::Rust).next().unwrap();
+        assert_eq!(rust.version, 9);
+        assert_eq!(rust.rules[0].risk_level(), RiskLevel::Low);
+
+        let project = temp.path().join("game");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("ProjectSettings"), "").unwrap();
+        let pack = registry.detect_pack(&project).unwrap();
+        assert_eq!(pack.name, "unity");
+        assert_eq!(pack.rules[0].risk_level(), RiskLevel::Low);
+        let (matched, rule) = registry
+            .find_rule(ProjectType::Generic, &project, "Library", "Library")
+            .unwrap();
+        assert_eq!(matched.name, "unity");
+        assert_eq!(rule.category, Category::Cache);
+    }
+
+    #[test]
+    fn test_pack_validation_rejects_missing_category_and_version() {
+        let missing_version = r#"
+name = "x"
+version = 0
+project_type = "Generic"
+"#;
+        assert!(RulePack::from_toml(missing_version).is_err());
+
+        let unknown_category = r#"
+name = "x"
+version = 1
+project_type = "Generic"
+
+[[rules]]
+pattern = "out"
+category = "unknown"
+"#;
+        assert!(RulePack::from_toml(unknown_category).is_err());
+
+        let escaping_check = r#"
+name = "x"
+version = 1
+project_type = "Generic"
+
+[[rules]]
+pattern = "out"
+category = "build"
+verify = ["../marker"]
+"#;
+        assert!(RulePack::from_toml(escaping_check).is_err());
+
+        let endless_window = r#"
+name = "x"
+version = 1
+project_type = "Generic"
+
+[in_use]
+window_days = 9223372036854775807
+"#;
+        assert!(RulePack::from_toml(endless_window).is_err());
+    }
+
+    #[test]
+    fn test_prune_rules_parse_and_keep_latest_matches() {
+        let pack = RulePack::from_toml(
+            r#"
+name = "x"
+version = 1
+project_type = "Generic"
+
+[[rules]]
+pattern = "out"
+category = "build"
+prune = ["cache", { path = "v*", keep_latest = 1 }]
+"#,
+        )
+        .unwrap();
+        let prune = &pack.rules[0].prune;
+        assert_eq!(prune[0].keep_latest, 0);
+        assert_eq!(prune[1].path, "v*");
+        assert_eq!(prune[1].keep_latest, 1);
+
+        let escaping = r#"
+name = "x"
+version = 1
+project_type = "Generic"
+
+[[rules]]
+pattern = "out"
+category = "build"
+prune = ["../src"]
+"#;
+        assert!(RulePack::from_toml(escaping).is_err());
+
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("out");
+        for dir in ["cache/a", "v1", "v2", "src"] {
+            std::fs::create_dir_all(target.join(dir)).unwrap();
+        }
+        let newer = SystemTime::now();
+        let older = newer - std::time::Duration::from_secs(3600);
+        std::fs::File::open(target.join("v1"))
+            .unwrap()
+            .set_modified(older)
+            .unwrap();
+        std::fs::File::open(target.join("v2"))
+            .unwrap()
+            .set_modified(newer)
+            .unwrap();
+
+        let mut found = resolve_prune_rules(&target, prune)
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, vec![target.join("cache"), target.join("v1")]);
+    }
+}
@@ -1,10 +1,18 @@
-use super::emit_perf_trace;
+use super::cache::DirStamp;
+use super::diagnostics::{DiagnosticKind, DiagnosticsLog, SizeTimeout};
+use super::mounts::MountFilter;
+use super::observer::ScanObserver;
+use super::{emit_perf_trace, DiskUsage, ScanCache};
+use crate::cancel::{CancellationToken, Cancelled};
 use crate::ProjectInfo;
 use anyhow::Result;
 use crossbeam::channel::Sender;
 use rayon::prelude::*;
-use serde::Serialize;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -17,21 +25,240 @@ struct SizeCalculationTrace {
     timeout_secs: u64,
 }
 
+/// Default time budget for measuring one directory
+pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// `(device, inode)` of a file
+type InodeKey = (u64, u64);
+
+/// A file with more than one hard link, as seen below one directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SharedInode {
+    dev: u64,
+    ino: u64,
+    /// Links the file has in total
+    nlink: u64,
+    /// Links found below the measured directory
+    links: u64,
+    allocated: u64,
+}
+
+/// Space used below one measured directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DirUsage {
+    pub(crate) apparent: u64,
+    /// Allocated bytes of files with a single link
+    pub(crate) single_link: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) shared: Vec<SharedInode>,
+}
+
+/// Usage measured by the discovery walk, keyed by directory; `None` when the walk gave
+/// up on it after the timeout
+pub(crate) type MeasuredUsage = HashMap<PathBuf, Option<DirUsage>>;
+
+/// Running totals of one directory being measured, fed an entry at a time
+#[derive(Debug, Default)]
+pub(crate) struct UsageTally {
+    apparent: u64,
+    single_link: u64,
+    shared: HashMap<InodeKey, SharedInode>,
+    /// Directories below the measured one, for the scan cache
+    stamps: Vec<DirStamp>,
+}
+
+impl UsageTally {
+    pub(crate) fn add_file(&mut self, metadata: &Metadata) {
+        self.apparent += metadata.len();
+        match shared_inode(metadata) {
+            Some(inode) => {
+                self.shared
+                    .entry((inode.dev, inode.ino))
+                    .and_modify(|seen| seen.links += 1)
+                    .or_insert(inode);
+            }
+            None => self.single_link += allocated_bytes(metadata),
+        }
+    }
+
+    /// Record a directory at `relative` to the measured one
+    pub(crate) fn add_dir(&mut self, relative: PathBuf, metadata: &Metadata) {
+        self.stamps.extend(DirStamp::new(relative, metadata));
+    }
+
+    pub(crate) fn merge(&mut self, other: UsageTally) {
+        self.apparent += other.apparent;
+        self.single_link += other.single_link;
+        for (key, inode) in other.shared {
+            self.shared
+                .entry(key)
+                .and_modify(|seen| seen.links += inode.links)
+                .or_insert(inode);
+        }
+        self.stamps.extend(other.stamps);
+    }
+
+    pub(crate) fn finish(self) -> (DirUsage, Vec<DirStamp>) {
+        let usage = DirUsage {
+            apparent: self.apparent,
+            single_link: self.single_link,
+            shared: self.shared.into_values().collect(),
+        };
+        (usage, self.stamps)
+    }
+}
+
+/// Hardlinked inodes already attributed to a candidate of the current batch, so that
+/// each one is counted once across all candidates
+#[derive(Debug, Default)]
+struct InodeLedger {
+    owners: HashMap<InodeKey, PathBuf>,
+}
+
+impl InodeLedger {
+    /// Claim the inodes of `target` not claimed by an earlier candidate and turn its
+    /// tally into usage. A full and a partial clean of the same target are
+    /// alternatives, so they share their claims.
+    fn charge(&mut self, target: &Path, tally: &TargetTally) -> DiskUsage {
+        let mut allocated = 0;
+        let mut exclusive = 0;
+        for (key, inode) in &tally.shared {
+            if self
+                .owners
+                .entry(*key)
+                .or_insert_with(|| target.to_path_buf())
+                != target
+            {
+                continue;
+            }
+            allocated += inode.allocated;
+            if inode.links >= inode.nlink {
+                exclusive += inode.allocated;
+            }
+        }
+        DiskUsage {
+            apparent: tally.apparent,
+            allocated: tally.single_link + allocated,
+            reclaimable: tally.single_link + exclusive,
+        }
+    }
+}
+
+/// What a clean of one candidate would remove, before its hardlinks are attributed
+#[derive(Debug, Default)]
+struct TargetTally {
+    apparent: u64,
+    single_link: u64,
+    shared: HashMap<InodeKey, SharedInode>,
+}
+
+impl TargetTally {
+    fn add(&mut self, usage: DirUsage) {
+        self.apparent += usage.apparent;
+        self.single_link += usage.single_link;
+        for inode in usage.shared {
+            self.shared
+                .entry((inode.dev, inode.ino))
+                .and_modify(|seen| seen.links += inode.links)
+                .or_insert(inode);
+        }
+    }
+}
+
+/// Settings and shared state of one sizing run
+struct SizingContext<'a> {
+    timeout: Duration,
+    cache: Option<&'a ScanCache>,
+    measured: &'a MeasuredUsage,
+    mounts: Option<&'a MountFilter>,
+    diagnostics: Option<&'a DiagnosticsLog>,
+    observer: Option<&'a dyn ScanObserver>,
+    cancel: &'a CancellationToken,
+}
+
 /// Size calculator for parallel and streaming directory size computation
+///
+/// Besides the apparent size, every target gets a [`DiskUsage`]: allocated blocks
+/// (sparse files count what they occupy) and the bytes that removing it actually frees.
 pub struct SizeCalculator {
     /// Timeout for calculating a single directory (in seconds)
     timeout_secs: u64,
+
+    /// Reuses sizes of unchanged directories from earlier runs
+    cache: Option<Arc<ScanCache>>,
+
+    /// Directories the discovery walk already measured
+    measured: MeasuredUsage,
+
+    /// Mount points not to descend into
+    mounts: Option<Arc<MountFilter>>,
+
+    /// Where unreadable entries, timeouts and unresolved sizes are reported
+    diagnostics: Option<Arc<DiagnosticsLog>>,
+
+    /// Told about every target once its size is resolved
+    observer: Option<Arc<dyn ScanObserver>>,
+
+    /// Stops measuring; targets not finished by then are left out
+    cancel: CancellationToken,
 }
 
 impl SizeCalculator {
     /// Create a new size calculator with default timeout (60 seconds)
     pub fn new() -> Self {
-        Self { timeout_secs: 60 }
+        Self::with_timeout(DEFAULT_TIMEOUT_SECS)
     }
 
     /// Create a new size calculator with custom timeout
     pub fn with_timeout(timeout_secs: u64) -> Self {
-        Self { timeout_secs }
+        Self {
+            timeout_secs,
+            cache: None,
+            measured: HashMap::new(),
+            mounts: None,
+            diagnostics: None,
+            observer: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Reuse and record sizes in a persistent scan cache
+    pub fn with_cache(mut self, cache: Arc<ScanCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Report each resolved size to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn ScanObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stop measuring once `token` is cancelled
+    ///
+    /// Batch calls then return or stream only the targets measured so far; single
+    /// calls fail with [`Cancelled`].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Take usage measured during discovery instead of walking those directories again
+    pub(crate) fn with_measured(mut self, measured: MeasuredUsage) -> Self {
+        self.measured = measured;
+        self
+    }
+
+    /// Stop at the mount points the scanner's walks stop at
+    pub(crate) fn with_mounts(mut self, mounts: Arc<MountFilter>) -> Self {
+        self.mounts = Some(mounts);
+        self
+    }
+
+    /// Report problems into the scanner's diagnostics
+    pub(crate) fn with_diagnostics(mut self, diagnostics: Arc<DiagnosticsLog>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
     }
 
     /// Calculate sizes for projects in parallel, streaming results as they complete
@@ -46,6 +273,8 @@ impl SizeCalculator {
     ///
     /// # Returns
     /// The number of projects successfully processed
+    ///
+    /// After cancellation, projects not measured yet are not sent.
     pub fn calculate_batch_streaming(
         &self,
         mut projects: Vec<ProjectInfo>,
@@ -53,20 +282,17 @@ impl SizeCalculator {
     ) -> usize {
         let total_projects = projects.len();
         let started = Instant::now();
-        let timeout = Duration::from_secs(self.timeout_secs);
-        let completed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut completed = 0;
 
-        projects.par_iter_mut().for_each(|project| {
-            let finished = calculate_project_size(project, timeout);
+        size_projects(&mut projects, &self.context(), |project, finished| {
             if finished {
-                completed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                completed += 1;
             }
 
             // Ignore errors if receiver is dropped.
             let _ = tx.send(project.clone());
         });
 
-        let completed = completed.load(std::sync::atomic::Ordering::Relaxed);
         emit_perf_trace(
             "size_calculation",
             &SizeCalculationTrace {
@@ -81,15 +307,16 @@ impl SizeCalculator {
     }
 
     /// Calculate sizes for projects in parallel and return the updated list.
+    ///
+    /// After cancellation, projects not measured yet keep `size_calculated` false.
     pub fn calculate_batch(&self, mut projects: Vec<ProjectInfo>) -> Vec<ProjectInfo> {
         let total_projects = projects.len();
         let started = Instant::now();
-        let timeout = Duration::from_secs(self.timeout_secs);
-        let completed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut completed = 0;
 
-        projects.par_iter_mut().for_each(|project| {
-            if calculate_project_size(project, timeout) {
-                completed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        size_projects(&mut projects, &self.context(), |_, finished| {
+            if finished {
+                completed += 1;
             }
         });
 
@@ -98,7 +325,7 @@ impl SizeCalculator {
             &SizeCalculationTrace {
                 mode: "batch",
                 projects: total_projects,
-                completed: completed.load(std::sync::atomic::Ordering::Relaxed),
+                completed,
                 elapsed_ms: started.elapsed().as_millis(),
                 timeout_secs: self.timeout_secs,
             },
@@ -111,12 +338,33 @@ impl SizeCalculator {
     ///
     /// This is a convenience method for calculating size for a single project.
     /// For batch operations, use `calculate_batch_streaming` instead.
+    ///
+    /// A failure is reported to the diagnostics, like in batch calls.
     pub fn calculate_single(&self, project: &mut ProjectInfo) -> Result<u64> {
-        let timeout = Duration::from_secs(self.timeout_secs);
-        let size = calculate_dir_size_with_timeout(&project.cleanable_dir, timeout)?;
-        project.size = size;
+        let context = self.context();
+        let tally = measure_target(project, &context).inspect_err(|err| {
+            if let Some(diagnostics) = context.diagnostics.filter(|_| !err.is::<Cancelled>()) {
+                record_size_error(diagnostics, &project.cleanable_dir, err);
+                diagnostics.unresolved_size(&project.cleanable_dir);
+            }
+        })?;
+        let usage = InodeLedger::default().charge(&project.cleanable_dir, &tally);
+        project.size = usage.apparent;
+        project.disk_usage = Some(usage);
         project.size_calculated = true;
-        Ok(size)
+        Ok(usage.apparent)
+    }
+
+    fn context(&self) -> SizingContext<'_> {
+        SizingContext {
+            timeout: Duration::from_secs(self.timeout_secs),
+            cache: self.cache.as_deref(),
+            measured: &self.measured,
+            mounts: self.mounts.as_deref(),
+            diagnostics: self.diagnostics.as_deref(),
+            observer: self.observer.as_deref(),
+            cancel: &self.cancel,
+        }
     }
 }
 
@@ -126,52 +374,239 @@ impl Default for SizeCalculator {
     }
 }
 
-fn calculate_project_size(project: &mut ProjectInfo, timeout: Duration) -> bool {
-    match calculate_dir_size_with_timeout(&project.cleanable_dir, timeout) {
-        Ok(size) => {
-            project.size = size;
+/// Measure `projects` in parallel, then attribute shared inodes in path order so the
+/// figures do not depend on which target finished first
+///
+/// `resolved` gets every project not cancelled, with whether its size is known.
+fn size_projects(
+    projects: &mut [ProjectInfo],
+    context: &SizingContext,
+    mut resolved: impl FnMut(&ProjectInfo, bool),
+) {
+    let mut tallies: Vec<_> = projects
+        .par_iter_mut()
+        .map(|project| measure_project(project, context))
+        .collect();
+    let mut order: Vec<usize> = (0..projects.len()).collect();
+    order.sort_by(|&a, &b| projects[a].cleanable_dir.cmp(&projects[b].cleanable_dir));
+
+    let mut ledger = InodeLedger::default();
+    for index in order {
+        let Some(tally) = tallies[index].take() else {
+            continue;
+        };
+        let project = &mut projects[index];
+        let finished = resolve_project_size(project, tally, &mut ledger, context);
+        resolved(project, finished);
+    }
+}
+
+/// Measure one project; `None` when cancelled before its size was known
+fn measure_project(
+    project: &mut ProjectInfo,
+    context: &SizingContext,
+) -> Option<Result<TargetTally>> {
+    if context.cancel.is_cancelled() {
+        return None;
+    }
+    match measure_target(project, context) {
+        Err(err) if err.is::<Cancelled>() => None,
+        measured => Some(measured),
+    }
+}
+
+/// Store the measured usage in `project`; returns whether its size is known
+fn resolve_project_size(
+    project: &mut ProjectInfo,
+    measured: Result<TargetTally>,
+    ledger: &mut InodeLedger,
+    context: &SizingContext,
+) -> bool {
+    let finished = match measured {
+        Ok(tally) => {
+            let usage = ledger.charge(&project.cleanable_dir, &tally);
+            project.size = usage.apparent;
+            project.disk_usage = Some(usage);
             project.size_calculated = true;
             true
         }
-        Err(_) => {
+        Err(err) => {
+            if let Some(diagnostics) = context.diagnostics {
+                record_size_error(diagnostics, &project.cleanable_dir, &err);
+                diagnostics.unresolved_size(&project.cleanable_dir);
+            }
             // Preserve the "unknown size" state so batch callers do not mistake failures for 0 B.
             project.size = 0;
+            project.disk_usage = None;
             project.size_calculated = false;
             false
         }
+    };
+    if let Some(observer) = context.observer {
+        observer.on_size_resolved(project);
+    }
+    finished
+}
+
+fn record_size_error(diagnostics: &DiagnosticsLog, dir: &Path, err: &anyhow::Error) {
+    if let Some(timeout) = err.downcast_ref::<SizeTimeout>() {
+        diagnostics.record(DiagnosticKind::Timeout, Some(&timeout.0), timeout);
+    } else if let Some(io_err) = err.downcast_ref::<walkdir::Error>() {
+        let path = io_err.path().unwrap_or(dir);
+        match io_err.io_error() {
+            Some(io_err) => diagnostics.io_error(path, io_err),
+            None => diagnostics.record(DiagnosticKind::Io, Some(path), io_err),
+        }
+    } else {
+        diagnostics.record(DiagnosticKind::Io, Some(dir), err);
+    }
+}
+
+/// Usage of what a clean would remove: the whole dir, or only its prune paths.
+///
+/// Links are tallied across all removed paths together, so a file linked from two
+/// prune paths of the same target is still freed by removing both.
+fn measure_target(project: &mut ProjectInfo, context: &SizingContext) -> Result<TargetTally> {
+    let start = Instant::now();
+    let mut tally = TargetTally::default();
+
+    if project.prune_paths.is_empty() {
+        tally.add(calculate_dir_usage(
+            &project.cleanable_dir,
+            context.timeout,
+            context,
+        )?);
+    } else {
+        for prune in &mut project.prune_paths {
+            let usage = calculate_dir_usage(
+                &prune.path,
+                context.timeout.saturating_sub(start.elapsed()),
+                context,
+            )?;
+            prune.size = usage.apparent;
+            tally.add(usage);
+        }
     }
+    Ok(tally)
 }
 
-/// Calculate directory size with timeout protection
-fn calculate_dir_size_with_timeout(dir: &Path, timeout: Duration) -> Result<u64> {
+/// Measure a directory with timeout protection
+///
+/// A directory measured during discovery is taken as is. With a cache, an unchanged
+/// directory is answered from it and a measured one records the mtime of every
+/// directory it walked.
+fn calculate_dir_usage(dir: &Path, timeout: Duration, context: &SizingContext) -> Result<DirUsage> {
+    let timed_out = || anyhow::Error::new(SizeTimeout(dir.to_path_buf()));
+    if let Some(measured) = context.measured.get(dir) {
+        return measured.clone().ok_or_else(timed_out);
+    }
+    if context.cancel.is_cancelled() {
+        return Err(Cancelled.into());
+    }
     if timeout.is_zero() {
-        return Err(anyhow::anyhow!("Timeout calculating size for {:?}", dir));
+        return Err(timed_out());
+    }
+    let cache = context.cache;
+    if let Some(usage) = cache.and_then(|cache| cache.cached_size(dir)) {
+        return Ok(usage);
     }
 
     let start = Instant::now();
-    let mut total = 0u64;
+    let mut tally = UsageTally::default();
     let mut checked_entries = 0usize;
+    let report = |err: &walkdir::Error| {
+        if let Some(diagnostics) = context.diagnostics {
+            let path = err.path().unwrap_or(dir);
+            match err.io_error() {
+                Some(io_err) => diagnostics.io_error(path, io_err),
+                None => diagnostics.record(DiagnosticKind::Io, Some(path), err),
+            }
+        }
+    };
 
     for entry in walkdir::WalkDir::new(dir)
         .follow_links(false)
         .into_iter()
-        .filter_map(|e| e.ok())
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || context
+                    .mounts
+                    .is_none_or(|mounts| mounts.admits(entry.path(), || entry.metadata().ok()))
+        })
+        .filter_map(|entry| entry.map_err(|err| report(&err)).ok())
     {
         checked_entries += 1;
-        if checked_entries % 256 == 0 && start.elapsed() >= timeout {
-            return Err(anyhow::anyhow!("Timeout calculating size for {:?}", dir));
+        if checked_entries % 256 == 0 {
+            if context.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            if start.elapsed() >= timeout {
+                return Err(timed_out());
+            }
         }
 
-        if entry.file_type().is_file() {
-            total += entry.metadata()?.len();
+        let file_type = entry.file_type();
+        let wanted = file_type.is_file() || (cache.is_some() && file_type.is_dir());
+        if !wanted {
+            continue;
+        }
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            // Removed since it was listed, e.g. by a build running next to the scan.
+            Err(err) if err.io_error().map(|err| err.kind()) == Some(io::ErrorKind::NotFound) => {
+                continue;
+            }
+            Err(err) => {
+                report(&err);
+                continue;
+            }
+        };
+        if file_type.is_file() {
+            tally.add_file(&metadata);
+        } else {
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            tally.add_dir(relative.to_path_buf(), &metadata);
         }
     }
 
     if start.elapsed() >= timeout {
-        Err(anyhow::anyhow!("Timeout calculating size for {:?}", dir))
-    } else {
-        Ok(total)
+        return Err(timed_out());
+    }
+    let (usage, stamps) = tally.finish();
+    if let Some(cache) = cache {
+        cache.store_size(dir, &usage, stamps);
     }
+    Ok(usage)
+}
+
+/// Bytes allocated on disk; smaller than the length for sparse files
+#[cfg(unix)]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+fn shared_inode(metadata: &Metadata) -> Option<SharedInode> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| SharedInode {
+        dev: metadata.dev(),
+        ino: metadata.ino(),
+        nlink: metadata.nlink(),
+        links: 1,
+        allocated: allocated_bytes(metadata),
+    })
+}
+
+#[cfg(not(unix))]
+fn shared_inode(_metadata: &Metadata) -> Option<SharedInode> {
+    None
 }
 
 #[cfg(test)]
@@ -292,9 +727,14 @@ mod tests {
         let mut project =
             ProjectInfo::new_pending(dir.clone(), ProjectType::NodeJs, dir, Utc::now(), false);
 
-        let calculator = SizeCalculator::with_timeout(0);
+        let diagnostics = Arc::new(DiagnosticsLog::default());
+        let calculator = SizeCalculator::with_timeout(0).with_diagnostics(Arc::clone(&diagnostics));
         let result = calculator.calculate_single(&mut project);
         assert!(result.is_err());
+
+        let diagnostics = diagnostics.snapshot();
+        assert_eq!(diagnostics.count(DiagnosticKind::Timeout), 1);
+        assert_eq!(diagnostics.unresolved_sizes.len(), 1);
     }
 
     #[test]
@@ -318,7 +758,8 @@ mod tests {
 
         let expected_len = projects.len();
         let (tx, rx) = crossbeam::channel::unbounded();
-        let calculator = SizeCalculator::with_timeout(0);
+        let diagnostics = Arc::new(DiagnosticsLog::default());
+        let calculator = SizeCalculator::with_timeout(0).with_diagnostics(Arc::clone(&diagnostics));
         let completed = calculator.calculate_batch_streaming(projects, tx);
 
         let results = rx.try_iter().collect::<Vec<_>>();
@@ -328,5 +769,148 @@ mod tests {
             assert!(!project.size_calculated);
             assert_eq!(project.size, 0);
         }
+
+        let diagnostics = diagnostics.snapshot();
+        assert_eq!(diagnostics.count(DiagnosticKind::Timeout), expected_len);
+        assert_eq!(diagnostics.unresolved_sizes.len(), expected_len);
+        assert!(!diagnostics.is_complete());
+    }
+
+    #[test]
+    fn test_cancelled_calculator_skips_remaining_projects() {
+        #[derive(Default)]
+        struct CountSizes(std::sync::atomic::AtomicUsize);
+
+        impl ScanObserver for CountSizes {
+            fn on_size_resolved(&self, _project: &ProjectInfo) {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        let temp = TempDir::new().unwrap();
+        let projects = (0..3)
+            .map(|i| {
+                let dir = temp.path().join(format!("dir{i}"));
+                fs::create_dir(&dir).unwrap();
+                fs::write(dir.join("file.txt"), "content").unwrap();
+                ProjectInfo::new_pending(dir.clone(), ProjectType::NodeJs, dir, Utc::now(), false)
+            })
+            .collect::<Vec<_>>();
+
+        let observer = Arc::new(CountSizes::default());
+        let token = CancellationToken::new();
+        let calculator = SizeCalculator::new()
+            .with_observer(observer.clone())
+            .with_cancellation(token.clone());
+        let sized = calculator.calculate_batch(projects.clone());
+        assert!(sized.iter().all(|project| project.size_calculated));
+        assert_eq!(observer.0.load(std::sync::atomic::Ordering::Relaxed), 3);
+
+        token.cancel();
+        let (tx, rx) = crossbeam::channel::unbounded();
+        assert_eq!(
+            calculator.calculate_batch_streaming(projects.clone(), tx),
+            0
+        );
+        assert_eq!(rx.try_iter().count(), 0);
+        let mut project = projects[0].clone();
+        let err = calculator.calculate_single(&mut project).unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert_eq!(observer.0.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlinks_and_sparse_files_count_allocated_and_reclaimable_bytes() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("first");
+        let second = temp.path().join("second");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        // Linked from the other target: freed only when both are removed.
+        fs::write(first.join("shared.bin"), vec![1u8; 10_000]).unwrap();
+        fs::hard_link(first.join("shared.bin"), second.join("shared.bin")).unwrap();
+        // Linked twice inside the same target: freed once, counted once.
+        fs::write(first.join("inner.bin"), vec![2u8; 10_000]).unwrap();
+        fs::hard_link(first.join("inner.bin"), first.join("inner-link.bin")).unwrap();
+        // Sparse: one megabyte long, nothing written.
+        fs::File::create(first.join("sparse.img"))
+            .unwrap()
+            .set_len(1024 * 1024)
+            .unwrap();
+        let allocated = |path: PathBuf| fs::metadata(path).unwrap().blocks() * 512;
+        let shared = allocated(first.join("shared.bin"));
+        let inner = allocated(first.join("inner.bin"));
+        let sparse = allocated(first.join("sparse.img"));
+
+        let projects = [&first, &second]
+            .into_iter()
+            .map(|dir| {
+                ProjectInfo::new_pending(
+                    dir.clone(),
+                    ProjectType::NodeJs,
+                    dir.clone(),
+                    Utc::now(),
+                    false,
+                )
+            })
+            .collect();
+        let results = SizeCalculator::new().calculate_batch(projects);
+        let usage = |dir: &PathBuf| {
+            results
+                .iter()
+                .find(|project| &project.cleanable_dir == dir)
+                .unwrap()
+                .disk_usage
+                .unwrap()
+        };
+
+        let first_usage = usage(&first);
+        assert_eq!(first_usage.apparent, 3 * 10_000 + 1024 * 1024);
+        assert!(sparse < 1024 * 1024);
+        assert_eq!(first_usage.reclaimable, inner + sparse);
+        assert_eq!(usage(&second).reclaimable, 0);
+        assert_eq!(
+            first_usage.allocated + usage(&second).allocated,
+            shared + inner + sparse
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overlapping_targets_claim_shared_inodes_in_path_order() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = TempDir::new().unwrap();
+        let outer = temp.path().join("outer");
+        let inner = outer.join("inner");
+        fs::create_dir_all(&inner).unwrap();
+        fs::write(inner.join("linked.bin"), vec![1u8; 10_000]).unwrap();
+        fs::hard_link(inner.join("linked.bin"), inner.join("linked-too.bin")).unwrap();
+        let linked = fs::metadata(inner.join("linked.bin")).unwrap().blocks() * 512;
+
+        // Listed inner first, so that path order and listed order differ.
+        let projects: Vec<_> = [&inner, &outer]
+            .into_iter()
+            .map(|dir| {
+                ProjectInfo::new_pending(
+                    dir.clone(),
+                    ProjectType::NodeJs,
+                    dir.clone(),
+                    Utc::now(),
+                    false,
+                )
+            })
+            .collect();
+        let results = SizeCalculator::new().calculate_batch(projects);
+
+        let outer_usage = results[1].disk_usage.unwrap();
+        let inner_usage = results[0].disk_usage.unwrap();
+        assert_eq!(outer_usage.reclaimable, linked);
+        assert_eq!(outer_usage.allocated, linked);
+        assert_eq!(inner_usage.reclaimable, 0);
+        assert_eq!(inner_usage.allocated, 0);
     }
 }
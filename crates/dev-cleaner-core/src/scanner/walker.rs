@@ -1,21 +1,46 @@
+use super::cargo::{normalize, stale_target_artifacts, InstalledRustc};
+use super::detector::CACHEDIR_TAG_FILENAME;
+use super::diagnostics::{DiagnosticKind, DiagnosticsLog, ScanDiagnostics};
+use super::mounts::MountFilter;
+use super::observer::{ScanObserver, ScanPhase};
+use super::rules::{
+    default_risk_level, resolve_prune_rules, run_content_checks, DirListing, PruneRule,
+};
+use super::size_calculator::MeasuredUsage;
 use super::{
-    emit_perf_trace, Category, Confidence, ProjectDetector, ProjectInfo, ProjectType, RiskLevel,
-    RuleRef, RuleSource, SizeCalculator,
+    emit_perf_trace, CargoTargetResolver, Category, Confidence, ProjectDetector, ProjectInfo,
+    ProjectType, PruneCandidate, PrunePath, RiskLevel, RulePackRef, RuleRef, RuleRegistry,
+    RuleSource, ScanCache, ScanCacheStats, SizeCalculator,
 };
+use crate::cancel::CancellationToken;
 use crate::config::{CustomPattern, MarkerMode};
+use crate::policy::{ActivitySource, GitActivityProvider, ProcessSnapshot, VcsGuard};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use crossbeam::channel::{self, Receiver};
 use globset::{GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::{Match, WalkBuilder, WalkState};
+use rayon::prelude::*;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::{Instant, SystemTime};
 
+mod traversal;
+
+/// Default age after which an untouched Rust build profile is pruned
+const DEFAULT_RUST_STALE_DAYS: i64 = 30;
+
+/// Heuristic rule pattern recorded on stale Cargo artifact candidates
+const STALE_CARGO_PATTERN: &str = "cargo-stale-artifacts";
+
 #[derive(Debug, Clone, Copy)]
 enum PatternTarget {
     Basename,
@@ -55,10 +80,25 @@ impl CompiledPattern {
     }
 }
 
+struct CompiledRule {
+    pattern: CompiledPattern,
+    pack: RulePackRef,
+    category: Category,
+    risk_level: RiskLevel,
+    verify: Vec<String>,
+}
+
+struct BuiltinMatch {
+    rule: RuleRef,
+    category: Category,
+    risk_level: RiskLevel,
+    verify: Vec<String>,
+}
+
 struct CleanableMatchers {
     basename: GlobSet,
     relative_path: GlobSet,
-    builtin_patterns: Vec<CompiledPattern>,
+    builtin_rules: Vec<CompiledRule>,
 }
 
 impl CleanableMatchers {
@@ -66,17 +106,25 @@ impl CleanableMatchers {
         self.basename.is_match(basename) || self.relative_path.is_match(relative_path)
     }
 
-    fn matched_rule(&self, basename: &str, relative_path: &str) -> Option<RuleRef> {
+    fn matched_rule(&self, basename: &str, relative_path: &str) -> Option<BuiltinMatch> {
         if !self.matches(basename, relative_path) {
             return None;
         }
 
-        for pattern in &self.builtin_patterns {
-            if pattern.matches(basename, relative_path) {
-                return Some(RuleRef {
-                    source: RuleSource::Builtin,
-                    pattern: pattern.pattern.clone(),
-                    name: None,
+        for rule in &self.builtin_rules {
+            if rule.pattern.matches(basename, relative_path) {
+                return Some(BuiltinMatch {
+                    rule: RuleRef {
+                        source: RuleSource::Builtin,
+                        pattern: rule.pattern.pattern.clone(),
+                        name: None,
+                        pack: Some(rule.pack.clone()),
+                        cache_tagged: false,
+                        checks: Vec::new(),
+                    },
+                    category: rule.category,
+                    risk_level: rule.risk_level,
+                    verify: rule.verify.clone(),
                 });
             }
         }
@@ -109,10 +157,75 @@ impl CompiledCustomPattern {
     fn matches(&self, basename: &str, relative_path: &str) -> bool {
         self.matcher.matches(basename, relative_path)
     }
+
+    /// Whether a directory is a root for this pattern, given which markers it has
+    fn marks_root(&self, has_marker: impl Fn(&str) -> bool) -> bool {
+        if self.marker_files.is_empty() {
+            return false;
+        }
+        match self.marker_mode {
+            MarkerMode::AnyOf => self.marker_files.iter().any(|marker| has_marker(marker)),
+            MarkerMode::AllOf => self.marker_files.iter().all(|marker| has_marker(marker)),
+        }
+    }
+}
+
+/// A directory above a walked one whose markers make it a possible project root
+#[derive(Debug, Clone)]
+struct RootFrame {
+    dir: PathBuf,
+    project_types: Vec<ProjectType>,
+    /// Indexes of the custom patterns whose marker files the directory has
+    custom_roots: Vec<usize>,
+    /// Next marked directory further up
+    parent: Option<Arc<RootFrame>>,
+}
+
+impl RootFrame {
+    fn has_markers(&self) -> bool {
+        !self.project_types.is_empty() || !self.custom_roots.is_empty()
+    }
+
+    /// Frame to hand to the directories below: this one, or its parent's when the
+    /// directory is unmarked
+    fn marked(self) -> Option<Arc<RootFrame>> {
+        if self.has_markers() {
+            Some(Arc::new(self))
+        } else {
+            self.parent
+        }
+    }
+
+    /// This frame and every marked directory above it, nearest first
+    fn chain(frame: Option<&RootFrame>) -> impl Iterator<Item = &RootFrame> {
+        std::iter::successors(frame, |frame| frame.parent.as_deref())
+    }
+}
+
+/// Where matching looks for the project root owning a directory
+#[derive(Debug, Clone, Copy)]
+enum RootSearch<'a> {
+    /// Probe every parent up to the scan root
+    Parents,
+    /// Marked directories the walk collected on its way down
+    Frames(Option<&'a RootFrame>),
+}
+
+/// What a discovery walk found, before Cargo targets, dedup and partial cleans
+#[derive(Default)]
+struct WalkOutput {
+    /// Matched targets that pass the filters
+    targets: Vec<ProjectInfo>,
+    /// Matched targets with prune rules, whether or not they pass the filters
+    prunable: Vec<ProjectInfo>,
+    cargo_manifest_dirs: Vec<PathBuf>,
+    /// Targets already measured by the walk
+    measured: MeasuredUsage,
 }
 
 struct DiscoveryResult {
     pending_projects: Vec<ProjectInfo>,
+    measured: MeasuredUsage,
     discovery_ms: u128,
     dedup_ms: u128,
 }
@@ -132,6 +245,15 @@ struct ScanPerfTrace {
     max_age_days: Option<i64>,
     max_risk: Option<RiskLevel>,
     respect_gitignore: bool,
+    single_pass: bool,
+    /// Targets sized by the discovery walk rather than afterwards
+    measured_in_walk: usize,
+    cancelled: bool,
+    /// Problems met so far, by kind
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    diagnostics: BTreeMap<DiagnosticKind, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache: Option<ScanCacheStats>,
 }
 
 /// Main scanner for finding cleanable project directories
@@ -157,8 +279,8 @@ pub struct Scanner {
     /// Cache of compiled `.gitignore` matchers per project root
     gitignore_cache: Mutex<HashMap<PathBuf, Arc<Gitignore>>>,
 
-    /// Cache of project type detection per traversed directory
-    project_type_cache: Mutex<HashMap<PathBuf, Option<ProjectType>>>,
+    /// Cache of detected project types per traversed directory (all matches, in order)
+    project_type_cache: Mutex<HashMap<PathBuf, Vec<ProjectType>>>,
 
     /// Cache of custom root marker checks per project root and custom pattern index
     custom_root_cache: Mutex<HashMap<(PathBuf, usize), bool>>,
@@ -177,6 +299,48 @@ pub struct Scanner {
 
     /// Filter results by max risk level (None = all)
     max_risk: Option<RiskLevel>,
+
+    /// Ecosystem rule packs used for detection and matching
+    rules: Arc<RuleRegistry>,
+
+    /// Days after which an untouched Rust build profile counts as stale
+    rust_stale_days: i64,
+
+    /// Counts git-tracked files inside candidates (None = check disabled)
+    vcs_guard: Option<VcsGuard>,
+
+    /// Reads git activity for recent/in-use decisions (None = heuristics only)
+    activity_provider: Option<Arc<GitActivityProvider>>,
+
+    /// Whether to look for running processes using candidates
+    check_processes: bool,
+
+    /// Targets and sizes from earlier runs (None = always walk and measure)
+    scan_cache: Option<Arc<ScanCache>>,
+
+    /// Whether discovery measures targets in the same walk
+    single_pass: bool,
+
+    /// Stay on the scan root's filesystem
+    one_file_system: bool,
+
+    /// Skip network and pseudo filesystem mounts below the root
+    skip_special_mounts: bool,
+
+    /// Mount points walks stop at, read once per scanner
+    mounts: OnceLock<Arc<MountFilter>>,
+
+    /// Rust toolchains whose artifacts are kept, read once per scanner
+    installed_rustc: OnceLock<InstalledRustc>,
+
+    /// Problems the walks and size calculations ran into
+    diagnostics: Arc<DiagnosticsLog>,
+
+    /// Receives progress of walks and size calculations (None = unobserved)
+    observer: Option<Arc<dyn ScanObserver>>,
+
+    /// Stops walks and size calculations early, keeping what they finished
+    cancel: CancellationToken,
 }
 
 impl Scanner {
@@ -197,6 +361,20 @@ impl Scanner {
             compiled_custom_patterns: Arc::new(Vec::new()),
             category_filter: None,
             max_risk: None,
+            rules: RuleRegistry::global(),
+            rust_stale_days: DEFAULT_RUST_STALE_DAYS,
+            vcs_guard: Some(VcsGuard::new()),
+            activity_provider: Some(Arc::new(GitActivityProvider::new())),
+            check_processes: true,
+            scan_cache: None,
+            single_pass: true,
+            one_file_system: false,
+            skip_special_mounts: true,
+            mounts: OnceLock::new(),
+            installed_rustc: OnceLock::new(),
+            diagnostics: Arc::default(),
+            observer: None,
+            cancel: CancellationToken::new(),
         }
     }
 
@@ -213,6 +391,98 @@ impl Scanner {
         self
     }
 
+    /// Use a specific rule registry instead of the shared one
+    pub fn rule_registry(mut self, rules: Arc<RuleRegistry>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Set the age in days after which an untouched Rust build profile is pruned
+    pub fn rust_stale_days(mut self, days: i64) -> Self {
+        self.rust_stale_days = days;
+        self
+    }
+
+    /// Set whether to count git-tracked files inside candidates (default: true)
+    pub fn check_tracked_files(mut self, enabled: bool) -> Self {
+        self.vcs_guard = enabled.then(VcsGuard::new);
+        self
+    }
+
+    /// Set whether to read git activity for recent/in-use decisions (default: true)
+    pub fn git_activity(mut self, enabled: bool) -> Self {
+        self.activity_provider = enabled.then(|| Arc::new(GitActivityProvider::new()));
+        self
+    }
+
+    /// Set whether to flag candidates used by running processes (default: true)
+    pub fn check_processes(mut self, enabled: bool) -> Self {
+        self.check_processes = enabled;
+        self
+    }
+
+    /// Reuse unchanged directory listings, targets and sizes from a persistent scan
+    /// cache, and save it after sizing
+    pub fn scan_cache(mut self, cache: Arc<ScanCache>) -> Self {
+        self.scan_cache = Some(cache);
+        self
+    }
+
+    /// Set whether discovery measures targets in the same walk (default: true)
+    ///
+    /// When off, discovery walks with the `ignore` crate and each target is measured in
+    /// a second pass. Scans that respect `.gitignore` always work that way.
+    pub fn single_pass(mut self, enabled: bool) -> Self {
+        self.single_pass = enabled;
+        self
+    }
+
+    /// Set whether to stay on the scan root's filesystem (default: false)
+    ///
+    /// When on, walks stop at every mount point below the root, bind mounts included,
+    /// and at any directory on another device.
+    pub fn one_file_system(mut self, enabled: bool) -> Self {
+        self.one_file_system = enabled;
+        self
+    }
+
+    /// Set whether to skip network (NFS, SMB, SSHFS and other FUSE) and pseudo
+    /// (`/proc`, `/sys`, ...) filesystems mounted below the root (default: true)
+    pub fn skip_special_mounts(mut self, enabled: bool) -> Self {
+        self.skip_special_mounts = enabled;
+        self
+    }
+
+    /// Report progress to `observer`
+    pub fn observer(mut self, observer: Arc<dyn ScanObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stop the scan once `token` is cancelled
+    ///
+    /// A cancelled scan still returns: the targets it finished measuring, each with the
+    /// same safety checks as in a full scan.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Problems met by this scanner's walks and size calculations so far, including
+    /// the mount points not entered
+    pub fn diagnostics(&self) -> ScanDiagnostics {
+        let mut diagnostics = self.diagnostics.snapshot();
+        for mount in self
+            .mounts
+            .get()
+            .map(|mounts| mounts.skipped())
+            .unwrap_or_default()
+        {
+            diagnostics.push_skipped_mount(mount);
+        }
+        diagnostics
+    }
+
     pub fn category(mut self, category: Category) -> Self {
         self.category_filter = Some(category);
         self
@@ -252,9 +522,16 @@ impl Scanner {
         let started = Instant::now();
         let discovery = self.discover_pending_projects()?;
         let discovered_candidates = discovery.pending_projects.len();
+        let measured_in_walk = discovery.measured.len();
         let size_started = Instant::now();
-        let mut final_results = SizeCalculator::new().calculate_batch(discovery.pending_projects);
+        self.notify(|observer| observer.on_phase(ScanPhase::Sizing));
+        let mut final_results = self
+            .size_calculator()
+            .with_measured(discovery.measured)
+            .calculate_batch(discovery.pending_projects);
         let size_calc_ms = size_started.elapsed().as_millis();
+        let cache = self.scan_cache.as_deref().map(save_scan_cache);
+        let cancelled = self.cancel.is_cancelled();
 
         // Keep batch consumers on the old contract: only include entries whose size was measured.
         final_results.retain(|project| project.size_calculated);
@@ -279,15 +556,22 @@ impl Scanner {
             max_age_days: self.max_age_days,
             max_risk: self.max_risk,
             respect_gitignore: self.respect_gitignore,
+            single_pass: self.walks_once(),
+            measured_in_walk,
+            cancelled,
+            diagnostics: self.diagnostics().counts,
+            cache,
         });
+        self.notify(|observer| observer.on_phase(finished_phase(cancelled)));
 
         Ok(final_results)
     }
 
     /// Scan with streaming size calculation for real-time progress
     ///
-    /// This method performs a fast scan first (without calculating sizes), then
-    /// streams size calculation results through a channel as they complete.
+    /// This method discovers targets first, then streams size calculation results
+    /// through a channel as they complete. Targets the discovery walk already measured
+    /// are sent right away.
     ///
     /// # Returns
     /// A tuple of (total_count, receiver) where:
@@ -310,6 +594,7 @@ impl Scanner {
         let started = Instant::now();
         let discovery = self.discover_pending_projects()?;
         let total_count = discovery.pending_projects.len();
+        let measured_in_walk = discovery.measured.len();
         let (tx, rx) = channel::unbounded();
         let trace = ScanPerfTrace {
             mode: "scan_with_streaming",
@@ -325,18 +610,34 @@ impl Scanner {
             max_age_days: self.max_age_days,
             max_risk: self.max_risk,
             respect_gitignore: self.respect_gitignore,
+            single_pass: self.walks_once(),
+            measured_in_walk,
+            cancelled: false,
+            diagnostics: self.diagnostics().counts,
+            cache: None,
         };
+        let calculator = self.size_calculator().with_measured(discovery.measured);
+        let scan_cache = self.scan_cache.clone();
+        let observer = self.observer.clone();
+        let cancel = self.cancel.clone();
+        if let Some(observer) = &observer {
+            observer.on_phase(ScanPhase::Sizing);
+        }
 
         // Step 2: Calculate sizes in parallel and stream results.
         thread::spawn(move || {
             let size_started = Instant::now();
-            let calculator = SizeCalculator::new();
             calculator.calculate_batch_streaming(discovery.pending_projects, tx);
 
             let mut trace = trace;
+            trace.cache = scan_cache.as_deref().map(save_scan_cache);
             trace.size_calc_ms = size_started.elapsed().as_millis();
             trace.total_ms = started.elapsed().as_millis();
+            trace.cancelled = cancel.is_cancelled();
             emit_perf_trace("scan", &trace);
+            if let Some(observer) = &observer {
+                observer.on_phase(finished_phase(trace.cancelled));
+            }
         });
 
         Ok((total_count, rx))
@@ -344,48 +645,116 @@ impl Scanner {
 
     fn discover_pending_projects(&self) -> Result<DiscoveryResult> {
         let discovery_started = Instant::now();
-        let results = Arc::new(Mutex::new(Vec::new()));
+        self.notify(|observer| observer.on_phase(ScanPhase::Discovering));
+        let fingerprint = self.scan_cache.as_ref().map(|_| self.cache_fingerprint());
+        let walked = if self.walks_once() {
+            traversal::walk(self, fingerprint)
+        } else {
+            self.walk_for_targets(fingerprint)
+        };
+        let WalkOutput {
+            targets: mut pending_projects,
+            prunable,
+            cargo_manifest_dirs,
+            measured,
+        } = walked;
+        self.notify(|observer| observer.on_phase(ScanPhase::Analyzing));
+
+        let cargo_targets = self.cargo_targets(cargo_manifest_dirs);
+        pending_projects = self.attach_cargo_targets(pending_projects, &cargo_targets);
+        let discovery_ms = discovery_started.elapsed().as_millis();
+        let dedup_started = Instant::now();
+        pending_projects = self.deduplicate_nested_dirs(pending_projects);
+        let dedup_ms = dedup_started.elapsed().as_millis();
+        let partial_candidates =
+            self.partial_candidates(&pending_projects, prunable, &cargo_targets);
+        pending_projects.extend(partial_candidates);
+        for project in &mut pending_projects {
+            project.record_identity();
+        }
+        if let Some(vcs_guard) = &self.vcs_guard {
+            for project in &mut pending_projects {
+                project.tracked_files = vcs_guard.tracked_files_for(project);
+            }
+        }
+        // One `git log` per project root; run them in parallel.
+        pending_projects
+            .par_iter_mut()
+            .for_each(|project| self.apply_git_activity(project));
+        if self.check_processes && !pending_projects.is_empty() {
+            let processes = ProcessSnapshot::capture();
+            for project in &mut pending_projects {
+                let hold = processes.holder_of_project(project).cloned();
+                project.set_held_by(hold);
+            }
+        }
+
+        Ok(DiscoveryResult {
+            pending_projects,
+            measured,
+            discovery_ms,
+            dedup_ms,
+        })
+    }
+
+    fn walks_once(&self) -> bool {
+        self.single_pass && !self.respect_gitignore
+    }
+
+    /// Discovery with the `ignore` walker, leaving every target to be measured later
+    fn walk_for_targets(&self, fingerprint: Option<u64>) -> WalkOutput {
+        let found = Mutex::new(WalkOutput::default());
         let candidate_matchers = self.candidate_matchers();
         let walker = self.build_walker();
 
         let scanner = self;
         walker.build_parallel().run(|| {
-            let results = Arc::clone(&results);
-            let candidate_matchers = Arc::clone(&candidate_matchers);
+            let found = &found;
+            let candidate_matchers = &candidate_matchers;
             Box::new(move |entry| {
+                if scanner.cancel.is_cancelled() {
+                    return WalkState::Quit;
+                }
                 let entry = match entry {
                     Ok(e) => e,
-                    Err(_) => return WalkState::Continue,
+                    Err(err) => {
+                        scanner.record_walk_error(&err);
+                        return WalkState::Continue;
+                    }
                 };
 
                 if !entry.file_type().map_or(false, |ft| ft.is_dir()) {
+                    if entry.path_is_symlink() {
+                        if let Err(err) = fs::metadata(entry.path()) {
+                            scanner.record_symlink_error(entry.path(), &err);
+                        }
+                    }
+                    // Remember every crate so shared/redirected targets can list their feeders.
+                    if entry.file_name() == "Cargo.toml" {
+                        if let Some(parent) = entry.path().parent() {
+                            found
+                                .lock()
+                                .unwrap()
+                                .cargo_manifest_dirs
+                                .push(parent.to_path_buf());
+                        }
+                    }
                     return WalkState::Continue;
                 }
 
                 let dir = entry.path();
+                scanner.notify(|observer| observer.on_dir_visited(dir));
 
                 if let Some(candidate_matchers) = candidate_matchers.as_ref() {
-                    let Some(dir_name) = dir.file_name().map(|s| s.to_string_lossy()) else {
-                        return WalkState::Continue;
-                    };
-                    let relative_path = dir
-                        .strip_prefix(&scanner.root)
-                        .ok()
-                        .map(normalize_relative_path)
-                        .unwrap_or_else(|| dir.display().to_string().replace('\\', "/"));
-
-                    if !candidate_matchers.matches(dir_name.as_ref(), &relative_path)
-                        && !ProjectDetector::is_cmake_build_dir(dir)
-                    {
+                    if !scanner.is_candidate(dir, candidate_matchers, true) {
                         return WalkState::Continue;
                     }
                 }
 
-                if let Some(project_info) = scanner.check_directory_fast(dir) {
-                    // Apply non-size filters early (size filtering will be applied after size calculation).
-                    if scanner.passes_filters(&project_info) {
-                        results.lock().unwrap().push(project_info);
-                    }
+                if let Some(project_info) =
+                    scanner.detect_target(dir, fingerprint, RootSearch::Parents)
+                {
+                    scanner.record_target(project_info, found);
 
                     // Avoid walking into cleanable directories.
                     return WalkState::Skip;
@@ -395,41 +764,468 @@ impl Scanner {
             })
         });
 
-        let mut pending_projects = Arc::try_unwrap(results).unwrap().into_inner().unwrap();
-        let discovery_ms = discovery_started.elapsed().as_millis();
-        let dedup_started = Instant::now();
-        pending_projects = self.deduplicate_nested_dirs(pending_projects);
-        let dedup_ms = dedup_started.elapsed().as_millis();
+        found.into_inner().unwrap()
+    }
 
-        Ok(DiscoveryResult {
-            pending_projects,
-            discovery_ms,
-            dedup_ms,
+    /// Cheap pre-check used when high-risk results are filtered out: whether any
+    /// cleanable pattern could match `dir`. `special_files` is false when the directory
+    /// is known to hold neither `CMakeCache.txt` nor `CACHEDIR.TAG`.
+    fn is_candidate(
+        &self,
+        dir: &Path,
+        candidate_matchers: &CandidateMatchers,
+        special_files: bool,
+    ) -> bool {
+        let Some(dir_name) = dir.file_name().map(|s| s.to_string_lossy()) else {
+            return false;
+        };
+        let relative_path = dir
+            .strip_prefix(&self.root)
+            .ok()
+            .map(normalize_relative_path)
+            .unwrap_or_else(|| dir.display().to_string().replace('\\', "/"));
+
+        candidate_matchers.matches(dir_name.as_ref(), &relative_path)
+            || (special_files
+                && (ProjectDetector::is_cmake_build_dir(dir)
+                    || ProjectDetector::is_cache_dir_tagged(dir)))
+    }
+
+    /// Record an entry the walk could not read
+    fn record_walk_error(&self, err: &ignore::Error) {
+        let kind = if err.is_partial() {
+            DiagnosticKind::GitignoreError
+        } else if err
+            .io_error()
+            .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::PermissionDenied)
+        {
+            DiagnosticKind::PermissionDenied
+        } else {
+            DiagnosticKind::Io
+        };
+        self.diagnostics.record(kind, walk_error_path(err), err);
+    }
+
+    /// Record a symlink met while walking whose target could not be read
+    fn record_symlink_error(&self, path: &Path, err: &std::io::Error) {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            self.diagnostics.record(
+                DiagnosticKind::BrokenSymlink,
+                Some(path),
+                "symlink target does not exist",
+            );
+        } else {
+            self.diagnostics.io_error(path, err);
+        }
+    }
+
+    /// Add a matched target to the walk's findings; returns whether it passed the
+    /// filters and is worth measuring.
+    fn record_target(&self, info: ProjectInfo, found: &Mutex<WalkOutput>) -> bool {
+        let mut found = found.lock().unwrap();
+        // Targets with prune rules may still offer a partial clean when the full
+        // target is filtered out.
+        if !self.prune_rules_for(&info).is_empty() {
+            found.prunable.push(info.clone());
+        }
+
+        // Apply non-size filters early (size filtering will be applied after size calculation).
+        if self.passes_filters(&info) {
+            self.notify(|observer| observer.on_candidate(&info));
+            found.targets.push(info);
+            return true;
+        }
+        false
+    }
+
+    /// Markers of `dir` for the directories below it, read from its listing
+    fn root_frame(
+        &self,
+        dir: &Path,
+        listing: &DirListing,
+        parent: Option<Arc<RootFrame>>,
+    ) -> RootFrame {
+        RootFrame {
+            dir: dir.to_path_buf(),
+            project_types: self.rules.detect_all_listed(dir, listing),
+            custom_roots: self
+                .compiled_custom_patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, custom)| custom.marks_root(|marker| listing.contains(dir, marker)))
+                .map(|(index, _)| index)
+                .collect(),
+            parent,
+        }
+    }
+
+    /// Every parent of `dir` a match may attribute it to, nearest first: up to the
+    /// scan root's parent, or only the immediate parent outside the scan root.
+    fn parent_frames<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = RootFrame> + 'a {
+        let mut left_root = false;
+        dir.ancestors()
+            .skip(1)
+            .take_while(move |parent| {
+                let take = !left_root;
+                left_root = !parent.starts_with(&self.root);
+                take
+            })
+            .map(|parent| RootFrame {
+                dir: parent.to_path_buf(),
+                project_types: self.project_types_for(parent),
+                custom_roots: self
+                    .compiled_custom_patterns
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, custom)| self.custom_root_matches(parent, *index, custom))
+                    .map(|(index, _)| index)
+                    .collect(),
+                parent: None,
+            })
+    }
+
+    /// [`Self::check_directory_fast`] through the scan cache when one is set
+    fn detect_target(
+        &self,
+        dir: &Path,
+        fingerprint: Option<u64>,
+        roots: RootSearch,
+    ) -> Option<ProjectInfo> {
+        let (Some(cache), Some(fingerprint)) = (self.scan_cache.as_deref(), fingerprint) else {
+            return self.check_directory_impl(dir, true, roots);
+        };
+        if let Some(mut info) = cache.cached_target(dir, fingerprint) {
+            // Lock files age out without touching any directory; re-check them.
+            info.in_use = self.rules.is_in_use(&info.root, info.project_type);
+            return Some(info);
+        }
+
+        let info = self.check_directory_impl(dir, true, roots)?;
+        cache.store_target(&info, fingerprint);
+        Some(info)
+    }
+
+    /// Everything besides the directories themselves that decides whether and how a
+    /// directory matches: rule packs, custom patterns, the scan root bounding the marker
+    /// search, and whether `.gitignore` rules are consulted.
+    fn cache_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(self.rules.packs())
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        serde_json::to_string(&self.custom_patterns)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        self.root.hash(&mut hasher);
+        matches!(self.max_risk, Some(RiskLevel::Low | RiskLevel::Medium)).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn size_calculator(&self) -> SizeCalculator {
+        let mut calculator = SizeCalculator::new()
+            .with_mounts(Arc::clone(self.mount_filter()))
+            .with_diagnostics(Arc::clone(&self.diagnostics))
+            .with_cancellation(self.cancel.clone());
+        if let Some(observer) = &self.observer {
+            calculator = calculator.with_observer(Arc::clone(observer));
+        }
+        match &self.scan_cache {
+            Some(cache) => calculator.with_cache(Arc::clone(cache)),
+            None => calculator,
+        }
+    }
+
+    fn notify(&self, event: impl FnOnce(&dyn ScanObserver)) {
+        if let Some(observer) = &self.observer {
+            event(observer.as_ref());
+        }
+    }
+
+    fn mount_filter(&self) -> &Arc<MountFilter> {
+        self.mounts.get_or_init(|| {
+            Arc::new(MountFilter::new(
+                &self.root,
+                self.one_file_system,
+                self.skip_special_mounts,
+            ))
         })
     }
 
+    /// Resolve the effective Cargo target dir of every discovered crate, grouping the
+    /// crates that feed each target.
+    fn cargo_targets(&self, mut manifest_dirs: Vec<PathBuf>) -> BTreeMap<PathBuf, Vec<PathBuf>> {
+        manifest_dirs.sort();
+        manifest_dirs.dedup();
+
+        let absolute = |path: &Path| {
+            normalize(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+        };
+        let root = absolute(&self.root);
+        let mut resolver = CargoTargetResolver::from_env();
+        let mut feeders: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        for dir in manifest_dirs {
+            let target = resolver.resolve(&dir);
+            // A target redirected outside the scan root (an absolute `CARGO_TARGET_DIR`,
+            // `build.target-dir` in `~/.cargo/config.toml`) is shared with crates that
+            // were not scanned.
+            if !absolute(&target.target_dir).starts_with(&root) {
+                continue;
+            }
+            feeders.entry(target.target_dir).or_default().push(dir);
+        }
+        feeders
+    }
+
+    /// Attach resolved Cargo targets to the discovered projects.
+    ///
+    /// Workspace members and redirected targets (`CARGO_TARGET_DIR`, `build.target-dir`)
+    /// collapse into a single entry listing every feeding crate; a target outside the
+    /// usual `<crate>/target` layout is added even if the walk never reached it, as
+    /// long as it lies below the scan root.
+    fn attach_cargo_targets(
+        &self,
+        mut projects: Vec<ProjectInfo>,
+        cargo_targets: &BTreeMap<PathBuf, Vec<PathBuf>>,
+    ) -> Vec<ProjectInfo> {
+        for (target_dir, crates) in cargo_targets {
+            match projects
+                .iter()
+                .position(|project| &project.cleanable_dir == target_dir)
+            {
+                Some(index) => self.apply_cargo_feeders(&mut projects[index], crates),
+                None => {
+                    if let Some(info) = self
+                        .cargo_target_info(target_dir, crates)
+                        .filter(|info| self.passes_filters(info))
+                    {
+                        projects.push(info);
+                    }
+                }
+            }
+        }
+
+        projects
+    }
+
+    fn cargo_target_info(&self, target_dir: &Path, crates: &[PathBuf]) -> Option<ProjectInfo> {
+        let mut info = self
+            .check_directory_fast(target_dir)
+            .filter(|info| info.cleanable_dir == target_dir)?;
+        self.apply_cargo_feeders(&mut info, crates);
+        Some(info)
+    }
+
+    fn apply_cargo_feeders(&self, info: &mut ProjectInfo, crates: &[PathBuf]) {
+        info.in_use = info.in_use
+            || crates
+                .iter()
+                .any(|dir| self.rules.is_in_use(dir, ProjectType::Rust));
+        if crates.len() > 1 || crates[0] != info.root {
+            info.feeding_projects = crates.to_vec();
+        }
+    }
+
+    /// Partial-clean candidates: targets with prune rules, and Cargo targets holding
+    /// stale artifacts.
+    ///
+    /// Each one shares its `cleanable_dir` with the full target entry but only lists the
+    /// sub-paths to prune, so it is offered at a lower risk even when the full target is
+    /// filtered out.
+    fn partial_candidates(
+        &self,
+        projects: &[ProjectInfo],
+        prunable: Vec<ProjectInfo>,
+        cargo_targets: &BTreeMap<PathBuf, Vec<PathBuf>>,
+    ) -> Vec<ProjectInfo> {
+        let mut targets: BTreeMap<PathBuf, ProjectInfo> = projects
+            .iter()
+            .map(|project| (project.cleanable_dir.clone(), project.clone()))
+            .collect();
+        for info in prunable {
+            targets.entry(info.cleanable_dir.clone()).or_insert(info);
+        }
+        for (target_dir, crates) in cargo_targets {
+            if !targets.contains_key(target_dir) && target_dir.is_dir() {
+                if let Some(info) = self.cargo_target_info(target_dir, crates) {
+                    targets.insert(target_dir.clone(), info);
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for (target_dir, info) in targets {
+            let nested = projects.iter().any(|project| {
+                target_dir != project.cleanable_dir
+                    && target_dir.starts_with(&project.cleanable_dir)
+            });
+            if nested {
+                continue;
+            }
+
+            let Some(candidate) = self.partial_candidate(info) else {
+                continue;
+            };
+            if self.passes_filters(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+
+    /// Prune rules declared by the custom pattern or pack rule that matched `info`.
+    fn prune_rules_for(&self, info: &ProjectInfo) -> &[PruneRule] {
+        let Some(rule) = info.matched_rule.as_ref() else {
+            return &[];
+        };
+        match rule.source {
+            RuleSource::Custom => self
+                .custom_patterns
+                .iter()
+                .find(|custom| Some(&custom.name) == rule.name.as_ref())
+                .map_or(&[], |custom| custom.prune.as_slice()),
+            RuleSource::Builtin => rule
+                .pack
+                .as_ref()
+                .and_then(|pack| self.rules.pack(&pack.name))
+                .and_then(|pack| {
+                    pack.rules
+                        .iter()
+                        .find(|pack_rule| pack_rule.pattern == rule.pattern)
+                })
+                .map_or(&[], |pack_rule| pack_rule.prune.as_slice()),
+            RuleSource::Gitignore | RuleSource::Heuristic | RuleSource::CacheTag => &[],
+        }
+    }
+
+    /// Turn a target into a partial clean of its prunable sub-paths, if it has any.
+    fn partial_candidate(&self, mut info: ProjectInfo) -> Option<ProjectInfo> {
+        let mut found = resolve_prune_rules(&info.cleanable_dir, self.prune_rules_for(&info));
+        let from_rules = !found.is_empty();
+        if info.project_type == ProjectType::Rust {
+            found.extend(stale_target_artifacts(
+                &info.cleanable_dir,
+                self.rust_stale_days,
+                self.installed_rustc.get_or_init(InstalledRustc::from_env),
+            ));
+        }
+        if found.is_empty() {
+            return None;
+        }
+
+        // Overlapping rules must not remove (or count) the same entry twice.
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut kept: Vec<PruneCandidate> = Vec::new();
+        for candidate in found {
+            if !kept
+                .iter()
+                .any(|parent| candidate.path.starts_with(&parent.path))
+            {
+                kept.push(candidate);
+            }
+        }
+
+        if let Some(newest) = kept.iter().map(|candidate| candidate.modified).max() {
+            info.last_modified = system_time_to_datetime(newest);
+        }
+        if !from_rules {
+            info.confidence = Confidence::Medium;
+            info.matched_rule = Some(RuleRef {
+                source: RuleSource::Heuristic,
+                pattern: STALE_CARGO_PATTERN.to_string(),
+                name: None,
+                pack: None,
+                cache_tagged: false,
+                checks: Vec::new(),
+            });
+        }
+        info.risk_level = RiskLevel::Low;
+        info.size = 0;
+        info.size_calculated = false;
+        info.prune_paths = kept
+            .into_iter()
+            .map(|candidate| PrunePath {
+                path: candidate.path,
+                size: 0,
+                reason: candidate.reason,
+            })
+            .collect();
+        Some(info)
+    }
+
     /// Re-validate an existing cleanable target path against current rules.
     pub fn revalidate_target<P: AsRef<Path>>(&self, cleanable_dir: P) -> Option<ProjectInfo> {
         let dir = cleanable_dir.as_ref();
         if !dir.is_dir() {
             return None;
         }
-        self.check_directory(dir)
-            .filter(|info| info.cleanable_dir == dir)
+        let mut info = self
+            .check_directory(dir)
+            .filter(|info| info.cleanable_dir == dir)?;
+        self.apply_git_activity(&mut info);
+        info.record_identity();
+        Some(info)
+    }
+
+    /// Re-validate a partial-clean candidate: the target must still match and only the
+    /// planned sub-paths that are still prunable are kept.
+    pub fn revalidate_prune_target<P: AsRef<Path>>(
+        &self,
+        cleanable_dir: P,
+        prune_paths: &[PrunePath],
+    ) -> Option<ProjectInfo> {
+        let dir = cleanable_dir.as_ref();
+        let info = self
+            .check_directory_fast(dir)
+            .filter(|info| info.cleanable_dir == dir)?;
+        let mut info = self.partial_candidate(info)?;
+
+        let planned = prune_paths
+            .iter()
+            .map(|prune| prune.path.as_path())
+            .collect::<HashSet<_>>();
+        info.prune_paths
+            .retain(|prune| planned.contains(prune.path.as_path()));
+        if info.prune_paths.is_empty() {
+            return None;
+        }
+
+        SizeCalculator::new().calculate_single(&mut info).ok()?;
+        self.apply_git_activity(&mut info);
+        info.record_identity();
+        Some(info)
+    }
+
+    /// Record the project's git activity; activity inside the in-use window marks it in
+    /// use with verified evidence.
+    fn apply_git_activity(&self, info: &mut ProjectInfo) {
+        let Some(provider) = &self.activity_provider else {
+            return;
+        };
+        info.activity = provider.activity_for(&info.root);
+        let window = self.rules.in_use_window_days(info.project_type) as i64;
+        if info
+            .activity
+            .is_some_and(|activity| activity.days_since_activity() < window)
+        {
+            info.in_use = true;
+            info.in_use_source = ActivitySource::VerifiedGit;
+        }
     }
 
     fn build_walker(&self) -> WalkBuilder {
         let mut walker = WalkBuilder::new(&self.root);
         let exclude_dirs = self.exclude_dirs.clone();
+        let mounts = Arc::clone(self.mount_filter());
         walker
             .hidden(false)
             .ignore(self.respect_gitignore)
             .git_ignore(self.respect_gitignore)
             .git_exclude(self.respect_gitignore)
             .filter_entry(move |entry| {
-                let file_name = entry.file_name().to_string_lossy();
-                !matches!(file_name.as_ref(), ".git" | ".svn" | ".hg")
-                    && !exclude_dirs.contains(file_name.as_ref())
+                !is_excluded(&entry.file_name().to_string_lossy(), &exclude_dirs)
+                    && (entry.depth() == 0
+                        || !entry.file_type().is_some_and(|ft| ft.is_dir())
+                        || mounts.admits(entry.path(), || entry.metadata().ok()))
             });
 
         if let Some(depth) = self.max_depth {
@@ -444,15 +1240,15 @@ impl Scanner {
         emit_perf_trace("scan", &trace);
     }
 
-    fn candidate_matchers(&self) -> Arc<Option<CandidateMatchers>> {
+    fn candidate_matchers(&self) -> Option<CandidateMatchers> {
         // We can safely prefilter only when high-risk results (including .gitignore-derived)
         // are filtered out, otherwise we could miss them.
         let can_prefilter = matches!(self.max_risk, Some(RiskLevel::Low | RiskLevel::Medium));
         if !can_prefilter {
-            return Arc::new(None);
+            return None;
         }
 
-        Arc::new(Some(build_candidate_matchers(&self.custom_patterns)))
+        Some(build_candidate_matchers(&self.rules, &self.custom_patterns))
     }
 
     /// Remove nested cleanable directories, keeping only the topmost ones
@@ -491,32 +1287,81 @@ impl Scanner {
 
     /// Check if a directory is a cleanable project directory
     fn check_directory(&self, dir: &Path) -> Option<ProjectInfo> {
-        self.check_directory_impl(dir, false)
+        self.check_directory_impl(dir, false, RootSearch::Parents)
     }
 
     /// Check if a directory is a cleanable project directory (fast mode - no size calc)
     fn check_directory_fast(&self, dir: &Path) -> Option<ProjectInfo> {
-        self.check_directory_impl(dir, true)
+        self.check_directory_impl(dir, true, RootSearch::Parents)
     }
 
     /// Implementation of directory checking with configurable fast mode
-    fn check_directory_impl(&self, dir: &Path, fast_mode: bool) -> Option<ProjectInfo> {
-        // Try to detect project type by looking at parent directories
-        let mut current = dir;
+    ///
+    /// A `CACHEDIR.TAG` corroborates any other match and raises its confidence; on its
+    /// own it still marks the directory as a cache, even without a project marker.
+    fn check_directory_impl(
+        &self,
+        dir: &Path,
+        fast_mode: bool,
+        roots: RootSearch,
+    ) -> Option<ProjectInfo> {
+        let cache_tagged = ProjectDetector::is_cache_dir_tagged(dir);
+        match self.match_directory(dir, fast_mode, roots) {
+            Some(mut info) => {
+                if cache_tagged {
+                    info.confidence = info.confidence.raised();
+                    if let Some(rule) = info.matched_rule.as_mut() {
+                        rule.cache_tagged = true;
+                    }
+                }
+                Some(info)
+            }
+            None if cache_tagged => self.cache_tagged_info(dir, fast_mode),
+            None => None,
+        }
+    }
+
+    fn cache_tagged_info(&self, dir: &Path, fast_mode: bool) -> Option<ProjectInfo> {
+        let parent = dir.parent()?;
+        let mut info = if fast_mode {
+            self.build_project_info_fast(parent, ProjectType::Generic, dir)
+        } else {
+            self.build_project_info(parent, ProjectType::Generic, dir)
+        }?;
+        let rule = RuleRef {
+            source: RuleSource::CacheTag,
+            pattern: CACHEDIR_TAG_FILENAME.to_string(),
+            name: None,
+            pack: None,
+            cache_tagged: true,
+            checks: Vec::new(),
+        };
+        apply_matched_rule(&mut info, rule, Category::Cache);
+        Some(info)
+    }
 
+    /// Match a directory against custom, builtin, `.gitignore` and heuristic rules
+    fn match_directory(
+        &self,
+        dir: &Path,
+        fast_mode: bool,
+        roots: RootSearch,
+    ) -> Option<ProjectInfo> {
         // Check if this directory itself is a cleanable target
         let dir_name = dir.file_name()?.to_string_lossy();
 
-        // Look for project root by checking parent directories
-        while let Some(parent) = current.parent() {
+        // Look for the project root among the marked directories above, nearest first
+        let frames: Box<dyn Iterator<Item = Cow<RootFrame>>> = match roots {
+            RootSearch::Parents => Box::new(self.parent_frames(dir).map(Cow::Owned)),
+            RootSearch::Frames(frame) => Box::new(RootFrame::chain(frame).map(Cow::Borrowed)),
+        };
+        for frame in frames {
+            let parent = frame.dir.as_path();
             let relative_path = normalize_relative_path(dir.strip_prefix(parent).ok()?);
 
             // Custom patterns (higher priority than builtin/.gitignore patterns)
-            for (custom_index, custom) in self.compiled_custom_patterns.iter().enumerate() {
-                if !self.custom_root_matches(parent, custom_index, custom) {
-                    continue;
-                }
-
+            for &custom_index in &frame.custom_roots {
+                let custom = &self.compiled_custom_patterns[custom_index];
                 if !custom.matches(dir_name.as_ref(), &relative_path) {
                     continue;
                 }
@@ -532,19 +1377,35 @@ impl Scanner {
                         source: RuleSource::Custom,
                         pattern: custom.directory.clone(),
                         name: Some(custom.name.clone()),
+                        pack: None,
+                        cache_tagged: false,
+                        checks: Vec::new(),
                     };
-                    apply_matched_rule(&mut info, rule, dir_name.as_ref(), &relative_path);
+                    let category = self.rules.classify(dir_name.as_ref(), &relative_path);
+                    apply_matched_rule(&mut info, rule, category);
                     info.project_name = Some(custom.name.clone());
                     return Some(info);
                 }
             }
 
-            if let Some(project_type) = self.project_type_for(parent) {
-                // Check if current directory is a cleanable dir for this project type
-                // This includes both default patterns AND patterns from `.gitignore` discovery.
-                let matchers = self.matchers_for(project_type, parent);
-                let gitignore_rule = self.gitignore_rule_for(parent, dir, dir_name.as_ref());
-                let builtin_rule = matchers.matched_rule(dir_name.as_ref(), &relative_path);
+            let project_types = &frame.project_types;
+            if let Some(&primary_type) = project_types.first() {
+                // Check if current directory is a cleanable dir for any detected project type.
+                // Builtin rules are attributed to the ecosystem that owns them; `.gitignore`
+                // discovery falls back to the primary type.
+                let builtin_rule = project_types.iter().find_map(|&project_type| {
+                    self.matchers_for(project_type, parent)
+                        .matched_rule(dir_name.as_ref(), &relative_path)
+                        .map(|matched| (project_type, matched))
+                });
+                let gitignore_rule = if builtin_rule.is_none() {
+                    self.gitignore_rule_for(parent, dir, dir_name.as_ref())
+                } else {
+                    None
+                };
+                let project_type = builtin_rule
+                    .as_ref()
+                    .map_or(primary_type, |(project_type, _)| *project_type);
 
                 if builtin_rule.is_some() || gitignore_rule.is_some() {
                     let info = if fast_mode {
@@ -554,25 +1415,31 @@ impl Scanner {
                     };
 
                     if let Some(mut info) = info {
-                        if let Some(rule) = builtin_rule {
-                            apply_matched_rule(&mut info, rule, dir_name.as_ref(), &relative_path);
+                        let pack = if let Some((_, matched)) = builtin_rule {
+                            let pack = matched
+                                .rule
+                                .pack
+                                .as_ref()
+                                .and_then(|pack| self.rules.pack(&pack.name));
+                            apply_matched_rule(&mut info, matched.rule, matched.category);
+                            info.risk_level = matched.risk_level;
+                            apply_content_checks(&mut info, dir, &matched.verify);
+                            pack
                         } else if let Some(rule) = gitignore_rule {
-                            apply_matched_rule(&mut info, rule, dir_name.as_ref(), &relative_path);
+                            let category = self.rules.classify(dir_name.as_ref(), &relative_path);
+                            apply_matched_rule(&mut info, rule, category);
+                            self.rules.detect_pack(parent)
                         } else {
-                            // Shouldn't happen, but keep the default fields intact if we can't pinpoint a rule.
-                            info.category = classify_category(dir_name.as_ref(), &relative_path);
-                            info.risk_level = default_risk_level(info.category);
+                            None
+                        };
+                        if let Some(display_name) = pack.and_then(|pack| pack.display_name.clone())
+                        {
+                            info.project_name = Some(display_name);
                         }
                         return Some(info);
                     }
                 }
             }
-            current = parent;
-
-            // Don't go too far up
-            if !current.starts_with(&self.root) {
-                break;
-            }
         }
 
         // Heuristic detection for CMake build directories
@@ -598,6 +1465,9 @@ impl Scanner {
                             source: RuleSource::Heuristic,
                             pattern: "cmake-out-of-source-build".to_string(),
                             name: None,
+                            pack: None,
+                            cache_tagged: false,
+                            checks: Vec::new(),
                         });
                         return Some(info);
                     }
@@ -612,19 +1482,45 @@ impl Scanner {
             }
         }
 
+        // Heuristic detection for Cargo target dirs that don't sit in `<crate>/target`
+        // (shared via `CARGO_TARGET_DIR` or `build.target-dir`).
+        if ProjectDetector::is_cargo_target_dir(dir) {
+            let parent = dir.parent()?;
+            let info = if fast_mode {
+                self.build_project_info_fast(parent, ProjectType::Rust, dir)
+            } else {
+                self.build_project_info(parent, ProjectType::Rust, dir)
+            };
+
+            if let Some(mut info) = info {
+                info.category = Category::Build;
+                info.risk_level = RiskLevel::Medium;
+                info.confidence = Confidence::Medium;
+                info.matched_rule = Some(RuleRef {
+                    source: RuleSource::Heuristic,
+                    pattern: "cargo-target-dir".to_string(),
+                    name: None,
+                    pack: None,
+                    cache_tagged: false,
+                    checks: Vec::new(),
+                });
+                return Some(info);
+            }
+        }
+
         None
     }
 
-    fn project_type_for(&self, dir: &Path) -> Option<ProjectType> {
+    fn project_types_for(&self, dir: &Path) -> Vec<ProjectType> {
         let key = dir.to_path_buf();
 
-        if let Some(cached) = self.project_type_cache.lock().unwrap().get(&key).copied() {
-            return cached;
+        if let Some(cached) = self.project_type_cache.lock().unwrap().get(&key) {
+            return cached.clone();
         }
 
-        let detected = ProjectDetector::detect(dir);
+        let detected = self.rules.detect_all(dir);
         let mut cache = self.project_type_cache.lock().unwrap();
-        cache.insert(key, detected);
+        cache.insert(key, detected.clone());
         detected
     }
 
@@ -640,20 +1536,7 @@ impl Scanner {
             return cached;
         }
 
-        let matched = if custom.marker_files.is_empty() {
-            false
-        } else {
-            match custom.marker_mode {
-                MarkerMode::AnyOf => custom
-                    .marker_files
-                    .iter()
-                    .any(|marker| project_root.join(marker).exists()),
-                MarkerMode::AllOf => custom
-                    .marker_files
-                    .iter()
-                    .all(|marker| project_root.join(marker).exists()),
-            }
-        };
+        let matched = custom.marks_root(|marker| project_root.join(marker).exists());
 
         let mut cache = self.custom_root_cache.lock().unwrap();
         cache.insert(key, matched);
@@ -673,7 +1556,7 @@ impl Scanner {
         }
 
         // Build outside lock to avoid blocking other threads while compiling patterns.
-        let built = Arc::new(build_matchers(project_type, project_root));
+        let built = Arc::new(build_matchers(&self.rules, project_type, project_root));
 
         let mut cache = self.matcher_cache.lock().unwrap();
         Arc::clone(cache.entry(key).or_insert_with(|| Arc::clone(&built)))
@@ -687,7 +1570,7 @@ impl Scanner {
         }
 
         // Build outside lock to avoid blocking other threads while reading `.gitignore`.
-        let built = Arc::new(build_gitignore(project_root));
+        let built = Arc::new(build_gitignore(project_root, &self.diagnostics));
 
         let mut cache = self.gitignore_cache.lock().unwrap();
         Arc::clone(cache.entry(key).or_insert_with(|| Arc::clone(&built)))
@@ -723,6 +1606,9 @@ impl Scanner {
             source: RuleSource::Gitignore,
             pattern,
             name: None,
+            pack: None,
+            cache_tagged: false,
+            checks: Vec::new(),
         })
     }
 
@@ -739,7 +1625,7 @@ impl Scanner {
         let last_modified = system_time_to_datetime(modified);
 
         // Check if project is in use
-        let in_use = ProjectDetector::is_in_use(project_root, project_type);
+        let in_use = self.rules.is_in_use(project_root, project_type);
 
         Some(ProjectInfo::new_pending(
             project_root.to_path_buf(),
@@ -757,36 +1643,9 @@ impl Scanner {
         project_type: ProjectType,
         cleanable_dir: &Path,
     ) -> Option<ProjectInfo> {
-        // Calculate directory size
-        let size = calculate_dir_size(cleanable_dir).ok()?;
-
-        // Get last modified time
-        let metadata = cleanable_dir.metadata().ok()?;
-        let modified = metadata.modified().ok()?;
-        let last_modified = system_time_to_datetime(modified);
-
-        // Check if project is in use
-        let in_use = ProjectDetector::is_in_use(project_root, project_type);
-
-        Some(ProjectInfo {
-            root: project_root.to_path_buf(),
-            project_type,
-            project_name: None,
-            category: Category::Unknown,
-            risk_level: RiskLevel::Medium,
-            confidence: Confidence::Unknown,
-            matched_rule: None,
-            cleanable_dir: cleanable_dir.to_path_buf(),
-            size,
-            size_calculated: true,
-            last_modified,
-            in_use,
-            protected: false,
-            protected_by: None,
-            recent: false,
-            selection_reason: None,
-            skip_reason: None,
-        })
+        let mut info = self.build_project_info_fast(project_root, project_type, cleanable_dir)?;
+        self.size_calculator().calculate_single(&mut info).ok()?;
+        Some(info)
     }
 
     /// Check if project info passes all filters
@@ -823,16 +1682,62 @@ impl Scanner {
     }
 }
 
-fn build_gitignore(project_root: &Path) -> Gitignore {
+/// Phase a scan ends in, depending on whether it was cancelled
+fn finished_phase(cancelled: bool) -> ScanPhase {
+    if cancelled {
+        ScanPhase::Cancelled
+    } else {
+        ScanPhase::Finished
+    }
+}
+
+/// Persist the cache and report how it did; a failed save only costs the next run time.
+fn save_scan_cache(cache: &ScanCache) -> ScanCacheStats {
+    let mut stats = cache.stats();
+    if let Err(err) = cache.save() {
+        stats.save_error = Some(format!("{err:#}"));
+    }
+    stats
+}
+
+/// Path an `ignore` walk error is about, when it names one
+fn walk_error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        ignore::Error::Partial(errs) => errs.iter().find_map(walk_error_path),
+        _ => None,
+    }
+}
+
+/// Entries no walk enters: VCS metadata, tombstones and the configured excludes
+fn is_excluded(name: &str, exclude_dirs: &HashSet<String>) -> bool {
+    matches!(name, ".git" | ".svn" | ".hg")
+        || crate::tombstone::is_tombstone_name(name)
+        || exclude_dirs.contains(name)
+}
+
+fn build_gitignore(project_root: &Path, diagnostics: &DiagnosticsLog) -> Gitignore {
     let mut builder = GitignoreBuilder::new(project_root);
     let path = project_root.join(".gitignore");
     if path.is_file() {
-        let _ = builder.add(path);
+        if let Some(err) = builder.add(&path) {
+            diagnostics.record(DiagnosticKind::GitignoreError, Some(&path), err);
+        }
     }
-    builder.build().unwrap_or_else(|_| Gitignore::empty())
+    builder.build().unwrap_or_else(|err| {
+        diagnostics.record(DiagnosticKind::GitignoreError, Some(&path), err);
+        Gitignore::empty()
+    })
 }
 
-fn build_candidate_matchers(custom_patterns: &[CustomPattern]) -> CandidateMatchers {
+fn build_candidate_matchers(
+    rules: &RuleRegistry,
+    custom_patterns: &[CustomPattern],
+) -> CandidateMatchers {
     let mut basename_builder = GlobSetBuilder::new();
     let mut relpath_builder = GlobSetBuilder::new();
 
@@ -871,33 +1776,10 @@ fn build_candidate_matchers(custom_patterns: &[CustomPattern]) -> CandidateMatch
         }
     };
 
-    // Built-in cleanable patterns across all supported project types.
-    const ALL_PROJECT_TYPES: [ProjectType; 20] = [
-        ProjectType::NodeJs,
-        ProjectType::Rust,
-        ProjectType::Python,
-        ProjectType::Java,
-        ProjectType::Kotlin,
-        ProjectType::Scala,
-        ProjectType::Clojure,
-        ProjectType::Dart,
-        ProjectType::Haskell,
-        ProjectType::Go,
-        ProjectType::C,
-        ProjectType::Cpp,
-        ProjectType::Ruby,
-        ProjectType::Swift,
-        ProjectType::Php,
-        ProjectType::Elixir,
-        ProjectType::DotNet,
-        ProjectType::Maven,
-        ProjectType::Gradle,
-        ProjectType::Generic,
-    ];
-
-    for project_type in ALL_PROJECT_TYPES {
-        for pattern in ProjectDetector::cleanable_dirs(project_type) {
-            add_pattern(pattern);
+    // Built-in cleanable patterns across all loaded rule packs.
+    for pack in rules.packs() {
+        for rule in &pack.rules {
+            add_pattern(&rule.pattern);
         }
     }
 
@@ -939,21 +1821,38 @@ fn compile_custom_patterns(patterns: &[CustomPattern]) -> Vec<CompiledCustomPatt
     compiled
 }
 
-fn build_matchers(project_type: ProjectType, _project_root: &Path) -> CleanableMatchers {
-    let builtin_patterns = ProjectDetector::cleanable_dirs(project_type)
-        .iter()
-        .filter_map(|pattern| CompiledPattern::new(pattern))
+fn build_matchers(
+    rules: &RuleRegistry,
+    project_type: ProjectType,
+    project_root: &Path,
+) -> CleanableMatchers {
+    let builtin_rules = rules
+        .packs_for(project_type)
+        .filter(|pack| pack.applies_to(project_root))
+        .flat_map(|pack| {
+            pack.rules.iter().filter_map(|rule| {
+                Some(CompiledRule {
+                    pattern: CompiledPattern::new(&rule.pattern)?,
+                    pack: RulePackRef {
+                        name: pack.name.clone(),
+                        version: pack.version,
+                    },
+                    category: rule.category,
+                    risk_level: rule.risk_level(),
+                    verify: rule.verify.clone(),
+                })
+            })
+        })
         .collect::<Vec<_>>();
-    let patterns = ProjectDetector::cleanable_dirs(project_type);
 
     let mut basename_builder = GlobSetBuilder::new();
     let mut relpath_builder = GlobSetBuilder::new();
 
-    for pattern in patterns {
-        let pattern = pattern.replace('\\', "/");
+    for rule in &builtin_rules {
+        let pattern = &rule.pattern.pattern;
         let is_relpath = pattern.contains('/');
 
-        let glob = match GlobBuilder::new(&pattern).literal_separator(true).build() {
+        let glob = match GlobBuilder::new(pattern).literal_separator(true).build() {
             Ok(g) => g,
             Err(_) => continue,
         };
@@ -975,7 +1874,7 @@ fn build_matchers(project_type: ProjectType, _project_root: &Path) -> CleanableM
     CleanableMatchers {
         basename,
         relative_path,
-        builtin_patterns,
+        builtin_rules,
     }
 }
 
@@ -983,8 +1882,7 @@ fn normalize_relative_path(relative: &Path) -> String {
     relative.to_string_lossy().replace('\\', "/")
 }
 
-fn apply_matched_rule(info: &mut ProjectInfo, rule: RuleRef, basename: &str, relative_path: &str) {
-    let category = classify_category(basename, relative_path);
+fn apply_matched_rule(info: &mut ProjectInfo, rule: RuleRef, category: Category) {
     let risk_level = risk_for_rule(rule.source, category);
     let confidence = confidence_for_rule(rule.source);
 
@@ -994,68 +1892,19 @@ fn apply_matched_rule(info: &mut ProjectInfo, rule: RuleRef, basename: &str, rel
     info.matched_rule = Some(rule);
 }
 
-fn classify_category(basename: &str, relative_path: &str) -> Category {
-    let basename = basename.to_ascii_lowercase();
-    let relative_path = relative_path.to_ascii_lowercase();
-
-    // deps
-    if basename == "node_modules"
-        || basename == ".venv"
-        || basename == "venv"
-        || basename == "vendor"
-        || basename == "deps"
-        || basename == ".bundle"
-        || relative_path == "vendor/bundle"
-        || relative_path.starts_with("vendor/bundle/")
-    {
-        return Category::Deps;
-    }
-
-    // cache
-    if basename == "__pycache__"
-        || basename == ".pytest_cache"
-        || basename == ".mypy_cache"
-        || basename == ".tox"
-        || basename == ".eggs"
-        || basename == ".cache"
-        || basename == ".dart_tool"
-        || basename == ".turbo"
-        || basename == ".parcel-cache"
-    {
-        return Category::Cache;
-    }
-
-    // build
-    if basename == "target"
-        || basename == "build"
-        || basename == "dist"
-        || basename == "dist-newstyle"
-        || basename == "out"
-        || basename == "_build"
-        || basename == ".stack-work"
-        || basename == "deriveddata"
-        || basename == ".build"
-        || basename == ".next"
-        || basename == ".nuxt"
-        || basename == ".gradle"
-        || basename == ".swiftpm"
-        || basename == "bin"
-        || basename == "obj"
-        || basename.starts_with("cmake-build")
-        || basename.ends_with(".egg-info")
-    {
-        return Category::Build;
-    }
-
-    Category::Unknown
-}
-
-fn default_risk_level(category: Category) -> RiskLevel {
-    match category {
-        Category::Cache => RiskLevel::Low,
-        Category::Build => RiskLevel::Medium,
-        Category::Deps => RiskLevel::High,
-        Category::Unknown => RiskLevel::Medium,
+/// Confirm a builtin match by its content. When none of the rule's checks pass the
+/// directory may be hand-written, so confidence drops to low and risk goes up a step.
+fn apply_content_checks(info: &mut ProjectInfo, dir: &Path, verify: &[String]) {
+    if verify.is_empty() {
+        return;
+    }
+    let checks = run_content_checks(dir, verify);
+    if !checks.iter().any(|check| check.passed) {
+        info.confidence = Confidence::Low;
+        info.risk_level = info.risk_level.raised();
+    }
+    if let Some(rule) = info.matched_rule.as_mut() {
+        rule.checks = checks;
     }
 }
 
@@ -1068,29 +1917,12 @@ fn risk_for_rule(source: RuleSource, category: Category) -> RiskLevel {
 
 fn confidence_for_rule(source: RuleSource) -> Confidence {
     match source {
-        RuleSource::Custom | RuleSource::Builtin => Confidence::High,
+        RuleSource::Custom | RuleSource::Builtin | RuleSource::CacheTag => Confidence::High,
         RuleSource::Heuristic => Confidence::Medium,
         RuleSource::Gitignore => Confidence::Low,
     }
 }
 
-/// Calculate total size of a directory recursively
-fn calculate_dir_size(dir: &Path) -> Result<u64> {
-    let mut total = 0u64;
-
-    for entry in walkdir::WalkDir::new(dir)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if entry.file_type().is_file() {
-            total += entry.metadata()?.len();
-        }
-    }
-
-    Ok(total)
-}
-
 /// Convert SystemTime to DateTime<Utc>
 fn system_time_to_datetime(time: SystemTime) -> DateTime<Utc> {
     let duration = time
@@ -1102,6 +1934,7 @@ fn system_time_to_datetime(time: SystemTime) -> DateTime<Utc> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scanner::ContentCheck;
     use chrono::Utc;
     use std::fs;
     use std::process::Command;
@@ -1345,6 +2178,7 @@ mod tests {
             directory: "Library".to_string(),
             marker_files: vec!["Assets".to_string(), "ProjectSettings".to_string()],
             marker_mode: MarkerMode::AllOf,
+            prune: Vec::new(),
         }];
 
         let scanner = Scanner::new(root).custom_patterns(&patterns);
@@ -1526,42 +2360,23 @@ mod tests {
 
     #[test]
     fn test_category_gradle_is_build() {
-        assert_eq!(classify_category(".gradle", ".gradle"), Category::Build);
+        let rules = RuleRegistry::builtin();
+        assert_eq!(rules.classify(".gradle", ".gradle"), Category::Build);
     }
 
     #[test]
     fn test_category_swiftpm_is_build() {
-        assert_eq!(classify_category(".swiftpm", ".swiftpm"), Category::Build);
+        let rules = RuleRegistry::builtin();
+        assert_eq!(rules.classify(".swiftpm", ".swiftpm"), Category::Build);
     }
 
     #[test]
-    fn test_builtin_patterns_have_known_categories() {
-        const ALL_PROJECT_TYPES: [ProjectType; 20] = [
-            ProjectType::NodeJs,
-            ProjectType::Rust,
-            ProjectType::Python,
-            ProjectType::Java,
-            ProjectType::Kotlin,
-            ProjectType::Scala,
-            ProjectType::Clojure,
-            ProjectType::Dart,
-            ProjectType::Haskell,
-            ProjectType::Go,
-            ProjectType::C,
-            ProjectType::Cpp,
-            ProjectType::Ruby,
-            ProjectType::Swift,
-            ProjectType::Php,
-            ProjectType::Elixir,
-            ProjectType::DotNet,
-            ProjectType::Maven,
-            ProjectType::Gradle,
-            ProjectType::Generic,
-        ];
+    fn test_builtin_patterns_have_consistent_categories() {
+        let rules = RuleRegistry::builtin();
 
-        for project_type in ALL_PROJECT_TYPES {
-            for pattern in ProjectDetector::cleanable_dirs(project_type) {
-                let normalized = pattern.replace('\\', "/");
+        for pack in rules.packs() {
+            for rule in &pack.rules {
+                let normalized = rule.pattern.replace('\\', "/");
                 let sample_relative = if normalized.contains('*') {
                     normalized.replace('*', "sample")
                 } else {
@@ -1571,16 +2386,785 @@ mod tests {
                     .rsplit('/')
                     .next()
                     .unwrap_or(sample_relative.as_str());
-                let category = classify_category(basename, &sample_relative);
-
-                assert_ne!(
-                    category,
-                    Category::Unknown,
-                    "pattern `{}` from {:?} should map to a known category",
-                    pattern,
-                    project_type
+                let category = rules.classify(basename, &sample_relative);
+
+                assert_eq!(
+                    category, rule.category,
+                    "pattern `{}` from pack `{}` should classify consistently",
+                    rule.pattern, pack.name
                 );
             }
         }
     }
+
+    #[test]
+    fn test_scanner_mixed_node_and_rust_root() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("tauri-app");
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(root.join("target/debug/app"), "bin").unwrap();
+
+        let mut results = Scanner::new(temp.path()).scan().unwrap();
+        results.sort_by(|a, b| a.cleanable_dir.cmp(&b.cleanable_dir));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].cleanable_dir, root.join("node_modules"));
+        assert_eq!(results[0].project_type, ProjectType::NodeJs);
+        assert_eq!(results[0].category, Category::Deps);
+        assert_eq!(results[1].cleanable_dir, root.join("target"));
+        assert_eq!(results[1].project_type, ProjectType::Rust);
+        assert_eq!(
+            results[1]
+                .matched_rule
+                .as_ref()
+                .and_then(|rule| rule.pack.as_ref()),
+            Some(&RulePackRef {
+                name: "rust".to_string(),
+                version: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_scanner_cargo_workspace_members_share_root_target() {
+        let temp = TempDir::new().unwrap();
+        let ws = temp.path().join("ws");
+        fs::create_dir_all(ws.join("crates/a/src")).unwrap();
+        fs::create_dir_all(ws.join("crates/b/src")).unwrap();
+        fs::create_dir_all(ws.join("target/debug")).unwrap();
+        fs::write(
+            ws.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::write(ws.join("crates/a/Cargo.toml"), "[package]\nname = \"a\"\n").unwrap();
+        fs::write(ws.join("crates/b/Cargo.toml"), "[package]\nname = \"b\"\n").unwrap();
+        fs::write(ws.join("target/debug/a"), "bin").unwrap();
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].cleanable_dir, ws.join("target"));
+        assert_eq!(results[0].root, ws);
+        assert_eq!(
+            results[0].feeding_projects,
+            vec![ws.clone(), ws.join("crates/a"), ws.join("crates/b")]
+        );
+    }
+
+    #[test]
+    fn test_scanner_reports_redirected_cargo_target_once() {
+        let temp = TempDir::new().unwrap();
+        let projects = temp.path().join("projects");
+        fs::create_dir_all(projects.join(".cargo")).unwrap();
+        fs::write(
+            projects.join(".cargo/config.toml"),
+            "[build]\ntarget-dir = \"shared-build\"\n",
+        )
+        .unwrap();
+        for name in ["one", "two"] {
+            fs::create_dir_all(projects.join(name)).unwrap();
+            fs::write(
+                projects.join(name).join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+        }
+        let shared = projects.join("shared-build");
+        fs::create_dir_all(shared.join("debug")).unwrap();
+        fs::write(shared.join(".rustc_info.json"), "{}").unwrap();
+        fs::write(shared.join("debug/one"), "bin").unwrap();
+
+        for max_risk in [None, Some(RiskLevel::Medium)] {
+            let mut scanner = Scanner::new(temp.path());
+            if let Some(max_risk) = max_risk {
+                scanner = scanner.max_risk(max_risk);
+            }
+            let results = scanner.scan().unwrap();
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].cleanable_dir, shared);
+            assert_eq!(results[0].project_type, ProjectType::Rust);
+            assert_eq!(
+                results[0]
+                    .matched_rule
+                    .as_ref()
+                    .map(|rule| rule.pattern.as_str()),
+                Some("cargo-target-dir")
+            );
+            assert_eq!(
+                results[0].feeding_projects,
+                vec![projects.join("one"), projects.join("two")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_scanner_skips_cargo_target_outside_scan_root() {
+        let temp = TempDir::new().unwrap();
+        let projects = temp.path().join("projects");
+        let shared = temp.path().join("global-target");
+        fs::create_dir_all(projects.join(".cargo")).unwrap();
+        fs::write(
+            projects.join(".cargo/config.toml"),
+            format!("[build]\ntarget-dir = {:?}\n", shared.display().to_string()),
+        )
+        .unwrap();
+        fs::create_dir_all(projects.join("one")).unwrap();
+        fs::write(
+            projects.join("one/Cargo.toml"),
+            "[package]\nname = \"one\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(shared.join("debug")).unwrap();
+        fs::write(shared.join(".rustc_info.json"), "{}").unwrap();
+        fs::write(shared.join("debug/one"), "bin").unwrap();
+
+        let results = Scanner::new(&projects).scan().unwrap();
+
+        assert!(results.is_empty());
+        assert!(Scanner::new(temp.path())
+            .scan()
+            .unwrap()
+            .iter()
+            .any(|project| project.cleanable_dir == shared));
+    }
+
+    #[test]
+    fn test_builtin_match_records_rule_pack() {
+        let temp = TempDir::new().unwrap();
+        make_node_project(temp.path(), "app");
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+        let rule = results[0].matched_rule.as_ref().unwrap();
+        assert_eq!(rule.source, RuleSource::Builtin);
+        assert_eq!(
+            rule.pack,
+            Some(RulePackRef {
+                name: "nodejs".to_string(),
+                version: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_scanner_uses_custom_rule_registry() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("game");
+        fs::create_dir_all(project.join("Library")).unwrap();
+        fs::write(project.join("Library/cache.bin"), "x").unwrap();
+        fs::write(project.join("ProjectSettings"), "").unwrap();
+
+        let mut rules = RuleRegistry::builtin();
+        rules.add_pack(
+            crate::scanner::RulePack::from_toml(
+                r#"
+name = "unity"
+version = 1
+project_type = "Generic"
+display_name = "Unity"
+
+[markers]
+files = ["ProjectSettings"]
+
+[[rules]]
+pattern = "Library"
+category = "cache"
+"#,
+            )
+            .unwrap(),
+        );
+
+        let results = Scanner::new(temp.path())
+            .rule_registry(Arc::new(rules))
+            .scan()
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].project_type_display_name(), "Unity");
+        assert_eq!(results[0].category, Category::Cache);
+        assert_eq!(results[0].risk_level, RiskLevel::Low);
+    }
+
+    #[test]
+    fn test_scanner_offers_stale_rust_artifacts_as_partial_clean() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        let target = app.join("target");
+        fs::create_dir_all(target.join("debug/.fingerprint/app-aaa")).unwrap();
+        fs::write(
+            target.join("debug/.fingerprint/app-aaa/bin-app.json"),
+            r#"{"rustc":1}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(target.join("debug/deps")).unwrap();
+        fs::write(target.join("debug/deps/app-aaa"), "binary").unwrap();
+        fs::create_dir_all(target.join("debug/incremental/app-x")).unwrap();
+        fs::write(
+            target.join("debug/incremental/app-x/dep-graph.bin"),
+            "cache",
+        )
+        .unwrap();
+        // An incremental cache is only stale once it has not been used for a while.
+        let unused = SystemTime::now() - std::time::Duration::from_secs(45 * 24 * 60 * 60);
+        for path in [
+            "debug/incremental/app-x/dep-graph.bin",
+            "debug/incremental/app-x",
+        ] {
+            fs::File::open(target.join(path))
+                .unwrap()
+                .set_modified(unused)
+                .unwrap();
+        }
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+        assert_eq!(results.len(), 2);
+        let full = results.iter().find(|p| !p.is_partial()).unwrap();
+        let partial = results.iter().find(|p| p.is_partial()).unwrap();
+        assert_eq!(full.cleanable_dir, target);
+        assert_eq!(partial.cleanable_dir, target);
+        assert_eq!(partial.risk_level, RiskLevel::Low);
+        assert_eq!(
+            partial
+                .matched_rule
+                .as_ref()
+                .map(|rule| rule.pattern.as_str()),
+            Some(STALE_CARGO_PATTERN)
+        );
+        assert_eq!(partial.prune_paths.len(), 1);
+        assert_eq!(
+            partial.prune_paths[0].path,
+            target.join("debug/incremental/app-x")
+        );
+        assert_eq!(partial.prune_paths[0].size, 5);
+        assert_eq!(partial.size, 5);
+
+        // The full target is filtered out at low risk; the partial clean is still offered.
+        let low = Scanner::new(temp.path())
+            .max_risk(RiskLevel::Low)
+            .scan()
+            .unwrap();
+        assert_eq!(low.len(), 1);
+        assert!(low[0].is_partial());
+
+        let revalidated = Scanner::new(&app)
+            .revalidate_prune_target(&target, &partial.prune_paths)
+            .unwrap();
+        assert_eq!(revalidated.prune_paths, partial.prune_paths);
+        assert!(Scanner::new(&app)
+            .revalidate_prune_target(&target, &[])
+            .is_none());
+    }
+
+    #[test]
+    fn test_scanner_offers_rule_prune_paths_as_partial_clean() {
+        let temp = TempDir::new().unwrap();
+        let web = temp.path().join("web");
+        fs::create_dir_all(web.join("node_modules/.cache/babel")).unwrap();
+        fs::create_dir_all(web.join("node_modules/react")).unwrap();
+        fs::write(web.join("package.json"), "{}").unwrap();
+        fs::write(web.join("node_modules/.cache/babel/x.json"), "cache").unwrap();
+        fs::write(web.join("node_modules/react/index.js"), "code").unwrap();
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+        let partial = results.iter().find(|p| p.is_partial()).unwrap();
+        assert_eq!(partial.cleanable_dir, web.join("node_modules"));
+        assert_eq!(partial.prune_paths.len(), 1);
+        assert_eq!(partial.prune_paths[0].path, web.join("node_modules/.cache"));
+        assert_eq!(partial.size, 5);
+
+        let tool = temp.path().join("tool");
+        fs::create_dir_all(tool.join("out/logs")).unwrap();
+        fs::create_dir_all(tool.join("out/bin")).unwrap();
+        fs::write(tool.join("tool.cfg"), "").unwrap();
+        fs::write(tool.join("out/logs/run.log"), "log").unwrap();
+        let pattern = CustomPattern {
+            name: "tool-out".to_string(),
+            directory: "out".to_string(),
+            marker_files: vec!["tool.cfg".to_string()],
+            marker_mode: MarkerMode::AnyOf,
+            prune: vec![PruneRule {
+                path: "logs".to_string(),
+                keep_latest: 0,
+            }],
+        };
+        let results = Scanner::new(&tool)
+            .custom_patterns(&[pattern])
+            .scan()
+            .unwrap();
+        let partial = results.iter().find(|p| p.is_partial()).unwrap();
+        assert_eq!(partial.prune_paths[0].path, tool.join("out/logs"));
+        assert_eq!(partial.size, 3);
+    }
+
+    #[test]
+    fn test_scanner_detects_cache_dir_tags() {
+        const TAG: &str = "Signature: 8a477f597d28d172789f06886806bc55\n# cache\n";
+        let temp = TempDir::new().unwrap();
+
+        // Tagged directory without any project marker.
+        let tool_cache = temp.path().join("misc/tool-cache");
+        fs::create_dir_all(&tool_cache).unwrap();
+        fs::write(tool_cache.join("CACHEDIR.TAG"), TAG).unwrap();
+        fs::write(tool_cache.join("blob"), "data").unwrap();
+
+        // Tagged directory that also matches a heuristic rule.
+        let app = temp.path().join("app");
+        let build = app.join("objdir");
+        fs::create_dir_all(&build).unwrap();
+        fs::write(app.join("CMakeLists.txt"), "").unwrap();
+        fs::write(build.join("CMakeCache.txt"), "").unwrap();
+        fs::write(build.join("CACHEDIR.TAG"), TAG).unwrap();
+
+        // A file with the wrong signature is not a tag.
+        let fake = temp.path().join("misc/fake");
+        fs::create_dir_all(&fake).unwrap();
+        fs::write(fake.join("CACHEDIR.TAG"), "not a tag").unwrap();
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+        assert_eq!(results.len(), 2);
+
+        let tagged = results
+            .iter()
+            .find(|p| p.cleanable_dir == tool_cache)
+            .unwrap();
+        let rule = tagged.matched_rule.as_ref().unwrap();
+        assert_eq!(rule.source, RuleSource::CacheTag);
+        assert!(rule.cache_tagged);
+        assert_eq!(tagged.category, Category::Cache);
+        assert_eq!(tagged.confidence, Confidence::High);
+        assert_eq!(tagged.root, temp.path().join("misc"));
+
+        let cmake = results.iter().find(|p| p.cleanable_dir == build).unwrap();
+        let rule = cmake.matched_rule.as_ref().unwrap();
+        assert_eq!(rule.source, RuleSource::Heuristic);
+        assert!(rule.cache_tagged);
+        assert_eq!(cmake.confidence, Confidence::High);
+
+        // The tag also survives the low-risk candidate prefilter.
+        let low = Scanner::new(temp.path())
+            .max_risk(RiskLevel::Low)
+            .scan()
+            .unwrap();
+        assert!(low.iter().any(|p| p.cleanable_dir == tool_cache));
+    }
+
+    #[test]
+    fn test_content_checks_drive_confidence_and_risk() {
+        let temp = TempDir::new().unwrap();
+        let verified = temp.path().join("verified");
+        fs::create_dir_all(verified.join(".venv/lib")).unwrap();
+        fs::write(verified.join("pyproject.toml"), "").unwrap();
+        fs::write(verified.join(".venv/pyvenv.cfg"), "home = /usr/bin").unwrap();
+
+        let app = temp.path().join("app");
+        fs::create_dir_all(app.join("target/debug")).unwrap();
+        fs::write(app.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(app.join("target/debug/notes.txt"), "hand-written").unwrap();
+
+        let results = Scanner::new(temp.path()).scan().unwrap();
+        let venv = results
+            .iter()
+            .find(|p| p.cleanable_dir == verified.join(".venv"))
+            .unwrap();
+        assert_eq!(venv.confidence, Confidence::High);
+        assert_eq!(venv.risk_level, RiskLevel::High);
+        let checks = &venv.matched_rule.as_ref().unwrap().checks;
+        assert_eq!(
+            checks,
+            &vec![ContentCheck {
+                path: "pyvenv.cfg".to_string(),
+                passed: true,
+            }]
+        );
+
+        let target = results
+            .iter()
+            .find(|p| p.cleanable_dir == app.join("target"))
+            .unwrap();
+        assert_eq!(target.confidence, Confidence::Low);
+        assert_eq!(target.risk_level, RiskLevel::High);
+        assert!(target
+            .matched_rule
+            .as_ref()
+            .unwrap()
+            .checks
+            .iter()
+            .all(|check| !check.passed));
+    }
+
+    #[test]
+    fn test_recent_git_activity_marks_projects_in_use() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let active = make_node_project(root, "active");
+        let idle = make_node_project(root, "idle");
+        let git = |repo: &Path, args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .status()
+                .expect("failed to run git");
+            assert!(status.success());
+        };
+        git(&active, &["init", "-q"]);
+        git(&active, &["add", "package.json"]);
+        git(&active, &["commit", "-q", "-m", "init"]);
+
+        let results = Scanner::new(root).scan().unwrap();
+        let active_target = results.iter().find(|p| p.root == active).unwrap();
+        assert!(active_target.activity.is_some());
+        assert!(active_target.in_use);
+        assert_eq!(active_target.in_use_source, ActivitySource::VerifiedGit);
+        let idle_target = results.iter().find(|p| p.root == idle).unwrap();
+        assert!(idle_target.activity.is_none());
+        assert_eq!(idle_target.in_use_source, ActivitySource::Heuristic);
+
+        let results = Scanner::new(root).git_activity(false).scan().unwrap();
+        assert!(results.iter().all(|p| p.activity.is_none() && !p.in_use));
+    }
+
+    #[test]
+    fn test_scan_cache_reuses_unchanged_targets_and_sizes() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("work");
+        let project = make_node_project(&root, "app");
+        let modules = project.join("node_modules");
+        fs::create_dir_all(modules.join("pkg")).unwrap();
+        fs::write(modules.join("pkg/index.js"), "x".repeat(100)).unwrap();
+        // Fresh directories fall inside the racy window and are never cached.
+        let past = SystemTime::now() - std::time::Duration::from_secs(3600);
+        for dir in [&root, &project, &modules, &modules.join("pkg")] {
+            fs::File::open(dir).unwrap().set_modified(past).unwrap();
+        }
+        let cache_path = temp.path().join("scan-cache.json");
+        let scan = |refresh: bool| {
+            let cache = Arc::new(ScanCache::open(&cache_path, refresh));
+            let results = Scanner::new(&root)
+                .git_activity(false)
+                .scan_cache(Arc::clone(&cache))
+                .scan()
+                .unwrap();
+            (results[0].size, cache.stats())
+        };
+
+        let (size, stats) = scan(false);
+        assert_eq!(size, 100);
+        assert_eq!((stats.target_misses, stats.size_misses), (1, 1));
+
+        // An in-place rewrite leaves every directory mtime alone: served from the cache.
+        fs::write(modules.join("pkg/index.js"), "x".repeat(150)).unwrap();
+        let (size, stats) = scan(false);
+        assert_eq!(size, 100);
+        assert_eq!((stats.target_hits, stats.size_hits), (1, 1));
+
+        let (size, stats) = scan(true);
+        assert_eq!(size, 150);
+        assert!(stats.refresh);
+        assert_eq!((stats.target_hits, stats.size_hits), (0, 0));
+
+        // Adding a file changes the directory's mtime and invalidates the size.
+        fs::write(modules.join("pkg/extra.js"), "x".repeat(50)).unwrap();
+        let (size, stats) = scan(false);
+        assert_eq!(size, 200);
+        assert_eq!((stats.target_hits, stats.size_misses), (1, 1));
+    }
+
+    #[test]
+    fn test_scan_cache_skips_listing_unchanged_dirs() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("work");
+        make_node_project(&root, "docs/a/app");
+        let lib = root.join("lib");
+        fs::create_dir_all(lib.join("node_modules/pkg")).unwrap();
+        // Fresh directories fall inside the racy window and are never cached.
+        let past = SystemTime::now() - std::time::Duration::from_secs(3600);
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry.unwrap();
+            if entry.file_type().is_dir() {
+                fs::File::open(entry.path())
+                    .unwrap()
+                    .set_modified(past)
+                    .unwrap();
+            }
+        }
+        let cache_path = temp.path().join("scan-cache.json");
+        let scan = || {
+            let cache = Arc::new(ScanCache::open(&cache_path, false));
+            let results = Scanner::new(&root)
+                .git_activity(false)
+                .scan_cache(Arc::clone(&cache))
+                .scan()
+                .unwrap();
+            (snapshot(&results), cache.stats())
+        };
+
+        let (first, stats) = scan();
+        assert_eq!(first.len(), 1);
+        assert_eq!(stats.dir_hits, 0);
+        let (second, stats) = scan();
+        assert_eq!(second, first);
+        assert_eq!(stats.dir_misses, 0);
+        assert!(stats.dir_hits >= 5);
+
+        // A new project changes its parent's mtime, so that parent is listed again.
+        make_node_project(&root, "docs/a/other");
+        let (results, _) = scan();
+        assert_eq!(results.len(), 2);
+
+        // A marker added above changes how the directories below match, though their
+        // own listings are unchanged.
+        fs::write(lib.join("package.json"), "{}").unwrap();
+        let (results, stats) = scan();
+        assert_eq!(results.len(), 3);
+        assert!(results
+            .iter()
+            .any(|(dir, ..)| Path::new(dir) == lib.join("node_modules")));
+        assert!(stats.dir_hits > 0);
+    }
+
+    #[test]
+    fn test_single_pass_walk_matches_two_pass_walk() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("work");
+
+        // Nested projects: the nearest marked directory owns a target.
+        let mono = make_node_project(&root, "mono");
+        fs::write(mono.join("node_modules/a.js"), "x".repeat(40)).unwrap();
+        let app = make_node_project(&mono, "packages/app");
+        fs::create_dir_all(app.join("node_modules/dep/lib")).unwrap();
+        fs::write(app.join("node_modules/dep/lib/index.js"), "x".repeat(300)).unwrap();
+        // Extension markers and a Cargo crate below plain directories.
+        let dotnet = root.join("deep/er/still/dotnet");
+        fs::create_dir_all(dotnet.join("bin/Debug")).unwrap();
+        fs::write(dotnet.join("App.csproj"), "<Project/>").unwrap();
+        fs::write(dotnet.join("bin/Debug/App.dll"), "x".repeat(700)).unwrap();
+        let krate = root.join("deep/crate");
+        fs::create_dir_all(krate.join("target/debug")).unwrap();
+        fs::write(krate.join("Cargo.toml"), "[package]\nname=\"x\"\n").unwrap();
+        fs::write(krate.join("target/debug/app"), "x".repeat(900)).unwrap();
+        // Custom root markers and an excluded directory.
+        let unity = root.join("games/unity");
+        fs::create_dir_all(unity.join("Assets")).unwrap();
+        fs::create_dir_all(unity.join("Library/cache")).unwrap();
+        fs::write(unity.join("Library/cache/blob"), "x".repeat(500)).unwrap();
+        make_node_project(&root.join("vendor"), "skipped");
+        // A scan root inside a project is matched against the directory above it.
+        let inner = app.join("node_modules");
+
+        let patterns = vec![CustomPattern {
+            name: "Unity".to_string(),
+            directory: "Library".to_string(),
+            marker_files: vec!["Assets".to_string()],
+            marker_mode: MarkerMode::AnyOf,
+            prune: Vec::new(),
+        }];
+        let configure = |scanner: Scanner| {
+            scanner
+                .custom_patterns(&patterns)
+                .exclude_dirs(&["vendor".to_string()])
+                .git_activity(false)
+                .check_processes(false)
+        };
+        let scanners: Vec<Box<dyn Fn() -> Scanner>> = vec![
+            Box::new(|| configure(Scanner::new(&root))),
+            Box::new(|| configure(Scanner::new(&root)).max_risk(RiskLevel::Medium)),
+            Box::new(|| configure(Scanner::new(&root)).max_depth(3)),
+            Box::new(|| configure(Scanner::new(&inner))),
+        ];
+
+        for (index, scanner) in scanners.iter().enumerate() {
+            let single = scanner().scan().unwrap();
+            let two_pass = scanner().single_pass(false).scan().unwrap();
+            assert_eq!(
+                snapshot(&single),
+                snapshot(&two_pass),
+                "configuration {index}"
+            );
+            let usage = |results: &[ProjectInfo]| {
+                let mut usage = results
+                    .iter()
+                    .map(|project| (project.cleanable_dir.clone(), project.disk_usage))
+                    .collect::<Vec<_>>();
+                usage.sort_by(|a, b| a.0.cmp(&b.0));
+                usage
+            };
+            assert_eq!(usage(&single), usage(&two_pass), "configuration {index}");
+        }
+
+        let results = configure(Scanner::new(&root)).scan().unwrap();
+        let dirs = results
+            .iter()
+            .map(|project| (project.cleanable_dir.clone(), project.size))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(dirs.get(&mono.join("node_modules")), Some(&40));
+        assert_eq!(dirs.get(&app.join("node_modules")), Some(&300));
+        assert_eq!(dirs.get(&dotnet.join("bin")), Some(&700));
+        assert_eq!(dirs.get(&krate.join("target")), Some(&900));
+        assert_eq!(dirs.get(&unity.join("Library")), Some(&500));
+        assert_eq!(dirs.len(), 5);
+        let inner_results = configure(Scanner::new(&inner)).scan().unwrap();
+        assert_eq!(inner_results.len(), 1);
+        assert_eq!(inner_results[0].root, app);
+    }
+
+    /// Counts scan events; cancels `cancel_on_candidate` at the first candidate
+    #[derive(Default)]
+    struct RecordingObserver {
+        phases: Mutex<Vec<ScanPhase>>,
+        dirs: std::sync::atomic::AtomicUsize,
+        candidates: std::sync::atomic::AtomicUsize,
+        sizes: std::sync::atomic::AtomicUsize,
+        cancel_on_candidate: Option<CancellationToken>,
+    }
+
+    impl ScanObserver for RecordingObserver {
+        fn on_phase(&self, phase: ScanPhase) {
+            self.phases.lock().unwrap().push(phase);
+        }
+
+        fn on_dir_visited(&self, _dir: &Path) {
+            self.dirs.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn on_candidate(&self, _project: &ProjectInfo) {
+            self.candidates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            if let Some(token) = &self.cancel_on_candidate {
+                token.cancel();
+            }
+        }
+
+        fn on_size_resolved(&self, _project: &ProjectInfo) {
+            self.sizes
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_phases_candidates_and_sizes() {
+        let temp = TempDir::new().unwrap();
+        make_node_project(temp.path(), "a");
+        make_node_project(temp.path(), "b");
+
+        for single_pass in [true, false] {
+            let observer = Arc::new(RecordingObserver::default());
+            let results = Scanner::new(temp.path())
+                .single_pass(single_pass)
+                .git_activity(false)
+                .check_processes(false)
+                .observer(observer.clone())
+                .scan()
+                .unwrap();
+            assert_eq!(results.len(), 2);
+
+            assert_eq!(
+                *observer.phases.lock().unwrap(),
+                vec![
+                    ScanPhase::Discovering,
+                    ScanPhase::Analyzing,
+                    ScanPhase::Sizing,
+                    ScanPhase::Finished
+                ]
+            );
+            // The root and both project roots; targets are not descended into.
+            assert!(observer.dirs.load(std::sync::atomic::Ordering::Relaxed) >= 3);
+            assert_eq!(
+                observer
+                    .candidates
+                    .load(std::sync::atomic::Ordering::Relaxed),
+                2
+            );
+            assert_eq!(observer.sizes.load(std::sync::atomic::Ordering::Relaxed), 2);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_scan_returns_only_finished_targets() {
+        let temp = TempDir::new().unwrap();
+        for name in ["a", "b", "c"] {
+            make_node_project(temp.path(), name);
+        }
+
+        for single_pass in [true, false] {
+            let token = CancellationToken::new();
+            let observer = Arc::new(RecordingObserver {
+                cancel_on_candidate: Some(token.clone()),
+                ..RecordingObserver::default()
+            });
+            let scanner = Scanner::new(temp.path())
+                .single_pass(single_pass)
+                .git_activity(false)
+                .check_processes(false)
+                .observer(observer.clone())
+                .cancellation(token);
+            let results = scanner.scan().unwrap();
+
+            // Cancelled before any target was measured.
+            assert!(results.is_empty());
+            assert_eq!(observer.sizes.load(std::sync::atomic::Ordering::Relaxed), 0);
+            assert_eq!(
+                observer.phases.lock().unwrap().last(),
+                Some(&ScanPhase::Cancelled)
+            );
+            assert!(scanner.diagnostics().is_complete());
+
+            let (_, rx) = scanner.scan_with_streaming().unwrap();
+            assert_eq!(rx.iter().count(), 0);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_reports_broken_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let project = make_node_project(temp.path(), "app");
+        std::os::unix::fs::symlink(temp.path().join("gone"), project.join("dangling")).unwrap();
+
+        for single_pass in [true, false] {
+            let scanner = Scanner::new(temp.path())
+                .single_pass(single_pass)
+                .git_activity(false)
+                .check_processes(false);
+            let results = scanner.scan().unwrap();
+            assert_eq!(results.len(), 1);
+
+            let diagnostics = scanner.diagnostics();
+            assert_eq!(diagnostics.count(DiagnosticKind::BrokenSymlink), 1);
+            assert_eq!(
+                diagnostics.entries[0].path.as_deref(),
+                Some(project.join("dangling").as_path())
+            );
+            assert!(diagnostics.is_complete());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_one_file_system_scan_records_target_device() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = TempDir::new().unwrap();
+        let project = make_node_project(temp.path(), "app");
+        let device = fs::metadata(project.join("node_modules")).unwrap().dev();
+
+        for single_pass in [true, false] {
+            let scanner = Scanner::new(temp.path())
+                .one_file_system(true)
+                .single_pass(single_pass)
+                .git_activity(false)
+                .check_processes(false);
+            let results = scanner.scan().unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].device, Some(device));
+            assert!(scanner.diagnostics().skipped_mounts.is_empty());
+        }
+    }
 }
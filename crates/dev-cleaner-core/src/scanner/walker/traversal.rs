@@ -0,0 +1,378 @@
+//! Single-pass discovery walk.
+//!
+//! Every directory is listed once. Its project markers are read from that listing and
+//! handed down to the directories below as [`RootFrame`]s, so matching never probes
+//! parent directories again, and a matched target is measured by the same parallel
+//! walk instead of a second one. With a scan cache, a directory that has not changed
+//! since it was last listed is not listed again: the walk takes its subdirectories and
+//! markers from the cache.
+
+use super::{is_excluded, CandidateMatchers, RootFrame, RootSearch, Scanner, WalkOutput};
+use crate::scanner::cache::{mtime_ns, ListedDir};
+use crate::scanner::detector::CACHEDIR_TAG_FILENAME;
+use crate::scanner::mounts::MountFilter;
+use crate::scanner::rules::DirListing;
+use crate::scanner::size_calculator::{UsageTally, DEFAULT_TIMEOUT_SECS};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, DirEntry};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Files that can make a directory match even when no cleanable pattern names it
+const SPECIAL_FILES: [&str; 2] = ["CMakeCache.txt", CACHEDIR_TAG_FILENAME];
+
+/// Walk the scan root once, matching directories and measuring the targets found
+pub(super) fn walk(scanner: &Scanner, fingerprint: Option<u64>) -> WalkOutput {
+    let walk = SinglePassWalk {
+        scanner,
+        fingerprint,
+        candidate_matchers: scanner.candidate_matchers(),
+        mounts: scanner.mount_filter(),
+        timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        found: Mutex::new(WalkOutput::default()),
+        measuring: Mutex::new(Vec::new()),
+    };
+    // The root itself is matched against the directory above it.
+    let above_root = scanner
+        .parent_frames(&scanner.root)
+        .next()
+        .filter(RootFrame::has_markers)
+        .map(Arc::new);
+
+    rayon::scope(|scope| walk.enter(scope, scanner.root.clone(), 0, above_root));
+    walk.finish()
+}
+
+struct SinglePassWalk<'a> {
+    scanner: &'a Scanner,
+    fingerprint: Option<u64>,
+    candidate_matchers: Option<CandidateMatchers>,
+    mounts: &'a MountFilter,
+    /// Budget for measuring one target, as in [`crate::scanner::SizeCalculator`]
+    timeout: Duration,
+    found: Mutex<WalkOutput>,
+    measuring: Mutex<Vec<Arc<TargetTally>>>,
+}
+
+/// A target being measured, added to by every directory task below it
+struct TargetTally {
+    root: PathBuf,
+    started: Instant,
+    timed_out: AtomicBool,
+    /// Left unfinished by a cancelled scan; its tally is not the full size
+    cancelled: AtomicBool,
+    tally: Mutex<UsageTally>,
+}
+
+impl<'a> SinglePassWalk<'a> {
+    /// Match `dir`, then measure it as a target or descend into it
+    fn enter<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PathBuf,
+        depth: usize,
+        roots: Option<Arc<RootFrame>>,
+    ) {
+        if self.scanner.cancel.is_cancelled() {
+            return;
+        }
+        let descend = self.scanner.max_depth.is_none_or(|max| depth < max);
+        let cache = self.scanner.scan_cache.as_deref().filter(|_| descend);
+        let context = self
+            .fingerprint
+            .filter(|_| cache.is_some())
+            .map(|fingerprint| listing_context(fingerprint, roots.as_deref()));
+        if let Some(listed) = cache
+            .zip(context)
+            .and_then(|(cache, context)| cache.cached_dir(&dir, context))
+        {
+            self.scanner
+                .notify(|observer| observer.on_dir_visited(&dir));
+            self.enter_listed(scope, dir, depth, roots, listed);
+            return;
+        }
+        // Taken before listing, so a change made meanwhile makes the record stale.
+        let mtime = context
+            .and_then(|_| fs::symlink_metadata(&dir).ok())
+            .and_then(|metadata| mtime_ns(&metadata));
+
+        let entries = if descend {
+            self.read_entries(&dir)
+        } else {
+            None
+        };
+        self.scanner
+            .notify(|observer| observer.on_dir_visited(&dir));
+
+        if self.may_match(&dir, entries.as_deref()) {
+            if let Some(info) = self.scanner.detect_target(
+                &dir,
+                self.fingerprint,
+                RootSearch::Frames(roots.as_deref()),
+            ) {
+                if self.scanner.record_target(info, &self.found) {
+                    self.measure_target(scope, dir, entries);
+                }
+                // Nothing below a target is matched on its own.
+                return;
+            }
+        }
+        let Some(entries) = entries else {
+            return;
+        };
+
+        let mut listing = DirListing::default();
+        let mut subdirs = Vec::new();
+        let mut has_manifest = false;
+        // What the scan cache keeps of the listing. A symlink can change where it points
+        // without changing this directory, so listings with symlinks are not kept.
+        let mut all_subdirs = Some(Vec::new()).filter(|_| mtime.is_some());
+        let mut any_manifest = false;
+        for entry in entries {
+            let Ok(file_type) = entry.file_type() else {
+                all_subdirs = None;
+                continue;
+            };
+            let name = entry.file_name();
+            if file_type.is_dir() {
+                match (name.to_str(), &mut all_subdirs) {
+                    (Some(name), Some(all_subdirs)) => all_subdirs.push(name.to_string()),
+                    // Only UTF-8 names can be stored.
+                    (None, _) => all_subdirs = None,
+                    (_, None) => {}
+                }
+            } else {
+                any_manifest |= name == "Cargo.toml";
+            }
+            if !is_excluded(&name.to_string_lossy(), &self.scanner.exclude_dirs) {
+                if file_type.is_dir() {
+                    if self.admits(&entry) {
+                        subdirs.push(entry.path());
+                    }
+                } else if name == "Cargo.toml" {
+                    has_manifest = true;
+                }
+            }
+            if file_type.is_symlink() {
+                all_subdirs = None;
+                // Markers count where their symlink resolves; dangling ones not at all.
+                match fs::metadata(entry.path()) {
+                    Ok(metadata) => listing.insert(name, metadata.is_file()),
+                    Err(err) => self.scanner.record_symlink_error(&entry.path(), &err),
+                }
+            } else {
+                listing.insert(name, file_type.is_file());
+            }
+        }
+        if has_manifest {
+            // Remember every crate so shared/redirected targets can list their feeders.
+            self.found
+                .lock()
+                .unwrap()
+                .cargo_manifest_dirs
+                .push(dir.clone());
+        }
+
+        let frame = self.scanner.root_frame(&dir, &listing, roots);
+        if let (Some(cache), Some(context), Some(mtime_ns), Some(all_subdirs)) =
+            (cache, context, mtime, all_subdirs)
+        {
+            let listed = ListedDir {
+                context,
+                mtime_ns,
+                subdirs: all_subdirs,
+                has_manifest: any_manifest,
+                project_types: frame.project_types.clone(),
+                custom_roots: frame.custom_roots.clone(),
+            };
+            cache.store_dir(&dir, listed);
+        }
+        let roots = frame.marked();
+        for subdir in subdirs {
+            let roots = roots.clone();
+            scope.spawn(move |scope| self.enter(scope, subdir, depth + 1, roots));
+        }
+    }
+
+    /// Descend into a directory the scan cache still knows, without listing or matching
+    /// it again
+    fn enter_listed<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PathBuf,
+        depth: usize,
+        roots: Option<Arc<RootFrame>>,
+        listed: ListedDir,
+    ) {
+        let excluded = |name: &str| is_excluded(name, &self.scanner.exclude_dirs);
+        if listed.has_manifest && !excluded("Cargo.toml") {
+            self.found
+                .lock()
+                .unwrap()
+                .cargo_manifest_dirs
+                .push(dir.clone());
+        }
+
+        let roots = RootFrame {
+            dir: dir.clone(),
+            project_types: listed.project_types,
+            custom_roots: listed.custom_roots,
+            parent: roots,
+        }
+        .marked();
+        for name in listed.subdirs.into_iter().filter(|name| !excluded(name)) {
+            let subdir = dir.join(name);
+            if !self
+                .mounts
+                .admits(&subdir, || fs::symlink_metadata(&subdir).ok())
+            {
+                continue;
+            }
+            let roots = roots.clone();
+            scope.spawn(move |scope| self.enter(scope, subdir, depth + 1, roots));
+        }
+    }
+
+    /// Whether the scanner's mount policy lets the walk enter a subdirectory
+    fn admits(&self, entry: &DirEntry) -> bool {
+        self.mounts.admits(&entry.path(), || entry.metadata().ok())
+    }
+
+    /// With the candidate prefilter on, whether `dir` can match at all
+    fn may_match(&self, dir: &Path, entries: Option<&[DirEntry]>) -> bool {
+        let Some(candidate_matchers) = &self.candidate_matchers else {
+            return true;
+        };
+        let special_files = entries.is_none_or(|entries| {
+            entries
+                .iter()
+                .any(|entry| SPECIAL_FILES.iter().any(|name| entry.file_name() == *name))
+        });
+        self.scanner
+            .is_candidate(dir, candidate_matchers, special_files)
+    }
+
+    /// Measure a matched target in this walk, unless the scan cache still knows its size
+    fn measure_target<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PathBuf,
+        entries: Option<Vec<DirEntry>>,
+    ) {
+        if let Some(usage) = self
+            .scanner
+            .scan_cache
+            .as_deref()
+            .and_then(|cache| cache.cached_size(&dir))
+        {
+            self.found.lock().unwrap().measured.insert(dir, Some(usage));
+            return;
+        }
+
+        let target = Arc::new(TargetTally {
+            root: dir.clone(),
+            started: Instant::now(),
+            timed_out: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            tally: Mutex::default(),
+        });
+        self.measuring.lock().unwrap().push(Arc::clone(&target));
+        self.measure_dir(scope, target, dir, entries);
+    }
+
+    fn measure_dir<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        target: Arc<TargetTally>,
+        dir: PathBuf,
+        entries: Option<Vec<DirEntry>>,
+    ) {
+        if target.started.elapsed() >= self.timeout {
+            target.timed_out.store(true, Ordering::Relaxed);
+            return;
+        }
+        if self.scanner.cancel.is_cancelled() {
+            target.cancelled.store(true, Ordering::Relaxed);
+            return;
+        }
+
+        let mut tally = UsageTally::default();
+        if self.scanner.scan_cache.is_some() {
+            if let Ok(metadata) = fs::symlink_metadata(&dir) {
+                let relative = dir.strip_prefix(&target.root).unwrap_or(&dir);
+                tally.add_dir(relative.to_path_buf(), &metadata);
+            }
+        }
+        for entry in entries
+            .or_else(|| self.read_entries(&dir))
+            .unwrap_or_default()
+        {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_file() {
+                match entry.metadata() {
+                    Ok(metadata) => tally.add_file(&metadata),
+                    // Removed since it was listed, e.g. by a build running next to the scan.
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => self.scanner.diagnostics.io_error(&entry.path(), &err),
+                }
+            } else if file_type.is_dir() && self.admits(&entry) {
+                let target = Arc::clone(&target);
+                scope.spawn(move |scope| self.measure_dir(scope, target, entry.path(), None));
+            }
+        }
+        target.tally.lock().unwrap().merge(tally);
+    }
+
+    /// Collect the findings once every task has finished
+    fn finish(self) -> WalkOutput {
+        let mut found = self.found.into_inner().unwrap();
+        let cache = self.scanner.scan_cache.as_deref();
+        for target in self.measuring.into_inner().unwrap() {
+            let Ok(target) = Arc::try_unwrap(target) else {
+                continue;
+            };
+            if target.cancelled.into_inner() {
+                continue;
+            }
+            let usage = (!target.timed_out.into_inner()).then(|| {
+                let (usage, stamps) = target.tally.into_inner().unwrap().finish();
+                if let Some(cache) = cache {
+                    cache.store_size(&target.root, &usage, stamps);
+                }
+                usage
+            });
+            found.measured.insert(target.root, usage);
+        }
+        found
+    }
+
+    /// List `dir`, recording what could not be read
+    fn read_entries(&self, dir: &Path) -> Option<Vec<DirEntry>> {
+        let diagnostics = &self.scanner.diagnostics;
+        let entries = fs::read_dir(dir)
+            .map_err(|err| diagnostics.io_error(dir, &err))
+            .ok()?;
+        let entries = entries
+            .filter_map(|entry| entry.map_err(|err| diagnostics.io_error(dir, &err)).ok())
+            .collect();
+        Some(entries)
+    }
+}
+
+/// What matching a directory depends on besides its own listing: the scanner's cache
+/// fingerprint and the marked directories above it
+fn listing_context(fingerprint: u64, roots: Option<&RootFrame>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    fingerprint.hash(&mut hasher);
+    for frame in RootFrame::chain(roots) {
+        frame.dir.hash(&mut hasher);
+        frame.project_types.hash(&mut hasher);
+        frame.custom_roots.hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -1,13 +1,19 @@
 use crate::ProjectInfo;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 /// Statistics about cleanable directories
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
-    /// Total size of all cleanable directories
+    /// Bytes freed by cleaning every directory (hardlinks shared with other paths and
+    /// sparse holes excluded)
     pub total_size: u64,
 
+    /// Sum of file lengths in all cleanable directories
+    #[serde(default)]
+    pub total_apparent_size: u64,
+
     /// Total number of projects
     pub total_projects: usize,
 
@@ -40,7 +46,7 @@ pub struct ProjectStats {
     /// Path to cleanable directory
     pub path: String,
 
-    /// Size in bytes
+    /// Reclaimable size in bytes
     pub size: u64,
 
     /// Project type
@@ -65,9 +71,11 @@ pub struct AgeGroupStats {
 
 impl Statistics {
     /// Create statistics from a list of projects
-    pub fn from_projects(projects: Vec<ProjectInfo>) -> Self {
+    pub fn from_projects(mut projects: Vec<ProjectInfo>) -> Self {
+        drop_covered_partials(&mut projects);
         let total_projects = projects.len();
-        let total_size: u64 = projects.iter().map(|p| p.size).sum();
+        let total_size: u64 = projects.iter().map(|p| p.reclaimable_size()).sum();
+        let total_apparent_size: u64 = projects.iter().map(|p| p.size).sum();
 
         // Group by type
         let mut by_type: HashMap<String, TypeStats> = HashMap::new();
@@ -78,7 +86,7 @@ impl Statistics {
                 count: 0,
                 avg_size: 0,
             });
-            entry.total_size += project.size;
+            entry.total_size += project.reclaimable_size();
             entry.count += 1;
         }
 
@@ -93,12 +101,12 @@ impl Statistics {
 
         // Create top largest list
         let mut sorted_projects = projects.clone();
-        sorted_projects.sort_by(|a, b| b.size.cmp(&a.size));
+        sorted_projects.sort_by(|a, b| b.reclaimable_size().cmp(&a.reclaimable_size()));
         let top_largest: Vec<ProjectStats> = sorted_projects
             .iter()
             .map(|p| ProjectStats {
                 path: p.cleanable_dir.display().to_string(),
-                size: p.size,
+                size: p.reclaimable_size(),
                 project_type: p.project_type_display_name(),
                 age_days: p.days_since_modified(),
             })
@@ -113,13 +121,13 @@ impl Statistics {
             let age = project.days_since_modified();
             if age < 30 {
                 recent.0 += 1;
-                recent.1 += project.size;
+                recent.1 += project.reclaimable_size();
             } else if age < 90 {
                 medium.0 += 1;
-                medium.1 += project.size;
+                medium.1 += project.reclaimable_size();
             } else {
                 old.0 += 1;
-                old.1 += project.size;
+                old.1 += project.reclaimable_size();
             }
         }
 
@@ -131,6 +139,7 @@ impl Statistics {
 
         Self {
             total_size,
+            total_apparent_size,
             total_projects,
             by_type,
             top_largest,
@@ -144,6 +153,17 @@ impl Statistics {
     }
 }
 
+/// Drop partial cleans whose target is also listed in full; the full candidate already
+/// counts their bytes.
+pub fn drop_covered_partials(projects: &mut Vec<ProjectInfo>) {
+    let full_targets: HashSet<PathBuf> = projects
+        .iter()
+        .filter(|p| !p.is_partial())
+        .map(|p| p.cleanable_dir.clone())
+        .collect();
+    projects.retain(|p| !p.is_partial() || !full_targets.contains(&p.cleanable_dir));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +197,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -201,6 +231,16 @@ mod tests {
                 recent: false,
                 selection_reason: None,
                 skip_reason: None,
+                feeding_projects: Vec::new(),
+                prune_paths: Vec::new(),
+                tracked_files: 0,
+                in_use_source: Default::default(),
+                recent_source: Default::default(),
+                activity: None,
+                in_use_by: None,
+                disk_usage: None,
+                device: None,
+                inode: None,
             },
             ProjectInfo {
                 root: PathBuf::from("/test2"),
@@ -220,6 +260,16 @@ mod tests {
                 recent: false,
                 selection_reason: None,
                 skip_reason: None,
+                feeding_projects: Vec::new(),
+                prune_paths: Vec::new(),
+                tracked_files: 0,
+                in_use_source: Default::default(),
+                recent_source: Default::default(),
+                activity: None,
+                in_use_by: None,
+                disk_usage: None,
+                device: None,
+                inode: None,
             },
         ];
 
@@ -265,4 +315,42 @@ mod tests {
         let json = stats.to_json().unwrap();
         assert!(json.contains("\"total_projects\": 4"));
     }
+
+    #[test]
+    fn partial_clean_of_a_listed_target_is_not_counted_twice() {
+        let full = project(
+            ProjectType::NodeJs,
+            4000,
+            10,
+            Category::Deps,
+            RiskLevel::High,
+        );
+        let mut partial = project(
+            ProjectType::NodeJs,
+            1000,
+            10,
+            Category::Deps,
+            RiskLevel::High,
+        );
+        partial.cleanable_dir = full.cleanable_dir.clone();
+        partial.prune_paths = vec![crate::scanner::PrunePath {
+            path: full.cleanable_dir.join(".cache"),
+            size: 1000,
+            reason: "cache".to_string(),
+        }];
+        let other = project(
+            ProjectType::Rust,
+            500,
+            10,
+            Category::Build,
+            RiskLevel::Medium,
+        );
+
+        let stats = Statistics::from_projects(vec![full, partial, other]);
+
+        assert_eq!(stats.total_projects, 2);
+        assert_eq!(stats.total_size, 4500);
+        assert_eq!(stats.by_type["Node.js"].count, 1);
+        assert_eq!(stats.top_largest.len(), 2);
+    }
 }
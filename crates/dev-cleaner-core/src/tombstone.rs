@@ -0,0 +1,597 @@
+//! Rename-then-reap deletion.
+//!
+//! Burying a target renames it to a hidden tombstone in its own parent directory.
+//! The rename is atomic and stays on the same filesystem, so the target is gone at
+//! once and the project can be rebuilt right away. Reaping deletes the tombstone
+//! later, in a background `dev-cleaner reap` or on the next run. A tombstone stays
+//! in a small journal until it is fully reaped, so an interrupted reap is resumed.
+
+use crate::cancel::CancellationToken;
+use crate::cleaner::{DeleteEngine, DeletionOptions, InterruptedClean};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// File name prefix of tombstones; scans never enter them
+pub const TOMBSTONE_PREFIX: &str = ".dev-cleaner-tombstone-";
+
+const JOURNAL_FILENAME: &str = "tombstones.jsonl";
+const JOURNAL_LOCK_FILENAME: &str = "tombstones.lock";
+/// Held by the one process reaping at a time
+const REAPER_LOCK_FILENAME: &str = "reaper.lock";
+
+/// A buried target waiting to be reaped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub original_path: PathBuf,
+    pub tombstone_path: PathBuf,
+    /// Bytes the target held when it was buried
+    pub size: u64,
+    /// Bytes removed by earlier reaps that were interrupted
+    #[serde(default)]
+    pub reaped_bytes: u64,
+    pub created_at: DateTime<Utc>,
+    /// Audit run that buried the target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl Tombstone {
+    /// Bytes still to be freed by reaping
+    pub fn pending_bytes(&self) -> u64 {
+        self.size.saturating_sub(self.reaped_bytes)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReapResult {
+    pub reaped_count: usize,
+    pub bytes_freed: u64,
+    pub failed_count: usize,
+    pub errors: Vec<String>,
+    /// Tombstones still pending afterwards
+    pub remaining: usize,
+    pub cancelled: bool,
+}
+
+pub trait ReapObserver {
+    fn on_reaped(&mut self, _tombstone: &Tombstone, _bytes_freed: u64) {}
+    fn on_failed(&mut self, _tombstone: &Tombstone, _error: &anyhow::Error) {}
+}
+
+#[derive(Debug, Default)]
+pub struct NoopReapObserver;
+
+impl ReapObserver for NoopReapObserver {}
+
+/// Lines of the tombstone journal
+#[derive(Debug, Default)]
+struct JournalContents {
+    entries: Vec<Tombstone>,
+    /// Lines that are not a tombstone, e.g. written by a newer version; kept so they
+    /// are not lost
+    unreadable: Vec<String>,
+}
+
+/// Journal of pending tombstones, shared by every `dev-cleaner` process
+#[derive(Debug, Clone)]
+pub struct TombstoneJournal {
+    root: PathBuf,
+}
+
+impl TombstoneJournal {
+    pub fn new_default() -> Self {
+        Self::new(default_tombstone_root())
+    }
+
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Rename `target` to a tombstone next to it and record it for reaping
+    pub fn bury(&self, target: &Path, size: u64, run_id: Option<&str>) -> Result<Tombstone> {
+        let name = target
+            .file_name()
+            .with_context(|| format!("Cannot bury {}", target.display()))?;
+        let unique = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let tombstone_path = target.with_file_name(format!(
+            "{TOMBSTONE_PREFIX}{}-{}-{}",
+            name.to_string_lossy(),
+            unique,
+            std::process::id()
+        ));
+        let tombstone = Tombstone {
+            original_path: target.to_path_buf(),
+            tombstone_path: tombstone_path.clone(),
+            size,
+            reaped_bytes: 0,
+            created_at: Utc::now(),
+            run_id: run_id.map(str::to_string),
+        };
+
+        // Recorded before the rename, so a crash in between never leaves a
+        // tombstone nobody knows about.
+        self.update(|entries| entries.push(tombstone.clone()))?;
+        if let Err(err) = fs::rename(target, &tombstone_path) {
+            let _ = self
+                .update(|entries| entries.retain(|entry| entry.tombstone_path != tombstone_path));
+            return Err(err).with_context(|| {
+                format!(
+                    "Failed to rename {} to {}",
+                    target.display(),
+                    tombstone_path.display()
+                )
+            });
+        }
+        Ok(tombstone)
+    }
+
+    /// Tombstones not reaped yet, oldest first
+    pub fn pending(&self) -> Result<Vec<Tombstone>> {
+        Ok(self.read()?.entries)
+    }
+
+    /// The journal as it is now, read under its lock
+    fn read(&self) -> Result<JournalContents> {
+        if !self.journal_path().exists() {
+            return Ok(JournalContents::default());
+        }
+        let _lock = self.lock_journal()?;
+        self.read_entries()
+    }
+
+    /// Pending tombstones and their bytes per audit run
+    pub fn pending_by_run(&self) -> Result<BTreeMap<String, (usize, u64)>> {
+        let mut by_run = BTreeMap::new();
+        for tombstone in self.pending()? {
+            if let Some(run_id) = tombstone.run_id.clone() {
+                let (count, bytes) = by_run.entry(run_id).or_insert((0, 0));
+                *count += 1;
+                *bytes += tombstone.pending_bytes();
+            }
+        }
+        Ok(by_run)
+    }
+
+    /// Whether some process is reaping right now
+    pub fn reaper_running(&self) -> bool {
+        match self.try_lock_reaper() {
+            Ok(Some(_lock)) => false,
+            Ok(None) => true,
+            Err(_) => false,
+        }
+    }
+
+    pub fn reap(
+        &self,
+        deletion: &DeletionOptions,
+        cancel: CancellationToken,
+    ) -> Result<Option<ReapResult>> {
+        let mut observer = NoopReapObserver;
+        self.reap_with_observer(deletion, cancel, &mut observer)
+    }
+
+    /// Delete every pending tombstone; `None` when another process is already reaping.
+    ///
+    /// A tombstone whose deletion fails or is cancelled stays in the journal, with the
+    /// bytes already removed, and is picked up by the next reap. An entry that does not
+    /// name a tombstone next to its original path, or names a symlink, is reported as
+    /// failed and left alone.
+    pub fn reap_with_observer<O: ReapObserver>(
+        &self,
+        deletion: &DeletionOptions,
+        cancel: CancellationToken,
+        observer: &mut O,
+    ) -> Result<Option<ReapResult>> {
+        let Some(_reaper) = self.try_lock_reaper()? else {
+            return Ok(None);
+        };
+
+        let mut result = ReapResult::default();
+        let JournalContents {
+            entries: pending,
+            unreadable,
+        } = self.read()?;
+        result.errors.extend(unreadable.iter().map(|line| {
+            format!(
+                "Kept unreadable line of {}: {}",
+                self.journal_path().display(),
+                line
+            )
+        }));
+        if pending.is_empty() {
+            return Ok(Some(result));
+        }
+        let engine = DeleteEngine::new(deletion, cancel.clone())?;
+
+        for tombstone in pending {
+            if cancel.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+            if let Err(err) = check_tombstone_path(&tombstone) {
+                record_failure(&mut result, observer, &tombstone, err);
+                continue;
+            }
+            match fs::symlink_metadata(&tombstone.tombstone_path) {
+                Err(_) => {
+                    // Never renamed (the bury failed half way), or removed by hand.
+                    self.forget(&tombstone.tombstone_path)?;
+                    continue;
+                }
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    let err = anyhow::anyhow!(
+                        "{} is a symlink, not a tombstone",
+                        tombstone.tombstone_path.display()
+                    );
+                    record_failure(&mut result, observer, &tombstone, err);
+                    continue;
+                }
+                Ok(_) => {}
+            }
+
+            match engine.remove(&tombstone.tombstone_path, |_| {}) {
+                Ok(()) => {
+                    self.forget(&tombstone.tombstone_path)?;
+                    result.reaped_count += 1;
+                    result.bytes_freed += tombstone.pending_bytes();
+                    observer.on_reaped(&tombstone, tombstone.pending_bytes());
+                }
+                Err(err) if err.is::<crate::cancel::Cancelled>() => {
+                    result.cancelled = true;
+                    break;
+                }
+                Err(err) => {
+                    if let Some(partial) = err.downcast_ref::<InterruptedClean>() {
+                        let freed = partial.bytes_freed;
+                        self.update(|entries| {
+                            for entry in entries
+                                .iter_mut()
+                                .filter(|entry| entry.tombstone_path == tombstone.tombstone_path)
+                            {
+                                entry.reaped_bytes = entry.reaped_bytes.saturating_add(freed);
+                            }
+                        })?;
+                        result.bytes_freed += freed;
+                        result.cancelled = true;
+                        break;
+                    }
+                    record_failure(&mut result, observer, &tombstone, err);
+                }
+            }
+        }
+
+        result.remaining = self.pending()?.len();
+        Ok(Some(result))
+    }
+
+    fn forget(&self, tombstone_path: &Path) -> Result<()> {
+        self.update(|entries| entries.retain(|entry| entry.tombstone_path != tombstone_path))
+    }
+
+    /// Rewrite the journal with `change` applied, under the journal lock; lines that
+    /// could not be read are written back as they were
+    fn update(&self, change: impl FnOnce(&mut Vec<Tombstone>)) -> Result<()> {
+        fs::create_dir_all(&self.root).with_context(|| {
+            format!(
+                "Failed to create tombstone journal directory: {}",
+                self.root.display()
+            )
+        })?;
+        let _lock = self.lock_journal()?;
+        let JournalContents {
+            mut entries,
+            unreadable,
+        } = self.read_entries()?;
+        change(&mut entries);
+
+        let path = self.journal_path();
+        if entries.is_empty() && unreadable.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(err).with_context(|| format!("Failed to remove {}", path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp)
+            .with_context(|| format!("Failed to write tombstone journal: {}", tmp.display()))?;
+        for line in &unreadable {
+            writeln!(file, "{line}")?;
+        }
+        for entry in &entries {
+            serde_json::to_writer(&mut file, entry)?;
+            writeln!(file)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write tombstone journal: {}", path.display()))
+    }
+
+    fn read_entries(&self) -> Result<JournalContents> {
+        let path = self.journal_path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(JournalContents::default())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read tombstone journal: {}", path.display())
+                })
+            }
+        };
+        let mut contents = JournalContents::default();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(entry) => contents.entries.push(entry),
+                Err(_) => contents.unreadable.push(line.to_string()),
+            }
+        }
+        Ok(contents)
+    }
+
+    fn lock_journal(&self) -> Result<File> {
+        let path = self.root.join(JOURNAL_LOCK_FILENAME);
+        let file = open_lock_file(&path)?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+        Ok(file)
+    }
+
+    fn try_lock_reaper(&self) -> Result<Option<File>> {
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create {}", self.root.display()))?;
+        let file = open_lock_file(&self.root.join(REAPER_LOCK_FILENAME))?;
+        Ok(file.try_lock_exclusive().ok().map(|()| file))
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.root.join(JOURNAL_FILENAME)
+    }
+}
+
+/// Refuse a journal entry that does not name a tombstone [`TombstoneJournal::bury`]
+/// could have made; the journal is a plain file anyone with access can edit
+fn check_tombstone_path(tombstone: &Tombstone) -> Result<()> {
+    let path = &tombstone.tombstone_path;
+    let name = path.file_name().and_then(|name| name.to_str());
+    if !name.is_some_and(is_tombstone_name) {
+        anyhow::bail!("{} is not a tombstone", path.display());
+    }
+    if path.parent() != tombstone.original_path.parent() {
+        anyhow::bail!(
+            "{} is not next to {}",
+            path.display(),
+            tombstone.original_path.display()
+        );
+    }
+    Ok(())
+}
+
+fn record_failure<O: ReapObserver>(
+    result: &mut ReapResult,
+    observer: &mut O,
+    tombstone: &Tombstone,
+    err: anyhow::Error,
+) {
+    result.failed_count += 1;
+    result.errors.push(format!(
+        "Failed to reap {}: {}",
+        tombstone.tombstone_path.display(),
+        err
+    ));
+    observer.on_failed(tombstone, &err);
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+/// Whether `name` is a tombstone left by [`TombstoneJournal::bury`]
+pub fn is_tombstone_name(name: &str) -> bool {
+    name.starts_with(TOMBSTONE_PREFIX)
+}
+
+pub fn default_tombstone_root() -> PathBuf {
+    if let Ok(custom) = std::env::var("DEV_CLEANER_TOMBSTONE_DIR") {
+        return PathBuf::from(custom);
+    }
+
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("dev-cleaner")
+        .join("tombstones")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn make_target(path: &Path) {
+        fs::create_dir_all(path.join("pkg")).unwrap();
+        fs::write(path.join("pkg/index.js"), "12345").unwrap();
+    }
+
+    #[test]
+    fn bury_renames_next_to_target_and_reap_deletes() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        let target = temp.path().join("app/node_modules");
+        make_target(&target);
+
+        let tombstone = journal.bury(&target, 5, Some("run-1")).unwrap();
+        assert!(!target.exists());
+        assert_eq!(tombstone.tombstone_path.parent(), target.parent());
+        assert!(is_tombstone_name(
+            &tombstone
+                .tombstone_path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+        ));
+        assert!(tombstone.tombstone_path.join("pkg/index.js").exists());
+        assert_eq!(journal.pending().unwrap(), vec![tombstone.clone()]);
+        assert_eq!(
+            journal.pending_by_run().unwrap().get("run-1"),
+            Some(&(1, 5))
+        );
+
+        let result = journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.reaped_count, 1);
+        assert_eq!(result.bytes_freed, 5);
+        assert_eq!(result.remaining, 0);
+        assert!(!tombstone.tombstone_path.exists());
+        assert!(journal.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cancelled_reap_keeps_tombstones_for_the_next_run() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        let first = temp.path().join("a/target");
+        let second = temp.path().join("b/target");
+        make_target(&first);
+        make_target(&second);
+        journal.bury(&first, 5, None).unwrap();
+        journal.bury(&second, 5, None).unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = journal
+            .reap(&DeletionOptions::default(), cancel)
+            .unwrap()
+            .unwrap();
+        assert!(result.cancelled);
+        assert_eq!(result.remaining, 2);
+
+        let result = journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.reaped_count, 2);
+        assert!(journal.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_one_process_reaps_at_a_time() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        assert!(!journal.reaper_running());
+
+        let _held = journal.try_lock_reaper().unwrap().unwrap();
+        assert!(journal.reaper_running());
+        assert!(journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn failed_rename_leaves_no_journal_entry() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        let missing = temp.path().join("missing/target");
+
+        assert!(journal.bury(&missing, 1, None).is_err());
+        assert!(journal.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unreadable_journal_lines_are_reported_and_kept() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        fs::create_dir_all(journal.root()).unwrap();
+        fs::write(journal.journal_path(), "{\"not\": \"a tombstone\"}\n").unwrap();
+        let target = temp.path().join("app/node_modules");
+        make_target(&target);
+        let tombstone = journal.bury(&target, 5, None).unwrap();
+        assert_eq!(journal.pending().unwrap(), vec![tombstone]);
+
+        let result = journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.reaped_count, 1);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("a tombstone"));
+        let content = fs::read_to_string(journal.journal_path()).unwrap();
+        assert_eq!(content, "{\"not\": \"a tombstone\"}\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reap_refuses_entries_that_do_not_name_a_tombstone() {
+        let temp = TempDir::new().unwrap();
+        let journal = TombstoneJournal::new(temp.path().join("state"));
+        fs::create_dir_all(journal.root()).unwrap();
+        let precious = temp.path().join("home/documents");
+        make_target(&precious);
+        let app = temp.path().join("app");
+        let elsewhere = temp.path().join("elsewhere");
+        fs::create_dir_all(&app).unwrap();
+        make_target(&elsewhere.join(format!("{TOMBSTONE_PREFIX}x")));
+        let link = app.join(format!("{TOMBSTONE_PREFIX}link"));
+        std::os::unix::fs::symlink(&precious, &link).unwrap();
+
+        let entry = |tombstone_path: PathBuf| Tombstone {
+            original_path: app.join("node_modules"),
+            tombstone_path,
+            size: 5,
+            reaped_bytes: 0,
+            created_at: Utc::now(),
+            run_id: None,
+        };
+        let entries = [
+            entry(precious.clone()),
+            entry(elsewhere.join(format!("{TOMBSTONE_PREFIX}x"))),
+            entry(link.clone()),
+        ];
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .collect();
+        fs::write(journal.journal_path(), lines.join("\n")).unwrap();
+
+        let result = journal
+            .reap(&DeletionOptions::default(), CancellationToken::new())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result.reaped_count, 0);
+        assert_eq!(result.failed_count, 3);
+        assert_eq!(result.remaining, 3);
+        assert!(precious.join("pkg/index.js").exists());
+        assert!(elsewhere
+            .join(format!("{TOMBSTONE_PREFIX}x/pkg/index.js"))
+            .exists());
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+}
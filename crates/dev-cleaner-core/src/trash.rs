@@ -1,13 +1,32 @@
+use crate::cancel::{CancellationToken, Cancelled, CANCEL_POLL_ENTRIES};
+use crate::cleaner::{DeleteEngine, DeletionOptions};
+use crate::guard::{check_path, FileIdentity};
+use crate::permissions::{make_tree_writable, make_writable};
+use crate::xdg_trash;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
 use std::time::SystemTime;
 
 const TRASH_LOG_FILENAME: &str = "trash_log.jsonl";
 
+/// Where `--trash` moves targets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrashBackend {
+    /// Dev Cleaner's own trash, in batch directories
+    #[default]
+    DevCleaner,
+    /// The desktop's trash, per the freedesktop.org Trash specification (see
+    /// [`crate::xdg_trash`])
+    Xdg,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrashEntry {
     pub batch_id: String,
@@ -17,12 +36,53 @@ pub struct TrashEntry {
     pub size: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tool_version: Option<String>,
+    /// Target this entry was pruned from (partial clean); the target itself stays in place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pruned_from: Option<PathBuf>,
+    /// Device and inode of `trashed_path` once moved; restore refuses anything else there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<FileIdentity>,
+    /// Trash root holding `trashed_path` when it is not the one keeping the log: the
+    /// root on the original's own file system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_root: Option<PathBuf>,
+    /// `.trashinfo` of an entry in a freedesktop.org trash; `trash_root` is that trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_info: Option<PathBuf>,
+}
+
+impl TrashEntry {
+    /// Trash root holding `trashed_path`, for a log kept in `root`
+    pub fn root_in<'a>(&'a self, root: &'a Path) -> &'a Path {
+        self.trash_root.as_deref().unwrap_or(root)
+    }
+
+    /// Directory `trashed_path` has to stay inside: its batch directory, or the
+    /// `files` directory of a freedesktop.org trash
+    fn container_in(&self, root: &Path) -> PathBuf {
+        match self.trash_info {
+            Some(_) => self.root_in(root).join("files"),
+            None => self.root_in(root).join(&self.batch_id),
+        }
+    }
 }
 
 pub struct TrashManager {
     pub batch_id: String,
     root: PathBuf,
     log_path: PathBuf,
+    /// Trash root picked for each device a target was on, `root` itself when the
+    /// device has none of its own
+    device_roots: Mutex<HashMap<u64, PathBuf>>,
+    /// Stops a cross-device copy; renames are atomic and always finish
+    cancel: CancellationToken,
+    /// Make read-only directories being moved writable first
+    repair_permissions: bool,
+    backend: TrashBackend,
+    /// Home trash of the freedesktop.org backend
+    xdg_home_trash: PathBuf,
+    /// Problems that did not stop a move, such as a stale `directorysizes`
+    warnings: Mutex<Vec<String>>,
 }
 
 impl TrashManager {
@@ -34,17 +94,7 @@ impl TrashManager {
         fs::create_dir_all(&root)
             .with_context(|| format!("Failed to create trash directory: {}", root.display()))?;
 
-        // Ensure the batch id is unique even across multiple runs started in the same second.
-        let unique = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let batch_id = format!(
-            "{}-{}-{}",
-            Utc::now().format("%Y%m%d%H%M%S"),
-            unique,
-            std::process::id()
-        );
+        let batch_id = new_batch_id();
         let batch_dir = root.join(&batch_id);
         fs::create_dir_all(&batch_dir).with_context(|| {
             format!(
@@ -53,18 +103,97 @@ impl TrashManager {
             )
         })?;
 
-        let log_path = root.join(TRASH_LOG_FILENAME);
+        let log_path = trash_log_path(&root);
         Ok(Self {
             batch_id,
             root,
             log_path,
+            device_roots: Mutex::new(HashMap::new()),
+            cancel: CancellationToken::new(),
+            repair_permissions: false,
+            backend: TrashBackend::default(),
+            xdg_home_trash: xdg_trash::default_home_trash(),
+            warnings: Mutex::new(Vec::new()),
         })
     }
 
+    /// Stop moving once `token` is cancelled; an interrupted move is rolled back.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Add owner write permission to read-only directories the user owns before moving
+    /// them (see [`crate::permissions`]).
+    pub fn repair_permissions(mut self, repair: bool) -> Self {
+        self.repair_permissions = repair;
+        self
+    }
+
+    /// Move targets into the desktop's trash instead of batch directories; the log
+    /// stays in this manager's root either way.
+    pub fn backend(mut self, backend: TrashBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Home trash for [`TrashBackend::Xdg`], in place of `$XDG_DATA_HOME/Trash`
+    pub fn xdg_home_trash(mut self, home_trash: PathBuf) -> Self {
+        self.xdg_home_trash = home_trash;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Problems met so far that did not stop anything being trashed; each is
+    /// returned once.
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    /// Trash root to move `original` into: the one on its own file system, so the
+    /// move is a rename, or this manager's root when that cannot be used.
+    fn root_for(&self, original: &Path) -> PathBuf {
+        let Some((device, candidate)) = device_trash_root(&self.root, original) else {
+            return self.root.clone();
+        };
+        self.device_roots
+            .lock()
+            .unwrap()
+            .entry(device)
+            .or_insert_with(|| {
+                if prepare_device_trash_root(&candidate, device) {
+                    candidate
+                } else {
+                    self.root.clone()
+                }
+            })
+            .clone()
+    }
+
     pub fn trash_dir(&self, original: &Path, size: u64) -> Result<TrashEntry> {
-        let batch_dir = self.root.join(&self.batch_id);
-        let rel = path_to_trash_relpath(original);
-        let trashed_path = batch_dir.join(rel);
+        self.trash_path(original, size, None)
+    }
+
+    /// Move one pruned sub-path of `target` to the trash.
+    pub fn trash_pruned(&self, original: &Path, size: u64, target: &Path) -> Result<TrashEntry> {
+        self.trash_path(original, size, Some(target))
+    }
+
+    fn trash_path(
+        &self,
+        original: &Path,
+        size: u64,
+        pruned_from: Option<&Path>,
+    ) -> Result<TrashEntry> {
+        if self.backend == TrashBackend::Xdg {
+            return self.trash_path_xdg(original, size, pruned_from);
+        }
+
+        let root = self.root_for(original);
+        let trashed_path = trashed_path_for(&root, &self.batch_id, original);
 
         if let Some(parent) = trashed_path.parent() {
             fs::create_dir_all(parent).with_context(|| {
@@ -75,7 +204,13 @@ impl TrashManager {
             })?;
         }
 
-        move_path_with_exdev_fallback(original, &trashed_path).with_context(|| {
+        let moved = move_path_with_exdev_fallback(
+            original,
+            &trashed_path,
+            &self.cancel,
+            self.repair_permissions,
+        );
+        moved.with_context(|| {
             format!(
                 "Failed to move to trash: {} -> {}",
                 original.display(),
@@ -90,22 +225,49 @@ impl TrashManager {
             trashed_path: trashed_path.clone(),
             size,
             tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            pruned_from: pruned_from.map(Path::to_path_buf),
+            identity: FileIdentity::of_path(&trashed_path),
+            trash_root: (root != self.root).then_some(root),
+            trash_info: None,
         };
-        self.append_log(&entry)?;
+        append_trash_log(&self.log_path, &entry)?;
 
         Ok(entry)
     }
 
-    fn append_log(&self, entry: &TrashEntry) -> Result<()> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.log_path)
-            .with_context(|| format!("Failed to open trash log: {}", self.log_path.display()))?;
+    fn trash_path_xdg(
+        &self,
+        original: &Path,
+        size: u64,
+        pruned_from: Option<&Path>,
+    ) -> Result<TrashEntry> {
+        let trash_dir = xdg_trash::trash_dir_for(&self.xdg_home_trash, original)?;
+        let trashed = xdg_trash::trash_item(
+            &trash_dir,
+            original,
+            size,
+            &self.cancel,
+            self.repair_permissions,
+        )?;
+        if let Some(err) = trashed.sizes_error {
+            self.warnings.lock().unwrap().push(format!("{err:#}"));
+        }
 
-        serde_json::to_writer(&mut file, entry)?;
-        writeln!(&mut file)?;
-        Ok(())
+        let entry = TrashEntry {
+            batch_id: self.batch_id.clone(),
+            created_at: Utc::now(),
+            original_path: original.to_path_buf(),
+            identity: FileIdentity::of_path(&trashed.files_path),
+            trashed_path: trashed.files_path,
+            size,
+            tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            pruned_from: pruned_from.map(Path::to_path_buf),
+            trash_root: Some(trash_dir),
+            trash_info: Some(trashed.info_path),
+        };
+        append_trash_log(&self.log_path, &entry)?;
+
+        Ok(entry)
     }
 
     pub fn load_log(&self) -> Result<Vec<TrashEntry>> {
@@ -125,6 +287,115 @@ pub fn default_trash_root() -> PathBuf {
         .join("trash")
 }
 
+/// Trash root on the file system holding `original`, and that file system's device,
+/// when `root` is on another one: `<mount point>/.dev-cleaner-trash-<uid>`.
+///
+/// Only computed, not created; the original's parent decides, so the answer stays the
+/// same after the original is moved.
+#[cfg(unix)]
+pub(crate) fn device_trash_root(root: &Path, original: &Path) -> Option<(u64, PathBuf)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let device_of = |path: &Path| fs::metadata(path).ok().map(|m| m.dev());
+    let parent = original.parent().filter(|p| !p.as_os_str().is_empty())?;
+    let device = device_of(parent)?;
+    if device_of(root)? == device {
+        return None;
+    }
+
+    let uid = unsafe { libc::geteuid() };
+    let mount_point = mount_point_of(parent, device);
+    Some((
+        device,
+        mount_point.join(format!(".dev-cleaner-trash-{uid}")),
+    ))
+}
+
+/// Topmost ancestor of `path` still on `device`: the mount point of its file system
+#[cfg(unix)]
+pub(crate) fn mount_point_of(path: &Path, device: u64) -> &Path {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut mount_point = path;
+    while let Some(up) = mount_point.parent() {
+        if fs::metadata(up).map(|m| m.dev()).ok() != Some(device) {
+            break;
+        }
+        mount_point = up;
+    }
+    mount_point
+}
+
+#[cfg(not(unix))]
+pub(crate) fn device_trash_root(_root: &Path, _original: &Path) -> Option<(u64, PathBuf)> {
+    None
+}
+
+/// Create the trash root `candidate` if needed and check it can be used: a directory
+/// of our own on `device`. Anyone who can write to the mount point could have put
+/// something else there first.
+#[cfg(unix)]
+fn prepare_device_trash_root(candidate: &Path, device: u64) -> bool {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    match fs::DirBuilder::new().mode(0o700).create(candidate) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(_) => return false,
+    }
+    fs::symlink_metadata(candidate)
+        .is_ok_and(|m| m.is_dir() && m.uid() == unsafe { libc::geteuid() } && m.dev() == device)
+}
+
+#[cfg(not(unix))]
+fn prepare_device_trash_root(_candidate: &Path, _device: u64) -> bool {
+    false
+}
+
+/// Trash roots the entries in the log at `root` are kept in, besides `root` itself
+pub fn other_trash_roots(root: &Path) -> Result<Vec<PathBuf>> {
+    let roots = load_trash_log(&trash_log_path(root))?
+        .into_iter()
+        .filter_map(|e| e.trash_root)
+        .collect::<BTreeSet<_>>();
+    Ok(roots.into_iter().collect())
+}
+
+/// A batch id that sorts by time and stays unique across runs started in the same second
+pub(crate) fn new_batch_id() -> String {
+    let unique = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{}-{}-{}",
+        Utc::now().format("%Y%m%d%H%M%S"),
+        unique,
+        std::process::id()
+    )
+}
+
+pub(crate) fn trash_log_path(root: &Path) -> PathBuf {
+    root.join(TRASH_LOG_FILENAME)
+}
+
+/// Where a batch keeps `original` once it is moved to the trash
+pub(crate) fn trashed_path_for(root: &Path, batch_id: &str, original: &Path) -> PathBuf {
+    root.join(batch_id).join(path_to_trash_relpath(original))
+}
+
+pub(crate) fn append_trash_log(log_path: &Path, entry: &TrashEntry) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Failed to open trash log: {}", log_path.display()))?;
+
+    serde_json::to_writer(&mut file, entry)?;
+    writeln!(&mut file)?;
+    Ok(())
+}
+
 pub fn load_trash_log(log_path: &Path) -> Result<Vec<TrashEntry>> {
     let content = match fs::read_to_string(log_path) {
         Ok(c) => c,
@@ -155,6 +426,9 @@ pub struct TrashBatchSummary {
     pub created_at: DateTime<Utc>,
     pub entries_count: usize,
     pub total_size: u64,
+    /// Entries pruned from a target rather than removing it whole
+    #[serde(default)]
+    pub pruned_count: usize,
 }
 
 pub fn list_trash_batches(root: &Path) -> Result<Vec<TrashBatchSummary>> {
@@ -171,10 +445,14 @@ pub fn list_trash_batches(root: &Path) -> Result<Vec<TrashBatchSummary>> {
                 created_at: entry.created_at,
                 entries_count: 0,
                 total_size: 0,
+                pruned_count: 0,
             });
 
         summary.entries_count += 1;
         summary.total_size += entry.size;
+        if entry.pruned_from.is_some() {
+            summary.pruned_count += 1;
+        }
         if entry.created_at < summary.created_at {
             summary.created_at = entry.created_at;
         }
@@ -264,11 +542,31 @@ pub fn restore_batch_with_observer<O: RestoreObserver>(
     let mut errors = Vec::new();
 
     for entry in entries {
+        let container = entry.container_in(root);
         if !entry.trashed_path.exists() {
             skipped_count += 1;
             continue;
         }
 
+        // The log is only a record of what was moved; check the disk still agrees.
+        if let Err(violation) = check_path(&entry.trashed_path, &container, entry.identity) {
+            skipped_count += 1;
+            errors.push(format!(
+                "Refusing to restore {}: {}",
+                entry.trashed_path.display(),
+                violation
+            ));
+            continue;
+        }
+        if is_symlink_path(&entry.original_path).unwrap_or(false) {
+            skipped_count += 1;
+            errors.push(format!(
+                "Refusing to restore over symlink path: {}",
+                entry.original_path.display()
+            ));
+            continue;
+        }
+
         if entry.original_path.exists() && !force {
             skipped_count += 1;
             errors.push(format!(
@@ -312,8 +610,18 @@ pub fn restore_batch_with_observer<O: RestoreObserver>(
             })?;
         }
 
-        match move_path_with_exdev_fallback(&entry.trashed_path, &entry.original_path) {
+        match move_path_with_exdev_fallback(
+            &entry.trashed_path,
+            &entry.original_path,
+            &CancellationToken::new(),
+            false,
+        ) {
             Ok(_) => {
+                if let Some(info_path) = &entry.trash_info {
+                    if let Err(err) = xdg_trash::forget_item(info_path) {
+                        errors.push(format!("{:#}", err));
+                    }
+                }
                 restored_count += 1;
                 observer.on_restored(&entry);
             }
@@ -345,7 +653,19 @@ pub struct PurgeResult {
     pub errors: Vec<String>,
 }
 
-pub fn purge_trash_batch(root: &Path, batch_id: &str, dry_run: bool) -> Result<PurgeResult> {
+/// Delete a batch for good, removing it the way `deletion` says until `cancel` fires.
+pub fn purge_trash_batch(
+    root: &Path,
+    batch_id: &str,
+    dry_run: bool,
+    deletion: &DeletionOptions,
+    cancel: CancellationToken,
+) -> Result<PurgeResult> {
+    // `root.join` would follow `..` or an absolute id anywhere on disk.
+    if !is_batch_id(batch_id) {
+        anyhow::bail!("Invalid trash batch id: {}", batch_id);
+    }
+
     let log_path = root.join(TRASH_LOG_FILENAME);
     let entries = load_trash_log(&log_path)?;
 
@@ -364,29 +684,50 @@ pub fn purge_trash_batch(root: &Path, batch_id: &str, dry_run: bool) -> Result<P
         });
     }
 
-    let mut failed_batches = 0;
+    let engine = DeleteEngine::new(deletion, cancel)?;
     let mut errors = Vec::new();
 
-    let batch_dir = root.join(batch_id);
-    if batch_dir.exists() {
+    let (xdg_items, removed_batch): (Vec<_>, Vec<_>) =
+        removed.iter().partition(|e| e.trash_info.is_some());
+    for entry in xdg_items {
+        if let Err(err) = remove_xdg_item(&engine, root, entry) {
+            errors.push(err);
+        }
+    }
+
+    let roots = removed_batch
+        .iter()
+        .map(|e| e.root_in(root))
+        .chain([root])
+        .collect::<BTreeSet<_>>();
+    for batch_root in roots {
+        let batch_dir = batch_root.join(batch_id);
+        if !batch_dir.exists() {
+            continue;
+        }
         if is_symlink_path(&batch_dir)? {
-            failed_batches += 1;
             errors.push(format!(
                 "Refusing to purge symlink path: {}",
                 batch_dir.display()
             ));
-        } else if let Err(err) = fs::remove_dir_all(&batch_dir) {
-            failed_batches += 1;
+        } else if let Err(violation) = check_path(&batch_dir, batch_root, None) {
             errors.push(format!(
-                "Failed to remove batch dir {}: {}",
+                "Refusing to purge {}: {}",
+                batch_dir.display(),
+                violation
+            ));
+        } else if let Err(err) = engine.remove(&batch_dir, |_| {}) {
+            errors.push(format!(
+                "Failed to remove batch dir {}: {:#}",
                 batch_dir.display(),
                 err
             ));
         }
     }
+    let failed_batches = usize::from(!errors.is_empty());
 
     if failed_batches == 0 {
-        save_trash_log(&log_path, &kept)?;
+        save_log(&log_path, &kept)?;
     }
 
     Ok(PurgeResult {
@@ -410,39 +751,182 @@ pub struct GcResult {
     pub errors: Vec<String>,
 }
 
+/// Remove whole batches by age and total size, the way `deletion` says until
+/// `cancel` fires.
 pub fn gc_trash(
     root: &Path,
     keep_days: Option<i64>,
     keep_bytes: Option<u64>,
     dry_run: bool,
+    deletion: &DeletionOptions,
+    cancel: CancellationToken,
 ) -> Result<GcResult> {
-    let now = Utc::now();
     let log_path = root.join(TRASH_LOG_FILENAME);
     let entries = load_trash_log(&log_path)?;
 
     let summaries = summarize_batches(entries.iter().cloned().collect());
+    let usage = summaries
+        .iter()
+        .map(|s| (s.batch_id.clone(), s.created_at, s.total_size))
+        .collect::<Vec<_>>();
     let total_bytes = summaries.iter().map(|s| s.total_size).sum::<u64>();
+    let selection = select_gc_batches(&usage, keep_days, keep_bytes);
+    let selected = summaries
+        .iter()
+        .filter(|s| selection.batch_ids.contains(&s.batch_id))
+        .collect::<Vec<_>>();
+
+    if dry_run {
+        let removed_batches = selected.len();
+        let removed_entries = selected.iter().map(|s| s.entries_count).sum();
+        let removed_bytes = selected.iter().map(|s| s.total_size).sum();
+        return Ok(GcResult {
+            removed_batches,
+            removed_entries,
+            removed_bytes,
+            remaining_bytes: selection.bytes_after,
+            target_keep_bytes: keep_bytes,
+            blocked_by_keep_days: selection.blocked_by_keep_days,
+            failed_batches: 0,
+            errors: Vec::new(),
+        });
+    }
+
+    let engine = DeleteEngine::new(deletion, cancel)?;
+    let (removed_ok_ids, failed_batches, errors) =
+        remove_trash_batches(&engine, root, &entries, &selection.batch_ids)?;
+
+    let kept_entries = entries
+        .into_iter()
+        .filter(|e| !removed_ok_ids.contains(&e.batch_id))
+        .collect::<Vec<_>>();
+
+    if !removed_ok_ids.is_empty() {
+        save_log(&log_path, &kept_entries)?;
+    }
+
+    let removed_batches = removed_ok_ids.len();
+    let removed_entries = selected
+        .iter()
+        .filter(|s| removed_ok_ids.contains(&s.batch_id))
+        .map(|s| s.entries_count)
+        .sum();
+    let removed_bytes = selected
+        .iter()
+        .filter(|s| removed_ok_ids.contains(&s.batch_id))
+        .map(|s| s.total_size)
+        .sum();
+
+    Ok(GcResult {
+        removed_batches,
+        removed_entries,
+        removed_bytes,
+        remaining_bytes: total_bytes.saturating_sub(removed_bytes),
+        target_keep_bytes: keep_bytes,
+        blocked_by_keep_days: selection.blocked_by_keep_days,
+        failed_batches,
+        errors,
+    })
+}
+
+/// Remove the directories of `batch_ids` from every trash root their entries are in.
+///
+/// A batch only counts as removed once it is gone from all of them.
+fn remove_trash_batches(
+    engine: &DeleteEngine,
+    root: &Path,
+    entries: &[TrashEntry],
+    batch_ids: &HashSet<String>,
+) -> Result<(HashSet<String>, usize, Vec<String>)> {
+    let mut removed_ok_ids = batch_ids.clone();
+    let mut errors = Vec::new();
+
+    let mut by_root: BTreeMap<&Path, BTreeSet<&str>> = BTreeMap::new();
+    by_root
+        .entry(root)
+        .or_default()
+        .extend(batch_ids.iter().map(String::as_str));
+    for entry in entries.iter().filter(|e| batch_ids.contains(&e.batch_id)) {
+        if entry.trash_info.is_some() {
+            if let Err(err) = remove_xdg_item(engine, root, entry) {
+                removed_ok_ids.remove(&entry.batch_id);
+                errors.push(err);
+            }
+            continue;
+        }
+        by_root
+            .entry(entry.root_in(root))
+            .or_default()
+            .insert(&entry.batch_id);
+    }
+    for (batch_root, ids) in by_root {
+        let (removed, _, root_errors) = remove_batch_dirs(engine, batch_root, ids.iter().copied())?;
+        removed_ok_ids.retain(|id| !ids.contains(id.as_str()) || removed.contains(id));
+        errors.extend(root_errors);
+    }
+    let failed_batches = batch_ids.len() - removed_ok_ids.len();
+    Ok((removed_ok_ids, failed_batches, errors))
+}
+
+/// Delete one entry from a freedesktop.org trash for good, once the disk still agrees
+/// with the log
+fn remove_xdg_item(
+    engine: &DeleteEngine,
+    root: &Path,
+    entry: &TrashEntry,
+) -> std::result::Result<(), String> {
+    let Some(info_path) = &entry.trash_info else {
+        return Ok(());
+    };
+    if let Err(violation) = check_path(
+        &entry.trashed_path,
+        &entry.container_in(root),
+        entry.identity,
+    ) {
+        return Err(format!(
+            "Refusing to purge {}: {}",
+            entry.trashed_path.display(),
+            violation
+        ));
+    }
+    xdg_trash::delete_item(engine, &entry.trashed_path, info_path)
+        .map_err(|err| format!("{:#}", err))
+}
+
+/// Batches a gc run picks, and the bytes left once they are gone
+pub(crate) struct GcSelection {
+    pub batch_ids: HashSet<String>,
+    pub bytes_after: u64,
+    pub blocked_by_keep_days: bool,
+}
+
+/// Pick the batches to remove from `(batch_id, created_at, bytes)` usage.
+///
+/// Batches older than `keep_days` always go. `keep_bytes` then removes the oldest
+/// batches until under the cap, unless `keep_days` is set: it protects newer batches
+/// even when the cap cannot be met.
+pub(crate) fn select_gc_batches(
+    usage: &[(String, DateTime<Utc>, u64)],
+    keep_days: Option<i64>,
+    keep_bytes: Option<u64>,
+) -> GcSelection {
+    let now = Utc::now();
+    let total_bytes = usage.iter().map(|(_, _, bytes)| bytes).sum::<u64>();
 
     let mut blocked_by_keep_days = false;
-    let mut selected = Vec::new();
+    let mut batch_ids = HashSet::new();
+    let mut bytes_after = total_bytes;
 
     // Always delete batches older than keep-days (if set).
     if let Some(days) = keep_days {
-        selected.extend(
-            summaries
-                .iter()
-                .filter(|s| (now - s.created_at).num_days() > days)
-                .cloned(),
-        );
+        for (batch_id, created_at, bytes) in usage {
+            if (now - *created_at).num_days() > days {
+                batch_ids.insert(batch_id.clone());
+                bytes_after = bytes_after.saturating_sub(*bytes);
+            }
+        }
     }
 
-    let mut selected_ids = selected
-        .iter()
-        .map(|s| s.batch_id.clone())
-        .collect::<std::collections::HashSet<_>>();
-    let selected_bytes = selected.iter().map(|s| s.total_size).sum::<u64>();
-    let mut bytes_after = total_bytes.saturating_sub(selected_bytes);
-
     // Enforce keep-bytes cap.
     if let Some(limit) = keep_bytes {
         if bytes_after > limit {
@@ -451,48 +935,45 @@ pub fn gc_trash(
                 blocked_by_keep_days = true;
             } else {
                 // No keep-days: delete oldest batches until within keep-gb.
-                let mut candidates = summaries
+                let mut candidates = usage
                     .iter()
-                    .filter(|s| !selected_ids.contains(&s.batch_id))
-                    .cloned()
+                    .filter(|(batch_id, _, _)| !batch_ids.contains(batch_id))
                     .collect::<Vec<_>>();
-                candidates.sort_by(|a, b| a.created_at.cmp(&b.created_at)); // oldest first
+                candidates.sort_by_key(|(_, created_at, _)| *created_at); // oldest first
 
-                while bytes_after > limit {
-                    let Some(next) = candidates.first().cloned() else {
+                for (batch_id, _, bytes) in candidates {
+                    if bytes_after <= limit {
                         break;
-                    };
-                    candidates.remove(0);
-                    bytes_after = bytes_after.saturating_sub(next.total_size);
-                    selected_ids.insert(next.batch_id.clone());
-                    selected.push(next);
+                    }
+                    bytes_after = bytes_after.saturating_sub(*bytes);
+                    batch_ids.insert(batch_id.clone());
                 }
             }
         }
     }
 
-    if dry_run {
-        let removed_batches = selected.len();
-        let removed_entries = selected.iter().map(|s| s.entries_count).sum();
-        let removed_bytes = selected.iter().map(|s| s.total_size).sum();
-        return Ok(GcResult {
-            removed_batches,
-            removed_entries,
-            removed_bytes,
-            remaining_bytes: bytes_after,
-            target_keep_bytes: keep_bytes,
-            blocked_by_keep_days,
-            failed_batches: 0,
-            errors: Vec::new(),
-        });
+    GcSelection {
+        batch_ids,
+        bytes_after,
+        blocked_by_keep_days,
     }
+}
 
+/// Remove the batch directories of `batch_ids` under `root` through `engine`,
+/// refusing symlinks.
+///
+/// Returns the ids that are gone, the number that failed and why.
+pub(crate) fn remove_batch_dirs<'a>(
+    engine: &DeleteEngine,
+    root: &Path,
+    batch_ids: impl IntoIterator<Item = &'a str>,
+) -> Result<(HashSet<String>, usize, Vec<String>)> {
     let mut failed_batches = 0;
     let mut errors = Vec::new();
-    let mut removed_ok_ids = std::collections::HashSet::new();
+    let mut removed_ok_ids = HashSet::new();
 
-    for summary in &selected {
-        let batch_dir = root.join(&summary.batch_id);
+    for batch_id in batch_ids {
+        let batch_dir = root.join(batch_id);
         if batch_dir.exists() {
             if is_symlink_path(&batch_dir)? {
                 failed_batches += 1;
@@ -503,53 +984,23 @@ pub fn gc_trash(
                 continue;
             }
 
-            if let Err(err) = fs::remove_dir_all(&batch_dir) {
+            if let Err(err) = engine.remove(&batch_dir, |_| {}) {
                 failed_batches += 1;
                 errors.push(format!(
-                    "Failed to remove batch dir {}: {}",
+                    "Failed to remove batch dir {}: {:#}",
                     batch_dir.display(),
                     err
                 ));
                 continue;
             }
         }
-        removed_ok_ids.insert(summary.batch_id.clone());
-    }
-
-    let kept_entries = entries
-        .into_iter()
-        .filter(|e| !removed_ok_ids.contains(&e.batch_id))
-        .collect::<Vec<_>>();
-
-    if !removed_ok_ids.is_empty() {
-        save_trash_log(&log_path, &kept_entries)?;
+        removed_ok_ids.insert(batch_id.to_string());
     }
 
-    let removed_batches = removed_ok_ids.len();
-    let removed_entries = selected
-        .iter()
-        .filter(|s| removed_ok_ids.contains(&s.batch_id))
-        .map(|s| s.entries_count)
-        .sum();
-    let removed_bytes = selected
-        .iter()
-        .filter(|s| removed_ok_ids.contains(&s.batch_id))
-        .map(|s| s.total_size)
-        .sum();
-
-    Ok(GcResult {
-        removed_batches,
-        removed_entries,
-        removed_bytes,
-        remaining_bytes: total_bytes.saturating_sub(removed_bytes),
-        target_keep_bytes: keep_bytes,
-        blocked_by_keep_days,
-        failed_batches,
-        errors,
-    })
+    Ok((removed_ok_ids, failed_batches, errors))
 }
 
-fn path_to_trash_relpath(path: &Path) -> PathBuf {
+pub(crate) fn path_to_trash_relpath(path: &Path) -> PathBuf {
     let mut rel = PathBuf::new();
     for comp in path.components() {
         match comp {
@@ -580,10 +1031,14 @@ fn summarize_batches(entries: Vec<TrashEntry>) -> Vec<TrashBatchSummary> {
                 created_at: entry.created_at,
                 entries_count: 0,
                 total_size: 0,
+                pruned_count: 0,
             });
 
         summary.entries_count += 1;
         summary.total_size += entry.size;
+        if entry.pruned_from.is_some() {
+            summary.pruned_count += 1;
+        }
         if entry.created_at < summary.created_at {
             summary.created_at = entry.created_at;
         }
@@ -594,11 +1049,12 @@ fn summarize_batches(entries: Vec<TrashEntry>) -> Vec<TrashBatchSummary> {
     results
 }
 
-fn save_trash_log(log_path: &Path, entries: &[TrashEntry]) -> Result<()> {
+/// Atomically replace the JSONL log at `log_path` with `entries`
+pub(crate) fn save_log<T: Serialize>(log_path: &Path, entries: &[T]) -> Result<()> {
     if let Some(parent) = log_path.parent() {
         fs::create_dir_all(parent).with_context(|| {
             format!(
-                "Failed to create parent directory for trash log: {}",
+                "Failed to create parent directory for log: {}",
                 parent.display()
             )
         })?;
@@ -616,7 +1072,7 @@ fn save_trash_log(log_path: &Path, entries: &[TrashEntry]) -> Result<()> {
 
     fs::rename(&tmp_path, log_path).with_context(|| {
         format!(
-            "Failed to replace trash log: {} -> {}",
+            "Failed to replace log: {} -> {}",
             tmp_path.display(),
             log_path.display()
         )
@@ -625,6 +1081,15 @@ fn save_trash_log(log_path: &Path, entries: &[TrashEntry]) -> Result<()> {
     Ok(())
 }
 
+/// Whether `batch_id` names a single directory directly under the trash root
+pub(crate) fn is_batch_id(batch_id: &str) -> bool {
+    let mut components = Path::new(batch_id).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
 fn is_symlink_path(path: &Path) -> Result<bool> {
     Ok(fs::symlink_metadata(path)
         .with_context(|| format!("Failed to stat {}", path.display()))?
@@ -632,21 +1097,56 @@ fn is_symlink_path(path: &Path) -> Result<bool> {
         .is_symlink())
 }
 
-fn move_path_with_exdev_fallback(src: &Path, dst: &Path) -> Result<()> {
+/// Rename `src` to `dst`, copying and removing it when they are on different devices.
+///
+/// Until a copy is complete the source stays the only valid copy: a copy that fails or
+/// is cancelled is removed from `dst` again. Once it is complete the move is committed
+/// and the source removal runs to the end.
+///
+/// With `repair`, a read-only `src` directory is made writable first: moving a directory
+/// to another parent rewrites its `..` entry. Before the source removal after a copy,
+/// the whole tree is.
+pub(crate) fn move_path_with_exdev_fallback(
+    src: &Path,
+    dst: &Path,
+    cancel: &CancellationToken,
+    repair: bool,
+) -> Result<()> {
     if is_symlink_path(src)? {
         anyhow::bail!("Refusing to move symlink path: {}", src.display());
     }
+    if repair && src.is_dir() {
+        let _ = make_writable(src);
+    }
 
     match fs::rename(src, dst) {
         Ok(()) => Ok(()),
-        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
-            copy_dir_recursive(src, dst).with_context(|| {
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices && src.is_file() => {
+            fs::copy(src, dst).with_context(|| {
                 format!(
                     "Failed to copy across devices: {} -> {}",
                     src.display(),
                     dst.display()
                 )
             })?;
+            fs::remove_file(src).with_context(|| {
+                format!("Failed to remove source file after copy: {}", src.display())
+            })?;
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_dir_recursive(src, dst, cancel)
+                .map_err(|err| roll_back_copy(dst, err))
+                .with_context(|| {
+                    format!(
+                        "Failed to copy across devices: {} -> {}",
+                        src.display(),
+                        dst.display()
+                    )
+                })?;
+            if repair {
+                make_tree_writable(src);
+            }
             fs::remove_dir_all(src).with_context(|| {
                 format!(
                     "Failed to remove source directory after copy: {}",
@@ -665,13 +1165,35 @@ fn move_path_with_exdev_fallback(src: &Path, dst: &Path) -> Result<()> {
     }
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+/// Remove what an unfinished copy left at `dst`, keeping `err` as the cause
+fn roll_back_copy(dst: &Path, err: anyhow::Error) -> anyhow::Error {
+    if !dst.exists() {
+        return err;
+    }
+    match fs::remove_dir_all(dst) {
+        Ok(()) => err,
+        Err(rollback_err) => err.context(format!(
+            "Partial copy left at {} (rollback failed: {})",
+            dst.display(),
+            rollback_err
+        )),
+    }
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path, cancel: &CancellationToken) -> Result<()> {
     if dst.exists() {
         anyhow::bail!("Destination already exists: {}", dst.display());
     }
     fs::create_dir_all(dst).with_context(|| format!("Failed to create {}", dst.display()))?;
 
-    for entry in walkdir::WalkDir::new(src).follow_links(false).into_iter() {
+    for (index, entry) in walkdir::WalkDir::new(src)
+        .follow_links(false)
+        .into_iter()
+        .enumerate()
+    {
+        if (index as u64).is_multiple_of(CANCEL_POLL_ENTRIES) && cancel.is_cancelled() {
+            return Err(Cancelled.into());
+        }
         let entry =
             entry.with_context(|| format!("Failed to read dir entry under {}", src.display()))?;
         let rel = entry.path().strip_prefix(src).with_context(|| {
@@ -767,6 +1289,10 @@ mod tests {
             trashed_path,
             size,
             tool_version: Some("test".to_string()),
+            pruned_from: None,
+            identity: None,
+            trash_root: None,
+            trash_info: None,
         }
     }
 
@@ -806,14 +1332,58 @@ mod tests {
         fs::write(dir.join("x"), "y").unwrap();
 
         let original = dir.clone();
-        manager.trash_dir(&original, 1).unwrap();
+        let entry = manager.trash_dir(&original, 1).unwrap();
         assert!(!original.exists());
+        // Same file system as the trash root: no root of its own.
+        assert!(entry.trash_root.is_none());
 
         let result = restore_batch(&trash_root, &manager.batch_id, false, false).unwrap();
         assert_eq!(result.restored_count, 1);
         assert!(original.exists());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_batch_refuses_replaced_trash_entries() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("trash");
+        let manager = TrashManager::new_with_root(trash_root.clone()).unwrap();
+
+        let original = temp.path().join("src/to-delete");
+        fs::create_dir_all(&original).unwrap();
+        let entry = manager.trash_dir(&original, 1).unwrap();
+        assert!(entry.identity.is_some());
+
+        fs::rename(&entry.trashed_path, temp.path().join("elsewhere")).unwrap();
+        fs::create_dir(&entry.trashed_path).unwrap();
+
+        let result = restore_batch(&trash_root, &manager.batch_id, false, false).unwrap();
+        assert_eq!(result.restored_count, 0);
+        assert_eq!(result.skipped_count, 1);
+        assert!(result.errors[0].contains("Refusing to restore"));
+        assert!(!original.exists());
+    }
+
+    #[test]
+    fn test_purge_trash_batch_rejects_ids_outside_root() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("trash");
+        fs::create_dir_all(&trash_root).unwrap();
+        fs::create_dir_all(temp.path().join("victim")).unwrap();
+
+        for batch_id in ["../victim", "", "a/b"] {
+            assert!(purge_trash_batch(
+                &trash_root,
+                batch_id,
+                false,
+                &DeletionOptions::default(),
+                CancellationToken::new()
+            )
+            .is_err());
+        }
+        assert!(temp.path().join("victim").exists());
+    }
+
     #[test]
     fn test_list_and_purge_trash_batch() {
         let temp = TempDir::new().unwrap();
@@ -842,7 +1412,14 @@ mod tests {
         let entries = trash_entries_for_batch(&trash_root, &manager.batch_id).unwrap();
         assert_eq!(entries.len(), 2);
 
-        let purge = purge_trash_batch(&trash_root, &manager.batch_id, false).unwrap();
+        let purge = purge_trash_batch(
+            &trash_root,
+            &manager.batch_id,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(purge.removed_entries, 2);
         assert_eq!(purge.removed_bytes, 30);
         assert!(trash_root.join(&manager.batch_id).exists() == false);
@@ -851,6 +1428,31 @@ mod tests {
         assert!(batches_after.is_empty());
     }
 
+    #[test]
+    fn test_trash_pruned_records_target_and_restores_sub_path() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("trash");
+        let manager = TrashManager::new_with_root(trash_root.clone()).unwrap();
+
+        let target = temp.path().join("web/node_modules");
+        let cache = target.join(".cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("x"), "y").unwrap();
+
+        let entry = manager.trash_pruned(&cache, 1, &target).unwrap();
+        assert_eq!(entry.pruned_from.as_deref(), Some(target.as_path()));
+        assert!(!cache.exists());
+        assert!(target.exists());
+
+        let batches = list_trash_batches(&trash_root).unwrap();
+        assert_eq!(batches[0].entries_count, 1);
+        assert_eq!(batches[0].pruned_count, 1);
+
+        let result = restore_batch(&trash_root, &manager.batch_id, false, false).unwrap();
+        assert_eq!(result.restored_count, 1);
+        assert!(cache.join("x").exists());
+    }
+
     #[test]
     fn test_load_trash_log_skips_invalid_lines() {
         let temp = TempDir::new().unwrap();
@@ -929,8 +1531,13 @@ mod tests {
         fs::create_dir_all(&src).unwrap();
         symlink(&src, temp.path().join("src-link")).unwrap();
 
-        let err = move_path_with_exdev_fallback(&temp.path().join("src-link"), &dst)
-            .expect_err("symlink sources must be rejected");
+        let err = move_path_with_exdev_fallback(
+            &temp.path().join("src-link"),
+            &dst,
+            &CancellationToken::new(),
+            true,
+        )
+        .expect_err("symlink sources must be rejected");
         assert!(err.to_string().contains("Refusing to move symlink path"));
     }
 
@@ -1142,7 +1749,14 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = purge_trash_batch(&trash_root, "batch-1", true).unwrap();
+        let result = purge_trash_batch(
+            &trash_root,
+            "batch-1",
+            true,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 1);
         assert_eq!(result.removed_entries, 1);
         assert_eq!(result.removed_bytes, 5);
@@ -1167,7 +1781,14 @@ mod tests {
         )];
         write_entries(&log_path, &entries);
 
-        let result = purge_trash_batch(&trash_root, "missing", false).unwrap();
+        let result = purge_trash_batch(
+            &trash_root,
+            "missing",
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 0);
         assert_eq!(result.removed_entries, 0);
         assert_eq!(result.removed_bytes, 0);
@@ -1194,7 +1815,14 @@ mod tests {
         )];
         write_entries(&log_path, &entries);
 
-        let result = purge_trash_batch(&trash_root, "batch-1", false).unwrap();
+        let result = purge_trash_batch(
+            &trash_root,
+            "batch-1",
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.failed_batches, 1);
         assert!(result.errors[0].contains("Failed to remove batch dir"));
         assert_eq!(load_trash_log(&log_path).unwrap().len(), 1);
@@ -1232,13 +1860,200 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = purge_trash_batch(&trash_root, "batch-1", false).unwrap();
+        let result = purge_trash_batch(
+            &trash_root,
+            "batch-1",
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.failed_batches, 1);
         assert!(result.errors[0].contains("Refusing to purge symlink path"));
         assert!(batch_dir.exists());
         assert_eq!(load_trash_log(&log_path).unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_entries_in_other_trash_roots_are_restored_and_collected() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("trash");
+        let disk_root = temp.path().join("disk/.dev-cleaner-trash-1000");
+        let log_path = trash_log_path(&trash_root);
+
+        let original = temp.path().join("disk/project/target");
+        let trashed = trashed_path_for(&disk_root, "batch", &original);
+        create_dir_with_file(&trashed);
+        let mut entry = make_entry("batch", Utc::now(), original.clone(), trashed, 7);
+        entry.trash_root = Some(disk_root.clone());
+        write_entries(&log_path, &[entry]);
+        fs::create_dir_all(trash_root.join("batch")).unwrap();
+
+        assert_eq!(
+            other_trash_roots(&trash_root).unwrap(),
+            vec![disk_root.clone()]
+        );
+        let restored = restore_batch(&trash_root, "batch", false, false).unwrap();
+        assert_eq!(restored.restored_count, 1);
+        assert!(original.join("payload.txt").exists());
+
+        let trashed = trashed_path_for(&disk_root, "old", &original);
+        create_dir_with_file(&trashed);
+        let mut entry = make_entry(
+            "old",
+            Utc::now() - Duration::days(10),
+            original.clone(),
+            trashed,
+            7,
+        );
+        entry.trash_root = Some(disk_root.clone());
+        write_entries(&log_path, &[entry]);
+        fs::create_dir_all(trash_root.join("old")).unwrap();
+
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            None,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(result.removed_batches, 1);
+        assert_eq!(result.failed_batches, 0);
+        assert!(!disk_root.join("old").exists());
+        assert!(!trash_root.join("old").exists());
+        assert!(load_trash_log(&log_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_xdg_backend_items_are_listed_restored_and_purged() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("dev-cleaner-trash");
+        let home_trash = temp.path().join("share/Trash");
+        let manager = TrashManager::new_with_root(trash_root.clone())
+            .unwrap()
+            .backend(TrashBackend::Xdg)
+            .xdg_home_trash(home_trash.clone());
+
+        let original = temp.path().join("src/target");
+        create_dir_with_file(&original);
+        let entry = manager.trash_dir(&original, 7).unwrap();
+        assert_eq!(entry.trashed_path, home_trash.join("files/target"));
+        assert_eq!(
+            entry.trash_info.as_deref(),
+            Some(home_trash.join("info/target.trashinfo").as_path())
+        );
+        assert!(entry.trash_info.as_ref().unwrap().exists());
+
+        let batches = list_trash_batches(&trash_root).unwrap();
+        assert_eq!(batches[0].total_size, 7);
+
+        let restored = restore_batch(&trash_root, &manager.batch_id, false, false).unwrap();
+        assert_eq!(restored.restored_count, 1);
+        assert!(original.join("payload.txt").exists());
+        assert!(!entry.trash_info.as_ref().unwrap().exists());
+
+        let entry = manager.trash_dir(&original, 7).unwrap();
+        let purged = purge_trash_batch(
+            &trash_root,
+            &manager.batch_id,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(purged.failed_batches, 0);
+        assert!(!entry.trashed_path.exists());
+        assert!(!entry.trash_info.unwrap().exists());
+        assert!(load_trash_log(&trash_log_path(&trash_root))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_gc_trash_repairs_read_only_xdg_items() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("dev-cleaner-trash");
+        let manager = TrashManager::new_with_root(trash_root.clone())
+            .unwrap()
+            .backend(TrashBackend::Xdg)
+            .xdg_home_trash(temp.path().join("share/Trash"));
+
+        let original = temp.path().join("src/target");
+        create_dir_with_file(&original);
+        let locked = original.join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("blob"), "x").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        let entry = manager.trash_dir(&original, 7).unwrap();
+
+        // Root removes entries from read-only directories anyway.
+        // SAFETY: `geteuid` takes no arguments and cannot fail.
+        if unsafe { libc::geteuid() } != 0 {
+            let deletion = DeletionOptions::default();
+            let result = gc_trash(
+                &trash_root,
+                None,
+                Some(0),
+                false,
+                &deletion,
+                CancellationToken::new(),
+            )
+            .unwrap();
+            assert_eq!(result.failed_batches, 1);
+            assert!(entry.trashed_path.join("locked/blob").exists());
+            assert_eq!(list_trash_batches(&trash_root).unwrap().len(), 1);
+        }
+
+        let deletion = DeletionOptions {
+            repair_permissions: true,
+            ..DeletionOptions::default()
+        };
+        let result = gc_trash(
+            &trash_root,
+            None,
+            Some(0),
+            false,
+            &deletion,
+            CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(result.removed_batches, 1);
+        assert_eq!(result.failed_batches, 0);
+        assert!(!entry.trashed_path.exists());
+        assert!(!entry.trash_info.unwrap().exists());
+        assert!(list_trash_batches(&trash_root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_xdg_backend_logs_item_when_directorysizes_cannot_be_updated() {
+        let temp = TempDir::new().unwrap();
+        let trash_root = temp.path().join("dev-cleaner-trash");
+        let home_trash = temp.path().join("share/Trash");
+        fs::create_dir_all(home_trash.join("directorysizes")).unwrap();
+        let manager = TrashManager::new_with_root(trash_root.clone())
+            .unwrap()
+            .backend(TrashBackend::Xdg)
+            .xdg_home_trash(home_trash.clone());
+
+        let original = temp.path().join("src/target");
+        create_dir_with_file(&original);
+        let entry = manager.trash_dir(&original, 7).unwrap();
+
+        assert!(entry.trashed_path.join("payload.txt").exists());
+        let logged = load_trash_log(&trash_log_path(&trash_root)).unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].trashed_path, entry.trashed_path);
+        let warnings = manager.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("directorysizes"));
+        assert!(manager.take_warnings().is_empty());
+    }
+
     #[test]
     fn test_gc_trash_removes_old_batches_by_keep_days() {
         let temp = TempDir::new().unwrap();
@@ -1267,7 +2082,15 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, Some(3), None, false).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            None,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 1);
         assert_eq!(result.removed_entries, 1);
         assert_eq!(result.removed_bytes, 5);
@@ -1315,7 +2138,15 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, None, Some(9), false).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            None,
+            Some(9),
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 2);
         assert_eq!(result.removed_entries, 2);
         assert_eq!(result.removed_bytes, 13);
@@ -1355,7 +2186,15 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, Some(3), Some(1), true).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            Some(1),
+            true,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 1);
         assert!(result.blocked_by_keep_days);
         assert_eq!(result.remaining_bytes, 100);
@@ -1386,7 +2225,15 @@ mod tests {
         )];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, Some(3), None, false).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            None,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.failed_batches, 1);
         assert!(result.errors[0].contains("Refusing to purge symlink path"));
         assert!(batch_dir.exists());
@@ -1411,7 +2258,15 @@ mod tests {
         )];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, Some(3), None, false).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            None,
+            false,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.failed_batches, 1);
         assert_eq!(result.removed_batches, 0);
         assert!(result.errors[0].contains("Failed to remove batch dir"));
@@ -1446,11 +2301,37 @@ mod tests {
         ];
         write_entries(&log_path, &entries);
 
-        let result = gc_trash(&trash_root, Some(3), Some(1), true).unwrap();
+        let result = gc_trash(
+            &trash_root,
+            Some(3),
+            Some(1),
+            true,
+            &DeletionOptions::default(),
+            CancellationToken::new(),
+        )
+        .unwrap();
         assert_eq!(result.removed_batches, 1);
         assert!(result.blocked_by_keep_days);
         assert_eq!(result.remaining_bytes, 100);
         assert!(old_dir.exists());
         assert!(recent_dir.exists());
     }
+
+    #[test]
+    fn test_cancelled_copy_is_rolled_back() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        create_dir_with_file(&src);
+        let dst = temp.path().join("dst");
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = copy_dir_recursive(&src, &dst, &token)
+            .map_err(|err| roll_back_copy(&dst, err))
+            .unwrap_err();
+
+        assert!(err.is::<Cancelled>());
+        assert!(!dst.exists());
+        assert!(src.join("payload.txt").exists());
+    }
 }
@@ -0,0 +1,381 @@
+//! freedesktop.org trash backend.
+//!
+//! Follows the XDG Trash specification, so trashed targets show up in the desktop's
+//! file manager. A target on the home trash's file system goes to
+//! `$XDG_DATA_HOME/Trash`; one on another volume goes to `$topdir/.Trash/$uid` when
+//! the administrator set that up, else to `$topdir/.Trash-$uid`. Each item is moved to
+//! `files/` under a unique name, next to an `info/<name>.trashinfo` with its original
+//! path and deletion date; trashed directories are also listed in `directorysizes`.
+//!
+//! Dev Cleaner's own trash log keeps a [`crate::trash::TrashEntry`] per item, so its
+//! batches can still be listed, restored and collected from here.
+
+use crate::cancel::CancellationToken;
+use crate::cleaner::DeleteEngine;
+use crate::trash::move_path_with_exdev_fallback;
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const TRASHINFO_EXTENSION: &str = "trashinfo";
+const DIRECTORYSIZES_FILENAME: &str = "directorysizes";
+
+/// The home trash, `$XDG_DATA_HOME/Trash`
+pub fn default_home_trash() -> PathBuf {
+    dirs::data_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Trash")
+}
+
+/// Trash directory `original` should go to, created if needed.
+///
+/// The home trash when `original` is on its file system, the volume's own trash
+/// otherwise. When neither volume trash can be used the home trash takes it anyway,
+/// as the specification allows; the move is then a copy.
+pub(crate) fn trash_dir_for(home_trash: &Path, original: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(home_trash)
+        .with_context(|| format!("Failed to create trash directory: {}", home_trash.display()))?;
+    Ok(volume_trash_dir(home_trash, original).unwrap_or_else(|| home_trash.to_path_buf()))
+}
+
+#[cfg(unix)]
+fn volume_trash_dir(home_trash: &Path, original: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    let device_of = |path: &Path| fs::metadata(path).ok().map(|m| m.dev());
+    let parent = original.parent().filter(|p| !p.as_os_str().is_empty())?;
+    let device = device_of(parent)?;
+    if device_of(home_trash)? == device {
+        return None;
+    }
+
+    let uid = unsafe { libc::geteuid() };
+    let topdir = crate::trash::mount_point_of(parent, device);
+    // Only a directory of our own on that volume will do; anyone who can write to
+    // `$topdir` could have put something else there first.
+    let usable = |dir: &Path| {
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(_) => return false,
+        }
+        fs::symlink_metadata(dir).is_ok_and(|m| m.is_dir() && m.uid() == uid && m.dev() == device)
+    };
+
+    // `$topdir/.Trash` is shared and has to be a real, sticky directory.
+    let shared = topdir.join(".Trash");
+    let shared_ok = fs::symlink_metadata(&shared)
+        .is_ok_and(|m| m.is_dir() && m.mode() & 0o1000 != 0 && m.dev() == device);
+    if shared_ok {
+        let dir = shared.join(uid.to_string());
+        if usable(&dir) {
+            return Some(dir);
+        }
+    }
+
+    let dir = topdir.join(format!(".Trash-{uid}"));
+    usable(&dir).then_some(dir)
+}
+
+#[cfg(not(unix))]
+fn volume_trash_dir(_home_trash: &Path, _original: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// An item moved into a trash's `files/`
+pub(crate) struct TrashedItem {
+    pub files_path: PathBuf,
+    pub info_path: PathBuf,
+    /// Why `directorysizes` could not be updated; it is only a cache, so the item is
+    /// trashed all the same
+    pub sizes_error: Option<anyhow::Error>,
+}
+
+/// Move `original` into `trash_dir`.
+///
+/// The `.trashinfo` is written first, which reserves the name; it is removed again if
+/// the move fails.
+pub(crate) fn trash_item(
+    trash_dir: &Path,
+    original: &Path,
+    size: u64,
+    cancel: &CancellationToken,
+    repair: bool,
+) -> Result<TrashedItem> {
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    for dir in [&files_dir, &info_dir] {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create trash directory: {}", dir.display()))?;
+    }
+
+    let name = original
+        .file_name()
+        .with_context(|| format!("Cannot trash a path without a name: {}", original.display()))?;
+    let (files_path, info_path) = reserve_name(&files_dir, &info_dir, original, name)?;
+
+    let moved = move_path_with_exdev_fallback(original, &files_path, cancel, repair);
+    if let Err(err) = moved {
+        let _ = fs::remove_file(&info_path);
+        return Err(err).with_context(|| {
+            format!(
+                "Failed to move to trash: {} -> {}",
+                original.display(),
+                files_path.display()
+            )
+        });
+    }
+
+    let mut sizes_error = None;
+    if files_path.is_dir() {
+        let mtime = fs::metadata(&info_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let name = Path::new(files_path.file_name().unwrap_or_default());
+        let line = format!("{} {} {}", size, mtime, encode(name));
+        sizes_error = update_directorysizes(trash_dir, |lines| lines.push(line)).err();
+    }
+
+    Ok(TrashedItem {
+        files_path,
+        info_path,
+        sizes_error,
+    })
+}
+
+/// Create `info/<name>.trashinfo` for the first `name`, `name.2`, ... free in both
+/// `files/` and `info/`
+fn reserve_name(
+    files_dir: &Path,
+    info_dir: &Path,
+    original: &Path,
+    name: &std::ffi::OsStr,
+) -> Result<(PathBuf, PathBuf)> {
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode(original),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    for attempt in 1_u32.. {
+        let mut candidate = name.to_os_string();
+        if attempt > 1 {
+            candidate.push(format!(".{attempt}"));
+        }
+        let files_path = files_dir.join(&candidate);
+        let info_path = info_path_for(info_dir, &candidate);
+
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to create {}", info_path.display()))
+            }
+        };
+        if fs::symlink_metadata(&files_path).is_ok() {
+            // Left in `files/` without its info; not ours to reuse.
+            drop(file);
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+        file.write_all(contents.as_bytes())
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("Failed to write {}", info_path.display()))?;
+        return Ok((files_path, info_path));
+    }
+    unreachable!("ran out of trash names for {}", original.display())
+}
+
+/// Drop the records of an item that is no longer in `files/`: its `.trashinfo` and
+/// `directorysizes` line
+pub(crate) fn forget_item(info_path: &Path) -> Result<()> {
+    match fs::remove_file(info_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to remove {}", info_path.display()))
+        }
+    }
+
+    let Some(trash_dir) = info_path.parent().and_then(Path::parent) else {
+        return Ok(());
+    };
+    let Some(name) = info_path.file_stem() else {
+        return Ok(());
+    };
+    let name = encode(Path::new(name));
+    if !trash_dir.join(DIRECTORYSIZES_FILENAME).exists() {
+        return Ok(());
+    }
+    update_directorysizes(trash_dir, |lines| {
+        lines.retain(|line| line.splitn(3, ' ').nth(2) != Some(name.as_str()))
+    })
+}
+
+/// Delete an item from `files/` for good through `engine`, then its records
+pub(crate) fn delete_item(
+    engine: &DeleteEngine,
+    files_path: &Path,
+    info_path: &Path,
+) -> Result<()> {
+    let removed = match fs::symlink_metadata(files_path) {
+        Ok(metadata) if metadata.is_dir() => engine.remove(files_path, |_| {}),
+        Ok(_) => fs::remove_file(files_path).map_err(Into::into),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    };
+    removed.with_context(|| format!("Failed to remove {}", files_path.display()))?;
+    forget_item(info_path)
+}
+
+fn info_path_for(info_dir: &Path, name: &std::ffi::OsStr) -> PathBuf {
+    let mut file_name = OsString::from(name);
+    file_name.push(".");
+    file_name.push(TRASHINFO_EXTENSION);
+    info_dir.join(file_name)
+}
+
+/// Rewrite `directorysizes` through a temporary file and a rename, as the
+/// specification requires
+fn update_directorysizes(trash_dir: &Path, update: impl FnOnce(&mut Vec<String>)) -> Result<()> {
+    let path = trash_dir.join(DIRECTORYSIZES_FILENAME);
+    let mut lines = match fs::read_to_string(&path) {
+        Ok(content) => content.lines().map(str::to_string).collect(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    update(&mut lines);
+
+    let tmp_path = trash_dir.join(format!(
+        "{}.{}.tmp",
+        DIRECTORYSIZES_FILENAME,
+        std::process::id()
+    ));
+    let mut file = fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    for line in &lines {
+        writeln!(file, "{line}")?;
+    }
+    file.sync_all()
+        .with_context(|| format!("Failed to fsync {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path).with_context(|| {
+        format!(
+            "Failed to replace {}: {}",
+            DIRECTORYSIZES_FILENAME,
+            path.display()
+        )
+    })
+}
+
+/// Percent-encode a path's bytes the way `.trashinfo` and `directorysizes` store them
+fn encode(path: &Path) -> String {
+    let bytes = path_bytes(path);
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes.iter() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn trashed_items_get_unique_names_and_spec_records() {
+        let temp = TempDir::new().unwrap();
+        let trash_dir = temp.path().join("Trash");
+        let cancel = CancellationToken::new();
+
+        let mut trashed = Vec::new();
+        for project in ["a b", "c"] {
+            let original = temp.path().join(project).join("target");
+            fs::create_dir_all(&original).unwrap();
+            fs::write(original.join("blob"), "x").unwrap();
+            trashed.push(trash_item(&trash_dir, &original, 42, &cancel, false).unwrap());
+            assert!(!original.exists());
+        }
+
+        assert!(trashed.iter().all(|item| item.sizes_error.is_none()));
+        let (first, second) = (&trashed[0], &trashed[1]);
+        assert_eq!(first.files_path, trash_dir.join("files/target"));
+        assert_eq!(second.files_path, trash_dir.join("files/target.2"));
+        assert_eq!(second.info_path, trash_dir.join("info/target.2.trashinfo"));
+
+        let info = fs::read_to_string(&first.info_path).unwrap();
+        let mut lines = info.lines();
+        assert_eq!(lines.next(), Some("[Trash Info]"));
+        let expected = format!("Path={}", encode(&temp.path().join("a b/target")));
+        assert_eq!(lines.next(), Some(expected.as_str()));
+        assert!(expected.contains("a%20b/target"));
+        assert!(lines.next().unwrap().starts_with("DeletionDate="));
+
+        let sizes = fs::read_to_string(trash_dir.join(DIRECTORYSIZES_FILENAME)).unwrap();
+        let names = sizes
+            .lines()
+            .map(|line| line.split(' ').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0][0], "42");
+        assert_eq!(names[1][2], "target.2");
+
+        let engine = DeleteEngine::new(&Default::default(), cancel).unwrap();
+        delete_item(&engine, &second.files_path, &second.info_path).unwrap();
+        assert!(!second.files_path.exists());
+        assert!(!second.info_path.exists());
+        let sizes = fs::read_to_string(trash_dir.join(DIRECTORYSIZES_FILENAME)).unwrap();
+        assert_eq!(sizes.lines().count(), 1);
+        assert!(sizes.ends_with(" target\n"));
+    }
+
+    #[test]
+    fn failed_directorysizes_update_still_trashes_the_item() {
+        let temp = TempDir::new().unwrap();
+        let trash_dir = temp.path().join("Trash");
+        // A directory in its place makes reading `directorysizes` fail.
+        fs::create_dir_all(trash_dir.join(DIRECTORYSIZES_FILENAME)).unwrap();
+        let original = temp.path().join("project").join("target");
+        fs::create_dir_all(&original).unwrap();
+
+        let trashed =
+            trash_item(&trash_dir, &original, 42, &CancellationToken::new(), false).unwrap();
+
+        assert!(trashed.sizes_error.is_some());
+        assert!(trashed.files_path.is_dir());
+        assert!(trashed.info_path.is_file());
+        assert!(!original.exists());
+    }
+}
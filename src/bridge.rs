@@ -5,14 +5,17 @@ use dev_cleaner_core::app::{
 };
 use dev_cleaner_core::audit::AuditLogger;
 use dev_cleaner_core::cleaner::{CleanAction, CleanObserver, CleanOptions};
+use dev_cleaner_core::guard::PathViolation;
+use dev_cleaner_core::policy::{KeepPolicy, ProcessHold};
 use dev_cleaner_core::recommend::{recommend_projects, RecommendOptions, RecommendStrategy};
-use dev_cleaner_core::scanner::{Category, ProjectInfo, RiskLevel};
+use dev_cleaner_core::scanner::{Category, ProjectInfo, RiskLevel, ScanDiagnostics};
+use dev_cleaner_core::stats::drop_covered_partials;
 use dev_cleaner_core::trash::{
     default_trash_root, gc_trash, list_trash_batches, purge_trash_batch,
     restore_batch_with_observer, trash_entries_for_batch, RestoreObserver, TrashEntry,
 };
 use dev_cleaner_core::utils::{format_size, parse_size};
-use dev_cleaner_core::{Cleaner, CleanupPlan, Config};
+use dev_cleaner_core::{CancellationToken, Cleaner, CleanupPlan, Config};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs;
@@ -301,6 +304,8 @@ pub enum BridgeEvent {
     ScanFinished {
         total_count: usize,
         total_bytes: u64,
+        #[serde(default)]
+        diagnostics: ScanDiagnostics,
     },
     RecommendationReady {
         payload: serde_json::Value,
@@ -314,6 +319,11 @@ pub enum BridgeEvent {
         path: String,
         size: u64,
     },
+    CleanupProgress {
+        path: String,
+        bytes_freed: u64,
+        size: u64,
+    },
     CleanupDryRun {
         path: String,
         action: String,
@@ -323,6 +333,8 @@ pub enum BridgeEvent {
         path: String,
         reason: String,
         size: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        process: Option<ProcessHold>,
     },
     CleanupCompleted {
         path: String,
@@ -332,6 +344,10 @@ pub enum BridgeEvent {
         path: String,
         error: String,
     },
+    CleanupInterrupted {
+        path: String,
+        bytes_freed: u64,
+    },
     CleanupCancelled {
         remaining: usize,
     },
@@ -439,7 +455,7 @@ pub fn run_bridge(command: BridgeCommands, config: &Config, config_path: PathBuf
         BridgeCommands::Recommend(args) => bridge_recommend(args, config),
         BridgeCommands::Clean(args) => bridge_clean(args, config),
         BridgeCommands::Apply(args) => bridge_apply(args, config),
-        BridgeCommands::Trash { command } => bridge_trash(command),
+        BridgeCommands::Trash { command } => bridge_trash(command, config),
         BridgeCommands::Audit { command } => bridge_audit(command, config),
         BridgeCommands::Config { command } => bridge_config(command, config, config_path),
     }
@@ -479,6 +495,8 @@ fn bridge_scan(args: BridgeScanArgs, config: &Config) -> Result<()> {
     let service = ScanService::new();
     let request = build_scan_request(&args);
     let resolved = service.resolve_inputs(config, &request)?;
+    let keep_policy = KeepPolicy::from_config(config);
+    let mut diagnostics = service.config_diagnostics(&keep_policy);
     let mut projects = Vec::new();
     let mut total_seen = 0usize;
     let mut completed = 0usize;
@@ -509,10 +527,11 @@ fn bridge_scan(args: BridgeScanArgs, config: &Config) -> Result<()> {
                 continue;
             }
 
-            let evaluated = service.evaluate_project_with_config(
-                config,
+            let evaluated = service.evaluate_project_reporting(
                 project,
+                &keep_policy,
                 resolved.visibility.recent_days,
+                &mut diagnostics,
             );
             if resolved.visibility.is_visible(&evaluated) {
                 let project = evaluated.into_project_info();
@@ -526,12 +545,16 @@ fn bridge_scan(args: BridgeScanArgs, config: &Config) -> Result<()> {
                 total: total_seen,
             });
         }
+        diagnostics.merge(scanner.diagnostics());
     }
 
     projects.sort_by(|a, b| b.size.cmp(&a.size));
+    let mut counted = projects.clone();
+    drop_covered_partials(&mut counted);
     emit(&BridgeEvent::ScanFinished {
         total_count: projects.len(),
-        total_bytes: projects.iter().map(|p| p.size).sum(),
+        total_bytes: counted.iter().map(|p| p.size).sum(),
+        diagnostics,
     });
     Ok(())
 }
@@ -613,10 +636,12 @@ fn bridge_recommend(args: BridgeRecommendArgs, config: &Config) -> Result<()> {
             "in_use": { "count": result.blocked.in_use_count, "bytes": result.blocked.in_use_bytes },
             "protected": { "count": result.blocked.protected_count, "bytes": result.blocked.protected_bytes },
             "recent": { "count": result.blocked.recent_count, "bytes": result.blocked.recent_bytes },
-            "risk": { "count": result.blocked.risk_count, "bytes": result.blocked.risk_bytes }
+            "risk": { "count": result.blocked.risk_count, "bytes": result.blocked.risk_bytes },
+            "tracked_by_vcs": { "count": result.blocked.tracked_count, "bytes": result.blocked.tracked_bytes }
         },
         "projects": selected_projects,
-        "plan_path": args.output_plan
+        "plan_path": args.output_plan,
+        "diagnostics": discovered.diagnostics
     });
     emit(&BridgeEvent::RecommendationReady { payload });
     Ok(())
@@ -717,6 +742,8 @@ fn run_cleaner(
         total_bytes: projects.iter().map(|p| p.size).sum(),
         mode: mode.to_string(),
     });
+    let audit = AuditLogger::from_config(config);
+    let run_id = audit.start_run(audit_command).ok();
     let cleaner = Cleaner::with_options(CleanOptions {
         dry_run,
         verbose: false,
@@ -726,9 +753,11 @@ fn run_cleaner(
         trash,
         trash_root: None,
         cancel_file,
+        deletion: config.deletion.clone(),
+        trash_backend: config.trash_backend,
+        run_id: run_id.clone(),
+        ..CleanOptions::default()
     });
-    let audit = AuditLogger::from_config(config);
-    let run_id = audit.start_run(audit_command).ok();
     let mut observer = BridgeCleanObserver::new(if dry_run {
         "dry_run"
     } else if trash {
@@ -739,13 +768,12 @@ fn run_cleaner(
     let result = cleaner.clean_multiple_with_observer(&projects, &mut observer)?;
     if let Some(run_id) = &run_id {
         for item in &observer.audit_items {
-            let _ = audit.log_item(
+            let _ = audit.log_project_item(
                 run_id,
                 audit_command,
-                &item.path,
+                &item.project,
                 item.action,
                 item.result,
-                item.bytes,
                 item.reason.clone(),
             );
         }
@@ -769,6 +797,10 @@ fn run_cleaner(
             "trash_batch_id": result.trash_batch_id,
             "run_id": run_id,
             "cancelled": observer.cancelled,
+            "interrupted": result.interrupted,
+            "pending_reap_bytes": result.pending_reap_bytes,
+            "unremovable": result.unremovable,
+            "warnings": result.warnings,
         }),
     });
     Ok(())
@@ -782,10 +814,9 @@ struct BridgeCleanObserver {
 }
 
 struct BridgeAuditItem {
-    path: PathBuf,
+    project: ProjectInfo,
     action: &'static str,
     result: &'static str,
-    bytes: u64,
     reason: Option<String>,
 }
 
@@ -797,6 +828,14 @@ impl CleanObserver for BridgeCleanObserver {
         });
     }
 
+    fn on_bytes_freed(&mut self, project: &ProjectInfo, bytes_freed: u64) {
+        emit(&BridgeEvent::CleanupProgress {
+            path: project.cleanable_dir.display().to_string(),
+            bytes_freed,
+            size: project.size,
+        });
+    }
+
     fn on_skipped_in_use(&mut self, project: &ProjectInfo) {
         self.skipped(project, "in_use");
     }
@@ -805,32 +844,55 @@ impl CleanObserver for BridgeCleanObserver {
         self.skipped(project, "protected");
     }
 
+    fn on_skipped_tracked(&mut self, project: &ProjectInfo) {
+        self.skipped(project, "tracked_by_vcs");
+    }
+
     fn on_skipped_recent(&mut self, project: &ProjectInfo) {
         self.skipped(project, "recent");
     }
 
+    fn on_skipped_held(&mut self, project: &ProjectInfo, hold: &ProcessHold) {
+        emit(&BridgeEvent::CleanupSkipped {
+            path: project.cleanable_dir.display().to_string(),
+            reason: "held_by_process".to_string(),
+            size: project.size,
+            process: Some(hold.clone()),
+        });
+        self.audit_items.push(BridgeAuditItem {
+            project: project.clone(),
+            action: self.audit_action,
+            result: "skipped",
+            reason: Some(format!("held_by_process: {}", hold)),
+        });
+    }
+
+    fn on_skipped_unsafe(&mut self, project: &ProjectInfo, violation: PathViolation) {
+        emit(&BridgeEvent::CleanupSkipped {
+            path: project.cleanable_dir.display().to_string(),
+            reason: "unsafe_path".to_string(),
+            size: project.size,
+            process: None,
+        });
+        self.audit_items.push(BridgeAuditItem {
+            project: project.clone(),
+            action: self.audit_action,
+            result: "skipped",
+            reason: Some(format!("unsafe_path: {}", violation.label())),
+        });
+    }
+
     fn on_dry_run(&mut self, project: &ProjectInfo, action: CleanAction) {
         emit(&BridgeEvent::CleanupDryRun {
             path: project.cleanable_dir.display().to_string(),
-            action: match action {
-                CleanAction::Delete => "delete",
-                CleanAction::Trash => "trash",
-            }
-            .to_string(),
+            action: action.as_str().to_string(),
             size: project.size,
         });
         self.audit_items.push(BridgeAuditItem {
-            path: project.cleanable_dir.clone(),
+            project: project.clone(),
             action: "dry_run",
             result: "dry_run",
-            bytes: project.size,
-            reason: Some(
-                match action {
-                    CleanAction::Delete => "delete",
-                    CleanAction::Trash => "trash",
-                }
-                .to_string(),
-            ),
+            reason: Some(action.as_str().to_string()),
         });
     }
 
@@ -840,10 +902,12 @@ impl CleanObserver for BridgeCleanObserver {
             size,
         });
         self.audit_items.push(BridgeAuditItem {
-            path: project.cleanable_dir.clone(),
+            project: ProjectInfo {
+                size,
+                ..project.clone()
+            },
             action: self.audit_action,
             result: "completed",
-            bytes: size,
             reason: None,
         });
     }
@@ -854,14 +918,29 @@ impl CleanObserver for BridgeCleanObserver {
             error: error.to_string(),
         });
         self.audit_items.push(BridgeAuditItem {
-            path: project.cleanable_dir.clone(),
+            project: project.clone(),
             action: self.audit_action,
             result: "failed",
-            bytes: project.size,
             reason: Some(error.to_string()),
         });
     }
 
+    fn on_interrupted(&mut self, project: &ProjectInfo, bytes_freed: u64) {
+        emit(&BridgeEvent::CleanupInterrupted {
+            path: project.cleanable_dir.display().to_string(),
+            bytes_freed,
+        });
+        self.audit_items.push(BridgeAuditItem {
+            project: ProjectInfo {
+                size: bytes_freed,
+                ..project.clone()
+            },
+            action: self.audit_action,
+            result: "interrupted",
+            reason: Some("cancelled".to_string()),
+        });
+    }
+
     fn on_cancelled(&mut self, remaining_projects: usize) {
         self.cancelled = true;
         emit(&BridgeEvent::CleanupCancelled {
@@ -884,18 +963,18 @@ impl BridgeCleanObserver {
             path: project.cleanable_dir.display().to_string(),
             reason: reason.to_string(),
             size: project.size,
+            process: None,
         });
         self.audit_items.push(BridgeAuditItem {
-            path: project.cleanable_dir.clone(),
+            project: project.clone(),
             action: self.audit_action,
             result: "skipped",
-            bytes: project.size,
             reason: Some(reason.to_string()),
         });
     }
 }
 
-fn bridge_trash(command: BridgeTrashCommands) -> Result<()> {
+fn bridge_trash(command: BridgeTrashCommands, config: &Config) -> Result<()> {
     let root = default_trash_root();
     match command {
         BridgeTrashCommands::List { top } => {
@@ -925,7 +1004,13 @@ fn bridge_trash(command: BridgeTrashCommands) -> Result<()> {
             });
         }
         BridgeTrashCommands::Purge { batch, dry_run } => {
-            let result = purge_trash_batch(&root, &batch, dry_run)?;
+            let result = purge_trash_batch(
+                &root,
+                &batch,
+                dry_run,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
             emit(&BridgeEvent::TrashOperationFinished {
                 payload: json!({ "operation": "purge", "batch": batch, "result": result }),
             });
@@ -936,7 +1021,14 @@ fn bridge_trash(command: BridgeTrashCommands) -> Result<()> {
             dry_run,
         } => {
             let keep_bytes = keep_gb.map(|gb| gb.saturating_mul(1024 * 1024 * 1024));
-            let result = gc_trash(&root, keep_days, keep_bytes, dry_run)?;
+            let result = gc_trash(
+                &root,
+                keep_days,
+                keep_bytes,
+                dry_run,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
             emit(&BridgeEvent::TrashOperationFinished {
                 payload: json!({ "operation": "gc", "result": result }),
             });
@@ -1,4 +1,6 @@
 use dev_cleaner_core::cleaner::{CleanAction, CleanObserver, CleanResult};
+use dev_cleaner_core::guard::PathViolation;
+use dev_cleaner_core::policy::ProcessHold;
 use dev_cleaner_core::trash::{RestoreObserver, TrashEntry};
 use dev_cleaner_core::utils::format_size;
 use dev_cleaner_core::ProjectInfo;
@@ -79,6 +81,17 @@ impl CleanObserver for TerminalCleanObserver {
         }
     }
 
+    fn on_bytes_freed(&mut self, project: &ProjectInfo, bytes_freed: u64) {
+        if let Some(progress) = self.progress_bar() {
+            progress.set_message(format!(
+                "Cleaning: {} ({} of {})",
+                project.cleanable_dir.display(),
+                format_size(bytes_freed),
+                format_size(project.size)
+            ));
+        }
+    }
+
     fn on_skipped_in_use(&mut self, project: &ProjectInfo) {
         if self.verbose {
             println!("↷ Skipped {} (in use)", project.cleanable_dir.display());
@@ -100,6 +113,38 @@ impl CleanObserver for TerminalCleanObserver {
         self.inc();
     }
 
+    fn on_skipped_tracked(&mut self, project: &ProjectInfo) {
+        if self.verbose {
+            println!(
+                "↷ Skipped {} ({} git-tracked files)",
+                project.cleanable_dir.display(),
+                project.tracked_files
+            );
+        }
+        self.inc();
+    }
+
+    fn on_skipped_held(&mut self, project: &ProjectInfo, hold: &ProcessHold) {
+        // Always shown: the user has to close the process before retrying.
+        println!(
+            "↷ Skipped {} (in use by PID {} {}; close it and retry)",
+            project.cleanable_dir.display(),
+            hold.pid,
+            hold.command
+        );
+        self.inc();
+    }
+
+    fn on_skipped_unsafe(&mut self, project: &ProjectInfo, violation: PathViolation) {
+        // Always shown: the target has to be scanned again before it can be cleaned.
+        println!(
+            "↷ Skipped {} ({}; rescan and retry)",
+            project.cleanable_dir.display(),
+            violation
+        );
+        self.inc();
+    }
+
     fn on_dry_run(&mut self, project: &ProjectInfo, action: CleanAction) {
         println!(
             "{}: {} ({})",
@@ -107,6 +152,13 @@ impl CleanObserver for TerminalCleanObserver {
             project.cleanable_dir.display(),
             format_size(project.size)
         );
+        for prune in &project.prune_paths {
+            println!(
+                "    ↳ {} ({})",
+                prune.path.display(),
+                format_size(prune.size)
+            );
+        }
         self.inc();
     }
 
@@ -133,6 +185,16 @@ impl CleanObserver for TerminalCleanObserver {
         self.inc();
     }
 
+    fn on_interrupted(&mut self, project: &ProjectInfo, bytes_freed: u64) {
+        // Always shown: the target is half gone until it is cleaned again.
+        println!(
+            "⚠ Stopped partway through {} ({} removed; clean it again to finish)",
+            project.cleanable_dir.display(),
+            format_size(bytes_freed)
+        );
+        self.inc();
+    }
+
     fn on_finish(&mut self, result: &CleanResult) {
         if let Some(progress) = self.progress_bar() {
             progress.finish_with_message(format!(
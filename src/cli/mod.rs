@@ -12,22 +12,33 @@ use dev_cleaner_core::app::{
     ApplyPlanRequest, ApplyPlanService, BlockedSummary as AppBlockedSummary, CleanupRequest,
     CleanupService, ScanRequest, ScanService, VisibilityOptions,
 };
+use dev_cleaner_core::archive::{
+    archive_entries_for_batch, compression_ratio, default_archive_root, gc_archives,
+    latest_archive_batch_id, list_archive_batches, restore_archive_batch,
+    DEFAULT_COMPRESSION_RATIO,
+};
 use dev_cleaner_core::audit::AuditLogger;
 use dev_cleaner_core::cleaner::CleanOptions;
+use dev_cleaner_core::policy::KeepPolicy;
 use dev_cleaner_core::recommend::{recommend_projects, RecommendOptions, RecommendStrategy};
 use dev_cleaner_core::scanner::{Category, ProjectDetector, RiskLevel, RuleSource};
+use dev_cleaner_core::stats::drop_covered_partials;
 use dev_cleaner_core::trash::{
-    default_trash_root, gc_trash, latest_batch_id, list_trash_batches, purge_trash_batch,
-    restore_batch_with_observer, trash_entries_for_batch,
+    default_trash_root, gc_trash, latest_batch_id, list_trash_batches, other_trash_roots,
+    purge_trash_batch, restore_batch_with_observer, trash_entries_for_batch,
 };
 use dev_cleaner_core::utils::{format_size, parse_size};
 use dev_cleaner_core::{
-    Cleaner, CleanupPlan, Config, EvaluatedProject as AppEvaluatedProject, ProjectInfo,
+    default_run_journal_root, unfinished_runs, CancellationToken, Cleaner, CleanupPlan, Config,
+    DiagnosticKind, EvaluatedProject as AppEvaluatedProject, MountSkipReason, ProjectInfo,
+    ReapObserver, Reconciliation, RunJournal, ScanDiagnostics, Tombstone, TombstoneJournal,
+    UnfinishedRun,
 };
 use serde_json::json;
 use std::fs;
 use std::io::{self, IsTerminal, Write};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(name = "dev-cleaner")]
@@ -43,6 +54,55 @@ pub struct Cli {
     /// Named scan profile from config
     #[arg(long, global = true)]
     pub profile: Option<String>,
+
+    /// Ignore the scan cache and re-walk every directory
+    #[arg(long, global = true)]
+    pub refresh: bool,
+
+    /// Do not cross into other filesystems below the scan root
+    #[arg(long, global = true)]
+    pub one_file_system: bool,
+}
+
+/// Deletion settings of the subcommands that remove targets
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct DeleteArgs {
+    /// Threads used to delete targets (0 = one per CPU)
+    #[arg(long, value_name = "N")]
+    pub delete_workers: Option<usize>,
+
+    /// Delete at idle I/O and lowest CPU priority so running builds are not slowed down
+    #[arg(long)]
+    pub low_priority: bool,
+
+    /// Cap deletion at this many MB per second
+    #[arg(long, value_name = "MB")]
+    pub max_delete_rate: Option<u64>,
+
+    /// Cap deletion at this many files and directories per second
+    #[arg(long, value_name = "OPS")]
+    pub max_delete_ops: Option<u64>,
+
+    /// Make read-only directories you own writable so they can be deleted or trashed
+    #[arg(long)]
+    pub repair_permissions: bool,
+}
+
+impl DeleteArgs {
+    /// Override the `[deletion]` settings from the config file
+    fn apply_to(&self, config: &mut Config) {
+        if let Some(workers) = self.delete_workers {
+            config.deletion.workers = workers;
+        }
+        config.deletion.low_priority |= self.low_priority;
+        if let Some(rate) = self.max_delete_rate {
+            config.deletion.max_bytes_per_sec = Some(rate.saturating_mul(1024 * 1024));
+        }
+        if let Some(ops) = self.max_delete_ops {
+            config.deletion.max_ops_per_sec = Some(ops);
+        }
+        config.deletion.repair_permissions |= self.repair_permissions;
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -182,6 +242,10 @@ pub enum Commands {
         #[arg(long)]
         trash: bool,
 
+        /// Pack directories into compressed tarballs (undoable) instead of deleting
+        #[arg(long, conflicts_with = "trash")]
+        archive: bool,
+
         /// Auto mode - clean all matching without confirmation
         #[arg(long)]
         auto: bool,
@@ -225,6 +289,17 @@ pub enum Commands {
         /// Mark as recent when modified within N days
         #[arg(long, default_value = "7")]
         recent_days: i64,
+
+        /// Finish the last clean that was interrupted instead of scanning
+        #[arg(long)]
+        resume: bool,
+
+        /// Rename targets away at once and delete them in the background
+        #[arg(long)]
+        tombstone: bool,
+
+        #[command(flatten)]
+        delete: DeleteArgs,
     },
 
     /// Launch interactive TUI mode
@@ -388,6 +463,10 @@ pub enum Commands {
         #[arg(long, value_enum, default_value = "safe-first")]
         strategy: RecommendStrategyArg,
 
+        /// Count only what archiving would free: each target minus its tarball
+        #[arg(long)]
+        archive: bool,
+
         /// Output recommended plan to a JSON file
         #[arg(long)]
         output_plan: Option<PathBuf>,
@@ -412,7 +491,12 @@ pub enum Commands {
     /// Apply a cleanup plan JSON file
     Apply {
         /// Path to plan JSON file
-        plan: PathBuf,
+        #[arg(required_unless_present = "resume")]
+        plan: Option<PathBuf>,
+
+        /// Finish an interrupted run instead of applying a plan
+        #[arg(long, value_name = "RUN_ID", conflicts_with = "plan")]
+        resume: Option<String>,
 
         /// Dry run - don't actually delete
         #[arg(long)]
@@ -422,6 +506,10 @@ pub enum Commands {
         #[arg(long)]
         trash: bool,
 
+        /// Pack directories into compressed tarballs (undoable) instead of deleting
+        #[arg(long, conflicts_with = "trash")]
+        archive: bool,
+
         /// Force mode - skip confirmation and allow in-use cleaning
         #[arg(short, long)]
         force: bool,
@@ -445,14 +533,25 @@ pub enum Commands {
         /// Verbose output
         #[arg(short, long)]
         verbose: bool,
+
+        /// Rename targets away at once and delete them in the background
+        #[arg(long)]
+        tombstone: bool,
+
+        #[command(flatten)]
+        delete: DeleteArgs,
     },
 
-    /// Undo a trash batch (restore directories)
+    /// Undo a trash or archive batch (restore directories)
     Undo {
         /// Batch id to restore (defaults to latest)
         #[arg(long)]
         batch: Option<String>,
 
+        /// Unpack an archive batch instead of restoring from the trash
+        #[arg(long)]
+        archive: bool,
+
         /// Dry run - don't actually restore
         #[arg(long)]
         dry_run: bool,
@@ -466,12 +565,28 @@ pub enum Commands {
         verbose: bool,
     },
 
+    /// Delete tombstones left by `--tombstone` cleans
+    Reap {
+        /// Print nothing; used by the background reaper
+        #[arg(short, long)]
+        quiet: bool,
+
+        #[command(flatten)]
+        delete: DeleteArgs,
+    },
+
     /// Manage Dev Cleaner trash (list/show/purge/gc)
     Trash {
         #[command(subcommand)]
         command: TrashCommands,
     },
 
+    /// Manage the archive store (list/show/gc)
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommands,
+    },
+
     /// Manage named scan profiles
     Profile {
         #[command(subcommand)]
@@ -541,6 +656,53 @@ pub enum TrashCommands {
         #[arg(long)]
         dry_run: bool,
     },
+
+    /// List tombstones waiting to be reaped
+    Tombstones {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ArchiveCommands {
+    /// List archive batches
+    List {
+        /// Show only top N batches (by most recent)
+        #[arg(long, default_value = "20")]
+        top: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show entries for an archive batch
+    Show {
+        /// Batch id to show
+        #[arg(long)]
+        batch: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Garbage-collect old/oversize archive batches
+    Gc {
+        /// Keep batches newer than N days
+        #[arg(long)]
+        keep_days: Option<i64>,
+
+        /// Keep total archive size under N GiB
+        #[arg(long)]
+        keep_gb: Option<u64>,
+
+        /// Dry run (show what would be deleted)
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -626,7 +788,35 @@ impl Cli {
         } else {
             Config::load_or_default(Config::default_path())?
         };
+        config.scan_cache.refresh = self.refresh;
+        config.one_file_system |= self.one_file_system;
+        match &self.command {
+            Commands::Clean {
+                delete, tombstone, ..
+            }
+            | Commands::Apply {
+                delete, tombstone, ..
+            } => {
+                delete.apply_to(&mut config);
+                config.deletion.tombstone |= *tombstone;
+            }
+            Commands::Reap { delete, .. } => delete.apply_to(&mut config),
+            _ => {}
+        }
         let profile = self.profile.clone();
+        let reaps_in_background = !matches!(self.command, Commands::Reap { .. });
+        let resuming = matches!(
+            self.command,
+            Commands::Clean { resume: true, .. }
+                | Commands::Apply {
+                    resume: Some(_),
+                    ..
+                }
+        );
+        if !resuming && !matches!(self.command, Commands::Bridge { .. }) {
+            report_unfinished_runs(&config);
+        }
+        let config_path = self.config.clone();
 
         match self.command {
             Commands::Scan {
@@ -667,6 +857,7 @@ impl Cli {
                 older_than,
                 dry_run,
                 trash,
+                archive,
                 auto,
                 force,
                 share,
@@ -678,7 +869,14 @@ impl Cli {
                 include_protected,
                 force_protected,
                 recent_days,
+                resume,
+                tombstone: _,
+                delete: _,
             } => {
+                if resume {
+                    run_resume(None, "clean", auto || force, verbose, &config)?;
+                    return Ok(());
+                }
                 run_clean(
                     path,
                     profile.as_deref(),
@@ -687,6 +885,7 @@ impl Cli {
                     older_than,
                     dry_run,
                     trash,
+                    archive,
                     auto,
                     force,
                     share,
@@ -788,6 +987,7 @@ impl Cli {
                 include_protected,
                 recent_days,
                 strategy,
+                archive,
                 output_plan,
                 json,
                 explain,
@@ -808,6 +1008,7 @@ impl Cli {
                     include_protected,
                     recent_days,
                     strategy,
+                    archive,
                     output_plan,
                     json,
                     explain,
@@ -818,19 +1019,32 @@ impl Cli {
             }
             Commands::Apply {
                 plan,
+                resume,
                 dry_run,
                 trash,
+                archive,
                 force,
                 no_verify,
                 include_recent,
                 force_protected,
                 recent_days,
                 verbose,
+                tombstone: _,
+                delete: _,
             } => {
+                let plan = match (resume, plan) {
+                    (Some(run_id), _) => {
+                        run_resume(Some(run_id), "apply", force, verbose, &config)?;
+                        return Ok(());
+                    }
+                    (None, Some(plan)) => plan,
+                    (None, None) => anyhow::bail!("A plan file or --resume is required"),
+                };
                 run_apply(
                     plan,
                     dry_run,
                     trash,
+                    archive,
                     force,
                     no_verify,
                     include_recent,
@@ -842,15 +1056,22 @@ impl Cli {
             }
             Commands::Undo {
                 batch,
+                archive,
                 dry_run,
                 force,
                 verbose,
             } => {
-                run_undo(batch, dry_run, force, verbose, &config)?;
+                run_undo(batch, archive, dry_run, force, verbose, &config)?;
+            }
+            Commands::Reap { quiet, .. } => {
+                run_reap(quiet, &config)?;
             }
             Commands::Trash { command } => {
                 run_trash(command, &config)?;
             }
+            Commands::Archive { command } => {
+                run_archive(command, &config)?;
+            }
             Commands::Profile { command } => {
                 run_profile(command, &mut config, self.config)?;
             }
@@ -863,6 +1084,10 @@ impl Cli {
             }
         }
 
+        if reaps_in_background {
+            spawn_background_reaper(config_path.as_deref(), &config);
+        }
+
         Ok(())
     }
 }
@@ -951,8 +1176,12 @@ fn run_scan(
     let resolved = scan_service.resolve_inputs(config, &request)?;
 
     if json_output || resolved.roots.len() > 1 {
-        let projects =
-            project_infos_from_evaluated(scan_service.discover_visible(config, &request)?.projects);
+        let discovered = scan_service.discover_visible(config, &request)?;
+        // Keep stdout a plain result array; problems go to stderr as one JSON object.
+        if !discovered.diagnostics.is_empty() {
+            eprintln!("{}", json!({ "diagnostics": &discovered.diagnostics }));
+        }
+        let projects = project_infos_from_evaluated(discovered.projects);
         println!("{}", serde_json::to_string_pretty(&projects)?);
         return Ok(());
     }
@@ -960,8 +1189,12 @@ fn run_scan(
     println!("{}", "Scanning for cleanable directories...".cyan().bold());
     let root = resolved.roots[0].clone();
     let scanner = scan_service.build_scanner(&root, config, &resolved);
+    let keep_policy = KeepPolicy::from_config(config);
+    let mut diagnostics = scan_service.config_diagnostics(&keep_policy);
     let (total_count, rx) = scanner.scan_with_streaming()?;
     if total_count == 0 {
+        diagnostics.merge(scanner.diagnostics());
+        print_diagnostics(&diagnostics, explain);
         println!("{}", "No cleanable directories found.".yellow());
         return Ok(());
     }
@@ -980,7 +1213,6 @@ fn run_scan(
     );
 
     let mut projects = Vec::new();
-    let mut total_size = 0u64;
     for project in rx.iter() {
         pb.inc(1);
         if !resolved
@@ -990,17 +1222,17 @@ fn run_scan(
             continue;
         }
 
-        let evaluated = scan_service.evaluate_project_with_config(
-            config,
+        let evaluated = scan_service.evaluate_project_reporting(
             project,
+            &keep_policy,
             resolved.visibility.recent_days,
+            &mut diagnostics,
         );
         if !resolved.visibility.is_visible(&evaluated) {
             continue;
         }
         let project = evaluated.into_project_info();
 
-        total_size += project.size;
         let dir_display = project.cleanable_dir.display().to_string();
         let short_path = if dir_display.len() > 50 {
             format!("...{}", &dir_display[dir_display.len() - 47..])
@@ -1016,7 +1248,9 @@ fn run_scan(
             rule_meta.bright_black(),
             dir_display.bright_white(),
             project.size_human().yellow(),
-            if project.protected {
+            if project.tracked_files > 0 {
+                " [TRACKED]".yellow().to_string()
+            } else if project.protected {
                 " [PROTECTED]".yellow().to_string()
             } else {
                 String::new()
@@ -1040,11 +1274,62 @@ fn run_scan(
                 "↳".bright_black(),
                 reason.bright_black()
             ));
+            if let Some(checks) = content_checks_summary(&project) {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    checks.bright_black()
+                ));
+            }
+            if project.tracked_files > 0 {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    tracked_files_summary(&project).yellow()
+                ));
+            }
+            if let Some(usage) = disk_usage_summary(&project) {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    usage.bright_black()
+                ));
+            }
+            if let Some(activity) = activity_summary(&project) {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    activity.bright_black()
+                ));
+            }
+            if let Some(hold) = &project.in_use_by {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    format!("in use: {}", hold).yellow()
+                ));
+            }
+            if !project.feeding_projects.is_empty() {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    feeding_projects_summary(&project).bright_black()
+                ));
+            }
+            if project.is_partial() {
+                pb.println(format!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    prune_paths_summary(&project).bright_black()
+                ));
+            }
         }
 
         projects.push(project);
     }
     pb.finish_and_clear();
+    diagnostics.merge(scanner.diagnostics());
+    print_diagnostics(&diagnostics, explain);
 
     if projects.is_empty() {
         println!("\n{}", "No directories match the filter criteria.".yellow());
@@ -1052,6 +1337,9 @@ fn run_scan(
     }
 
     projects.sort_by(|a, b| b.size.cmp(&a.size));
+    let mut counted = projects.clone();
+    drop_covered_partials(&mut counted);
+    let total_size: u64 = counted.iter().map(|p| p.size).sum();
     println!(
         "\n{} {} cleanable directories found",
         "✓".green().bold(),
@@ -1065,6 +1353,83 @@ fn run_scan(
     Ok(())
 }
 
+/// Entries shown per scan unless `--explain` asks for all of them
+const SHOWN_DIAGNOSTICS: usize = 5;
+
+fn print_diagnostics(diagnostics: &ScanDiagnostics, explain: bool) {
+    for mount in &diagnostics.skipped_mounts {
+        let kind = match mount.reason {
+            MountSkipReason::OtherFilesystem => "other filesystem",
+            MountSkipReason::Network => "network filesystem",
+            MountSkipReason::Pseudo => "pseudo filesystem",
+        };
+        let fs_type = mount
+            .fs_type
+            .as_deref()
+            .map(|fs_type| format!(", {fs_type}"))
+            .unwrap_or_default();
+        println!(
+            "{} {} ({}{})",
+            "Skipped mount:".bright_black(),
+            mount.path.display(),
+            kind,
+            fs_type
+        );
+    }
+
+    let problems = diagnostics
+        .counts
+        .keys()
+        .any(|kind| *kind != DiagnosticKind::SkippedMount);
+    if !problems {
+        return;
+    }
+    if diagnostics.is_complete() {
+        println!("{} {}", "Note:".yellow(), diagnostics.summary());
+    } else {
+        println!(
+            "{} {}",
+            "⚠ Scan incomplete:".yellow().bold(),
+            diagnostics.summary()
+        );
+    }
+
+    let shown = if explain {
+        diagnostics.entries.len()
+    } else {
+        SHOWN_DIAGNOSTICS
+    };
+    for diagnostic in diagnostics.entries.iter().take(shown) {
+        let path = diagnostic
+            .path
+            .as_ref()
+            .map(|path| format!("{}: ", path.display()))
+            .unwrap_or_default();
+        println!(
+            "    {} {}{} ({})",
+            "↳".bright_black(),
+            path,
+            diagnostic.message.bright_black(),
+            diagnostic.kind.label()
+        );
+    }
+    for path in diagnostics.unresolved_sizes.iter().take(shown) {
+        println!(
+            "    {} {}: {}",
+            "↳".bright_black(),
+            path.display(),
+            "size unknown".bright_black()
+        );
+    }
+    let listed = diagnostics.entries.len() + diagnostics.unresolved_sizes.len();
+    if !explain && listed > SHOWN_DIAGNOSTICS {
+        println!(
+            "    {}",
+            "(run with --explain to list every problem)".bright_black()
+        );
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 struct BlockedSummary {
     in_use_count: usize,
@@ -1073,17 +1438,20 @@ struct BlockedSummary {
     protected_bytes: u64,
     recent_count: usize,
     recent_bytes: u64,
+    tracked_count: usize,
+    tracked_bytes: u64,
 }
 
 impl BlockedSummary {
     fn total_count(&self) -> usize {
-        self.in_use_count + self.protected_count + self.recent_count
+        self.in_use_count + self.protected_count + self.recent_count + self.tracked_count
     }
 
     fn total_bytes(&self) -> u64 {
         self.in_use_bytes
             .saturating_add(self.protected_bytes)
             .saturating_add(self.recent_bytes)
+            .saturating_add(self.tracked_bytes)
     }
 }
 
@@ -1096,6 +1464,8 @@ impl From<AppBlockedSummary> for BlockedSummary {
             protected_bytes: value.protected_bytes,
             recent_count: value.recent_count,
             recent_bytes: value.recent_bytes,
+            tracked_count: value.tracked_count,
+            tracked_bytes: value.tracked_bytes,
         }
     }
 }
@@ -1150,9 +1520,11 @@ fn run_keyboard_selector(
     Ok(selector.run()?.unwrap_or_default())
 }
 
-fn execution_mode_label(dry_run: bool, trash: bool) -> &'static str {
+fn execution_mode_label(dry_run: bool, trash: bool, archive: bool) -> &'static str {
     if dry_run {
         "dry-run"
+    } else if archive {
+        "archive (undoable)"
     } else if trash {
         "trash (undoable)"
     } else {
@@ -1194,13 +1566,15 @@ fn confirm_execution_summary(
 
     if blocked.total_count() > 0 {
         println!(
-            "  Blocked: in_use={} ({}) protected={} ({}) recent={} ({})",
+            "  Blocked: in_use={} ({}) protected={} ({}) recent={} ({}) tracked={} ({})",
             blocked.in_use_count.to_string().yellow(),
             format_size(blocked.in_use_bytes).yellow(),
             blocked.protected_count.to_string().yellow(),
             format_size(blocked.protected_bytes).yellow(),
             blocked.recent_count.to_string().yellow(),
             format_size(blocked.recent_bytes).yellow(),
+            blocked.tracked_count.to_string().yellow(),
+            format_size(blocked.tracked_bytes).yellow(),
         );
     }
 
@@ -1239,6 +1613,7 @@ fn run_clean(
     older_than: Option<i64>,
     dry_run: bool,
     trash: bool,
+    archive: bool,
     auto: bool,
     force: bool,
     share: bool,
@@ -1314,10 +1689,11 @@ fn run_clean(
         );
         if split.blocked_summary.total_count() > 0 {
             println!(
-                "  Blocked: in_use={} protected={} recent={}",
+                "  Blocked: in_use={} protected={} recent={} tracked={}",
                 split.blocked_summary.in_use_count.to_string().yellow(),
                 split.blocked_summary.protected_count.to_string().yellow(),
-                split.blocked_summary.recent_count.to_string().yellow()
+                split.blocked_summary.recent_count.to_string().yellow(),
+                split.blocked_summary.tracked_count.to_string().yellow()
             );
         }
         return Ok(());
@@ -1329,7 +1705,7 @@ fn run_clean(
             "clean",
             split.selected.len(),
             selected_total_size,
-            execution_mode_label(dry_run, trash),
+            execution_mode_label(dry_run, trash, archive),
             split.blocked_summary,
             0,
         )?
@@ -1343,14 +1719,13 @@ fn run_clean(
 
     if let Some(run_id) = &run_id {
         for project in &split.blocked {
-            let _ = audit.log_item(
+            let _ = audit.log_project_item(
                 run_id,
                 "clean",
-                &project.cleanable_dir,
+                project,
                 "remove",
                 "skipped",
-                project.size,
-                project.skip_reason.clone(),
+                audit_skip_reason(project),
             );
         }
     }
@@ -1364,10 +1739,16 @@ fn run_clean(
         force_protected,
         trash,
         trash_root: None,
+        archive,
         cancel_file: None,
+        deletion: config.deletion.clone(),
+        trash_backend: config.trash_backend,
+        run_id: run_id.clone(),
+        ..CleanOptions::default()
     };
 
-    let cleaner = Cleaner::with_options(options);
+    let journal = start_run_journal(run_id.as_deref(), "clean", &options)?;
+    let cleaner = journaled_cleaner(options, journal.as_ref());
     let mut observer = TerminalCleanObserver::new(verbose);
     let mut result = cleaner.clean_multiple_with_observer(&split.selected, &mut observer)?;
     result.skipped_count += split.blocked_summary.total_count();
@@ -1378,13 +1759,12 @@ fn run_clean(
 
     if let Some(run_id) = &run_id {
         for project in &split.selected {
-            let _ = audit.log_item(
+            let _ = audit.log_project_item(
                 run_id,
                 "clean",
-                &project.cleanable_dir,
-                if dry_run { "dry_run" } else { "remove" },
+                project,
+                audit_clean_action(dry_run, archive),
                 "attempted",
-                project.size,
                 None,
             );
         }
@@ -1402,6 +1782,12 @@ fn run_clean(
         "  Space freed: {}",
         result.size_freed_human().green().bold()
     );
+    if result.pending_reap_bytes > 0 {
+        println!(
+            "  Pending reap: {} (deleting in the background)",
+            format_size(result.pending_reap_bytes).yellow()
+        );
+    }
 
     if share {
         print_share_block_if_applicable(&result, dry_run, trash, auto, force, verbose);
@@ -1414,13 +1800,21 @@ fn run_clean(
             format!("dev-cleaner undo --batch {}", batch_id).bright_black()
         );
     }
+    print_archive_batch(&result);
 
+    if !result.warnings.is_empty() {
+        println!("\n{}", "Warnings:".yellow().bold());
+        for warning in &result.warnings {
+            println!("  {}", warning.yellow());
+        }
+    }
     if !result.errors.is_empty() {
         println!("\n{}", "Errors:".red().bold());
         for error in &result.errors {
             println!("  {}", error.red());
         }
     }
+    print_unremovable(&result, config.deletion.repair_permissions);
 
     if let Some(run_id) = run_id {
         let _ = audit.finish_run(
@@ -1432,6 +1826,9 @@ fn run_clean(
             result.bytes_freed,
         );
     }
+    if let Some(journal) = journal {
+        let _ = journal.finish();
+    }
 
     Ok(())
 }
@@ -1512,7 +1909,9 @@ fn display_projects(projects: &[ProjectInfo]) {
             _ => project_type.white(),
         };
 
-        let in_use = if project.in_use {
+        let in_use = if let Some(hold) = &project.in_use_by {
+            format!(" [IN USE: PID {} {}]", hold.pid, hold.command).yellow()
+        } else if project.in_use {
             " [IN USE]".yellow()
         } else {
             "".white()
@@ -1550,18 +1949,136 @@ fn rule_source_label(source: RuleSource) -> &'static str {
         RuleSource::Builtin => "builtin",
         RuleSource::Gitignore => "gitignore",
         RuleSource::Heuristic => "heuristic",
+        RuleSource::CacheTag => "cache_tag",
     }
 }
 
 fn detection_meta(project: &ProjectInfo) -> String {
-    let source = project
-        .matched_rule
-        .as_ref()
-        .map(|rule| rule_source_label(rule.source))
-        .unwrap_or("unknown");
+    let source = match project.matched_rule.as_ref() {
+        Some(rule) if rule.cache_tagged && rule.source != RuleSource::CacheTag => {
+            format!("{} + CACHEDIR.TAG", rule_source_label(rule.source))
+        }
+        Some(rule) => rule_source_label(rule.source).to_string(),
+        None => "unknown".to_string(),
+    };
+    if project.is_partial() {
+        return format!(
+            "source: {}, prunes {} stale paths",
+            source,
+            project.prune_paths.len()
+        );
+    }
     format!("source: {}", source)
 }
 
+fn feeding_projects_summary(project: &ProjectInfo) -> String {
+    let feeders = project
+        .feeding_projects
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    format!(
+        "shared target fed by {} projects: {}",
+        feeders.len(),
+        feeders.join(", ")
+    )
+}
+
+/// Git activity behind the recent/in-use decisions, e.g.
+/// "git activity 3 days ago (last commit 12 days ago); in use: verified:git".
+fn activity_summary(project: &ProjectInfo) -> Option<String> {
+    let activity = project.activity?;
+    let last_commit_days = (chrono::Utc::now() - activity.last_commit).num_days();
+    let mut summary = format!(
+        "git activity {} days ago (last commit {} days ago)",
+        activity.days_since_activity(),
+        last_commit_days
+    );
+    if project.in_use {
+        summary.push_str(&format!("; in use: {}", project.in_use_source));
+    }
+    if project.recent {
+        summary.push_str(&format!("; recent: {}", project.recent_source));
+    }
+    Some(summary)
+}
+
+/// Allocated and reclaimable bytes, when hardlinks or sparse files make them differ
+/// from the apparent size
+fn disk_usage_summary(project: &ProjectInfo) -> Option<String> {
+    let usage = project.disk_usage?;
+    if usage.allocated == usage.apparent && usage.reclaimable == usage.apparent {
+        return None;
+    }
+    Some(format!(
+        "disk usage: {} apparent, {} allocated, {} reclaimable",
+        format_size(usage.apparent),
+        format_size(usage.allocated),
+        format_size(usage.reclaimable)
+    ))
+}
+
+/// Skip reason for audit records, including the PID and command for process holds
+fn audit_skip_reason(project: &ProjectInfo) -> Option<String> {
+    AppEvaluatedProject::from(project.clone())
+        .skip_reason
+        .map(|reason| reason.to_string())
+        .or_else(|| project.skip_reason.clone())
+}
+
+fn tracked_files_summary(project: &ProjectInfo) -> String {
+    format!(
+        "contains {} git-tracked files; blocked unless --force-protected",
+        project.tracked_files
+    )
+}
+
+/// Outcome of the matching rule's content checks, e.g. "content checks passed: pyvenv.cfg".
+fn content_checks_summary(project: &ProjectInfo) -> Option<String> {
+    let checks = &project.matched_rule.as_ref()?.checks;
+    if checks.is_empty() {
+        return None;
+    }
+    let passed = checks
+        .iter()
+        .filter(|check| check.passed)
+        .map(|check| check.path.as_str())
+        .collect::<Vec<_>>();
+    if passed.is_empty() {
+        let expected = checks
+            .iter()
+            .map(|check| check.path.as_str())
+            .collect::<Vec<_>>();
+        return Some(format!(
+            "no content check passed (expected one of: {}); may be hand-written",
+            expected.join(", ")
+        ));
+    }
+    Some(format!("content checks passed: {}", passed.join(", ")))
+}
+
+/// Stale sub-paths of a partial clean grouped by reason, e.g. "incremental compilation cache (2)".
+fn prune_paths_summary(project: &ProjectInfo) -> String {
+    let mut reasons: Vec<(&str, usize)> = Vec::new();
+    for prune in &project.prune_paths {
+        match reasons
+            .iter_mut()
+            .find(|(reason, _)| *reason == prune.reason)
+        {
+            Some((_, count)) => *count += 1,
+            None => reasons.push((prune.reason.as_str(), 1)),
+        }
+    }
+    let reasons = reasons
+        .iter()
+        .map(|(reason, count)| format!("{} ({})", reason, count))
+        .collect::<Vec<_>>();
+    format!(
+        "partial clean of stale artifacts only: {}",
+        reasons.join(", ")
+    )
+}
+
 fn select_projects_interactive(projects: &[ProjectInfo]) -> Result<Vec<ProjectInfo>> {
     println!("\n{}", "Select directories to clean:".cyan().bold());
     println!("  Enter numbers separated by spaces (e.g., 1 3 5)");
@@ -1739,6 +2256,7 @@ fn run_recommend(
     include_protected: bool,
     recent_days: i64,
     strategy: RecommendStrategyArg,
+    archive: bool,
     output_plan: Option<PathBuf>,
     json_output: bool,
     explain: bool,
@@ -1794,6 +2312,15 @@ fn run_recommend(
     opts.recent_days = recent_days;
     opts.strategy = strategy.to_strategy();
     opts.max_risk = Some(max_risk);
+    if archive {
+        // Past archives tell how well this machine's targets compress.
+        opts.archive_ratio = Some(
+            compression_ratio(&default_archive_root())
+                .ok()
+                .flatten()
+                .unwrap_or(DEFAULT_COMPRESSION_RATIO),
+        );
+    }
 
     let result = recommend_projects(projects, &opts);
     let selected_projects = result
@@ -1809,6 +2336,8 @@ fn run_recommend(
         selected_bytes: u64,
         selected_count: usize,
         strategy: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        archive_ratio: Option<f64>,
         blocked: serde_json::Value,
         projects: Vec<ProjectInfo>,
     }
@@ -1819,11 +2348,13 @@ fn run_recommend(
         selected_bytes: result.selected_bytes,
         selected_count: result.selected.len(),
         strategy: opts.strategy.as_str().to_string(),
+        archive_ratio: opts.archive_ratio,
         blocked: serde_json::json!({
             "in_use": { "count": result.blocked.in_use_count, "bytes": result.blocked.in_use_bytes },
             "protected": { "count": result.blocked.protected_count, "bytes": result.blocked.protected_bytes },
             "recent": { "count": result.blocked.recent_count, "bytes": result.blocked.recent_bytes },
             "risk": { "count": result.blocked.risk_count, "bytes": result.blocked.risk_bytes },
+            "tracked_by_vcs": { "count": result.blocked.tracked_count, "bytes": result.blocked.tracked_bytes },
         }),
         projects: selected_projects.clone(),
     };
@@ -1863,6 +2394,12 @@ fn run_recommend(
         .green()
     );
     println!("  Strategy: {}", opts.strategy.as_str().green().bold());
+    if let Some(ratio) = opts.archive_ratio {
+        println!(
+            "  Archiving: counted net of tarballs (~{:.0}% of each target)",
+            ratio * 100.0
+        );
+    }
     println!(
         "  Selected: {} ({})",
         out.selected_count.to_string().green(),
@@ -1871,11 +2408,12 @@ fn run_recommend(
 
     if !result.blocked.is_empty() {
         println!(
-            "  Blocked: in_use={} protected={} recent={} risk={}",
+            "  Blocked: in_use={} protected={} recent={} risk={} tracked={}",
             result.blocked.in_use_count.to_string().yellow(),
             result.blocked.protected_count.to_string().yellow(),
             result.blocked.recent_count.to_string().yellow(),
-            result.blocked.risk_count.to_string().yellow()
+            result.blocked.risk_count.to_string().yellow(),
+            result.blocked.tracked_count.to_string().yellow()
         );
     }
 
@@ -1897,6 +2435,43 @@ fn run_recommend(
                 &config.custom_patterns,
             );
             println!("  {} {}", "↳".bright_black(), reason.bright_black());
+            if let Some(checks) = content_checks_summary(project) {
+                println!("  {} {}", "↳".bright_black(), checks.bright_black());
+            }
+            if project.tracked_files > 0 {
+                println!(
+                    "  {} {}",
+                    "↳".bright_black(),
+                    tracked_files_summary(project).yellow()
+                );
+            }
+            if let Some(usage) = disk_usage_summary(project) {
+                println!("  {} {}", "↳".bright_black(), usage.bright_black());
+            }
+            if let Some(activity) = activity_summary(project) {
+                println!("  {} {}", "↳".bright_black(), activity.bright_black());
+            }
+            if let Some(hold) = &project.in_use_by {
+                println!(
+                    "  {} {}",
+                    "↳".bright_black(),
+                    format!("in use: {}", hold).yellow()
+                );
+            }
+            if !project.feeding_projects.is_empty() {
+                println!(
+                    "  {} {}",
+                    "↳".bright_black(),
+                    feeding_projects_summary(project).bright_black()
+                );
+            }
+            if project.is_partial() {
+                println!(
+                    "  {} {}",
+                    "↳".bright_black(),
+                    prune_paths_summary(project).bright_black()
+                );
+            }
         }
     }
 
@@ -1916,6 +2491,7 @@ fn run_apply(
     plan_path: PathBuf,
     dry_run: bool,
     trash: bool,
+    archive: bool,
     force: bool,
     no_verify: bool,
     include_recent: bool,
@@ -1946,14 +2522,16 @@ fn run_apply(
     if let Some(run_id) = &run_id {
         for project in &apply_result.skipped_projects {
             let skipped = project.to_project_info();
-            let _ = audit.log_item(
+            let _ = audit.log_project_item(
                 run_id,
                 "apply",
-                &skipped.cleanable_dir,
+                &skipped,
                 "verify",
                 "skipped",
-                skipped.size,
-                skipped.skip_reason.clone(),
+                project
+                    .skip_reason
+                    .as_ref()
+                    .map(|reason| reason.to_string()),
             );
         }
     }
@@ -1969,10 +2547,11 @@ fn run_apply(
     println!("  Total size: {}", format_size(total_size).green().bold());
     if verify_blocked.total_count() > 0 {
         println!(
-            "  Blocked: in_use={} protected={} recent={}",
+            "  Blocked: in_use={} protected={} recent={} tracked={}",
             verify_blocked.in_use_count.to_string().yellow(),
             verify_blocked.protected_count.to_string().yellow(),
-            verify_blocked.recent_count.to_string().yellow()
+            verify_blocked.recent_count.to_string().yellow(),
+            verify_blocked.tracked_count.to_string().yellow()
         );
     }
 
@@ -1989,7 +2568,7 @@ fn run_apply(
             "apply",
             verified_projects.len(),
             total_size,
-            execution_mode_label(dry_run, trash),
+            execution_mode_label(dry_run, trash, archive),
             verify_blocked,
             skipped_pre,
         )?;
@@ -2002,7 +2581,7 @@ fn run_apply(
         }
     }
 
-    let cleaner = Cleaner::with_options(CleanOptions {
+    let options = CleanOptions {
         dry_run,
         verbose,
         force,
@@ -2010,8 +2589,15 @@ fn run_apply(
         force_protected,
         trash,
         trash_root: None,
+        archive,
         cancel_file: None,
-    });
+        deletion: config.deletion.clone(),
+        trash_backend: config.trash_backend,
+        run_id: run_id.clone(),
+        ..CleanOptions::default()
+    };
+    let journal = start_run_journal(run_id.as_deref(), "apply", &options)?;
+    let cleaner = journaled_cleaner(options, journal.as_ref());
     let mut observer = TerminalCleanObserver::new(verbose);
     let mut result = cleaner.clean_multiple_with_observer(&verified_projects, &mut observer)?;
     result.skipped_count += skipped_pre;
@@ -2020,13 +2606,12 @@ fn run_apply(
 
     if let Some(run_id) = &run_id {
         for p in &verified_projects {
-            let _ = audit.log_item(
+            let _ = audit.log_project_item(
                 run_id,
                 "apply",
-                &p.cleanable_dir,
-                if dry_run { "dry_run" } else { "remove" },
+                p,
+                audit_clean_action(dry_run, archive),
                 "attempted",
-                p.size,
                 None,
             );
         }
@@ -2044,6 +2629,12 @@ fn run_apply(
         "  Space freed: {}",
         result.size_freed_human().green().bold()
     );
+    if result.pending_reap_bytes > 0 {
+        println!(
+            "  Pending reap: {} (deleting in the background)",
+            format_size(result.pending_reap_bytes).yellow()
+        );
+    }
 
     if let Some(batch_id) = &result.trash_batch_id {
         println!("  Trash batch: {}", batch_id.cyan().bold());
@@ -2052,13 +2643,21 @@ fn run_apply(
             format!("dev-cleaner undo --batch {}", batch_id).bright_black()
         );
     }
+    print_archive_batch(&result);
 
+    if !result.warnings.is_empty() {
+        println!("\n{}", "Warnings:".yellow().bold());
+        for warning in &result.warnings {
+            println!("  {}", warning.yellow());
+        }
+    }
     if !result.errors.is_empty() {
         println!("\n{}", "Errors:".red().bold());
         for error in &result.errors {
             println!("  {}", error.red());
         }
     }
+    print_unremovable(&result, config.deletion.repair_permissions);
 
     if let Some(run_id) = run_id {
         let _ = audit.finish_run(
@@ -2070,38 +2669,352 @@ fn run_apply(
             result.bytes_freed,
         );
     }
+    if let Some(journal) = journal {
+        let _ = journal.finish();
+    }
 
     Ok(())
 }
 
-fn confirm(prompt: &str) -> Result<bool> {
-    print!("\n{} [y/N] > ", prompt);
-    io::stdout().flush()?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let input = input.trim().to_lowercase();
-
-    Ok(matches!(input.as_str(), "y" | "yes"))
-}
-
-struct AuditRunGuard<'a> {
-    audit: &'a AuditLogger,
-    run_id: Option<String>,
-    command: &'static str,
+/// Journal a run that removes targets, so it can be resumed if the process dies
+fn start_run_journal(
+    run_id: Option<&str>,
+    command: &str,
+    options: &CleanOptions,
+) -> Result<Option<Arc<RunJournal>>> {
+    match run_id {
+        Some(run_id) if !options.dry_run => {
+            let journal =
+                RunJournal::create(&default_run_journal_root(), run_id, command, options)?;
+            Ok(Some(Arc::new(journal)))
+        }
+        _ => Ok(None),
+    }
 }
 
-impl<'a> AuditRunGuard<'a> {
-    fn new(audit: &'a AuditLogger, command: &'static str) -> Self {
-        Self {
-            audit,
-            run_id: audit.start_run(command).ok(),
-            command,
-        }
+fn journaled_cleaner(options: CleanOptions, journal: Option<&Arc<RunJournal>>) -> Cleaner {
+    let cleaner = Cleaner::with_options(options);
+    match journal {
+        Some(journal) => cleaner.journal(Arc::clone(journal)),
+        None => cleaner,
     }
+}
 
-    fn run_id(&self) -> Option<&str> {
-        self.run_id.as_deref()
+/// Action a clean's targets are logged with in the audit log
+fn audit_clean_action(dry_run: bool, archive: bool) -> &'static str {
+    if dry_run {
+        "dry_run"
+    } else if archive {
+        "archive"
+    } else {
+        "remove"
+    }
+}
+
+/// Where an archiving clean put its tarballs and how to unpack them again
+fn print_archive_batch(result: &dev_cleaner_core::cleaner::CleanResult) {
+    let Some(batch_id) = &result.archive_batch_id else {
+        return;
+    };
+    println!("  Archive batch: {}", batch_id.cyan().bold());
+    println!(
+        "  Packed: {} into {}",
+        format_size(result.bytes_freed + result.archive_bytes).green(),
+        format_size(result.archive_bytes).yellow()
+    );
+    println!(
+        "  Undo: {}",
+        format!("dev-cleaner undo --archive --batch {}", batch_id).bright_black()
+    );
+}
+
+/// Entries left behind listed after a clean before the rest are only counted
+const UNREMOVABLE_LISTED: usize = 20;
+
+/// List the files and directories failed targets left behind
+fn print_unremovable(result: &dev_cleaner_core::cleaner::CleanResult, repairing: bool) {
+    if result.unremovable.is_empty() {
+        return;
+    }
+    println!("\n{}", "Could not remove:".red().bold());
+    for entry in result.unremovable.iter().take(UNREMOVABLE_LISTED) {
+        println!(
+            "  {} {}",
+            entry.path.display(),
+            format!("({})", entry.error).bright_black()
+        );
+    }
+    if result.unremovable.len() > UNREMOVABLE_LISTED {
+        println!(
+            "  ... and {} more",
+            result.unremovable.len() - UNREMOVABLE_LISTED
+        );
+    }
+    if !repairing {
+        println!(
+            "  {}",
+            "Read-only directories you own can be made writable with --repair-permissions"
+                .bright_black()
+        );
+    }
+}
+
+/// How to pick an unfinished run back up
+fn resume_hint(run: &UnfinishedRun) -> String {
+    if run.command == "clean" {
+        "dev-cleaner clean --resume".to_string()
+    } else {
+        format!("dev-cleaner apply --resume {}", run.run_id)
+    }
+}
+
+/// Log what an interrupted run got done to its audit run
+fn log_reconciled(audit: &AuditLogger, run: &UnfinishedRun, reconciliation: &Reconciliation) {
+    for target in &reconciliation.completed {
+        let _ = audit.log_item(
+            &run.run_id,
+            &run.command,
+            &target.project.cleanable_dir,
+            "remove",
+            "completed",
+            target.bytes_freed,
+            Some("reconciled after interruption".to_string()),
+        );
+    }
+}
+
+/// Warn about runs a killed process left unfinished; close the ones with nothing left
+fn report_unfinished_runs(config: &Config) {
+    let Ok(runs) = unfinished_runs(&default_run_journal_root()) else {
+        return;
+    };
+    let audit = AuditLogger::from_config(config);
+    for run in runs {
+        let Ok(reconciliation) = run.reconcile() else {
+            continue;
+        };
+        if reconciliation.remaining.is_empty() && reconciliation.partial_copies.is_empty() {
+            if reconciliation.repair_trash().is_err() {
+                continue;
+            }
+            log_reconciled(&audit, &run, &reconciliation);
+            let freed: u64 = reconciliation
+                .completed
+                .iter()
+                .map(|target| target.bytes_freed)
+                .sum();
+            let _ = audit.finish_run(
+                &run.run_id,
+                &run.command,
+                reconciliation.completed.len(),
+                0,
+                0,
+                freed,
+            );
+            if run.close().is_ok() {
+                eprintln!(
+                    "{} interrupted {} run {} had already cleaned its targets ({}); recorded it as finished.",
+                    "Note:".yellow().bold(),
+                    run.command,
+                    run.run_id,
+                    format_size(freed)
+                );
+            }
+            continue;
+        }
+        eprintln!(
+            "{} {} run {} (started {}) was interrupted with {} targets left. Resume with `{}`.",
+            "Warning:".yellow().bold(),
+            run.command,
+            run.run_id,
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            reconciliation.remaining.len(),
+            resume_hint(&run)
+        );
+    }
+}
+
+/// Finish a run a killed process left unfinished: account for what it got done,
+/// repair the trash log, and clean the targets it did not get to
+fn run_resume(
+    run_id: Option<String>,
+    command: &str,
+    force: bool,
+    verbose: bool,
+    config: &Config,
+) -> Result<()> {
+    let root = default_run_journal_root();
+    let runs = unfinished_runs(&root)?;
+    let run = match &run_id {
+        Some(run_id) => runs.into_iter().find(|run| &run.run_id == run_id),
+        None => runs.into_iter().rev().find(|run| run.command == command),
+    };
+    let Some(run) = run else {
+        match run_id {
+            Some(run_id) => println!(
+                "{}",
+                format!("No unfinished run {run_id} (it finished, or is still running).").yellow()
+            ),
+            None => println!(
+                "{}",
+                format!("No unfinished {command} run to resume.").yellow()
+            ),
+        }
+        return Ok(());
+    };
+
+    let reconciliation = run.reconcile()?;
+    let done_bytes: u64 = reconciliation
+        .completed
+        .iter()
+        .map(|target| target.bytes_freed)
+        .sum();
+    let remaining_bytes: u64 = reconciliation.remaining.iter().map(|p| p.size).sum();
+    println!(
+        "{}",
+        format!("Resuming {} run {}", run.command, run.run_id)
+            .cyan()
+            .bold()
+    );
+    println!("  Started: {}", run.started_at.format("%Y-%m-%d %H:%M:%S"));
+    println!(
+        "  Already cleaned: {} ({})",
+        reconciliation.completed.len().to_string().green(),
+        format_size(done_bytes).green()
+    );
+    println!(
+        "  Remaining: {} ({})",
+        reconciliation.remaining.len().to_string().yellow(),
+        format_size(remaining_bytes).yellow()
+    );
+    if !reconciliation.unlogged.is_empty() {
+        println!(
+            "  Trash moves to log: {}",
+            reconciliation.unlogged.len().to_string().yellow()
+        );
+    }
+    if !reconciliation.partial_copies.is_empty() {
+        println!(
+            "  Partial trash copies to discard: {}",
+            reconciliation.partial_copies.len().to_string().yellow()
+        );
+    }
+
+    if !force && !reconciliation.remaining.is_empty() && !confirm("Resume cleaning?")? {
+        println!("{}", "Cancelled.".yellow());
+        return Ok(());
+    }
+
+    reconciliation.repair_trash()?;
+    let journal = Arc::new(RunJournal::resume(&root, &run.run_id)?);
+    let audit = AuditLogger::from_config(config);
+    log_reconciled(&audit, &run, &reconciliation);
+
+    let options = CleanOptions {
+        verbose,
+        deletion: config.deletion.clone(),
+        trash_backend: config.trash_backend,
+        ..run.clean_options()
+    };
+    let cleaner = journaled_cleaner(options, Some(&journal));
+    let mut observer = TerminalCleanObserver::new(verbose);
+    let mut result =
+        cleaner.clean_multiple_with_observer(&reconciliation.remaining, &mut observer)?;
+    result.cleaned_count += reconciliation.completed.len();
+    result.bytes_freed += done_bytes;
+
+    for project in &reconciliation.remaining {
+        let _ = audit.log_project_item(
+            &run.run_id,
+            &run.command,
+            project,
+            "remove",
+            "attempted",
+            None,
+        );
+    }
+
+    println!("\n{}", "Cleaning completed!".green().bold());
+    println!("  Cleaned: {}", result.cleaned_count.to_string().green());
+    println!(
+        "  Skipped: {} ({})",
+        result.skipped_count.to_string().yellow(),
+        format_size(result.bytes_skipped).yellow()
+    );
+    println!("  Failed: {}", result.failed_count.to_string().red());
+    println!(
+        "  Space freed: {}",
+        result.size_freed_human().green().bold()
+    );
+    // The interrupted run's batches stay separate from the one the resumed run uses.
+    let batches = run
+        .trash_batches
+        .iter()
+        .map(|(_, batch_id)| batch_id)
+        .chain(result.trash_batch_id.as_ref());
+    for batch_id in batches {
+        println!("  Trash batch: {}", batch_id.cyan().bold());
+        println!(
+            "  Undo: {}",
+            format!("dev-cleaner undo --batch {}", batch_id).bright_black()
+        );
+    }
+    print_archive_batch(&result);
+    if !result.warnings.is_empty() {
+        println!("\n{}", "Warnings:".yellow().bold());
+        for warning in &result.warnings {
+            println!("  {}", warning.yellow());
+        }
+    }
+    if !result.errors.is_empty() {
+        println!("\n{}", "Errors:".red().bold());
+        for error in &result.errors {
+            println!("  {}", error.red());
+        }
+    }
+    print_unremovable(&result, config.deletion.repair_permissions);
+
+    let _ = audit.finish_run(
+        &run.run_id,
+        &run.command,
+        result.cleaned_count,
+        result.skipped_count,
+        result.failed_count,
+        result.bytes_freed,
+    );
+    journal.finish()?;
+
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("\n{} [y/N] > ", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+
+    Ok(matches!(input.as_str(), "y" | "yes"))
+}
+
+struct AuditRunGuard<'a> {
+    audit: &'a AuditLogger,
+    run_id: Option<String>,
+    command: &'static str,
+}
+
+impl<'a> AuditRunGuard<'a> {
+    fn new(audit: &'a AuditLogger, command: &'static str) -> Self {
+        Self {
+            audit,
+            run_id: audit.start_run(command).ok(),
+            command,
+        }
+    }
+
+    fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
     }
 }
 
@@ -2115,31 +3028,44 @@ impl<'a> Drop for AuditRunGuard<'a> {
 
 fn run_undo(
     batch: Option<String>,
+    archive: bool,
     dry_run: bool,
     force: bool,
     verbose: bool,
     config: &Config,
 ) -> Result<()> {
-    let trash_root = default_trash_root();
+    let (root, store) = if archive {
+        (default_archive_root(), "archive")
+    } else {
+        (default_trash_root(), "trash")
+    };
     let audit = AuditLogger::from_config(config);
     let run_id = audit.start_run("undo").ok();
     let batch_id = match batch {
         Some(b) => Some(b),
-        None => latest_batch_id(&trash_root)?,
+        None if archive => latest_archive_batch_id(&root)?,
+        None => latest_batch_id(&root)?,
     };
 
     let Some(batch_id) = batch_id else {
-        println!("{}", "No trash batches found.".yellow());
+        println!("{}", format!("No {store} batches found.").yellow());
         return Ok(());
     };
 
-    println!("{}", "Restoring from trash...".cyan().bold());
-    println!("  Trash root: {}", trash_root.display());
+    println!("{}", format!("Restoring from {store}...").cyan().bold());
+    if archive {
+        println!("  Archive root: {}", root.display());
+    } else {
+        println!("  Trash root: {}", root.display());
+    }
     println!("  Batch: {}", batch_id.cyan().bold());
 
-    let mut observer = TerminalRestoreObserver::new(verbose);
-    let result =
-        restore_batch_with_observer(&trash_root, &batch_id, dry_run, force, &mut observer)?;
+    let result = if archive {
+        restore_archive_batch(&root, &batch_id, dry_run, force)?
+    } else {
+        let mut observer = TerminalRestoreObserver::new(verbose);
+        restore_batch_with_observer(&root, &batch_id, dry_run, force, &mut observer)?
+    };
 
     println!("\n{}", "Restore completed!".green().bold());
     println!("  Restored: {}", result.restored_count.to_string().green());
@@ -2157,7 +3083,7 @@ fn run_undo(
         let _ = audit.log_item(
             run_id,
             "undo",
-            &trash_root.join(&batch_id),
+            &root.join(&batch_id),
             if dry_run {
                 "dry_run_restore"
             } else {
@@ -2180,6 +3106,388 @@ fn run_undo(
     Ok(())
 }
 
+/// Logs reaped tombstones to the audit run of a `reap`
+struct AuditReapObserver<'a> {
+    audit: &'a AuditLogger,
+    run_id: Option<&'a str>,
+    quiet: bool,
+}
+
+impl ReapObserver for AuditReapObserver<'_> {
+    fn on_reaped(&mut self, tombstone: &Tombstone, bytes_freed: u64) {
+        if let Some(run_id) = self.run_id {
+            let _ = self.audit.log_item(
+                run_id,
+                "reap",
+                &tombstone.original_path,
+                "reap",
+                "completed",
+                bytes_freed,
+                None,
+            );
+        }
+        if !self.quiet {
+            println!(
+                "  {} {} ({})",
+                "Reaped".green(),
+                tombstone.original_path.display(),
+                format_size(bytes_freed)
+            );
+        }
+    }
+
+    fn on_failed(&mut self, tombstone: &Tombstone, error: &anyhow::Error) {
+        if let Some(run_id) = self.run_id {
+            let _ = self.audit.log_item(
+                run_id,
+                "reap",
+                &tombstone.original_path,
+                "reap",
+                "failed",
+                tombstone.pending_bytes(),
+                Some(error.to_string()),
+            );
+        }
+    }
+}
+
+fn run_reap(quiet: bool, config: &Config) -> Result<()> {
+    let journal = TombstoneJournal::new_default();
+    if journal.pending()?.is_empty() {
+        if !quiet {
+            println!("{}", "No tombstones to reap.".yellow());
+        }
+        return Ok(());
+    }
+
+    let audit = AuditLogger::from_config(config);
+    let run_id = audit.start_run("reap").ok();
+    let mut observer = AuditReapObserver {
+        audit: &audit,
+        run_id: run_id.as_deref(),
+        quiet,
+    };
+    let result =
+        journal.reap_with_observer(&config.deletion, CancellationToken::new(), &mut observer)?;
+
+    let Some(result) = result else {
+        if let Some(run_id) = &run_id {
+            let _ = audit.finish_run(run_id, "reap", 0, 0, 0, 0);
+        }
+        if !quiet {
+            println!("{}", "Another reaper is already running.".yellow());
+        }
+        return Ok(());
+    };
+
+    if !quiet {
+        println!("\n{}", "Reaping completed!".green().bold());
+        println!("  Reaped: {}", result.reaped_count.to_string().green());
+        println!("  Failed: {}", result.failed_count.to_string().red());
+        println!(
+            "  Space freed: {}",
+            format_size(result.bytes_freed).green().bold()
+        );
+        if result.remaining > 0 {
+            println!("  Remaining: {}", result.remaining.to_string().yellow());
+        }
+        if !result.errors.is_empty() {
+            println!("\n{}", "Errors:".red().bold());
+            for error in &result.errors {
+                println!("  {}", error.red());
+            }
+        }
+    }
+
+    if let Some(run_id) = &run_id {
+        let _ = audit.finish_run(
+            run_id,
+            "reap",
+            result.reaped_count,
+            0,
+            result.failed_count,
+            result.bytes_freed,
+        );
+    }
+
+    Ok(())
+}
+
+/// Start a detached `dev-cleaner reap` when tombstones are waiting and no one is reaping them
+fn spawn_background_reaper(config_path: Option<&std::path::Path>, config: &Config) {
+    let journal = TombstoneJournal::new_default();
+    match journal.pending() {
+        Ok(pending) if !pending.is_empty() => {}
+        _ => return,
+    }
+    if journal.reaper_running() {
+        return;
+    }
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+
+    let mut command = std::process::Command::new(exe);
+    command.args(["reap", "--quiet", "--low-priority"]);
+    if let Some(path) = config_path {
+        command.arg("--config").arg(path);
+    }
+    // Flags given on this command line, not just the config file, apply to the reaper too.
+    command
+        .arg("--delete-workers")
+        .arg(config.deletion.workers.to_string());
+    if let Some(rate) = config.deletion.max_bytes_per_sec {
+        command
+            .arg("--max-delete-rate")
+            .arg((rate / (1024 * 1024)).max(1).to_string());
+    }
+    if let Some(ops) = config.deletion.max_ops_per_sec {
+        command.arg("--max-delete-ops").arg(ops.to_string());
+    }
+    if config.deletion.repair_permissions {
+        command.arg("--repair-permissions");
+    }
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    #[cfg(unix)]
+    {
+        // Its own process group, so Ctrl-C in this terminal does not stop it.
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let _ = command.spawn();
+}
+
+/// One line on tombstones still waiting to be reaped, if any
+fn print_pending_tombstones(journal: &TombstoneJournal) -> Result<()> {
+    let pending = journal.pending()?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let bytes: u64 = pending.iter().map(Tombstone::pending_bytes).sum();
+    println!(
+        "  Pending tombstones: {} ({}), see `dev-cleaner trash tombstones`",
+        pending.len().to_string().yellow(),
+        format_size(bytes).yellow()
+    );
+    Ok(())
+}
+
+fn run_archive(command: ArchiveCommands, config: &Config) -> Result<()> {
+    let archive_root = default_archive_root();
+    let audit = AuditLogger::from_config(config);
+    let audit_run = AuditRunGuard::new(&audit, "archive");
+
+    match command {
+        ArchiveCommands::List { top, json } => {
+            let batches = list_archive_batches(&archive_root)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&batches)?);
+                return Ok(());
+            }
+
+            if batches.is_empty() {
+                println!("{}", "No archive batches found.".yellow());
+                return Ok(());
+            }
+
+            println!("{}", "Archive batches:".cyan().bold());
+            println!("  Archive root: {}", archive_root.display());
+            println!(
+                "  Showing: {} / {}",
+                std::cmp::min(top, batches.len()),
+                batches.len()
+            );
+
+            for batch in batches.iter().take(top) {
+                println!(
+                    "  {}  {}  {} -> {}  {}",
+                    batch.batch_id.cyan().bold(),
+                    format!("{} items", batch.entries_count).bright_black(),
+                    format_size(batch.size_before).bright_black(),
+                    format_size(batch.size_after).green(),
+                    batch
+                        .created_at
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                        .bright_black()
+                );
+            }
+        }
+        ArchiveCommands::Show { batch, json } => {
+            let entries = archive_entries_for_batch(&archive_root, &batch)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
+            }
+
+            if entries.is_empty() {
+                println!("{}", "No entries found for this batch.".yellow());
+                return Ok(());
+            }
+
+            let size_before: u64 = entries.iter().map(|e| e.size_before).sum();
+            let size_after: u64 = entries.iter().map(|e| e.size_after).sum();
+            println!("{}", "Archive batch:".cyan().bold());
+            println!("  Archive root: {}", archive_root.display());
+            println!("  Batch: {}", batch.cyan().bold());
+            println!("  Entries: {}", entries.len().to_string().green());
+            println!(
+                "  Packed: {} into {}",
+                format_size(size_before).green(),
+                format_size(size_after).green().bold()
+            );
+            println!();
+
+            for entry in entries {
+                println!(
+                    "  {} {} ({} -> {})",
+                    "•".bright_black(),
+                    entry.original_path.display(),
+                    format_size(entry.size_before).yellow(),
+                    format_size(entry.size_after).green()
+                );
+                println!(
+                    "    {} {}",
+                    "↳".bright_black(),
+                    entry.archive_path.display()
+                );
+                if let Some(target) = &entry.pruned_from {
+                    println!(
+                        "    {} {}",
+                        "↳".bright_black(),
+                        format!("pruned from {}", target.display()).bright_black()
+                    );
+                }
+            }
+        }
+        ArchiveCommands::Gc {
+            keep_days,
+            keep_gb,
+            dry_run,
+        } => {
+            let (keep_days, keep_bytes) = match (keep_days, keep_gb) {
+                (None, None) => (Some(90), None),
+                (days, gb) => (days, gb.map(|g| g.saturating_mul(1024 * 1024 * 1024))),
+            };
+
+            let result = gc_archives(
+                &archive_root,
+                keep_days,
+                keep_bytes,
+                true,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
+
+            if result.removed_batches == 0 {
+                println!("{}", "Nothing to delete.".yellow());
+                return Ok(());
+            }
+
+            println!("{}", "Archive GC:".cyan().bold());
+            println!("  Archive root: {}", archive_root.display());
+            println!(
+                "  Would delete batches: {}",
+                result.removed_batches.to_string().green()
+            );
+            println!(
+                "  Would free: {}",
+                format_size(result.removed_bytes).green().bold()
+            );
+
+            if result.blocked_by_keep_days {
+                println!(
+                    "  {} {}",
+                    "Note:".yellow().bold(),
+                    "keep-days prevents meeting keep-gb; only older batches are eligible.".yellow()
+                );
+            }
+
+            if dry_run {
+                println!("{}", "Dry run only; no changes made.".bright_black());
+                if let Some(run_id) = audit_run.run_id() {
+                    let _ = audit.log_item(
+                        run_id,
+                        "archive",
+                        &archive_root,
+                        "gc",
+                        "dry_run",
+                        result.removed_bytes,
+                        None,
+                    );
+                }
+                return Ok(());
+            }
+
+            if !confirm(&format!(
+                "Permanently delete {} archive batches (free {})? They can no longer be undone.",
+                result.removed_batches,
+                format_size(result.removed_bytes)
+            ))? {
+                println!("{}", "Cancelled.".yellow());
+                return Ok(());
+            }
+
+            let applied = gc_archives(
+                &archive_root,
+                keep_days,
+                keep_bytes,
+                false,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
+            if applied.failed_batches > 0 {
+                println!("{}", "Archive GC completed with errors.".yellow().bold());
+            } else {
+                println!("{}", "Archive GC completed.".green().bold());
+            }
+            println!(
+                "  Deleted: {} batches ({} freed)",
+                applied.removed_batches.to_string().green(),
+                format_size(applied.removed_bytes).green()
+            );
+            println!(
+                "  Remaining archives: {}",
+                format_size(applied.remaining_bytes).yellow()
+            );
+
+            if !applied.errors.is_empty() {
+                println!("\n{}", "Errors:".red().bold());
+                for error in &applied.errors {
+                    println!("  {}", error.red());
+                }
+            }
+            if let Some(run_id) = audit_run.run_id() {
+                let _ = audit.log_item(
+                    run_id,
+                    "archive",
+                    &archive_root,
+                    "gc",
+                    "completed",
+                    applied.removed_bytes,
+                    None,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The trash root, and the per-filesystem roots its log has entries in
+fn print_trash_roots(trash_root: &std::path::Path) -> Result<()> {
+    println!("  Trash root: {}", trash_root.display());
+    for other in other_trash_roots(trash_root)? {
+        println!("  Also in: {}", other.display().to_string().bright_black());
+    }
+    Ok(())
+}
+
 fn run_trash(command: TrashCommands, config: &Config) -> Result<()> {
     run_trash_with_root(command, config, default_trash_root())
 }
@@ -2199,11 +3507,12 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
 
             if batches.is_empty() {
                 println!("{}", "No trash batches found.".yellow());
+                print_pending_tombstones(&TombstoneJournal::new_default())?;
                 return Ok(());
             }
 
             println!("{}", "Trash batches:".cyan().bold());
-            println!("  Trash root: {}", trash_root.display());
+            print_trash_roots(&trash_root)?;
             println!(
                 "  Showing: {} / {}",
                 std::cmp::min(top, batches.len()),
@@ -2211,10 +3520,18 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
             );
 
             for batch in batches.iter().take(top) {
+                let items = if batch.pruned_count > 0 {
+                    format!(
+                        "{} items ({} pruned)",
+                        batch.entries_count, batch.pruned_count
+                    )
+                } else {
+                    format!("{} items", batch.entries_count)
+                };
                 println!(
                     "  {}  {}  {}  {}",
                     batch.batch_id.cyan().bold(),
-                    format!("{} items", batch.entries_count).bright_black(),
+                    items.bright_black(),
                     format_size(batch.total_size).green(),
                     batch
                         .created_at
@@ -2223,6 +3540,7 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                         .bright_black()
                 );
             }
+            print_pending_tombstones(&TombstoneJournal::new_default())?;
         }
         TrashCommands::Show { batch, json } => {
             let entries = trash_entries_for_batch(&trash_root, &batch)?;
@@ -2257,6 +3575,13 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                     "↳".bright_black(),
                     entry.trashed_path.display()
                 );
+                if let Some(target) = &entry.pruned_from {
+                    println!(
+                        "    {} {}",
+                        "↳".bright_black(),
+                        format!("pruned from {}", target.display()).bright_black()
+                    );
+                }
             }
         }
         TrashCommands::Purge { batch, force } => {
@@ -2275,7 +3600,13 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                 return Ok(());
             }
 
-            let result = purge_trash_batch(&trash_root, &batch, false)?;
+            let result = purge_trash_batch(
+                &trash_root,
+                &batch,
+                false,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
 
             if result.failed_batches > 0 {
                 println!("{}", "Purge completed with errors.".yellow().bold());
@@ -2316,7 +3647,14 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                 (days, gb) => (days, gb.map(|g| g.saturating_mul(1024 * 1024 * 1024))),
             };
 
-            let result = gc_trash(&trash_root, keep_days, keep_bytes, true)?;
+            let result = gc_trash(
+                &trash_root,
+                keep_days,
+                keep_bytes,
+                true,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
 
             if result.removed_batches == 0 {
                 println!("{}", "Nothing to delete.".yellow());
@@ -2324,7 +3662,7 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
             }
 
             println!("{}", "Trash GC:".cyan().bold());
-            println!("  Trash root: {}", trash_root.display());
+            print_trash_roots(&trash_root)?;
             println!(
                 "  Would delete batches: {}",
                 result.removed_batches.to_string().green()
@@ -2367,7 +3705,14 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                 return Ok(());
             }
 
-            let applied = gc_trash(&trash_root, keep_days, keep_bytes, false)?;
+            let applied = gc_trash(
+                &trash_root,
+                keep_days,
+                keep_bytes,
+                false,
+                &config.deletion,
+                CancellationToken::new(),
+            )?;
             if applied.failed_batches > 0 {
                 println!("{}", "Trash GC completed with errors.".yellow().bold());
             } else {
@@ -2401,6 +3746,44 @@ fn run_trash_with_root(command: TrashCommands, config: &Config, trash_root: Path
                 );
             }
         }
+        TrashCommands::Tombstones { json } => {
+            let journal = TombstoneJournal::new_default();
+            let pending = journal.pending()?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&pending)?);
+                return Ok(());
+            }
+
+            if pending.is_empty() {
+                println!("{}", "No tombstones waiting to be reaped.".yellow());
+                return Ok(());
+            }
+
+            let total: u64 = pending.iter().map(Tombstone::pending_bytes).sum();
+            println!("{}", "Tombstones:".cyan().bold());
+            println!("  Journal: {}", journal.root().display());
+            println!(
+                "  Pending: {} ({})",
+                pending.len().to_string().green(),
+                format_size(total).green()
+            );
+            if journal.reaper_running() {
+                println!("  Reaper: {}", "running".green());
+            }
+            for tombstone in &pending {
+                println!(
+                    "  {}  {}  {}",
+                    tombstone.original_path.display().to_string().cyan(),
+                    format_size(tombstone.pending_bytes()).green(),
+                    tombstone
+                        .created_at
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                        .bright_black()
+                );
+            }
+        }
     }
 
     Ok(())
@@ -2491,7 +3874,16 @@ fn run_audit(command: AuditCommands, config: &Config) -> Result<()> {
     match command {
         AuditCommands::List { top, json } => {
             let runs = logger.list_runs()?;
-            let shown = runs.into_iter().take(top).collect::<Vec<_>>();
+            let mut shown = runs.into_iter().take(top).collect::<Vec<_>>();
+            let pending = TombstoneJournal::new_default()
+                .pending_by_run()
+                .unwrap_or_default();
+            for run in &mut shown {
+                if let Some((count, bytes)) = pending.get(&run.run_id) {
+                    run.pending_reap_count = *count;
+                    run.pending_reap_bytes = *bytes;
+                }
+            }
             if json {
                 println!("{}", serde_json::to_string_pretty(&shown)?);
                 return Ok(());
@@ -2504,14 +3896,23 @@ fn run_audit(command: AuditCommands, config: &Config) -> Result<()> {
             println!("{}", "Audit runs:".cyan().bold());
             println!("  Log path: {}", logger.path().display());
             for run in shown {
+                let pending = if run.pending_reap_count > 0 {
+                    format!(
+                        " pending_reap={}",
+                        format_size(run.pending_reap_bytes).yellow()
+                    )
+                } else {
+                    String::new()
+                };
                 println!(
-                    "  {}  {}  cleaned={} skipped={} failed={} freed={}",
+                    "  {}  {}  cleaned={} skipped={} failed={} freed={}{}",
                     run.run_id.cyan().bold(),
                     run.command.bright_black(),
                     run.cleaned.to_string().green(),
                     run.skipped.to_string().yellow(),
                     run.failed.to_string().red(),
-                    format_size(run.freed_bytes).green()
+                    format_size(run.freed_bytes).green(),
+                    pending
                 );
             }
         }
@@ -2529,6 +3930,22 @@ fn run_audit(command: AuditCommands, config: &Config) -> Result<()> {
             for record in records {
                 println!("{}", serde_json::to_string_pretty(&record)?);
             }
+            let pending = TombstoneJournal::new_default()
+                .pending()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|tombstone| tombstone.run_id.as_deref() == Some(run.as_str()))
+                .collect::<Vec<_>>();
+            if !pending.is_empty() {
+                println!("{}", "Pending tombstones:".cyan().bold());
+                for tombstone in &pending {
+                    println!(
+                        "  {}  {}",
+                        tombstone.original_path.display(),
+                        format_size(tombstone.pending_bytes()).yellow()
+                    );
+                }
+            }
         }
         AuditCommands::Export {
             run,
@@ -2583,7 +4000,13 @@ fn run_tui(
     );
     let projects =
         project_infos_from_evaluated(scan_service.discover_visible(config, &request)?.projects);
-    crate::tui::run_tui_projects(projects, include_recent, include_protected, recent_days)
+    crate::tui::run_tui_projects(
+        projects,
+        include_recent,
+        include_protected,
+        recent_days,
+        &config.deletion,
+    )
 }
 
 #[cfg(test)]
@@ -2607,7 +4030,14 @@ mod tests {
             failed_count: 0,
             errors: Vec::new(),
             trash_batch_id: None,
+            archive_batch_id: None,
+            archive_bytes: 0,
             run_id: None,
+            cancelled: false,
+            interrupted: Vec::new(),
+            pending_reap_bytes: 0,
+            unremovable: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -2636,6 +4066,16 @@ mod tests {
             recent,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -2705,9 +4145,16 @@ mod tests {
 
     #[test]
     fn execution_mode_label_formats_modes() {
-        assert_eq!(execution_mode_label(true, false), "dry-run");
-        assert_eq!(execution_mode_label(false, true), "trash (undoable)");
-        assert_eq!(execution_mode_label(false, false), "permanent delete");
+        assert_eq!(execution_mode_label(true, false, false), "dry-run");
+        assert_eq!(execution_mode_label(false, true, false), "trash (undoable)");
+        assert_eq!(
+            execution_mode_label(false, false, true),
+            "archive (undoable)"
+        );
+        assert_eq!(
+            execution_mode_label(false, false, false),
+            "permanent delete"
+        );
     }
 
     #[test]
@@ -2779,6 +4226,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         };
         let mut large = small.clone();
         large.cleanable_dir = PathBuf::from("/workspace/large");
@@ -2833,6 +4290,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         };
 
         let plan = CleanupPlan {
@@ -2851,7 +4318,7 @@ mod tests {
         config.audit.path = Some(audit_path);
 
         run_apply(
-            plan_path, true, false, true, true, true, true, 7, false, &config,
+            plan_path, true, false, false, true, true, true, true, 7, false, &config,
         )
         .unwrap();
 
@@ -2913,6 +4380,16 @@ mod tests {
                 recent: false,
                 selection_reason: None,
                 skip_reason: None,
+                feeding_projects: Vec::new(),
+                prune_paths: Vec::new(),
+                tracked_files: 0,
+                in_use_source: Default::default(),
+                recent_source: Default::default(),
+                activity: None,
+                in_use_by: None,
+                disk_usage: None,
+                device: None,
+                inode: None,
             }],
         };
         plan.save_json(&plan_path).unwrap();
@@ -2923,7 +4400,7 @@ mod tests {
         config.audit.path = Some(audit_path);
 
         run_apply(
-            plan_path, false, true, false, true, false, false, 7, false, &config,
+            plan_path, false, true, false, false, true, false, false, 7, false, &config,
         )
         .unwrap();
 
@@ -368,8 +368,10 @@ impl SelectorState {
     }
 
     fn block_reason<'a>(&self, project: &'a ProjectInfo) -> Option<&'static str> {
-        if project.in_use && !self.options.force {
+        if project.in_use_by.is_some() || (project.in_use && !self.options.force) {
             Some("IN_USE")
+        } else if project.tracked_files > 0 && !self.options.force_protected {
+            Some("TRACKED")
         } else if project.protected && !self.options.force_protected {
             Some("PROTECTED")
         } else {
@@ -589,6 +591,7 @@ fn detection_source(project: &ProjectInfo) -> &'static str {
         Some(RuleSource::Builtin) => "builtin",
         Some(RuleSource::Gitignore) => "gitignore",
         Some(RuleSource::Heuristic) => "heuristic",
+        Some(RuleSource::CacheTag) => "cache_tag",
         None => "unknown",
     }
 }
@@ -597,9 +600,10 @@ fn source_rank(project: &ProjectInfo) -> u8 {
     match project.matched_rule.as_ref().map(|rule| rule.source) {
         Some(RuleSource::Custom) => 0,
         Some(RuleSource::Builtin) => 1,
-        Some(RuleSource::Heuristic) => 2,
-        Some(RuleSource::Gitignore) => 3,
-        None => 4,
+        Some(RuleSource::CacheTag) => 2,
+        Some(RuleSource::Heuristic) => 3,
+        Some(RuleSource::Gitignore) => 4,
+        None => 5,
     }
 }
 
@@ -668,6 +672,16 @@ mod tests {
             recent,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -28,6 +28,13 @@ fn display_overview(stats: &Statistics) {
         "  Cleanable space: {}",
         format_size(stats.total_size).bright_yellow()
     );
+    if stats.total_apparent_size > stats.total_size {
+        // Hardlinks into shared stores and sparse files occupy less than their length.
+        println!(
+            "  Apparent size: {} (shared hardlinks and sparse files excluded above)",
+            format_size(stats.total_apparent_size).bright_black()
+        );
+    }
 }
 
 fn display_by_type(stats: &Statistics) {
@@ -237,6 +244,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -7,9 +7,10 @@ use crossterm::{
 };
 use dev_cleaner_core::app::{ScanRequest, ScanService};
 use dev_cleaner_core::evaluation::EvaluatedProject;
+use dev_cleaner_core::policy::ActivitySource;
 use dev_cleaner_core::scanner::RiskLevel;
 use dev_cleaner_core::utils::format_size;
-use dev_cleaner_core::{Cleaner, Config, ProjectInfo};
+use dev_cleaner_core::{Cleaner, Config, DeletionOptions, ProjectInfo};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -85,9 +86,10 @@ impl AppState {
         let projects = projects
             .into_iter()
             .map(|project| {
-                let recent = project.days_since_modified() < recent_days;
+                let (recent, recent_source) = project.recent_within(recent_days);
                 let mut evaluated = EvaluatedProject::from(project);
                 evaluated.safety.recent = recent;
+                evaluated.safety.recent_source = recent_source;
                 evaluated
             })
             .collect::<Vec<_>>();
@@ -448,13 +450,16 @@ fn handle_key(app: &mut AppState, key: KeyCode) -> AppOutcome {
 
 fn default_selectable(project: &EvaluatedProject, recent_days: i64) -> bool {
     !project.info.in_use
+        && project.info.tracked_files == 0
         && !project.safety.protected
-        && project.info.days_since_modified() >= recent_days
+        && !project.info.recent_within(recent_days).0
 }
 
 fn selection_status(project: &EvaluatedProject) -> &'static str {
     if project.info.in_use {
         "in-use"
+    } else if project.info.tracked_files > 0 {
+        "tracked"
     } else if project.safety.protected {
         "protected"
     } else if project.safety.recent {
@@ -474,6 +479,7 @@ fn detection_source(project: &EvaluatedProject) -> &'static str {
         dev_cleaner_core::scanner::RuleSource::Custom => "custom",
         dev_cleaner_core::scanner::RuleSource::Gitignore => "gitignore",
         dev_cleaner_core::scanner::RuleSource::Heuristic => "heuristic",
+        dev_cleaner_core::scanner::RuleSource::CacheTag => "cache_tag",
     }
 }
 
@@ -481,9 +487,10 @@ fn source_sort_rank(project: &EvaluatedProject) -> u8 {
     match project.info.matched_rule.as_ref().map(|r| r.source) {
         Some(dev_cleaner_core::scanner::RuleSource::Custom) => 0,
         Some(dev_cleaner_core::scanner::RuleSource::Builtin) => 1,
-        Some(dev_cleaner_core::scanner::RuleSource::Heuristic) => 2,
-        Some(dev_cleaner_core::scanner::RuleSource::Gitignore) => 3,
-        None => 4,
+        Some(dev_cleaner_core::scanner::RuleSource::CacheTag) => 2,
+        Some(dev_cleaner_core::scanner::RuleSource::Heuristic) => 3,
+        Some(dev_cleaner_core::scanner::RuleSource::Gitignore) => 4,
+        None => 5,
     }
 }
 
@@ -497,7 +504,23 @@ fn decision_summary(project: &EvaluatedProject, recent_days: i64) -> &'static st
 
 fn block_reason(project: &EvaluatedProject, recent_days: i64) -> String {
     if project.info.in_use {
-        return "Project appears active (lock file modified recently).".to_string();
+        if let Some(hold) = &project.info.in_use_by {
+            return format!("In use by a running process: {}. Close it first.", hold);
+        }
+        return match project.info.in_use_source {
+            ActivitySource::VerifiedGit => {
+                "Project is active (recent git activity, verified:git).".to_string()
+            }
+            ActivitySource::Heuristic | ActivitySource::VerifiedProcess => {
+                "Project appears active (lock file modified recently).".to_string()
+            }
+        };
+    }
+    if project.info.tracked_files > 0 {
+        return format!(
+            "Target contains {} files tracked by git.",
+            project.info.tracked_files
+        );
     }
     if project.safety.protected {
         return format!(
@@ -510,10 +533,16 @@ fn block_reason(project: &EvaluatedProject, recent_days: i64) -> String {
         );
     }
     if project.safety.recent {
-        return format!(
-            "Target was modified within the recent window ({} days).",
-            recent_days
-        );
+        return match project.safety.recent_source {
+            ActivitySource::VerifiedGit => format!(
+                "Git activity within the recent window ({} days, verified:git).",
+                recent_days
+            ),
+            ActivitySource::Heuristic | ActivitySource::VerifiedProcess => format!(
+                "Target was modified within the recent window ({} days).",
+                recent_days
+            ),
+        };
     }
     "No blocker. Safe to include in cleanup selection.".to_string()
 }
@@ -525,7 +554,7 @@ pub fn run_tui(path: PathBuf) -> Result<()> {
 
 pub fn run_tui_with_config(path: PathBuf, config: &Config) -> Result<()> {
     let (projects, recent_days) = load_tui_projects(path, config)?;
-    run_tui_projects(projects, false, false, recent_days)
+    run_tui_projects(projects, false, false, recent_days, &config.deletion)
 }
 
 fn load_tui_projects(path: PathBuf, config: &Config) -> Result<(Vec<ProjectInfo>, i64)> {
@@ -549,6 +578,7 @@ pub fn run_tui_projects(
     include_recent: bool,
     include_protected: bool,
     recent_days: i64,
+    deletion: &DeletionOptions,
 ) -> Result<()> {
     if projects.is_empty() {
         println!("No cleanable directories found.");
@@ -562,7 +592,7 @@ pub fn run_tui_projects(
     let mut terminal = Terminal::new(backend)?;
 
     let app = AppState::new(projects, include_recent, include_protected, recent_days);
-    let res = run_app(&mut terminal, app);
+    let res = run_app(&mut terminal, app, deletion);
 
     disable_raw_mode()?;
     execute!(
@@ -578,7 +608,11 @@ pub fn run_tui_projects(
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: AppState) -> Result<()> {
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mut app: AppState,
+    deletion: &DeletionOptions,
+) -> Result<()> {
     loop {
         terminal.draw(|f| render_ui(f, &mut app))?;
 
@@ -589,7 +623,7 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: AppSt
                 AppOutcome::CleanSelected => {
                     disable_raw_mode()?;
                     let selected = app.get_selected_projects();
-                    let cleaner = Cleaner::new().verbose(true);
+                    let cleaner = Cleaner::new().verbose(true).deletion(deletion.clone());
                     let mut observer = TerminalCleanObserver::new(true);
                     let result = cleaner.clean_multiple_with_observer(&selected, &mut observer)?;
                     println!("\nCleaning completed!");
@@ -720,6 +754,13 @@ fn draw_project_list(f: &mut Frame, area: Rect, app: &mut AppState) {
     f.render_stateful_widget(list, area, &mut app.list_state);
 }
 
+fn git_activity_label(project: &EvaluatedProject) -> String {
+    match project.info.activity {
+        Some(activity) => format!("{} days ago", activity.days_since_activity()),
+        None => "-".to_string(),
+    }
+}
+
 fn draw_detail_panel(f: &mut Frame, area: Rect, app: &AppState) {
     let text = if let Some(p) = app.selected_project() {
         vec![
@@ -737,6 +778,8 @@ fn draw_detail_panel(f: &mut Frame, area: Rect, app: &AppState) {
             Line::from(format!("Size: {}", format_size(p.info.size))),
             Line::from(format!("Age: {} days", p.info.days_since_modified())),
             Line::from(format!("Source: {}", detection_source(p))),
+            Line::from(format!("Git activity: {}", git_activity_label(p))),
+            Line::from(format!("Tracked files: {}", p.info.tracked_files)),
             Line::from(format!(
                 "Protected by: {}",
                 p.safety
@@ -896,6 +939,16 @@ mod tests {
             recent: false,
             selection_reason: None,
             skip_reason: None,
+            feeding_projects: Vec::new(),
+            prune_paths: Vec::new(),
+            tracked_files: 0,
+            in_use_source: Default::default(),
+            recent_source: Default::default(),
+            activity: None,
+            in_use_by: None,
+            disk_usage: None,
+            device: None,
+            inode: None,
         }
     }
 
@@ -913,8 +966,9 @@ mod tests {
         fs::write(project_root.join(".dev-cleaner-keep"), "").unwrap();
         fs::write(target.join("artifact.bin"), b"x").unwrap();
 
-        let (projects, recent_days) =
-            load_tui_projects(project_root.clone(), &Config::default()).unwrap();
+        let mut config = Config::default();
+        config.scan_cache.path = Some(temp.path().join("scan-cache.json"));
+        let (projects, recent_days) = load_tui_projects(project_root.clone(), &config).unwrap();
 
         assert_eq!(recent_days, 7);
         assert_eq!(projects.len(), 1);
@@ -1130,6 +1184,6 @@ mod tests {
 
     #[test]
     fn run_tui_projects_returns_early_for_empty_input() {
-        assert!(run_tui_projects(Vec::new(), false, false, 7).is_ok());
+        assert!(run_tui_projects(Vec::new(), false, false, 7, &DeletionOptions::default()).is_ok());
     }
 }
@@ -1,9 +1,10 @@
 use serde_json::Value;
 use std::env;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output};
-use std::sync::{Mutex, OnceLock};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
 use tempfile::TempDir;
 
 fn binary_path() -> PathBuf {
@@ -18,6 +19,12 @@ fn command(workspace: &TempDir) -> Command {
     cmd.env("XDG_CONFIG_HOME", workspace.path().join("config"));
     cmd.env("XDG_DATA_HOME", workspace.path().join("data"));
     cmd.env("DEV_CLEANER_TRASH_DIR", workspace.path().join("trash"));
+    cmd.env(
+        "DEV_CLEANER_TOMBSTONE_DIR",
+        workspace.path().join("tombstones"),
+    );
+    cmd.env("DEV_CLEANER_RUN_JOURNAL_DIR", workspace.path().join("runs"));
+    cmd.env("DEV_CLEANER_ARCHIVE_DIR", workspace.path().join("archive"));
     cmd
 }
 
@@ -55,6 +62,8 @@ fn write_project(root: &Path, name: &str, artifact_bytes: usize) -> PathBuf {
     )
     .unwrap();
     fs::write(target_dir.join("artifact.bin"), vec![b'x'; artifact_bytes]).unwrap();
+    // Cargo writes this into every target dir; it confirms the builtin match.
+    fs::write(target_dir.join(".rustc_info.json"), "").unwrap();
     project_root
 }
 
@@ -73,6 +82,38 @@ fn write_apply_plan(workspace: &TempDir, project_root: &Path, plan_name: &str) -
     plan_path
 }
 
+/// Leave behind the journal of a `command` run over `project_root` that was cancelled
+/// after planning its targets, as if its process had been killed
+fn write_interrupted_run(workspace: &TempDir, project_root: &Path, command: &str, run_id: &str) {
+    use dev_cleaner::core::{CancellationToken, CleanOptions, Cleaner, ProjectInfo, RunJournal};
+
+    let output = run(
+        workspace,
+        &[
+            "scan",
+            project_root.to_str().unwrap(),
+            "--json",
+            "--include-recent",
+        ],
+    );
+    let projects: Vec<ProjectInfo> = serde_json::from_slice(&output.stdout).unwrap();
+    let options = CleanOptions {
+        include_recent: true,
+        run_id: Some(run_id.to_string()),
+        ..CleanOptions::default()
+    };
+    let journal =
+        RunJournal::create(&workspace.path().join("runs"), run_id, command, &options).unwrap();
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let result = Cleaner::with_options(options)
+        .cancellation(cancel)
+        .journal(Arc::new(journal))
+        .clean_multiple(&projects)
+        .unwrap();
+    assert_eq!(result.cleaned_count, 0);
+}
+
 fn parse_json_value(stdout: &[u8]) -> Value {
     serde_json::from_slice(stdout).unwrap()
 }
@@ -592,3 +633,223 @@ fn plan_apply_trash_moves_target_and_logs_apply() {
     assert!(audit_content.contains("\"command\":\"apply\""));
     assert!(audit_content.contains("\"type\":\"run_finished\""));
 }
+
+#[test]
+fn deletion_flags_are_only_accepted_by_deleting_commands() {
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "delete-flags-app", 1024);
+    let path = project_root.to_str().unwrap();
+
+    run_failure(&workspace, &["scan", path, "--low-priority"]);
+    run_failure(&workspace, &["stats", path, "--max-delete-rate", "10"]);
+    run(
+        &workspace,
+        &[
+            "clean",
+            path,
+            "--dry-run",
+            "--auto",
+            "--include-recent",
+            "--low-priority",
+            "--delete-workers",
+            "2",
+            "--max-delete-rate",
+            "10",
+            "--max-delete-ops",
+            "100",
+        ],
+    );
+    run(&workspace, &["reap", "--quiet", "--low-priority"]);
+    assert!(project_root.join("target/artifact.bin").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn clean_repair_permissions_removes_read_only_tree() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "read-only-app", 1024);
+    let locked = project_root.join("target/debug/locked");
+    fs::create_dir_all(&locked).unwrap();
+    fs::write(locked.join("file.bin"), "x").unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+    let path = project_root.to_str().unwrap();
+
+    run_failure(&workspace, &["scan", path, "--repair-permissions"]);
+    let output = run(
+        &workspace,
+        &[
+            "clean",
+            path,
+            "--auto",
+            "--include-recent",
+            "--repair-permissions",
+        ],
+    );
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Cleaning completed!"));
+    assert!(!project_root.join("target").exists());
+}
+
+#[test]
+fn clean_tombstone_renames_target_and_reap_deletes_it() {
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "tombstone-app", 2048);
+    let path = project_root.to_str().unwrap();
+    let tombstones_left = || {
+        let listed = run(&workspace, &["trash", "tombstones", "--json"]);
+        !parse_json_value(&listed.stdout)
+            .as_array()
+            .unwrap()
+            .is_empty()
+            || fs::read_dir(&project_root)
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .any(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(".dev-cleaner-tombstone-")
+                })
+    };
+
+    run_failure(&workspace, &["scan", path, "--tombstone"]);
+    let output = run(
+        &workspace,
+        &["clean", path, "--auto", "--include-recent", "--tombstone"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Cleaning completed!"));
+    assert!(stdout.contains("Pending reap:"));
+    assert!(!project_root.join("target").exists());
+
+    // The clean also starts a background reaper; whichever gets there first deletes it.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while tombstones_left() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "tombstone was never reaped"
+        );
+        run(&workspace, &["reap"]);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    let reap_output = run(&workspace, &["reap"]);
+    assert!(String::from_utf8_lossy(&reap_output.stdout).contains("No tombstones to reap."));
+    let audit_content = fs::read_to_string(audit_log_path(&workspace)).unwrap();
+    assert!(audit_content.contains("\"command\":\"reap\""));
+}
+
+#[test]
+fn clean_resume_finishes_interrupted_run() {
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "resume-clean-app", 2048);
+    let path = project_root.to_str().unwrap();
+    write_interrupted_run(&workspace, &project_root, "clean", "run-clean");
+
+    let scan_output = run(&workspace, &["scan", path, "--include-recent"]);
+    assert!(String::from_utf8_lossy(&scan_output.stderr)
+        .contains("Resume with `dev-cleaner clean --resume`"));
+    assert!(project_root.join("target").exists());
+
+    let output = run(&workspace, &["clean", "--resume", "--auto"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Resuming clean run run-clean"));
+    assert!(stdout.contains("Cleaning completed!"));
+    assert!(!project_root.join("target").exists());
+    assert_eq!(
+        fs::read_dir(workspace.path().join("runs")).unwrap().count(),
+        0
+    );
+
+    let again = run(&workspace, &["clean", "--resume", "--auto"]);
+    assert!(String::from_utf8_lossy(&again.stdout).contains("No unfinished clean run to resume."));
+    let audit_content = fs::read_to_string(audit_log_path(&workspace)).unwrap();
+    assert!(audit_content.contains("\"run_id\":\"run-clean\""));
+}
+
+#[test]
+fn apply_resume_finishes_named_run() {
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "resume-apply-app", 2048);
+    write_interrupted_run(&workspace, &project_root, "apply", "run-apply");
+
+    run_failure(&workspace, &["apply"]);
+    let other = run(&workspace, &["apply", "--resume", "run-other", "--force"]);
+    assert!(String::from_utf8_lossy(&other.stdout).contains("No unfinished run run-other"));
+    assert!(project_root.join("target").exists());
+
+    let output = run(&workspace, &["apply", "--resume", "run-apply", "--force"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Resuming apply run run-apply"));
+    assert!(stdout.contains("Cleaning completed!"));
+    assert!(!project_root.join("target").exists());
+
+    let again = run(&workspace, &["apply", "--resume", "run-apply", "--force"]);
+    assert!(String::from_utf8_lossy(&again.stdout).contains("No unfinished run run-apply"));
+}
+
+#[test]
+fn clean_archive_batches_can_be_listed_shown_and_collected() {
+    let workspace = TempDir::new().unwrap();
+    let project_root = write_project(workspace.path(), "archive-app", 4096);
+    let target = project_root.join("target");
+
+    let output = run(
+        &workspace,
+        &[
+            "clean",
+            project_root.to_str().unwrap(),
+            "--archive",
+            "--auto",
+            "--include-recent",
+        ],
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Archive batch:"));
+    assert!(!target.exists());
+
+    let list = parse_json_value(&run(&workspace, &["archive", "list", "--json"]).stdout);
+    let batches = list.as_array().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0]["entries_count"], 1);
+    let batch_id = batches[0]["batch_id"].as_str().unwrap();
+
+    let show = parse_json_value(
+        &run(
+            &workspace,
+            &["archive", "show", "--batch", batch_id, "--json"],
+        )
+        .stdout,
+    );
+    let entries = show.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(
+        Path::new(entries[0]["original_path"].as_str().unwrap()).ends_with("archive-app/target")
+    );
+    let archive_path = PathBuf::from(entries[0]["archive_path"].as_str().unwrap());
+    assert!(archive_path.exists());
+
+    let dry_run = run(
+        &workspace,
+        &["archive", "gc", "--keep-gb", "0", "--dry-run"],
+    );
+    assert!(String::from_utf8_lossy(&dry_run.stdout).contains("Would delete batches: 1"));
+    assert!(archive_path.exists());
+
+    // Collecting asks for confirmation since the batch can no longer be undone.
+    let mut gc = command(&workspace)
+        .args(["archive", "gc", "--keep-gb", "0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    gc.stdin.take().unwrap().write_all(b"y\n").unwrap();
+    let gc = gc.wait_with_output().unwrap();
+    assert!(gc.status.success());
+    assert!(String::from_utf8_lossy(&gc.stdout).contains("Archive GC completed."));
+    assert!(!archive_path.exists());
+
+    let list = parse_json_value(&run(&workspace, &["archive", "list", "--json"]).stdout);
+    assert!(list.as_array().unwrap().is_empty());
+}